@@ -2,6 +2,7 @@ from github import Github, Auth, InputFileContent
 
 import os
 import json
+import zipfile
 
 if "GITHUB_API_KEY" not in os.environ:
     raise Exception("GITHUB_API_KEY is required to run this script")
@@ -9,6 +10,14 @@ if "GITHUB_API_KEY" not in os.environ:
 if "TAURI_SIGNING_PRIVATE_KEY" not in os.environ:
     raise Exception("TAURI_SIGNING_PRIVATE_KEY is required to run this script")
 
+if "KIKITAN_UPDATER_PUBKEY" not in os.environ:
+    raise Exception("KIKITAN_UPDATER_PUBKEY is required to run this script")
+
+# "beta" releases only reach users on the beta update channel
+channel = os.getenv("KIKITAN_CHANNEL", "stable")
+if channel not in ("stable", "beta"):
+    raise Exception("KIKITAN_CHANNEL must be stable or beta")
+
 auth = Auth.Token(os.getenv("GITHUB_API_KEY"))
 g = Github(auth=auth)
 
@@ -26,8 +35,19 @@ new_release.upload_asset("src-tauri/target/release/bundle/nsis/Kikitan Translato
 print("Uploading update zip...")
 new_release.upload_asset("src-tauri/target/release/bundle/nsis/Kikitan Translator_{}_x64-setup.nsis.zip".format(tauri_conf["version"]), "Kikitan Translator_x64-setup.nsis.zip")
 
+# The portable build is the bare executable, the marker file keeps its data next to it
+print("Packing portable zip...")
+portable_zip = "src-tauri/target/release/Kikitan Translator_{}_x64-portable.zip".format(tauri_conf["version"])
+with zipfile.ZipFile(portable_zip, "w", zipfile.ZIP_DEFLATED) as archive:
+    archive.write("src-tauri/target/release/app.exe", "Kikitan Translator.exe")
+    archive.writestr("portable", "")
+os.system('npx tauri signer sign "{}"'.format(portable_zip))
+
+print("Uploading portable zip...")
+new_release.upload_asset(portable_zip, "Kikitan Translator_x64-portable.zip")
+
 print("Updating release...")
-new_release.update_release(make_latest="true", name=new_release.title, message=new_release.body)
+new_release.update_release(make_latest="true" if channel == "stable" else "false", prerelease=channel == "beta", name=new_release.title, message=new_release.body)
 
 print("Updating gist...")
 update_str = json.dumps({ 
@@ -36,13 +56,19 @@ update_str = json.dumps({
         "windows-x86_64": {
             "signature": open("src-tauri/target/release/bundle/nsis/Kikitan Translator_{}_x64-setup.nsis.zip.sig".format(tauri_conf["version"]), "r").read(),
             "url": "https://github.com/YusufOzmen01/kikitan-translator/releases/download/{}/Kikitan.Translator_{}_x64-setup.nsis.zip".format(tauri_conf["version"], tauri_conf["version"])
+        },
+        "windows-x86_64-portable": {
+            "signature": open(portable_zip + ".sig", "r").read(),
+            "url": "https://github.com/YusufOzmen01/kikitan-translator/releases/download/{}/Kikitan.Translator_x64-portable.zip".format(tauri_conf["version"])
         }
     }
 })
 
-g.get_gist("2739ef70ee7f4e60add7e5d87a4c745b").edit(files={
-    "current_version.json": InputFileContent(update_str)
-})
+files = { "beta_version.json": InputFileContent(update_str) }
+if channel == "stable":
+    files["current_version.json"] = InputFileContent(update_str)
+
+g.get_gist("2739ef70ee7f4e60add7e5d87a4c745b").edit(files=files)
 
 print("Done!")
 g.close()
\ No newline at end of file
@@ -0,0 +1,130 @@
+//! What each speech recognition provider can do, so the frontend doesn't have to hardcode it.
+
+use serde::Serialize;
+
+use crate::incoming::IncomingProvider;
+
+#[derive(Clone, Debug, Serialize)]
+pub struct AudioFormat {
+    pub encoding: &'static str,
+    pub sample_rate: u32,
+    pub channels: u16,
+}
+
+const PCM16_16K_MONO: AudioFormat = AudioFormat {
+    encoding: "pcm_s16le",
+    sample_rate: 16000,
+    channels: 1,
+};
+
+#[derive(Clone, Debug, Serialize)]
+pub struct AsrProvider {
+    /// Same value as in the `incoming.provider` setting, or `webspeech`.
+    pub id: &'static str,
+    pub name: &'static str,
+    /// Primary language subtags, `None` when the provider takes any tag and decides itself.
+    pub languages: Option<Vec<&'static str>>,
+    pub partial_results: bool,
+    pub word_timestamps: bool,
+    /// What we have to send, `None` when the provider captures audio on its own.
+    pub audio: Option<AudioFormat>,
+    pub local: bool,
+    /// Can recognize my own speech.
+    pub outgoing: bool,
+    /// Can recognize other players through the incoming pipeline.
+    pub incoming: bool,
+}
+
+fn incoming_provider(provider: IncomingProvider) -> AsrProvider {
+    let cloud = |id, name, languages: Option<Vec<&'static str>>, word_timestamps| AsrProvider {
+        id,
+        name,
+        languages,
+        partial_results: true,
+        word_timestamps,
+        audio: Some(PCM16_16K_MONO),
+        local: false,
+        outgoing: false,
+        incoming: true,
+    };
+
+    match provider {
+        IncomingProvider::Qwen => AsrProvider {
+            outgoing: true,
+            ..cloud(
+                "qwen",
+                "Qwen ASR",
+                Some(vec![
+                    "zh", "en", "ja", "ko", "de", "fr", "es", "it", "pt", "ru", "ar",
+                ]),
+                false,
+            )
+        },
+        // Whatever the engine behind the endpoint supports, it usually runs next to us
+        IncomingProvider::Grpc => AsrProvider {
+            local: true,
+            outgoing: true,
+            ..cloud("grpc", "External ASR engine", None, false)
+        },
+        IncomingProvider::GoogleStt => cloud("google_stt", "Google Speech-to-Text", None, true),
+        IncomingProvider::AwsTranscribe => cloud(
+            "aws_transcribe",
+            "Amazon Transcribe",
+            Some(vec![
+                "en", "ja", "ko", "zh", "fr", "de", "es", "it", "pt", "ru", "th", "ar", "hi",
+            ]),
+            true,
+        ),
+        IncomingProvider::Iflytek => cloud("iflytek", "iFlytek", Some(vec!["zh", "en"]), true),
+        IncomingProvider::TencentAsr => cloud(
+            "tencent_asr",
+            "Tencent Cloud ASR",
+            Some(vec![
+                "zh", "yue", "en", "ja", "ko", "fr", "de", "es", "pt", "ru", "th", "vi", "id",
+                "ms", "ar", "hi",
+            ]),
+            true,
+        ),
+        IncomingProvider::AssemblyAi => cloud(
+            "assembly_ai",
+            "AssemblyAI",
+            Some(vec!["en", "es", "fr", "de", "it", "pt"]),
+            true,
+        ),
+        IncomingProvider::Soniox => cloud("soniox", "Soniox", None, true),
+    }
+}
+
+/// Every speech recognition provider with its capabilities.
+#[tauri::command]
+pub fn list_asr_providers() -> Vec<AsrProvider> {
+    // Runs in the webview, the browser picks the languages and captures the microphone itself
+    let webspeech = AsrProvider {
+        id: "webspeech",
+        name: "Web Speech",
+        languages: None,
+        partial_results: true,
+        word_timestamps: false,
+        audio: None,
+        local: false,
+        outgoing: true,
+        incoming: false,
+    };
+
+    let mut providers = vec![webspeech];
+    providers.extend(
+        [
+            IncomingProvider::Qwen,
+            IncomingProvider::Grpc,
+            IncomingProvider::GoogleStt,
+            IncomingProvider::AwsTranscribe,
+            IncomingProvider::Iflytek,
+            IncomingProvider::TencentAsr,
+            IncomingProvider::AssemblyAi,
+            IncomingProvider::Soniox,
+        ]
+        .into_iter()
+        .map(incoming_provider),
+    );
+    providers
+}
@@ -0,0 +1,134 @@
+//! AssemblyAI universal streaming (v3) over WebSocket, as a backend ASR provider.
+//!
+//! The API key never goes into the socket URL: it buys a short lived token over HTTPS, and
+//! the stream is opened with that.
+
+use futures_util::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use tauri::{AppHandle, Manager};
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::protocol::Message;
+
+use crate::network;
+use crate::quota;
+use crate::secrets;
+use crate::usage::{self, UsageState};
+
+const TOKEN_URL: &str = "https://streaming.assemblyai.com/v3/token?expires_in_seconds=60";
+const STREAM_URL: &str = "wss://streaming.assemblyai.com/v3/ws";
+const SAMPLE_RATE: u32 = 16000;
+
+async fn token(app: &AppHandle, api_key: &str) -> Result<String, String> {
+    let response: Value = network::http_client(app)
+        .get(TOKEN_URL)
+        .header("Authorization", api_key)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| format!("Failed to get an AssemblyAI token: {}", e))?
+        .json()
+        .await
+        .map_err(|e| format!("Invalid AssemblyAI token response: {}", e))?;
+
+    response["token"]
+        .as_str()
+        .map(str::to_string)
+        .ok_or_else(|| "AssemblyAI returned no token".to_string())
+}
+
+// The default model is English only
+fn speech_model(language: &str) -> &'static str {
+    if language.starts_with("en") {
+        "universal-streaming-english"
+    } else {
+        "universal-streaming-multilingual"
+    }
+}
+
+/// Recognizes 16 kHz mono PCM16 chunks from `audio` and sends each finished turn to
+/// `transcripts`.
+pub async fn transcripts(
+    app: &AppHandle,
+    language: &str,
+    mut audio: mpsc::Receiver<Vec<u8>>,
+    transcripts: mpsc::Sender<String>,
+) -> Result<(), String> {
+    let api_key = secrets::get(secrets::ASSEMBLYAI_API_KEY)?
+        .ok_or_else(|| "AssemblyAI API key is not set".to_string())?;
+
+    let url = format!(
+        "{}?sample_rate={}&encoding=pcm_s16le&format_turns=true&speech_model={}&token={}",
+        STREAM_URL,
+        SAMPLE_RATE,
+        speech_model(language),
+        network::retry(app, || token(app, &api_key)).await?
+    );
+    let (ws, _) = network::connect_websocket(app, url)
+        .await
+        .map_err(|e| format!("Failed to connect to AssemblyAI: {}", e))?;
+    let (mut write, mut read) = ws.split();
+
+    log::info!("[ASSEMBLYAI] Connected");
+
+    let mut ping = network::keepalive_interval(app);
+
+    loop {
+        tokio::select! {
+            _ = ping.tick() => {
+                write
+                    .send(Message::Ping(Vec::new()))
+                    .await
+                    .map_err(|e| format!("Failed to ping AssemblyAI: {}", e))?;
+            }
+            chunk = audio.recv() => {
+                let chunk = match chunk {
+                    Some(chunk) => chunk,
+                    None => {
+                        let _ = write.send(Message::Text(json!({ "type": "Terminate" }).to_string())).await;
+                        return Ok(());
+                    }
+                };
+
+                quota::ensure_available(app, usage::ASSEMBLYAI)?;
+                let seconds = chunk.len() as f64 / (SAMPLE_RATE as f64 * 2.0);
+                write
+                    .send(Message::Binary(chunk))
+                    .await
+                    .map_err(|e| format!("Failed to send audio to AssemblyAI: {}", e))?;
+
+                app.state::<UsageState>().add(usage::ASSEMBLYAI, 0, seconds);
+            }
+            message = read.next() => match message {
+                Some(Ok(Message::Text(text))) => {
+                    let message: Value = serde_json::from_str(&text)
+                        .map_err(|e| format!("Invalid message from AssemblyAI: {}", e))?;
+
+                    match message["type"].as_str() {
+                        // With format_turns every finished turn comes again punctuated, only take that one
+                        Some("Turn") if message["end_of_turn"] == true && message["turn_is_formatted"] == true => {
+                            let text = message["transcript"].as_str().unwrap_or_default().trim();
+                            if !text.is_empty() {
+                                let _ = transcripts.send(text.to_string()).await;
+                            }
+                        }
+                        Some("Termination") => return Err("AssemblyAI ended the session".to_string()),
+                        _ => {
+                            if let Some(error) = message["error"].as_str() {
+                                return Err(format!("AssemblyAI failed: {}", error));
+                            }
+                        }
+                    }
+                }
+                Some(Ok(Message::Close(frame))) => {
+                    return Err(format!(
+                        "AssemblyAI closed the connection{}",
+                        frame.map(|frame| format!(": {}", frame.reason)).unwrap_or_default()
+                    ))
+                }
+                None => return Err("AssemblyAI closed the connection".to_string()),
+                Some(Err(e)) => return Err(format!("AssemblyAI connection failed: {}", e)),
+                Some(Ok(_)) => {}
+            }
+        }
+    }
+}
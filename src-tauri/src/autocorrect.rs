@@ -0,0 +1,211 @@
+//! Cleans up recognizer misspellings before they reach the translator, which would otherwise
+//! translate "recieve" or "Kikitan" heard as "kick a tan" word by word.
+//!
+//! Dictionaries are regular Hunspell `.aff`/`.dic` pairs in `<data dir>/dictionaries`, e.g.
+//! `en_US.aff` and `en_US.dic` from LibreOffice. Glossary terms always count as spelled right
+//! and win over dictionary suggestions, so names and jargon get fixed instead of mangled.
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use spellbook::Dictionary;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use tauri::{AppHandle, Manager, State};
+
+use crate::paths;
+use crate::settings::SettingsState;
+
+const DICTIONARY_DIR: &str = "dictionaries";
+
+// Replacing a word with something further away changes what was said more often than not
+const MAX_DISTANCE: usize = 1;
+const MAX_GLOSSARY_DISTANCE: usize = 2;
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AutocorrectSettings {
+    pub enabled: bool,
+    /// Names and terms the recognizer keeps getting wrong, in their right spelling.
+    pub glossary: Vec<String>,
+}
+
+#[derive(Default)]
+pub struct AutocorrectState {
+    // `None` remembers languages without a dictionary, so they aren't looked up every time
+    dictionaries: Mutex<HashMap<String, Option<Arc<Dictionary>>>>,
+}
+
+fn dictionary_dir(app: &AppHandle) -> PathBuf {
+    paths::data_dir(app).join(DICTIONARY_DIR)
+}
+
+fn words() -> &'static Regex {
+    static WORDS: OnceLock<Regex> = OnceLock::new();
+
+    WORDS.get_or_init(|| Regex::new(r"\p{L}+(?:['’]\p{L}+)*").expect("invalid word pattern"))
+}
+
+/// `en_US.dic` for `en-US`, otherwise any `en*.dic`, preferring the shortest name.
+fn find_dictionary(dir: &Path, language: &str) -> Option<PathBuf> {
+    let wanted = language.replace('-', "_").to_lowercase();
+    let base = wanted.split('_').next().unwrap_or_default().to_string();
+
+    let mut candidates: Vec<(String, PathBuf)> = fs::read_dir(dir)
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "dic"))
+        .filter_map(|path| {
+            let stem = path.file_stem()?.to_str()?.to_lowercase();
+            (stem == base || stem.starts_with(&format!("{}_", base))).then_some((stem, path))
+        })
+        .collect();
+    candidates.sort_by_key(|(stem, _)| (stem != &wanted, stem.len()));
+
+    candidates.into_iter().next().map(|(_, path)| path)
+}
+
+fn load(dic: &Path) -> Result<Dictionary, String> {
+    let aff = fs::read_to_string(dic.with_extension("aff")).map_err(|e| e.to_string())?;
+    let words = fs::read_to_string(dic).map_err(|e| e.to_string())?;
+
+    Dictionary::new(&aff, &words).map_err(|e| e.to_string())
+}
+
+fn dictionary(app: &AppHandle, language: &str) -> Option<Arc<Dictionary>> {
+    let state = app.state::<AutocorrectState>();
+    let mut dictionaries = state.dictionaries.lock().unwrap();
+
+    dictionaries
+        .entry(language.to_string())
+        .or_insert_with(|| {
+            let path = find_dictionary(&dictionary_dir(app), language)?;
+            match load(&path) {
+                Ok(dictionary) => {
+                    log::info!("[AUTOCORRECT] Loaded {} for {}", path.display(), language);
+                    Some(Arc::new(dictionary))
+                }
+                Err(e) => {
+                    log::warn!("[AUTOCORRECT] Failed to load {}: {}", path.display(), e);
+                    None
+                }
+            }
+        })
+        .clone()
+}
+
+fn distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
+fn glossary_match<'a>(word: &str, glossary: &'a [String]) -> Option<&'a str> {
+    let lower = word.to_lowercase();
+    // Short words are one letter away from too many others
+    let allowed = (lower.chars().count() / 4).min(MAX_GLOSSARY_DISTANCE);
+
+    glossary
+        .iter()
+        .map(|term| (distance(&lower, &term.to_lowercase()), term))
+        .filter(|(distance, _)| *distance <= allowed)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, term)| term.as_str())
+}
+
+fn suggestion(dictionary: &Dictionary, word: &str) -> Option<String> {
+    let mut suggestions = Vec::new();
+    dictionary.suggest(word, &mut suggestions);
+
+    // Only the first suggestion, and only when it's a typo away, otherwise it's a guess
+    suggestions.into_iter().next().filter(|suggestion| {
+        distance(&word.to_lowercase(), &suggestion.to_lowercase()) <= MAX_DISTANCE
+    })
+}
+
+/// Corrects `text` spoken in `language`, unchanged when disabled or without a dictionary
+/// and glossary to go by.
+pub fn correct(app: &AppHandle, text: &str, language: &str) -> String {
+    let settings = app.state::<SettingsState>().get().autocorrect;
+    if !settings.enabled {
+        return text.to_string();
+    }
+
+    let glossary: Vec<String> = settings
+        .glossary
+        .iter()
+        .map(|term| term.trim().to_string())
+        .filter(|term| !term.is_empty())
+        .collect();
+    let dictionary = dictionary(app, language);
+    if dictionary.is_none() && glossary.is_empty() {
+        return text.to_string();
+    }
+
+    words()
+        .replace_all(text, |captures: &regex::Captures| {
+            let word = &captures[0];
+            if glossary.iter().any(|term| term.eq_ignore_ascii_case(word)) {
+                return word.to_string();
+            }
+            if let Some(dictionary) = &dictionary {
+                if dictionary.check(word) {
+                    return word.to_string();
+                }
+            }
+
+            let corrected = glossary_match(word, &glossary)
+                .map(str::to_string)
+                .or_else(|| dictionary.as_ref().and_then(|d| suggestion(d, word)));
+            match corrected {
+                Some(corrected) => {
+                    log::debug!("[AUTOCORRECT] {} -> {}", word, corrected);
+                    corrected
+                }
+                None => word.to_string(),
+            }
+        })
+        .into_owned()
+}
+
+#[tauri::command]
+pub fn autocorrect(app: AppHandle, text: String, language: String) -> String {
+    correct(&app, &text, &language)
+}
+
+/// Languages with a dictionary installed, by file name, e.g. `en_US`.
+#[tauri::command]
+pub fn list_dictionaries(app: AppHandle) -> Vec<String> {
+    let mut names: Vec<String> = fs::read_dir(dictionary_dir(&app))
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|path| path.extension().is_some_and(|ext| ext == "dic"))
+                .filter(|path| path.with_extension("aff").exists())
+                .filter_map(|path| Some(path.file_stem()?.to_str()?.to_string()))
+                .collect()
+        })
+        .unwrap_or_default();
+    names.sort();
+
+    names
+}
+
+/// Drops loaded dictionaries so newly added or replaced files get picked up.
+#[tauri::command]
+pub fn reload_dictionaries(state: State<'_, AutocorrectState>) {
+    state.dictionaries.lock().unwrap().clear();
+}
@@ -0,0 +1,197 @@
+use std::env;
+
+const APP_NAME: &str = "Kikitan Translator";
+
+/// Passed by the login entry so the app starts without popping up its window.
+pub const MINIMIZED_ARG: &str = "--minimized";
+
+pub fn started_minimized() -> bool {
+    env::args().any(|arg| arg == MINIMIZED_ARG)
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use super::{APP_NAME, MINIMIZED_ARG};
+    use std::io;
+    use winreg::enums::HKEY_CURRENT_USER;
+    use winreg::RegKey;
+
+    const RUN_KEY: &str = r"Software\Microsoft\Windows\CurrentVersion\Run";
+
+    pub fn enable(exe: &str) -> io::Result<()> {
+        let (key, _) = RegKey::predef(HKEY_CURRENT_USER).create_subkey(RUN_KEY)?;
+        key.set_value(APP_NAME, &format!("\"{}\" {}", exe, MINIMIZED_ARG))
+    }
+
+    pub fn disable() -> io::Result<()> {
+        let (key, _) = RegKey::predef(HKEY_CURRENT_USER).create_subkey(RUN_KEY)?;
+        match key.delete_value(APP_NAME) {
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+            result => result,
+        }
+    }
+
+    pub fn is_enabled() -> bool {
+        RegKey::predef(HKEY_CURRENT_USER)
+            .open_subkey(RUN_KEY)
+            .and_then(|key| key.get_value::<String, _>(APP_NAME))
+            .is_ok()
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use super::{APP_NAME, MINIMIZED_ARG};
+    use std::fs;
+    use std::io;
+    use std::path::PathBuf;
+
+    fn desktop_file() -> io::Result<PathBuf> {
+        dirs::config_dir()
+            .map(|dir| dir.join("autostart").join("kikitan-translator.desktop"))
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "No config directory"))
+    }
+
+    pub fn enable(exe: &str) -> io::Result<()> {
+        let path = desktop_file()?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        fs::write(
+            path,
+            format!(
+                "[Desktop Entry]\nType=Application\nName={}\nExec=\"{}\" {}\nX-GNOME-Autostart-enabled=true\n",
+                APP_NAME, exe, MINIMIZED_ARG
+            ),
+        )
+    }
+
+    pub fn disable() -> io::Result<()> {
+        match fs::remove_file(desktop_file()?) {
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+            result => result,
+        }
+    }
+
+    pub fn is_enabled() -> bool {
+        desktop_file().map(|path| path.exists()).unwrap_or(false)
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use super::MINIMIZED_ARG;
+    use std::fs;
+    use std::io;
+    use std::path::PathBuf;
+
+    const LABEL: &str = "com.github.yusufozmen01.kikitan";
+
+    fn launch_agent() -> io::Result<PathBuf> {
+        dirs::home_dir()
+            .map(|home| {
+                home.join("Library")
+                    .join("LaunchAgents")
+                    .join(format!("{}.plist", LABEL))
+            })
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "No home directory"))
+    }
+
+    fn escape(value: &str) -> String {
+        value
+            .replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;")
+    }
+
+    pub fn enable(exe: &str) -> io::Result<()> {
+        let path = launch_agent()?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        fs::write(
+            path,
+            format!(
+                "<?xml version=\"1.0\" encoding=\"UTF-8\"?>
+<!DOCTYPE plist PUBLIC \"-//Apple//DTD PLIST 1.0//EN\" \"http://www.apple.com/DTDs/PropertyList-1.0.dtd\">
+<plist version=\"1.0\">
+<dict>
+    <key>Label</key>
+    <string>{}</string>
+    <key>ProgramArguments</key>
+    <array>
+        <string>{}</string>
+        <string>{}</string>
+    </array>
+    <key>RunAtLoad</key>
+    <true/>
+</dict>
+</plist>
+",
+                LABEL,
+                escape(exe),
+                MINIMIZED_ARG
+            ),
+        )
+    }
+
+    pub fn disable() -> io::Result<()> {
+        match fs::remove_file(launch_agent()?) {
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+            result => result,
+        }
+    }
+
+    pub fn is_enabled() -> bool {
+        launch_agent().map(|path| path.exists()).unwrap_or(false)
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "linux", target_os = "macos")))]
+mod platform {
+    use std::io;
+
+    fn unsupported() -> io::Error {
+        io::Error::new(
+            io::ErrorKind::Unsupported,
+            "Autostart is not supported on this platform",
+        )
+    }
+
+    pub fn enable(_exe: &str) -> io::Result<()> {
+        Err(unsupported())
+    }
+
+    pub fn disable() -> io::Result<()> {
+        Err(unsupported())
+    }
+
+    pub fn is_enabled() -> bool {
+        false
+    }
+}
+
+#[tauri::command]
+pub fn set_autostart(enabled: bool) -> Result<(), String> {
+    let result = if enabled {
+        let exe = env::current_exe().map_err(|e| format!("Failed to locate executable: {}", e))?;
+        platform::enable(&exe.to_string_lossy())
+    } else {
+        platform::disable()
+    };
+
+    result.map_err(|e| format!("Failed to update autostart: {}", e))?;
+
+    log::info!(
+        "[AUTOSTART] Run at login {}",
+        if enabled { "enabled" } else { "disabled" }
+    );
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_autostart() -> bool {
+    platform::is_enabled()
+}
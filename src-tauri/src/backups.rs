@@ -0,0 +1,137 @@
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, State};
+
+use crate::events;
+use crate::settings::{self, SettingsState};
+
+const BACKUP_DIR: &str = "backups";
+const MAX_BACKUPS: usize = 10;
+
+#[derive(Serialize)]
+pub struct BackupInfo {
+    pub id: String,
+    pub created: String,
+    pub reason: String,
+}
+
+fn backup_dir(settings_path: &Path) -> PathBuf {
+    settings_path
+        .parent()
+        .unwrap_or(Path::new("."))
+        .join(BACKUP_DIR)
+}
+
+/// Copies the settings file into the backup folder and prunes the oldest backups.
+/// Returns the backup id, or `None` if there was no settings file to back up.
+pub fn create(settings_path: &Path, reason: &str) -> Result<Option<String>, String> {
+    if !settings_path.exists() {
+        return Ok(None);
+    }
+
+    let dir = backup_dir(settings_path);
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create backup folder: {}", e))?;
+
+    // Milliseconds, so backups made within the same second don't overwrite each other
+    let id = format!(
+        "{}-{}",
+        chrono::Local::now().format("%Y%m%d-%H%M%S%3f"),
+        reason
+    );
+    fs::copy(settings_path, dir.join(format!("{}.json", id)))
+        .map_err(|e| format!("Failed to back up settings: {}", e))?;
+
+    log::info!("[BACKUP] Backed up settings as {}", id);
+
+    // Ids start with the timestamp, so they sort oldest first
+    let backups = list(settings_path);
+    for old in backups
+        .iter()
+        .take(backups.len().saturating_sub(MAX_BACKUPS))
+    {
+        let _ = fs::remove_file(dir.join(format!("{}.json", old.id)));
+    }
+
+    Ok(Some(id))
+}
+
+fn list(settings_path: &Path) -> Vec<BackupInfo> {
+    let mut backups: Vec<BackupInfo> = fs::read_dir(backup_dir(settings_path))
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|entry| {
+            let path = entry.path();
+            if path.extension()? != "json" {
+                return None;
+            }
+
+            let id = path.file_stem()?.to_str()?.to_string();
+            let mut parts = id.splitn(3, '-');
+            let (date, time, reason) = (parts.next()?, parts.next()?, parts.next()?);
+            // Older versions wrote the time without milliseconds
+            if time.len() != 6 && time.len() != 9 {
+                return None;
+            }
+            let created = chrono::NaiveDateTime::parse_from_str(
+                &format!("{}{}", date, time.get(..6)?),
+                "%Y%m%d%H%M%S",
+            )
+            .ok()?;
+
+            Some(BackupInfo {
+                created: created.format("%Y-%m-%d %H:%M:%S").to_string(),
+                reason: reason.to_string(),
+                id,
+            })
+        })
+        .collect();
+
+    backups.sort_by(|a, b| a.id.cmp(&b.id));
+    backups
+}
+
+#[tauri::command]
+pub fn list_config_backups(state: State<'_, SettingsState>) -> Vec<BackupInfo> {
+    let mut backups = list(state.path());
+    backups.reverse();
+    backups
+}
+
+#[tauri::command]
+pub fn restore_config(
+    app: AppHandle,
+    state: State<'_, SettingsState>,
+    backup_id: String,
+) -> Result<settings::Settings, String> {
+    if backup_id.is_empty()
+        || !backup_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(format!("Invalid backup id {}", backup_id));
+    }
+
+    let path = backup_dir(state.path()).join(format!("{}.json", backup_id));
+    let contents =
+        fs::read_to_string(&path).map_err(|_| format!("Backup {} does not exist", backup_id))?;
+    let value =
+        serde_json::from_str(&contents).map_err(|e| format!("Backup is corrupted: {}", e))?;
+
+    let mut restored = settings::parse(value)?;
+    // Backups never contain API keys, keep the ones in the credential store
+    restored.api_settings = state.get().api_settings;
+
+    // Restoring is itself undoable
+    create(state.path(), "restore")?;
+    state.set(restored)?;
+
+    settings::apply_replaced(&app);
+
+    log::info!("[BACKUP] Restored settings from {}", backup_id);
+
+    let restored = settings::for_webview(state.get());
+    let _ = events::emit(&app, "config-changed", restored.clone());
+    Ok(restored)
+}
@@ -0,0 +1,45 @@
+use tauri::{AppHandle, State};
+use tauri_plugin_clipboard_manager::ClipboardExt;
+
+use crate::events;
+use crate::history::HistoryState;
+
+/// Copies the latest translation so it can be pasted into Discord and the like.
+#[tauri::command]
+pub fn copy_last_translation(
+    app: AppHandle,
+    history: State<'_, HistoryState>,
+) -> Result<String, String> {
+    let entry = history
+        .latest()?
+        .ok_or_else(|| "Nothing has been translated yet".to_string())?;
+
+    app.clipboard()
+        .write_text(entry.translation.clone())
+        .map_err(|e| format!("Failed to write to the clipboard: {}", e))?;
+
+    Ok(entry.translation)
+}
+
+/// Hands the clipboard text to the frontend, which translates it and sends it
+/// to the chatbox the same way as recognized speech.
+#[tauri::command]
+pub fn send_clipboard_to_chatbox(app: AppHandle) -> Result<String, String> {
+    let text = app
+        .clipboard()
+        .read_text()
+        .map_err(|e| format!("Failed to read the clipboard: {}", e))?;
+
+    let text = text.trim().to_string();
+    if text.is_empty() {
+        return Err("The clipboard does not contain any text".to_string());
+    }
+
+    log::info!(
+        "[CLIPBOARD] Sending {} characters to the chatbox",
+        text.len()
+    );
+
+    let _ = events::emit(&app, "clipboard-text", text.clone());
+    Ok(text)
+}
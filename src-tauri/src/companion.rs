@@ -0,0 +1,278 @@
+//! Live subtitles for a companion app on a standalone Quest, while this PC does the
+//! recognition and translation.
+//!
+//! Protocol `kikitan-subtitles/1`:
+//! 1. The headset broadcasts `{"type": "discover", "protocol": "kikitan-subtitles/1"}` to UDP
+//!    port 7882 and every translator on the network answers with
+//!    `{"type": "announce", "protocol": ..., "name": <computer name>, "port": <ws port>}`.
+//! 2. It connects to `ws://<address>:<port>/?code=<pairing code>`, the code being the
+//!    twelve characters shown in Kikitan, case doesn't matter. A wrong code is refused with
+//!    403, and an address that got it wrong five times is ignored for five minutes.
+//! 3. The server sends `{"type": "hello", ...}`, then a `{"type": "subtitle", ...}` for every
+//!    translation. Pings every keepalive interval (15 seconds by default) let the headset
+//!    notice a dead connection.
+
+use futures_util::{SinkExt, StreamExt};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use sysinfo::System;
+use tauri::{AppHandle, Manager, State};
+use tokio::net::{TcpListener, TcpStream, UdpSocket};
+use tokio::sync::broadcast::error::RecvError;
+use tokio_tungstenite::tungstenite::protocol::Message;
+
+use crate::history::HistoryState;
+use crate::local_server;
+use crate::network;
+use crate::settings::SettingsState;
+
+const PROTOCOL: &str = "kikitan-subtitles/1";
+const DISCOVERY_PORT: u16 = 7882;
+// No 0/O or 1/I/L, the code is read off a monitor and typed on a headset keyboard
+const PAIRING_ALPHABET: &[u8] = b"ABCDEFGHJKMNPQRSTUVWXYZ23456789";
+const PAIRING_CODE_LENGTH: usize = 12;
+const MAX_FAILED_ATTEMPTS: u32 = 5;
+const LOCKOUT: Duration = Duration::from_secs(300);
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CompanionSettings {
+    pub enabled: bool,
+    pub port: u16,
+    /// Twelve letters and digits without look-alikes. Generated on first start.
+    pub pairing_code: String,
+    pub include_original: bool,
+}
+
+impl Default for CompanionSettings {
+    fn default() -> Self {
+        CompanionSettings {
+            enabled: false,
+            port: 7881,
+            pairing_code: String::new(),
+            include_original: true,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct CompanionState {
+    server: local_server::Server,
+    /// Wrong pairing codes per address, with when the last one came in.
+    failures: Mutex<HashMap<IpAddr, (u32, Instant)>>,
+}
+
+impl CompanionState {
+    fn locked_out(&self, address: IpAddr) -> bool {
+        let mut failures = self.failures.lock().unwrap();
+        failures.retain(|_, (_, last)| last.elapsed() < LOCKOUT);
+        failures
+            .get(&address)
+            .is_some_and(|(count, _)| *count >= MAX_FAILED_ATTEMPTS)
+    }
+
+    fn failed(&self, address: IpAddr) {
+        let mut failures = self.failures.lock().unwrap();
+        let entry = failures.entry(address).or_insert((0, Instant::now()));
+        *entry = (entry.0 + 1, Instant::now());
+    }
+
+    fn paired(&self, address: IpAddr) {
+        self.failures.lock().unwrap().remove(&address);
+    }
+}
+
+fn new_pairing_code() -> String {
+    let mut rng = rand::thread_rng();
+    (0..PAIRING_CODE_LENGTH)
+        .map(|_| PAIRING_ALPHABET[rng.gen_range(0..PAIRING_ALPHABET.len())] as char)
+        .collect()
+}
+
+fn computer_name() -> String {
+    System::host_name().unwrap_or_else(|| "Kikitan".to_string())
+}
+
+/// Starts, restarts or stops the companion server to match the current settings.
+pub fn apply(app: &AppHandle) {
+    let settings = local_server::ensure_secret(
+        app,
+        "companion",
+        |settings| &mut settings.companion.pairing_code,
+        // Codes from older versions were six digits, few enough to guess
+        |code| code.len() >= PAIRING_CODE_LENGTH,
+        new_pairing_code,
+    )
+    .companion;
+    app.state::<CompanionState>().server.follow(
+        app,
+        "companion",
+        settings.enabled.then_some(settings.port),
+        &settings.pairing_code,
+        serve,
+    );
+}
+
+async fn serve(app: AppHandle, port: u16, connections: local_server::Connections) {
+    // The headset is another device on the network, so all interfaces
+    let (discovery, tcp) = match tokio::try_join!(
+        UdpSocket::bind(("0.0.0.0", DISCOVERY_PORT)),
+        TcpListener::bind(("0.0.0.0", port))
+    ) {
+        Ok(sockets) => sockets,
+        Err(e) => {
+            log::error!("[COMPANION] Failed to listen on port {}: {}", port, e);
+            return;
+        }
+    };
+
+    log::info!(
+        "[COMPANION] Announcing on udp port {} and streaming on port {}",
+        DISCOVERY_PORT,
+        port
+    );
+
+    let announce = json!({
+        "type": "announce",
+        "protocol": PROTOCOL,
+        "name": computer_name(),
+        "port": port,
+    })
+    .to_string();
+    let mut buf = [0u8; 1024];
+
+    loop {
+        tokio::select! {
+            received = discovery.recv_from(&mut buf) => match received {
+                Ok((size, from)) => {
+                    let request: Value = serde_json::from_slice(&buf[..size]).unwrap_or_default();
+                    if request["type"] == "discover" && request["protocol"] == PROTOCOL {
+                        let _ = discovery.send_to(announce.as_bytes(), from).await;
+                    }
+                }
+                Err(e) => log::debug!("[COMPANION] {}", e),
+            },
+            accepted = tcp.accept() => match accepted {
+                Ok((stream, address)) => {
+                    if app.state::<CompanionState>().locked_out(address.ip()) {
+                        log::debug!("[COMPANION] Ignoring {}, too many wrong pairing codes", address);
+                        continue;
+                    }
+
+                    let connection = stream_subtitles(app.clone(), stream, address.ip());
+                    local_server::spawn_connection(&app, "companion", &connections, connection);
+                }
+                Err(e) => log::warn!("[COMPANION] Failed to accept connection: {}", e),
+            },
+        }
+    }
+}
+
+async fn stream_subtitles(
+    app: AppHandle,
+    stream: TcpStream,
+    address: IpAddr,
+) -> Result<(), String> {
+    let code = app.state::<SettingsState>().get().companion.pairing_code;
+    let state = app.state::<CompanionState>();
+    let ws = local_server::accept_websocket(stream, |request| {
+        let given = local_server::query_param(local_server::request_path(request), "code")
+            .unwrap_or_default()
+            .to_ascii_uppercase();
+        // Connections opened together all got past the check on accept
+        let paired = !state.locked_out(address) && local_server::tokens_match(&given, &code);
+        if paired {
+            state.paired(address);
+        } else {
+            state.failed(address);
+        }
+        paired
+    })
+    .await
+    .map_err(|e| format!("Rejected companion {}: {}", address, e))?;
+    let (mut write, mut read) = ws.split();
+
+    log::info!("[COMPANION] Headset connected");
+
+    let hello = json!({ "type": "hello", "protocol": PROTOCOL, "name": computer_name() });
+    write
+        .send(Message::Text(hello.to_string()))
+        .await
+        .map_err(|e| format!("Companion {} dropped: {}", address, e))?;
+
+    let mut recorded = app.state::<HistoryState>().subscribe();
+    let mut ping = network::keepalive_interval(&app);
+
+    loop {
+        let message = tokio::select! {
+            _ = ping.tick() => Message::Ping(Vec::new()),
+            entry = recorded.recv() => {
+                let entry = match entry {
+                    Ok(entry) => entry,
+                    Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => return Ok(()),
+                };
+
+                if !local_server::still_valid(&app, |settings| &settings.companion.pairing_code, &code) {
+                    return Err("Unpaired headset, the pairing code was replaced".to_string());
+                }
+
+                let include_original = app.state::<SettingsState>().get().companion.include_original;
+                let subtitle = json!({
+                    "type": "subtitle",
+                    "id": entry.id,
+                    "timestamp": entry.timestamp,
+                    "original": if include_original { entry.original } else { String::new() },
+                    "translation": entry.translation,
+                    "source_language": entry.source_language,
+                    "target_language": entry.target_language,
+                });
+                Message::Text(subtitle.to_string())
+            }
+            message = read.next() => match message {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => {
+                    log::info!("[COMPANION] Headset disconnected");
+                    return Ok(());
+                }
+                Some(Ok(_)) => continue,
+            }
+        };
+
+        write
+            .send(message)
+            .await
+            .map_err(|e| format!("Companion {} dropped: {}", address, e))?;
+    }
+}
+
+/// Pairing code and computer name to show next to it, `None` while the companion server is disabled.
+#[tauri::command]
+pub fn get_companion_pairing(state: State<'_, SettingsState>) -> Option<Value> {
+    let companion = state.get().companion;
+    companion.enabled.then(|| {
+        json!({
+            "code": companion.pairing_code,
+            "name": computer_name(),
+            "port": companion.port,
+        })
+    })
+}
+
+/// Disconnects paired headsets, they need the new code to connect again.
+#[tauri::command]
+pub fn regenerate_companion_code(
+    app: AppHandle,
+    state: State<'_, SettingsState>,
+) -> Result<Option<Value>, String> {
+    let mut settings = state.get();
+    settings.companion.pairing_code = new_pairing_code();
+    state.set(settings)?;
+    apply(&app);
+
+    Ok(get_companion_pairing(state))
+}
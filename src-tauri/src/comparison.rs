@@ -0,0 +1,280 @@
+//! Runs a second recognizer next to the incoming speech one on the same audio, to see how a
+//! provider does before switching to it. The results of both are paired up and emitted as
+//! `asr-comparison`, and `pick` decides which one of each pair gets translated.
+//!
+//! None of the providers report a confidence worth comparing, so the automatic pick goes by
+//! agreement: when both heard about the same words the primary one is kept, otherwise the
+//! longer one, as a recognizer that misses speech tends to drop words rather than add them.
+
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tauri::AppHandle;
+use tokio::sync::mpsc;
+use tokio::time::Instant;
+
+use crate::events;
+use crate::incoming::{self, IncomingProvider};
+use crate::shutdown;
+
+// A few seconds of audio per recognizer, a stalled one drops chunks instead of holding up the other
+const AUDIO_BUFFER: usize = 50;
+
+// Share of matching words above which both heard the same thing
+const AGREEMENT: f64 = 0.6;
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ComparisonPick {
+    /// Always translate what the incoming speech provider heard, only show the other.
+    #[default]
+    Primary,
+    Secondary,
+    /// The primary result when both agree, the longer one when they don't.
+    Agreement,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ComparisonSettings {
+    pub enabled: bool,
+    /// The recognizer to compare against the incoming speech provider.
+    pub provider: IncomingProvider,
+    pub pick: ComparisonPick,
+    /// How long to wait for the other recognizer before going with one result.
+    pub pairing_ms: u64,
+}
+
+impl Default for ComparisonSettings {
+    fn default() -> Self {
+        ComparisonSettings {
+            enabled: false,
+            provider: IncomingProvider::Grpc,
+            pick: ComparisonPick::Primary,
+            pairing_ms: 3000,
+        }
+    }
+}
+
+#[derive(Clone, Serialize)]
+struct Hypothesis {
+    provider: IncomingProvider,
+    text: Option<String>,
+}
+
+#[derive(Clone, Serialize)]
+struct ComparisonEvent {
+    primary: Hypothesis,
+    secondary: Hypothesis,
+    /// Share of matching words, `None` when only one recognizer answered.
+    agreement: Option<f64>,
+    /// `primary` or `secondary`.
+    picked: &'static str,
+}
+
+#[derive(Default)]
+struct Pair {
+    primary: Option<String>,
+    secondary: Option<String>,
+    since: Option<Instant>,
+}
+
+// Recognizers split speech differently, pieces arriving before the other side answers are joined
+fn append(slot: &mut Option<String>, text: String) {
+    match slot {
+        Some(existing) => {
+            existing.push(' ');
+            existing.push_str(&text);
+        }
+        None => *slot = Some(text),
+    }
+}
+
+fn words(text: &str) -> Vec<String> {
+    text.split_whitespace()
+        .map(|word| {
+            word.chars()
+                .filter(|c| c.is_alphanumeric())
+                .flat_map(char::to_lowercase)
+                .collect::<String>()
+        })
+        .filter(|word| !word.is_empty())
+        .collect()
+}
+
+/// 1 minus the word-level edit distance relative to the longer hypothesis. Languages written
+/// without spaces are compared character by character.
+pub(crate) fn agreement(a: &str, b: &str) -> f64 {
+    let (a, b): (Vec<String>, Vec<String>) = if a.contains(' ') || b.contains(' ') {
+        (words(a), words(b))
+    } else {
+        (
+            a.chars().map(String::from).collect(),
+            b.chars().map(String::from).collect(),
+        )
+    };
+    let longest = a.len().max(b.len());
+    if longest == 0 {
+        return 1.0;
+    }
+
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, wa) in a.iter().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, wb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(wa != wb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+
+    1.0 - previous[b.len()] as f64 / longest as f64
+}
+
+fn flush(
+    app: &AppHandle,
+    primary: &IncomingProvider,
+    settings: &ComparisonSettings,
+    pair: Pair,
+) -> Option<String> {
+    let agreement = match (&pair.primary, &pair.secondary) {
+        (Some(a), Some(b)) => Some(agreement(a, b)),
+        _ => None,
+    };
+
+    let secondary_wins = match (&pair.primary, &pair.secondary) {
+        (None, Some(_)) => true,
+        (Some(_), None) | (None, None) => false,
+        (Some(a), Some(b)) => match settings.pick {
+            ComparisonPick::Primary => false,
+            ComparisonPick::Secondary => true,
+            ComparisonPick::Agreement => {
+                agreement.unwrap_or_default() < AGREEMENT && words(b).len() > words(a).len()
+            }
+        },
+    };
+
+    let event = ComparisonEvent {
+        primary: Hypothesis {
+            provider: primary.clone(),
+            text: pair.primary.clone(),
+        },
+        secondary: Hypothesis {
+            provider: settings.provider.clone(),
+            text: pair.secondary.clone(),
+        },
+        agreement,
+        picked: if secondary_wins {
+            "secondary"
+        } else {
+            "primary"
+        },
+    };
+    log::debug!(
+        "[COMPARISON] Agreement {:?}, picked the {} result",
+        agreement,
+        event.picked
+    );
+    let _ = events::emit(app, "asr-comparison", event);
+
+    if secondary_wins {
+        pair.secondary
+    } else {
+        pair.primary
+    }
+}
+
+async fn run_recognizer(
+    app: AppHandle,
+    provider: IncomingProvider,
+    language: String,
+    audio: mpsc::Receiver<Vec<u8>>,
+    transcripts: mpsc::Sender<String>,
+) {
+    if let Err(e) = incoming::recognize(&app, &provider, &language, audio, transcripts).await {
+        log::warn!("[COMPARISON] {:?} stopped: {}", provider, e);
+    }
+}
+
+/// Runs `primary` and the compared provider side by side on `audio`, sending the picked
+/// result of each pair on to `transcripts`.
+pub async fn recognize(
+    app: &AppHandle,
+    primary: &IncomingProvider,
+    settings: &ComparisonSettings,
+    language: &str,
+    mut audio: mpsc::Receiver<Vec<u8>>,
+    transcripts: mpsc::Sender<String>,
+) -> Result<(), String> {
+    let (primary_audio, primary_rx) = mpsc::channel(AUDIO_BUFFER);
+    let (secondary_audio, secondary_rx) = mpsc::channel(AUDIO_BUFFER);
+    let (primary_tx, mut primary_results) = mpsc::channel(16);
+    let (secondary_tx, mut secondary_results) = mpsc::channel(16);
+
+    for (provider, audio, results) in [
+        (primary.clone(), primary_rx, primary_tx),
+        (settings.provider.clone(), secondary_rx, secondary_tx),
+    ] {
+        shutdown::spawn(
+            app,
+            "comparison",
+            run_recognizer(app.clone(), provider, language.to_string(), audio, results),
+        );
+    }
+
+    shutdown::spawn(app, "comparison", async move {
+        while let Some(chunk) = audio.recv().await {
+            let primary = primary_audio.try_send(chunk.clone());
+            let secondary = secondary_audio.try_send(chunk);
+            if primary.is_err_and(|e| matches!(e, mpsc::error::TrySendError::Closed(_)))
+                && secondary.is_err_and(|e| matches!(e, mpsc::error::TrySendError::Closed(_)))
+            {
+                break;
+            }
+        }
+    });
+
+    let window = Duration::from_millis(settings.pairing_ms);
+    let mut pair = Pair::default();
+    let (mut primary_open, mut secondary_open) = (true, true);
+
+    loop {
+        let deadline = pair.since.map(|since| since + window);
+
+        tokio::select! {
+            text = primary_results.recv(), if primary_open => match text {
+                Some(text) => {
+                    append(&mut pair.primary, text);
+                    pair.since.get_or_insert_with(Instant::now);
+                }
+                None => primary_open = false,
+            },
+            text = secondary_results.recv(), if secondary_open => match text {
+                Some(text) => {
+                    append(&mut pair.secondary, text);
+                    pair.since.get_or_insert_with(Instant::now);
+                }
+                None => secondary_open = false,
+            },
+            _ = tokio::time::sleep_until(deadline.unwrap_or_else(Instant::now)), if deadline.is_some() => {
+                if let Some(text) = flush(app, primary, settings, std::mem::take(&mut pair)) {
+                    let _ = transcripts.send(text).await;
+                }
+            },
+            else => break,
+        }
+
+        if pair.primary.is_some() && pair.secondary.is_some() {
+            if let Some(text) = flush(app, primary, settings, std::mem::take(&mut pair)) {
+                let _ = transcripts.send(text).await;
+            }
+        }
+    }
+
+    if pair.since.is_some() {
+        if let Some(text) = flush(app, primary, settings, pair) {
+            let _ = transcripts.send(text).await;
+        }
+    }
+
+    Err("Both recognizers stopped".to_string())
+}
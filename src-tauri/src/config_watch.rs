@@ -0,0 +1,57 @@
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+
+use crate::events;
+use crate::quota;
+use crate::settings::{self, SettingsState};
+
+/// Keeps the file watcher alive for as long as the app runs.
+pub struct ConfigWatcher(#[allow(dead_code)] Mutex<RecommendedWatcher>);
+
+pub fn start(app: &AppHandle) -> Result<ConfigWatcher, String> {
+    let path = app.state::<SettingsState>().path().clone();
+    let dir = path
+        .parent()
+        .ok_or_else(|| "Settings file has no parent directory".to_string())?
+        .to_path_buf();
+    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+
+    let app = app.clone();
+    let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+        let event = match res {
+            Ok(event) => event,
+            Err(e) => {
+                log::warn!("[CONFIG] Watch error: {}", e);
+                return;
+            }
+        };
+
+        // Writes land through a temporary file and a rename, so watch the directory
+        if !matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_))
+            || !event
+                .paths
+                .iter()
+                .any(|p| p.file_name() == path.file_name())
+        {
+            return;
+        }
+
+        match app.state::<SettingsState>().reload() {
+            Ok(Some(settings)) => {
+                log::info!("[CONFIG] Reloaded settings after an external edit");
+                quota::forget(&app);
+                let _ = events::emit(&app, "config-changed", settings::for_webview(settings));
+            }
+            Ok(None) => {}
+            Err(e) => log::warn!("[CONFIG] Ignoring settings file change: {}", e),
+        }
+    })
+    .map_err(|e| format!("Failed to create config watcher: {}", e))?;
+
+    watcher
+        .watch(&dir, RecursiveMode::NonRecursive)
+        .map_err(|e| format!("Failed to watch {}: {}", dir.display(), e))?;
+
+    Ok(ConfigWatcher(Mutex::new(watcher)))
+}
@@ -0,0 +1,292 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tauri::{AppHandle, Manager, State};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+use crate::events;
+use crate::history::HistoryState;
+use crate::local_server;
+use crate::osc::{self, ChatboxState};
+use crate::settings::SettingsState;
+use crate::tray::TrayState;
+
+// Requests are a few lines of JSON, anything bigger isn't meant for us
+const MAX_REQUEST: usize = 64 * 1024;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ControlApiSettings {
+    pub enabled: bool,
+    pub port: u16,
+    /// Sent as `Authorization: Bearer <token>`, generated on first start.
+    pub token: String,
+}
+
+impl Default for ControlApiSettings {
+    fn default() -> Self {
+        ControlApiSettings {
+            enabled: false,
+            port: 7879,
+            token: String::new(),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct ControlApiState {
+    server: local_server::Server,
+}
+
+/// Emitted as `remote-control` for actions the webview carries out.
+#[derive(Clone, Serialize)]
+struct RemoteControl {
+    action: String,
+    text: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct TextBody {
+    text: String,
+}
+
+struct HttpRequest {
+    method: String,
+    path: String,
+    token: Option<String>,
+    body: Vec<u8>,
+}
+
+/// Starts, restarts or stops the control API to match the current settings.
+pub fn apply(app: &AppHandle) {
+    let settings = local_server::ensure_token(app, "control_api", |settings| {
+        &mut settings.control_api.token
+    })
+    .control_api;
+    app.state::<ControlApiState>().server.follow(
+        app,
+        "control_api",
+        settings.enabled.then_some(settings.port),
+        &settings.token,
+        serve,
+    );
+}
+
+async fn serve(app: AppHandle, port: u16, connections: local_server::Connections) {
+    // Scripts and Stream Deck plugins run on the same machine
+    local_server::accept(app, "control_api", "127.0.0.1", port, connections, handle).await
+}
+
+async fn handle(app: AppHandle, mut stream: TcpStream) -> Result<(), String> {
+    let (status, body) = match read_request(&mut stream).await {
+        Ok(request) => {
+            let token = app.state::<SettingsState>().get().control_api.token;
+            let valid = request
+                .token
+                .as_deref()
+                .is_some_and(|given| local_server::tokens_match(given, &token));
+            if valid {
+                route(&app, request)
+            } else {
+                (403, json!({ "error": "Invalid token" }))
+            }
+        }
+        Err(e) => (400, json!({ "error": e })),
+    };
+
+    let body = body.to_string();
+    let response = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        reason(status),
+        body.len(),
+        body
+    );
+
+    stream
+        .write_all(response.as_bytes())
+        .await
+        .map_err(|e| format!("Failed to write response: {}", e))
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        403 => "Forbidden",
+        404 => "Not Found",
+        _ => "Internal Server Error",
+    }
+}
+
+async fn read_request(stream: &mut TcpStream) -> Result<HttpRequest, String> {
+    let mut buffer = Vec::new();
+    let mut chunk = [0u8; 4096];
+
+    let header_end = loop {
+        if let Some(end) = buffer.windows(4).position(|w| w == b"\r\n\r\n") {
+            break end + 4;
+        }
+        if buffer.len() > MAX_REQUEST {
+            return Err("Request too large".to_string());
+        }
+
+        let read = stream
+            .read(&mut chunk)
+            .await
+            .map_err(|e| format!("Failed to read request: {}", e))?;
+        if read == 0 {
+            return Err("Connection closed mid-request".to_string());
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+    };
+
+    let head = String::from_utf8_lossy(&buffer[..header_end]).to_string();
+    let mut lines = head.lines();
+    let mut request_line = lines.next().unwrap_or_default().split_whitespace();
+    let method = request_line.next().unwrap_or_default().to_string();
+    let path = request_line.next().unwrap_or("/").to_string();
+
+    let mut content_length = 0;
+    let mut token = None;
+    for line in lines {
+        let (name, value) = match line.split_once(':') {
+            Some(header) => header,
+            None => continue,
+        };
+        match name.trim().to_ascii_lowercase().as_str() {
+            "content-length" => content_length = value.trim().parse().unwrap_or(0),
+            "authorization" => {
+                token = value
+                    .trim()
+                    .strip_prefix("Bearer ")
+                    .map(|token| token.trim().to_string())
+            }
+            _ => {}
+        }
+    }
+
+    if content_length > MAX_REQUEST {
+        return Err("Request too large".to_string());
+    }
+
+    let mut body = buffer[header_end..].to_vec();
+    while body.len() < content_length {
+        let read = stream
+            .read(&mut chunk)
+            .await
+            .map_err(|e| format!("Failed to read request body: {}", e))?;
+        if read == 0 {
+            break;
+        }
+        body.extend_from_slice(&chunk[..read]);
+    }
+    body.truncate(content_length);
+
+    Ok(HttpRequest {
+        method,
+        path,
+        token,
+        body,
+    })
+}
+
+fn text_body(request: &HttpRequest) -> Result<String, (u16, Value)> {
+    serde_json::from_slice::<TextBody>(&request.body)
+        .map(|body| body.text)
+        .map_err(|e| {
+            (
+                400,
+                json!({ "error": format!("Expected {{\"text\": ...}}: {}", e) }),
+            )
+        })
+}
+
+fn remote_control(app: &AppHandle, action: &str, text: Option<String>) -> (u16, Value) {
+    let _ = events::emit(
+        app,
+        "remote-control",
+        RemoteControl {
+            action: action.to_string(),
+            text,
+        },
+    );
+    (200, json!({ "ok": true }))
+}
+
+fn route(app: &AppHandle, request: HttpRequest) -> (u16, Value) {
+    match (request.method.as_str(), request.path.as_str()) {
+        ("GET", "/status") => (200, status(app)),
+
+        ("POST", "/chatbox") => match text_body(&request) {
+            Ok(text) => {
+                let vrchat = app.state::<SettingsState>().get().vrchat_settings;
+                match osc::send_message(
+                    app.clone(),
+                    app.state::<ChatboxState>(),
+                    text,
+                    vrchat.osc_address,
+                    vrchat.osc_port.to_string(),
+                ) {
+                    Ok(()) => (200, json!({ "ok": true })),
+                    Err(e) => (500, json!({ "error": e })),
+                }
+            }
+            Err(error) => error,
+        },
+
+        ("POST", "/translate") => match text_body(&request) {
+            Ok(text) => remote_control(app, "translate", Some(text)),
+            Err(error) => error,
+        },
+
+        ("POST", "/pause") => remote_control(app, "pause", None),
+        ("POST", "/resume") => remote_control(app, "resume", None),
+
+        _ => (404, json!({ "error": "Unknown endpoint" })),
+    }
+}
+
+fn status(app: &AppHandle) -> Value {
+    let tray = app.try_state::<TrayState>();
+    let latest = app
+        .state::<HistoryState>()
+        .latest()
+        .ok()
+        .flatten()
+        .map(|entry| json!({ "original": entry.original, "translation": entry.translation }));
+
+    json!({
+        "paused": tray.as_ref().map_or(false, |tray| tray.paused()),
+        "capture_muted": tray.as_ref().map_or(false, |tray| tray.capture_muted()),
+        "connection": tray.as_ref().map(|tray| tray.connection()),
+        "osc_listening": osc::listener_running(app),
+        "latest": latest,
+    })
+}
+
+/// Base URL and token for external tools, `None` while the API is disabled.
+#[tauri::command]
+pub fn get_control_api(state: State<'_, SettingsState>) -> Option<Value> {
+    let api = state.get().control_api;
+    api.enabled.then(|| {
+        json!({
+            "url": format!("http://127.0.0.1:{}", api.port),
+            "token": api.token,
+        })
+    })
+}
+
+/// Invalidates the token every connected tool is using.
+#[tauri::command]
+pub fn regenerate_control_api_token(
+    app: AppHandle,
+    state: State<'_, SettingsState>,
+) -> Result<Option<Value>, String> {
+    let mut settings = state.get();
+    settings.control_api.token = local_server::new_token();
+    state.set(settings)?;
+    apply(&app);
+
+    Ok(get_control_api(state))
+}
@@ -0,0 +1,251 @@
+use serde::Serialize;
+use std::backtrace::Backtrace;
+use std::fs;
+use std::panic;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::thread;
+use tauri::{AppHandle, State, Url};
+
+use crate::events;
+use crate::paths;
+use crate::redact;
+
+const CRASH_DIR: &str = "crashes";
+const ISSUE_URL: &str = "https://github.com/YusufOzmen01/kikitan-translator/issues/new";
+
+// Keeps the prefilled issue URL within what browsers accept
+const MAX_ISSUE_BODY: usize = 6000;
+
+/// Where reports were written this run, for the commands below.
+pub struct CrashState {
+    dir: PathBuf,
+}
+
+#[derive(Serialize)]
+pub struct CrashReport {
+    pub id: String,
+    pub created: String,
+    pub summary: String,
+    /// Path of the minidump that belongs to the report, if one was written.
+    pub minidump: Option<String>,
+}
+
+/// Emitted as `backend-fatal` when a backend thread or task panics.
+#[derive(Clone, Serialize)]
+struct BackendFatal {
+    message: String,
+    thread: String,
+    backtrace: String,
+}
+
+#[derive(Serialize)]
+pub struct CrashSubmission {
+    /// New GitHub issue prefilled with the sanitized report, opened by the frontend.
+    pub issue_url: String,
+    /// Minidump the user is asked to attach to the issue.
+    pub attachment: Option<String>,
+}
+
+fn report_id() -> String {
+    chrono::Local::now().format("%Y%m%d-%H%M%S").to_string()
+}
+
+/// Installs the panic hook, and on Windows a handler for native crashes that writes a minidump.
+/// Nothing leaves the machine until the user submits a report.
+pub fn install(app: &AppHandle) -> CrashState {
+    let dir = paths::data_dir(app).join(CRASH_DIR);
+    let version = app.package_info().version.to_string();
+
+    // The hook can't emit itself, the panicking thread may hold a lock emitting needs
+    let (fatal_tx, fatal_rx) = mpsc::sync_channel::<BackendFatal>(8);
+    let emit_app = app.clone();
+    thread::spawn(move || {
+        for fatal in fatal_rx {
+            let _ = events::emit(&emit_app, "backend-fatal", fatal);
+        }
+    });
+
+    let hook_dir = dir.clone();
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        let thread = std::thread::current();
+        let thread = thread.name().unwrap_or("unnamed");
+        let backtrace = Backtrace::force_capture().to_string();
+        let report = format!(
+            "Kikitan Translator {}\nOS: {} {}\nThread: {}\n\n{}\n\n{}",
+            version,
+            std::env::consts::OS,
+            std::env::consts::ARCH,
+            thread,
+            info,
+            backtrace
+        );
+
+        let path = hook_dir.join(format!("{}.txt", report_id()));
+        if fs::create_dir_all(&hook_dir)
+            .and_then(|_| fs::write(&path, sanitize_in_hook(&report)))
+            .is_ok()
+        {
+            log::error!("[CRASH] {}, report written to {}", info, path.display());
+        }
+
+        // Panics in background threads and tasks don't take the app down, let the user know
+        let _ = fatal_tx.try_send(BackendFatal {
+            message: sanitize_in_hook(&info.to_string()),
+            thread: thread.to_string(),
+            backtrace: sanitize_in_hook(&backtrace),
+        });
+
+        default_hook(info);
+    }));
+
+    #[cfg(target_os = "windows")]
+    attach_minidump_handler(dir.clone());
+
+    CrashState { dir }
+}
+
+#[cfg(target_os = "windows")]
+fn attach_minidump_handler(dir: PathBuf) {
+    let handler = crash_handler::CrashHandler::attach(unsafe {
+        crash_handler::make_crash_event(move |context: &crash_handler::CrashContext| {
+            let id = report_id();
+            let _ = fs::create_dir_all(&dir);
+
+            let written = fs::File::create(dir.join(format!("{}.dmp", id)))
+                .ok()
+                .and_then(|mut file| {
+                    minidump_writer::minidump_writer::MinidumpWriter::dump_crash_context(
+                        context, None, &mut file,
+                    )
+                    .ok()
+                })
+                .is_some();
+
+            if written {
+                let _ = fs::write(
+                    dir.join(format!("{}.txt", id)),
+                    format!(
+                        "Kikitan Translator native crash\nOS: {} {}\nException code: {:#x}\n",
+                        std::env::consts::OS,
+                        std::env::consts::ARCH,
+                        context.exception_code
+                    ),
+                );
+            }
+
+            crash_handler::CrashEventResult::Handled(written)
+        })
+    });
+
+    match handler {
+        // Stays attached for the rest of the process
+        Ok(handler) => std::mem::forget(handler),
+        Err(e) => log::warn!("[CRASH] Failed to install crash handler: {}", e),
+    }
+}
+
+// Reports are meant to be shared, strip keys and the user's name from paths
+fn sanitize(report: &str) -> String {
+    hide_home(redact::redact(report))
+}
+
+fn sanitize_in_hook(report: &str) -> String {
+    hide_home(redact::redact_without_blocking(report))
+}
+
+fn hide_home(mut report: String) -> String {
+    if let Some(home) = dirs::home_dir() {
+        let home = home.to_string_lossy().to_string();
+        if !home.is_empty() {
+            report = report.replace(&home, "~");
+        }
+    }
+
+    report
+}
+
+fn list(dir: &Path) -> Vec<CrashReport> {
+    let mut reports: Vec<CrashReport> = fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|entry| {
+            let path = entry.path();
+            if path.extension()? != "txt" {
+                return None;
+            }
+
+            let id = path.file_stem()?.to_str()?.to_string();
+            let created = chrono::NaiveDateTime::parse_from_str(&id, "%Y%m%d-%H%M%S").ok()?;
+            let contents = fs::read_to_string(&path).ok()?;
+            let minidump = dir.join(format!("{}.dmp", id));
+
+            Some(CrashReport {
+                created: created.format("%Y-%m-%d %H:%M:%S").to_string(),
+                // The line after the header block is the panic message
+                summary: contents
+                    .split("\n\n")
+                    .nth(1)
+                    .unwrap_or(&contents)
+                    .lines()
+                    .next()
+                    .unwrap_or_default()
+                    .to_string(),
+                minidump: minidump
+                    .exists()
+                    .then(|| minidump.to_string_lossy().to_string()),
+                id,
+            })
+        })
+        .collect();
+
+    reports.sort_by(|a, b| b.id.cmp(&a.id));
+    reports
+}
+
+#[tauri::command]
+pub fn list_crash_reports(state: State<'_, CrashState>) -> Vec<CrashReport> {
+    list(&state.dir)
+}
+
+/// Prepares a crash report for submission. Only ever called from the "Submit report" button.
+#[tauri::command]
+pub fn submit_crash_report(
+    state: State<'_, CrashState>,
+    report_id: String,
+) -> Result<CrashSubmission, String> {
+    let report = list(&state.dir)
+        .into_iter()
+        .find(|report| report.id == report_id)
+        .ok_or_else(|| format!("Crash report {} does not exist", report_id))?;
+
+    let contents = fs::read_to_string(state.dir.join(format!("{}.txt", report.id)))
+        .map_err(|e| format!("Failed to read crash report: {}", e))?;
+    let mut body = format!("```\n{}\n```", sanitize(&contents));
+    if body.len() > MAX_ISSUE_BODY {
+        let end = (0..=MAX_ISSUE_BODY)
+            .rev()
+            .find(|&i| body.is_char_boundary(i))
+            .unwrap_or(0);
+        body.truncate(end);
+        body.push_str("\n... (truncated)\n```");
+    }
+
+    let issue_url = Url::parse_with_params(
+        ISSUE_URL,
+        &[
+            ("title", format!("Crash: {}", report.summary)),
+            ("body", body),
+        ],
+    )
+    .map_err(|e| format!("Failed to build issue URL: {}", e))?;
+
+    log::info!("[CRASH] Submitting crash report {}", report.id);
+
+    Ok(CrashSubmission {
+        issue_url: issue_url.to_string(),
+        attachment: report.minidump,
+    })
+}
@@ -0,0 +1,18 @@
+use rusqlite::Connection;
+use std::fs;
+use std::path::Path;
+
+/// Opens the SQLite database at `path`, creating its directory, and runs `prepare` on it to
+/// set up the schema.
+pub fn open(
+    path: &Path,
+    prepare: fn(&Connection) -> rusqlite::Result<()>,
+) -> rusqlite::Result<Connection> {
+    if let Some(dir) = path.parent() {
+        let _ = fs::create_dir_all(dir);
+    }
+
+    let conn = Connection::open(path)?;
+    prepare(&conn)?;
+    Ok(conn)
+}
@@ -0,0 +1,430 @@
+//! Checks for the first-run setup wizard, so new users can confirm the microphone, OSC and
+//! their recognition provider work before joining VRChat.
+
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{FromSample, Sample, SampleFormat, SizedSample};
+use rosc::{OscPacket, OscType};
+use serde::Serialize;
+use std::net::{Ipv4Addr, UdpSocket};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+use tokio::sync::mpsc;
+
+use crate::incoming::{self, IncomingProvider};
+use crate::input_device;
+use crate::osc;
+use crate::secrets;
+use crate::settings::SettingsState;
+use crate::vrchat;
+
+const RECORD_SECONDS: f32 = 3.0;
+
+// Quieter than this over the whole recording means the wrong or a muted microphone
+const SILENCE_DB: f32 = -50.0;
+
+// Harmless to VRChat, and tells the loopback packet apart from stray ones
+const TEST_ADDRESS: &str = "/chatbox/typing";
+
+// A provider gets this much silence before the stream is closed, enough for a rejected
+// key to show up as an error
+const PROBE_CHUNKS: usize = 15;
+const PROBE_TIMEOUT: Duration = Duration::from_secs(15);
+
+#[derive(Clone, Debug, Serialize)]
+pub struct MicrophoneReport {
+    pub device: String,
+    pub sample_rate: u32,
+    pub peak_db: f32,
+    pub rms_db: f32,
+    pub silent: bool,
+    /// The recording was played back on the default output device.
+    pub played_back: bool,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct OscReport {
+    pub target: String,
+    /// A test message made it through our encoder and a local socket.
+    pub loopback: bool,
+    pub vrchat_running: bool,
+    pub listener_running: bool,
+    /// Seconds since VRChat last sent us anything, `None` if it never did.
+    pub vrchat_heard_secs_ago: Option<u64>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ProviderReport {
+    pub provider: IncomingProvider,
+    pub ok: bool,
+    pub error: Option<String>,
+    pub elapsed_ms: u64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Check<T> {
+    pub ok: bool,
+    pub error: Option<String>,
+    pub report: Option<T>,
+}
+
+impl<T> From<Result<T, String>> for Check<T> {
+    fn from(result: Result<T, String>) -> Self {
+        match result {
+            Ok(report) => Check {
+                ok: true,
+                error: None,
+                report: Some(report),
+            },
+            Err(e) => Check {
+                ok: false,
+                error: Some(e),
+                report: None,
+            },
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct DiagnosticsReport {
+    pub microphone: Check<MicrophoneReport>,
+    pub osc: Check<OscReport>,
+    pub providers: Vec<ProviderReport>,
+    pub passed: bool,
+}
+
+fn decibels(level: f32) -> f32 {
+    20.0 * level.max(1e-6).log10()
+}
+
+fn input_stream<T>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    recorded: Arc<Mutex<Vec<f32>>>,
+) -> Result<cpal::Stream, String>
+where
+    T: SizedSample,
+    f32: FromSample<T>,
+{
+    let channels = config.channels as usize;
+    device
+        .build_input_stream(
+            config,
+            move |data: &[T], _| {
+                // Mixed down to mono, the level is all we look at
+                recorded
+                    .lock()
+                    .unwrap()
+                    .extend(data.chunks(channels).map(|frame| {
+                        frame
+                            .iter()
+                            .map(|sample| sample.to_sample::<f32>())
+                            .sum::<f32>()
+                            / channels as f32
+                    }));
+            },
+            |e| log::warn!("[DIAGNOSTICS] Recording error: {}", e),
+            None,
+        )
+        .map_err(|e| format!("Failed to open the microphone: {}", e))
+}
+
+fn output_stream<T>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    recorded: Arc<Vec<f32>>,
+    recorded_rate: u32,
+    done: Arc<AtomicBool>,
+) -> Result<cpal::Stream, String>
+where
+    T: SizedSample + FromSample<f32>,
+{
+    let channels = config.channels as usize;
+    let step = recorded_rate as f64 / config.sample_rate.0 as f64;
+    let mut position = 0.0;
+
+    device
+        .build_output_stream(
+            config,
+            move |data: &mut [T], _| {
+                // Nearest sample resampling is plenty for hearing yourself
+                for frame in data.chunks_mut(channels) {
+                    let value = recorded.get(position as usize).copied().unwrap_or_else(|| {
+                        done.store(true, Ordering::Relaxed);
+                        0.0
+                    });
+                    position += step;
+                    frame.fill(T::from_sample(value));
+                }
+            },
+            |e| log::warn!("[DIAGNOSTICS] Playback error: {}", e),
+            None,
+        )
+        .map_err(|e| format!("Failed to open the output device: {}", e))
+}
+
+// The same microphone the recognizers pick, by the preferred patterns or the default
+fn microphone(patterns: &[String]) -> Result<cpal::Device, String> {
+    let host = cpal::default_host();
+    let devices: Vec<cpal::Device> = host
+        .input_devices()
+        .map(|devices| devices.collect())
+        .unwrap_or_default();
+    let names: Vec<String> = devices
+        .iter()
+        .map(|device| device.name().unwrap_or_default())
+        .collect();
+
+    match input_device::pick(patterns, &names) {
+        Some(index) => Ok(devices
+            .into_iter()
+            .nth(index)
+            .expect("index into the same list")),
+        None => host
+            .default_input_device()
+            .ok_or_else(|| "No microphone found".to_string()),
+    }
+}
+
+fn record_and_play(patterns: Vec<String>) -> Result<MicrophoneReport, String> {
+    let device = microphone(&patterns)?;
+    let name = device.name().unwrap_or_default();
+    let config = device
+        .default_input_config()
+        .map_err(|e| format!("Failed to read the microphone format: {}", e))?;
+    let sample_rate = config.sample_rate().0;
+
+    let recorded = Arc::new(Mutex::new(Vec::new()));
+    let stream = match config.sample_format() {
+        SampleFormat::F32 => input_stream::<f32>(&device, &config.config(), recorded.clone()),
+        SampleFormat::I16 => input_stream::<i16>(&device, &config.config(), recorded.clone()),
+        SampleFormat::U16 => input_stream::<u16>(&device, &config.config(), recorded.clone()),
+        other => Err(format!("Unsupported sample format {:?}", other)),
+    }?;
+    stream
+        .play()
+        .map_err(|e| format!("Failed to start recording: {}", e))?;
+    thread::sleep(Duration::from_secs_f32(RECORD_SECONDS));
+    drop(stream);
+
+    let recorded = std::mem::take(&mut *recorded.lock().unwrap());
+    if recorded.is_empty() {
+        return Err(format!("{} delivered no audio", name));
+    }
+
+    let peak = recorded
+        .iter()
+        .fold(0.0f32, |peak, sample| peak.max(sample.abs()));
+    let rms =
+        (recorded.iter().map(|sample| sample * sample).sum::<f32>() / recorded.len() as f32).sqrt();
+
+    let played_back = match play(Arc::new(recorded), sample_rate) {
+        Ok(()) => true,
+        Err(e) => {
+            log::warn!("[DIAGNOSTICS] {}", e);
+            false
+        }
+    };
+
+    Ok(MicrophoneReport {
+        device: name,
+        sample_rate,
+        peak_db: decibels(peak),
+        rms_db: decibels(rms),
+        silent: decibels(peak) < SILENCE_DB,
+        played_back,
+    })
+}
+
+fn play(recorded: Arc<Vec<f32>>, recorded_rate: u32) -> Result<(), String> {
+    let device = cpal::default_host()
+        .default_output_device()
+        .ok_or("No output device to play back on")?;
+    let config = device
+        .default_output_config()
+        .map_err(|e| format!("Failed to read the output format: {}", e))?;
+    let done = Arc::new(AtomicBool::new(false));
+
+    let stream = match config.sample_format() {
+        SampleFormat::F32 => output_stream::<f32>(
+            &device,
+            &config.config(),
+            recorded,
+            recorded_rate,
+            done.clone(),
+        ),
+        SampleFormat::I16 => output_stream::<i16>(
+            &device,
+            &config.config(),
+            recorded,
+            recorded_rate,
+            done.clone(),
+        ),
+        SampleFormat::U16 => output_stream::<u16>(
+            &device,
+            &config.config(),
+            recorded,
+            recorded_rate,
+            done.clone(),
+        ),
+        other => Err(format!("Unsupported sample format {:?}", other)),
+    }?;
+    stream
+        .play()
+        .map_err(|e| format!("Failed to start playback: {}", e))?;
+
+    let deadline = Instant::now() + Duration::from_secs_f32(RECORD_SECONDS + 1.0);
+    while !done.load(Ordering::Relaxed) && Instant::now() < deadline {
+        thread::sleep(Duration::from_millis(50));
+    }
+    Ok(())
+}
+
+/// Records a few seconds from the microphone the recognizers would use, reports the levels
+/// and plays the recording back.
+#[tauri::command]
+pub async fn diagnose_microphone(app: AppHandle) -> Result<MicrophoneReport, String> {
+    let patterns = app
+        .state::<SettingsState>()
+        .get()
+        .input_device
+        .preferred_patterns;
+
+    // cpal streams aren't Send, everything happens on a blocking thread
+    tauri::async_runtime::spawn_blocking(move || record_and_play(patterns))
+        .await
+        .map_err(|e| format!("Microphone test failed: {}", e))?
+}
+
+fn loopback() -> Result<(), String> {
+    let socket = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0))
+        .and_then(|socket| {
+            socket.set_read_timeout(Some(Duration::from_secs(1)))?;
+            Ok(socket)
+        })
+        .map_err(|e| format!("Failed to open a loopback socket: {}", e))?;
+    let address = socket
+        .local_addr()
+        .map_err(|e| format!("Failed to open a loopback socket: {}", e))?;
+
+    osc::send(address, TEST_ADDRESS, vec![OscType::Bool(false)])?;
+
+    let mut buf = [0u8; rosc::decoder::MTU];
+    let size = socket
+        .recv(&mut buf)
+        .map_err(|e| format!("The loopback message never arrived: {}", e))?;
+    match rosc::decoder::decode_udp(&buf[..size]) {
+        Ok((_, OscPacket::Message(message))) if message.addr == TEST_ADDRESS => Ok(()),
+        _ => Err("The loopback message arrived garbled".to_string()),
+    }
+}
+
+/// Sends a test message through a local loopback and to VRChat, and reports whether VRChat
+/// runs and talks back to the listener.
+#[tauri::command]
+pub fn diagnose_osc(app: AppHandle) -> Result<OscReport, String> {
+    let settings = app.state::<SettingsState>().get().vrchat_settings;
+    let target = osc::resolve(&settings.osc_address, &settings.osc_port.to_string())?;
+
+    loopback()?;
+    osc::send(target, TEST_ADDRESS, vec![OscType::Bool(false)])?;
+
+    Ok(OscReport {
+        target: target.to_string(),
+        loopback: true,
+        vrchat_running: vrchat::is_vrchat_running(),
+        listener_running: osc::listener_running(&app),
+        vrchat_heard_secs_ago: osc::last_heard(&app).map(|ago| ago.as_secs()),
+    })
+}
+
+/// Opens a stream with `provider`, feeds it a moment of silence and closes it again. Missing
+/// or rejected credentials come back as the error.
+#[tauri::command]
+pub async fn diagnose_provider(app: AppHandle, provider: IncomingProvider) -> ProviderReport {
+    let started = Instant::now();
+    let language = app.state::<SettingsState>().get().incoming.source_language;
+    let (audio_tx, audio) = mpsc::channel(PROBE_CHUNKS);
+    let (transcripts, _discarded) = mpsc::channel(16);
+
+    tauri::async_runtime::spawn(async move {
+        for _ in 0..PROBE_CHUNKS {
+            if audio_tx.send(vec![0u8; 3200]).await.is_err() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+    });
+
+    let result = tokio::time::timeout(
+        PROBE_TIMEOUT,
+        incoming::recognize(&app, &provider, &language, audio, transcripts),
+    )
+    .await
+    .unwrap_or_else(|_| Err("Timed out waiting for the provider".to_string()));
+
+    ProviderReport {
+        provider,
+        ok: result.is_ok(),
+        error: result.err(),
+        elapsed_ms: started.elapsed().as_millis() as u64,
+    }
+}
+
+// What the user set up: the incoming provider, plus the ones the frontend would use
+fn configured_providers(app: &AppHandle) -> Vec<IncomingProvider> {
+    let settings = app.state::<SettingsState>().get();
+    let mut providers = vec![settings.incoming.provider];
+
+    let qwen = matches!(secrets::get(secrets::QWEN_ASR_API_KEY), Ok(Some(_)));
+    for (wanted, provider) in [
+        (settings.grpc_asr.enabled, IncomingProvider::Grpc),
+        (qwen, IncomingProvider::Qwen),
+    ] {
+        if wanted && !providers.contains(&provider) {
+            providers.push(provider);
+        }
+    }
+
+    providers
+}
+
+/// Runs every check and sums them up. `providers` defaults to the configured ones.
+#[tauri::command]
+pub async fn run_setup_diagnostics(
+    app: AppHandle,
+    providers: Option<Vec<IncomingProvider>>,
+) -> DiagnosticsReport {
+    let microphone = match diagnose_microphone(app.clone()).await {
+        Ok(report) if report.silent => Check {
+            ok: false,
+            error: Some(format!("{} picked up nothing but silence", report.device)),
+            report: Some(report),
+        },
+        result => result.into(),
+    };
+    let osc: Check<OscReport> = diagnose_osc(app.clone()).into();
+
+    let mut reports = Vec::new();
+    for provider in providers.unwrap_or_else(|| configured_providers(&app)) {
+        reports.push(diagnose_provider(app.clone(), provider).await);
+    }
+
+    let passed = microphone.ok && osc.ok && reports.iter().all(|report| report.ok);
+    log::info!(
+        "[DIAGNOSTICS] Microphone {} - OSC {} - Providers {}/{}",
+        if microphone.ok { "ok" } else { "failed" },
+        if osc.ok { "ok" } else { "failed" },
+        reports.iter().filter(|report| report.ok).count(),
+        reports.len()
+    );
+
+    DiagnosticsReport {
+        microphone,
+        osc,
+        providers: reports,
+        passed,
+    }
+}
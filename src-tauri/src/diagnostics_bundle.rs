@@ -0,0 +1,160 @@
+//! One zip with everything a bug report needs: recent logs, settings without secrets, system
+//! and audio device info and pipeline stats.
+
+use cpal::traits::{DeviceTrait, HostTrait};
+use serde_json::{json, Value};
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::PathBuf;
+use sysinfo::System;
+use tauri::{AppHandle, Manager};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use crate::latency::{self, LatencyState};
+use crate::logging;
+use crate::paths;
+use crate::redact;
+use crate::resources::{self, ResourceState};
+use crate::settings::{ApiSettings, SettingsState};
+use crate::shutdown;
+use crate::usage::{self, UsageState};
+use crate::vrchat;
+
+const REDACTED: &str = "***REDACTED***";
+
+// Settings fields named like this hold credentials, whatever section they are in
+const SECRET_FIELDS: &[&str] = &["token", "password", "secret", "api_key", "access_key"];
+
+fn strip_secrets(value: &mut Value) {
+    match value {
+        Value::Object(fields) => {
+            for (name, field) in fields.iter_mut() {
+                let name = name.to_ascii_lowercase();
+                if field.is_string() && SECRET_FIELDS.iter().any(|secret| name.contains(secret)) {
+                    if field.as_str() != Some("") {
+                        *field = json!(REDACTED);
+                    }
+                } else {
+                    strip_secrets(field);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(strip_secrets),
+        _ => {}
+    }
+}
+
+fn settings(app: &AppHandle) -> Result<Value, String> {
+    let mut settings = app.state::<SettingsState>().get();
+    settings.api_settings = ApiSettings::default();
+
+    let mut value = serde_json::to_value(&settings)
+        .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+    strip_secrets(&mut value);
+    Ok(value)
+}
+
+fn system_info(app: &AppHandle) -> Value {
+    let mut system = System::new();
+    system.refresh_memory();
+
+    json!({
+        "app_version": app.package_info().version.to_string(),
+        "os": System::long_os_version(),
+        "kernel": System::kernel_version(),
+        "arch": std::env::consts::ARCH,
+        "cores": std::thread::available_parallelism().map_or(1, |n| n.get()),
+        "memory_bytes": system.total_memory(),
+        "portable": paths::portable_dir().is_some(),
+        "vrchat_running": vrchat::is_vrchat_running(),
+    })
+}
+
+fn audio_devices() -> Value {
+    let host = cpal::default_host();
+    let names = |devices: Option<Vec<cpal::Device>>| -> Vec<String> {
+        devices
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|device| device.name().ok())
+            .collect()
+    };
+
+    json!({
+        "host": host.id().name(),
+        "default_input": host.default_input_device().and_then(|device| device.name().ok()),
+        "default_output": host.default_output_device().and_then(|device| device.name().ok()),
+        "inputs": names(host.input_devices().ok().map(|devices| devices.collect())),
+        "outputs": names(host.output_devices().ok().map(|devices| devices.collect())),
+    })
+}
+
+fn pipeline_stats(app: &AppHandle) -> Value {
+    json!({
+        "latency": latency::latency_stats(app.state::<LatencyState>()),
+        "resources": resources::resource_usage(app.clone(), app.state::<ResourceState>()).ok(),
+        "tasks": shutdown::running_tasks(app),
+        "usage": usage::usage_summary(app.state::<UsageState>(), "daily".to_string()).ok(),
+    })
+}
+
+/// Zips up logs, sanitized settings, system info, audio devices and pipeline stats. Writes
+/// to `path`, or into the data directory when not given, and returns where it went.
+#[tauri::command]
+pub fn create_diagnostics_bundle(app: AppHandle, path: Option<String>) -> Result<String, String> {
+    let path = match path {
+        Some(path) => PathBuf::from(path),
+        None => {
+            let dir = paths::data_dir(&app).join("diagnostics");
+            fs::create_dir_all(&dir)
+                .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+            dir.join(format!(
+                "kikitan-diagnostics-{}.zip",
+                chrono::Local::now().format("%Y%m%d-%H%M%S")
+            ))
+        }
+    };
+
+    let file =
+        File::create(&path).map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+    let mut zip = ZipWriter::new(file);
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+
+    let mut add = |name: &str, contents: &str| -> Result<(), String> {
+        let failed = |e: String| format!("Failed to add {} to the bundle: {}", name, e);
+        zip.start_file(name, options)
+            .map_err(|e| failed(e.to_string()))?;
+        zip.write_all(contents.as_bytes())
+            .map_err(|e| failed(e.to_string()))
+    };
+
+    let pretty = |value: Value| serde_json::to_string_pretty(&value).unwrap_or_default();
+    add("settings.json", &redact::redact(&pretty(settings(&app)?)))?;
+    add("system.json", &pretty(system_info(&app)))?;
+    add("audio_devices.json", &pretty(audio_devices()))?;
+    add("pipeline.json", &pretty(pipeline_stats(&app)))?;
+
+    // Lines are redacted as they are logged, again in case of older files from before a rule
+    for log in logging::log_files(&app) {
+        match fs::read(&log) {
+            Ok(contents) => {
+                let name = log.file_name().unwrap_or_default().to_string_lossy();
+                add(
+                    &format!("logs/{}", name),
+                    &redact::redact(&String::from_utf8_lossy(&contents)),
+                )?;
+            }
+            Err(e) => log::warn!("[DIAGNOSTICS] Skipping {}: {}", log.display(), e),
+        }
+    }
+
+    zip.finish()
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+
+    log::info!(
+        "[DIAGNOSTICS] Wrote diagnostics bundle to {}",
+        path.display()
+    );
+    Ok(path.to_string_lossy().to_string())
+}
@@ -0,0 +1,138 @@
+use discord_rich_presence::activity::{Activity, Assets, Timestamps};
+use discord_rich_presence::{DiscordIpc, DiscordIpcClient};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::history::HistoryState;
+use crate::languages;
+use crate::settings::{Settings, SettingsState};
+use crate::shutdown;
+
+/// Application id of the Discord app whose name and icon show up in the presence.
+const CLIENT_ID: Option<&str> = option_env!("KIKITAN_DISCORD_CLIENT_ID");
+
+// Picks up settings changes and a Discord client started after us
+const REFRESH_INTERVAL: Duration = Duration::from_secs(15);
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DiscordSettings {
+    pub enabled: bool,
+    /// Supports `{source}`, `{target}` and `{messages}`.
+    pub details_template: String,
+    pub state_template: String,
+}
+
+impl Default for DiscordSettings {
+    fn default() -> Self {
+        DiscordSettings {
+            enabled: false,
+            details_template: "Translating {source}⇄{target} in VRChat".to_string(),
+            state_template: "{messages} messages translated".to_string(),
+        }
+    }
+}
+
+/// Keeps the Rich Presence in sync with the settings and the translations of this session.
+pub fn start(app: &AppHandle) {
+    let Some(client_id) = CLIENT_ID else {
+        log::info!("[DISCORD] This build was made without a Discord application id");
+        return;
+    };
+
+    let mut recorded = app.state::<HistoryState>().subscribe();
+    let app = app.clone();
+    let started = chrono::Utc::now().timestamp();
+    let exiting = shutdown::token(&app);
+
+    let task = async move {
+        let mut client: Option<DiscordIpcClient> = None;
+        let mut messages = 0u64;
+
+        loop {
+            let received = tokio::select! {
+                _ = exiting.cancelled() => break,
+                received = tokio::time::timeout(REFRESH_INTERVAL, recorded.recv()) => received,
+            };
+
+            match received {
+                Ok(Ok(_)) => messages += 1,
+                Ok(Err(RecvError::Lagged(missed))) => messages += missed,
+                Ok(Err(RecvError::Closed)) => break,
+                Err(_) => {}
+            }
+
+            let settings = app.state::<SettingsState>().get();
+            if !settings.discord.enabled {
+                if let Some(mut client) = client.take() {
+                    let _ = client.clear_activity();
+                    let _ = client.close();
+                    log::info!("[DISCORD] Cleared Rich Presence");
+                }
+                continue;
+            }
+
+            if client.is_none() {
+                client = connect(client_id);
+            }
+
+            if let Some(ipc) = client.as_mut() {
+                let details = fill(&settings.discord.details_template, &settings, messages);
+                let state = fill(&settings.discord.state_template, &settings, messages);
+
+                let activity = Activity::new()
+                    .details(&details)
+                    .state(&state)
+                    .timestamps(Timestamps::new().start(started))
+                    .assets(
+                        Assets::new()
+                            .large_image("icon")
+                            .large_text("Kikitan Translator"),
+                    );
+
+                // Discord was closed, reconnect on the next refresh
+                if let Err(e) = ipc.set_activity(activity) {
+                    log::debug!("[DISCORD] Failed to update Rich Presence: {}", e);
+                    client = None;
+                }
+            }
+        }
+
+        // Don't leave a stale presence behind when quitting
+        if let Some(mut client) = client {
+            let _ = client.clear_activity();
+            let _ = client.close();
+        }
+    };
+
+    tauri::async_runtime::spawn(shutdown::track(&app, "discord", task));
+}
+
+fn connect(client_id: &str) -> Option<DiscordIpcClient> {
+    let mut client = DiscordIpcClient::new(client_id).ok()?;
+    match client.connect() {
+        Ok(()) => {
+            log::info!("[DISCORD] Connected to Discord");
+            Some(client)
+        }
+        // Discord isn't running, not worth more than a debug line
+        Err(e) => {
+            log::debug!("[DISCORD] Could not connect to Discord: {}", e);
+            None
+        }
+    }
+}
+
+fn fill(template: &str, settings: &Settings, messages: u64) -> String {
+    template
+        .replace("{source}", &language_code(&settings.source_language))
+        .replace("{target}", &language_code(&settings.target_language))
+        .replace("{messages}", &messages.to_string())
+}
+
+// "en-US" -> "EN"
+fn language_code(language: &str) -> String {
+    languages::primary(language).to_uppercase()
+}
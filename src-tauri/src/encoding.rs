@@ -0,0 +1,4 @@
+/// Lowercase hex, as request signatures want it.
+pub fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
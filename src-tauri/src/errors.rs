@@ -0,0 +1,160 @@
+//! Errors the UI shows to the user, as a code plus parameters so the message can be shown
+//! in the user's language. Commands returning [`AppError`] serialize it as
+//! `{"code": ..., "params": {...}, "message": <English text>}`.
+//!
+//! Plain `String` errors still work everywhere: they convert into [`ErrorCode::Unexpected`]
+//! with the original text, and an `AppError` converts back into its English message.
+
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
+use std::collections::BTreeMap;
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    /// `{message}`
+    Unexpected,
+    /// `{provider}`
+    ApiKeyMissing,
+    /// `{provider}`
+    BudgetExhausted,
+    /// `{provider}`, `{reason}`
+    ConnectFailed,
+    /// `{provider}`
+    NotConnected,
+    /// `{provider}`, `{reason}`
+    SendFailed,
+}
+
+const LANGUAGES: [&str; 4] = ["en", "ja", "zh", "ko"];
+
+fn template(code: ErrorCode, language: &str) -> &'static str {
+    use ErrorCode::*;
+
+    match (code, language) {
+        (Unexpected, "ja") => "予期しないエラーが発生しました: {message}",
+        (Unexpected, "zh") => "发生意外错误：{message}",
+        (Unexpected, "ko") => "예기치 않은 오류가 발생했습니다: {message}",
+        (Unexpected, _) => "{message}",
+
+        (ApiKeyMissing, "ja") => "{provider} の API キーが設定されていません。",
+        (ApiKeyMissing, "zh") => "尚未设置 {provider} 的 API 密钥。",
+        (ApiKeyMissing, "ko") => "{provider} API 키가 설정되지 않았습니다.",
+        (ApiKeyMissing, _) => "{provider} API key is not set",
+
+        (BudgetExhausted, "ja") => "{provider} の今月の予算を使い切りました。",
+        (BudgetExhausted, "zh") => "{provider} 本月的预算已用完。",
+        (BudgetExhausted, "ko") => "{provider}의 이번 달 예산을 모두 사용했습니다.",
+        (BudgetExhausted, _) => "Monthly budget for {provider} is exhausted",
+
+        (ConnectFailed, "ja") => "{provider} に接続できませんでした: {reason}",
+        (ConnectFailed, "zh") => "无法连接到 {provider}：{reason}",
+        (ConnectFailed, "ko") => "{provider}에 연결하지 못했습니다: {reason}",
+        (ConnectFailed, _) => "Failed to connect to {provider}: {reason}",
+
+        (NotConnected, "ja") => "{provider} に接続されていません。",
+        (NotConnected, "zh") => "未连接到 {provider}。",
+        (NotConnected, "ko") => "{provider}에 연결되어 있지 않습니다.",
+        (NotConnected, _) => "{provider} is not connected",
+
+        (SendFailed, "ja") => "{provider} への送信に失敗しました: {reason}",
+        (SendFailed, "zh") => "向 {provider} 发送失败：{reason}",
+        (SendFailed, "ko") => "{provider}(으)로 보내지 못했습니다: {reason}",
+        (SendFailed, _) => "Failed to send to {provider}: {reason}",
+    }
+}
+
+fn fill(template: &str, params: &BTreeMap<String, String>) -> String {
+    params
+        .iter()
+        .fold(template.to_string(), |text, (name, value)| {
+            text.replace(&format!("{{{}}}", name), value)
+        })
+}
+
+#[derive(Clone, Debug)]
+pub struct AppError {
+    pub code: ErrorCode,
+    pub params: BTreeMap<String, String>,
+}
+
+impl AppError {
+    pub fn new(code: ErrorCode) -> Self {
+        AppError {
+            code,
+            params: BTreeMap::new(),
+        }
+    }
+
+    pub fn with(mut self, name: &str, value: impl ToString) -> Self {
+        self.params.insert(name.to_string(), value.to_string());
+        self
+    }
+
+    /// The message in `language` (`en`, `ja`, `zh` or `ko`), English for anything else.
+    pub fn message(&self, language: &str) -> String {
+        fill(template(self.code, language), &self.params)
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message("en"))
+    }
+}
+
+impl Serialize for AppError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut error = serializer.serialize_struct("AppError", 3)?;
+        error.serialize_field("code", &self.code)?;
+        error.serialize_field("params", &self.params)?;
+        error.serialize_field("message", &self.message("en"))?;
+        error.end()
+    }
+}
+
+impl From<String> for AppError {
+    fn from(message: String) -> Self {
+        AppError::new(ErrorCode::Unexpected).with("message", message)
+    }
+}
+
+impl From<&str> for AppError {
+    fn from(message: &str) -> Self {
+        AppError::from(message.to_string())
+    }
+}
+
+impl From<AppError> for String {
+    fn from(error: AppError) -> Self {
+        error.message("en")
+    }
+}
+
+/// Every message template in `language`, keyed by error code, so the UI can format errors itself.
+#[tauri::command]
+pub fn get_error_messages(language: String) -> Result<BTreeMap<String, String>, String> {
+    if !LANGUAGES.contains(&language.as_str()) {
+        return Err(format!("Unsupported language {}", language));
+    }
+
+    use ErrorCode::*;
+    Ok([
+        Unexpected,
+        ApiKeyMissing,
+        BudgetExhausted,
+        ConnectFailed,
+        NotConnected,
+        SendFailed,
+    ]
+    .iter()
+    .map(|&code| {
+        let name = serde_json::to_value(code)
+            .ok()
+            .and_then(|name| name.as_str().map(str::to_string))
+            .unwrap_or_default();
+        (name, template(code, &language).to_string())
+    })
+    .collect())
+}
@@ -0,0 +1,85 @@
+//! Every event the backend emits is stamped with its place in its stream, so the webview
+//! can tell when one arrives late. Events are delivered from many threads and tasks, and an
+//! older partial transcript showing up after a newer one would otherwise overwrite it.
+//!
+//! The payload is wrapped as `{"seq": 4, "utterance": 2, "payload": ...}`. `seq` counts up
+//! per event name, `utterance` only moves on once a transcript stream sends a final result.
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Event, EventTarget, Manager};
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Stamped<T> {
+    pub seq: u64,
+    pub utterance: u64,
+    pub payload: T,
+}
+
+#[derive(Default)]
+struct Counter {
+    seq: u64,
+    utterance: u64,
+}
+
+#[derive(Default)]
+pub struct EventState {
+    streams: Mutex<HashMap<String, Counter>>,
+}
+
+fn stamp<T>(app: &AppHandle, event: &str, payload: T, ends_utterance: bool) -> Stamped<T> {
+    // Emitted before setup has run, e.g. by the panic hook, there's nothing to order against
+    let Some(state) = app.try_state::<EventState>() else {
+        return Stamped {
+            seq: 0,
+            utterance: 0,
+            payload,
+        };
+    };
+
+    let mut streams = state.streams.lock().unwrap();
+    let counter = streams.entry(event.to_string()).or_default();
+    counter.seq += 1;
+    let stamped = Stamped {
+        seq: counter.seq,
+        utterance: counter.utterance,
+        payload,
+    };
+    if ends_utterance {
+        counter.utterance += 1;
+    }
+
+    stamped
+}
+
+pub fn emit<T: Serialize + Clone>(app: &AppHandle, event: &str, payload: T) -> tauri::Result<()> {
+    app.emit(event, stamp(app, event, payload, false))
+}
+
+pub fn emit_to<T: Serialize + Clone>(
+    app: &AppHandle,
+    target: impl Into<EventTarget>,
+    event: &str,
+    payload: T,
+) -> tauri::Result<()> {
+    app.emit_to(target, event, stamp(app, event, payload, false))
+}
+
+/// Emits a recognizer result, `last` when it ends the current utterance.
+pub fn emit_transcript<T: Serialize + Clone>(
+    app: &AppHandle,
+    event: &str,
+    payload: T,
+    last: bool,
+) -> tauri::Result<()> {
+    app.emit(event, stamp(app, event, payload, last))
+}
+
+/// The payload of an event the backend emitted itself, for listeners inside the backend.
+pub fn payload(event: &Event) -> serde_json::Value {
+    serde_json::from_str::<serde_json::Value>(event.payload())
+        .ok()
+        .and_then(|mut stamped| stamped.get_mut("payload").map(serde_json::Value::take))
+        .unwrap_or(serde_json::Value::Null)
+}
@@ -0,0 +1,335 @@
+//! Runs recorded audio, e.g. a VRChat session captured with OBS, through the same recognizers
+//! and translation as the live "Understand others" pipeline, and writes the result next to the
+//! recording as a plain transcript and as SRT subtitles.
+//!
+//! The recognizers are streaming ones built for live audio, so the file is fed to them a few
+//! times faster than real time rather than all at once. Cues start where the audio of each
+//! utterance got loud enough to be speech, close enough to line up with the video.
+
+use serde::Serialize;
+use serde_json::json;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use symphonia::core::audio::SampleBuffer;
+use symphonia::core::codecs::DecoderOptions;
+use symphonia::core::errors::Error as DecodeError;
+use symphonia::core::formats::FormatOptions;
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::MetadataOptions;
+use symphonia::core::probe::Hint;
+use tauri::{AppHandle, Manager, State};
+use tokio::sync::mpsc;
+
+use crate::events;
+use crate::history::HistoryEntry;
+use crate::incoming::{self, Downsampler, IncomingProvider, CHUNK_SAMPLES, SAMPLE_RATE};
+use crate::network;
+use crate::settings::SettingsState;
+use crate::subtitle_export::{self, SubtitleFormat};
+
+/// How much faster than real time the file is fed to the recognizer.
+const PLAYBACK_SPEED: u64 = 4;
+// Lets the recognizer finish the last utterance before the stream ends
+const TRAILING_SILENCE_MS: u64 = 2000;
+/// Mean absolute level of a chunk above which it counts as speech.
+const SPEECH_LEVEL: f64 = 500.0;
+
+#[derive(Default)]
+pub struct FileTranscriptionState {
+    running: AtomicBool,
+    cancelled: Arc<AtomicBool>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct TranscriptionResult {
+    pub utterances: usize,
+    pub transcript: String,
+    pub subtitles: String,
+}
+
+#[derive(Default)]
+struct Timeline {
+    /// Audio fed to the recognizer so far.
+    position_ms: i64,
+    /// Where the speech of the utterance the recognizer is working on started.
+    speech_start_ms: Option<i64>,
+}
+
+/// Decodes `path` and sends it as 16 kHz mono PCM16 chunks, paced for a streaming recognizer.
+fn feed(
+    path: &Path,
+    audio: mpsc::Sender<Vec<u8>>,
+    timeline: &Mutex<Timeline>,
+    cancelled: &AtomicBool,
+    progress: impl Fn(i64, Option<i64>),
+) -> Result<(), String> {
+    let file = File::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let stream = MediaSourceStream::new(Box::new(file), Default::default());
+
+    let mut hint = Hint::new();
+    if let Some(extension) = path.extension().and_then(|e| e.to_str()) {
+        hint.with_extension(extension);
+    }
+    let mut format = symphonia::default::get_probe()
+        .format(
+            &hint,
+            stream,
+            &FormatOptions::default(),
+            &MetadataOptions::default(),
+        )
+        .map_err(|e| format!("Unsupported audio file: {}", e))?
+        .format;
+
+    let track = format
+        .default_track()
+        .ok_or_else(|| "The file has no audio track".to_string())?;
+    let track_id = track.id;
+    let rate = track
+        .codec_params
+        .sample_rate
+        .ok_or_else(|| "The file doesn't say its sample rate".to_string())?;
+    if rate < SAMPLE_RATE {
+        return Err(format!(
+            "The file is sampled at {} Hz, recognizers need at least {} Hz",
+            rate, SAMPLE_RATE
+        ));
+    }
+    let duration_ms = track
+        .codec_params
+        .n_frames
+        .map(|frames| (frames * 1000 / rate as u64) as i64);
+    let mut decoder = symphonia::default::get_codecs()
+        .make(&track.codec_params, &DecoderOptions::default())
+        .map_err(|e| format!("Unsupported audio codec: {}", e))?;
+
+    let mut downsampler = Downsampler::new(rate);
+    let mut pending = Vec::with_capacity(CHUNK_SAMPLES * 2);
+    let chunk_ms = (CHUNK_SAMPLES as u64 * 1000 / SAMPLE_RATE as u64) as i64;
+
+    let send = |samples: Vec<i16>| -> Result<(), String> {
+        let level = samples.iter().map(|s| (*s as f64).abs()).sum::<f64>() / samples.len() as f64;
+        let position = {
+            let mut timeline = timeline.lock().unwrap();
+            if level >= SPEECH_LEVEL && timeline.speech_start_ms.is_none() {
+                timeline.speech_start_ms = Some(timeline.position_ms);
+            }
+            timeline.position_ms += chunk_ms;
+            timeline.position_ms
+        };
+        progress(position, duration_ms);
+
+        audio
+            .blocking_send(samples.into_iter().flat_map(i16::to_le_bytes).collect())
+            .map_err(|_| "The recognizer stopped".to_string())?;
+        std::thread::sleep(Duration::from_millis(chunk_ms as u64 / PLAYBACK_SPEED));
+        Ok(())
+    };
+
+    loop {
+        if cancelled.load(Ordering::Relaxed) {
+            return Err("Cancelled".to_string());
+        }
+
+        let packet = match format.next_packet() {
+            Ok(packet) => packet,
+            Err(DecodeError::IoError(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(format!("Failed to read the file: {}", e)),
+        };
+        if packet.track_id() != track_id {
+            continue;
+        }
+
+        let decoded = match decoder.decode(&packet) {
+            Ok(decoded) => decoded,
+            // A damaged frame loses a few milliseconds, not the whole file
+            Err(DecodeError::DecodeError(e)) => {
+                log::warn!("[TRANSCRIBE FILE] Skipped a damaged frame: {}", e);
+                continue;
+            }
+            Err(e) => return Err(format!("Failed to decode the file: {}", e)),
+        };
+
+        let spec = *decoded.spec();
+        let mut buffer = SampleBuffer::<f32>::new(decoded.capacity() as u64, spec);
+        buffer.copy_interleaved_ref(decoded);
+        downsampler.push(buffer.samples(), spec.channels.count(), &mut pending);
+
+        while pending.len() >= CHUNK_SAMPLES {
+            send(pending.drain(..CHUNK_SAMPLES).collect())?;
+        }
+    }
+
+    if !pending.is_empty() {
+        pending.resize(CHUNK_SAMPLES, 0);
+        send(pending)?;
+    }
+    for _ in 0..TRAILING_SILENCE_MS / chunk_ms as u64 {
+        send(vec![0; CHUNK_SAMPLES])?;
+    }
+
+    Ok(())
+}
+
+/// Transcribes and translates a WAV, MP3 or OGG file with `provider`, writing `<name>.txt` and
+/// `<name>.srt` next to it. Translates into the "Understand others" target language unless
+/// `target_language` is given. Progress arrives as `transcribe-file-progress` events.
+#[tauri::command]
+pub async fn transcribe_file(
+    app: AppHandle,
+    state: State<'_, FileTranscriptionState>,
+    path: String,
+    provider: IncomingProvider,
+    language: String,
+    target_language: Option<String>,
+) -> Result<TranscriptionResult, String> {
+    if state.running.swap(true, Ordering::SeqCst) {
+        return Err("A file is already being transcribed".to_string());
+    }
+    state.cancelled.store(false, Ordering::SeqCst);
+
+    let result = transcribe(&app, &state, &path, &provider, &language, target_language).await;
+    state.running.store(false, Ordering::SeqCst);
+    result
+}
+
+async fn transcribe(
+    app: &AppHandle,
+    state: &FileTranscriptionState,
+    path: &str,
+    provider: &IncomingProvider,
+    language: &str,
+    target_language: Option<String>,
+) -> Result<TranscriptionResult, String> {
+    let path = PathBuf::from(path);
+    let target_language = target_language
+        .unwrap_or_else(|| app.state::<SettingsState>().get().incoming.target_language);
+    log::info!(
+        "[TRANSCRIBE FILE] Transcribing {} with {}",
+        path.display(),
+        provider.name()
+    );
+
+    let (audio_tx, audio) = mpsc::channel(incoming::AUDIO_BUFFER);
+    let (transcripts_tx, mut transcripts) = mpsc::channel(16);
+    let timeline = Arc::new(Mutex::new(Timeline::default()));
+
+    let feeder = {
+        let app = app.clone();
+        let path = path.clone();
+        let timeline = timeline.clone();
+        let cancelled = state.cancelled.clone();
+        tauri::async_runtime::spawn_blocking(move || {
+            feed(
+                &path,
+                audio_tx,
+                &timeline,
+                &cancelled,
+                |position, duration| {
+                    let _ = events::emit(
+                        &app,
+                        "transcribe-file-progress",
+                        json!({ "position_ms": position, "duration_ms": duration }),
+                    );
+                },
+            )
+        })
+    };
+
+    let recognizer = {
+        let app = app.clone();
+        let provider = provider.clone();
+        let language = language.to_string();
+        tauri::async_runtime::spawn(async move {
+            incoming::recognize(&app, &provider, &language, audio, transcripts_tx).await
+        })
+    };
+
+    let client = network::http_client(app);
+    let mut entries = Vec::new();
+    let mut previous_end = 0;
+    while let Some(original) = transcripts.recv().await {
+        let (start, end) = {
+            let mut timeline = timeline.lock().unwrap();
+            let start = timeline.speech_start_ms.take().unwrap_or(previous_end);
+            (start.max(previous_end), timeline.position_ms)
+        };
+        previous_end = end;
+
+        let original = original.trim();
+        if original.is_empty() {
+            continue;
+        }
+
+        // A failed translation still leaves the transcript worth keeping
+        let translated =
+            incoming::translate_utterance(app, &client, original, language, &target_language).await;
+        let (original, translation, translation_provider) = match translated {
+            Ok(translated) => (
+                translated.original,
+                translated.translation,
+                translated.provider,
+            ),
+            Err(e) => {
+                log::warn!("[TRANSCRIBE FILE] {}", e);
+                (original.to_string(), String::new(), "")
+            }
+        };
+
+        entries.push(HistoryEntry {
+            id: 0,
+            session_id: String::new(),
+            timestamp: start,
+            source_language: language.to_string(),
+            target_language: target_language.clone(),
+            asr_provider: provider.name().to_string(),
+            translation_provider: translation_provider.to_string(),
+            original,
+            translation,
+        });
+    }
+
+    let fed = feeder.await.map_err(|e| e.to_string())?;
+    let recognized = recognizer.await.map_err(|e| e.to_string())?;
+    // The feeder only notices a failed recognizer when it stops taking audio
+    recognized?;
+    fed?;
+
+    let transcript: String = entries
+        .iter()
+        .map(|entry| {
+            if entry.translation.is_empty() {
+                format!("{}\n\n", entry.original)
+            } else {
+                format!("{}\n{}\n\n", entry.original, entry.translation)
+            }
+        })
+        .collect();
+    let subtitles = subtitle_export::render(&entries, SubtitleFormat::Srt, 0, 0, true);
+
+    let transcript_path = path.with_extension("txt");
+    let subtitle_path = path.with_extension("srt");
+    fs::write(&transcript_path, transcript)
+        .map_err(|e| format!("Failed to write {}: {}", transcript_path.display(), e))?;
+    fs::write(&subtitle_path, subtitles)
+        .map_err(|e| format!("Failed to write {}: {}", subtitle_path.display(), e))?;
+
+    log::info!(
+        "[TRANSCRIBE FILE] Wrote {} utterances of {}",
+        entries.len(),
+        path.display()
+    );
+    Ok(TranscriptionResult {
+        utterances: entries.len(),
+        transcript: transcript_path.to_string_lossy().to_string(),
+        subtitles: subtitle_path.to_string_lossy().to_string(),
+    })
+}
+
+/// Stops the running transcription, nothing gets written.
+#[tauri::command]
+pub fn cancel_transcribe_file(state: State<'_, FileTranscriptionState>) {
+    state.cancelled.store(true, Ordering::SeqCst);
+}
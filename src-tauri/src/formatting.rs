@@ -0,0 +1,305 @@
+//! Turns a translation into the text that goes out, the same way for every output.
+//!
+//! Templates use `{original}`, `{translated}`, `{source}` and `{target}` (language codes),
+//! and `{original_romaji}` / `{translated_romaji}`, where kana is spelled out in Hepburn
+//! and everything else, kanji included, is kept as it is.
+
+use tauri::{AppHandle, State};
+
+use crate::headset;
+use crate::history::HistoryEntry;
+use crate::kat;
+use crate::osc::{self, ChatboxState};
+use crate::settings::{SettingsState, VrchatSettings};
+use crate::typewriter;
+use crate::widgets;
+
+/// The template in the settings, or the one matching the older translation first and
+/// only translation switches when none is set.
+pub fn template(settings: &VrchatSettings) -> String {
+    if !settings.message_template.is_empty() {
+        settings.message_template.clone()
+    } else if settings.only_translation {
+        "{translated}".to_string()
+    } else if settings.translation_first {
+        "{translated} ({original})".to_string()
+    } else {
+        "{original} ({translated})".to_string()
+    }
+}
+
+pub fn format(
+    template: &str,
+    original: &str,
+    translated: &str,
+    source: &str,
+    target: &str,
+) -> String {
+    // Converting kana is the only costly part, it only happens for placeholders that are used
+    let value = |name: &str| match name {
+        "original" => Some(original.to_string()),
+        "translated" => Some(translated.to_string()),
+        "source" => Some(source.to_string()),
+        "target" => Some(target.to_string()),
+        "original_romaji" => Some(romaji(original)),
+        "translated_romaji" => Some(romaji(translated)),
+        _ => None,
+    };
+
+    // A single pass over the template, so braces in what someone said are never filled in
+    let mut text = String::with_capacity(template.len() + original.len() + translated.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        text.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let filled = rest
+            .find('}')
+            .and_then(|end| Some((end, value(&rest[1..end])?)));
+        match filled {
+            Some((end, value)) => {
+                text.push_str(&value);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                text.push('{');
+                rest = &rest[1..];
+            }
+        }
+    }
+    text.push_str(rest);
+
+    text
+}
+
+/// A recorded translation formatted with the chatbox template.
+pub fn format_entry(settings: &VrchatSettings, entry: &HistoryEntry) -> String {
+    format(
+        &template(settings),
+        &entry.original,
+        &entry.translation,
+        &entry.source_language,
+        &entry.target_language,
+    )
+}
+
+const ROMAJI: [(char, &str); 71] = [
+    ('あ', "a"),
+    ('い', "i"),
+    ('う', "u"),
+    ('え', "e"),
+    ('お', "o"),
+    ('か', "ka"),
+    ('き', "ki"),
+    ('く', "ku"),
+    ('け', "ke"),
+    ('こ', "ko"),
+    ('が', "ga"),
+    ('ぎ', "gi"),
+    ('ぐ', "gu"),
+    ('げ', "ge"),
+    ('ご', "go"),
+    ('さ', "sa"),
+    ('し', "shi"),
+    ('す', "su"),
+    ('せ', "se"),
+    ('そ', "so"),
+    ('ざ', "za"),
+    ('じ', "ji"),
+    ('ず', "zu"),
+    ('ぜ', "ze"),
+    ('ぞ', "zo"),
+    ('た', "ta"),
+    ('ち', "chi"),
+    ('つ', "tsu"),
+    ('て', "te"),
+    ('と', "to"),
+    ('だ', "da"),
+    ('ぢ', "ji"),
+    ('づ', "zu"),
+    ('で', "de"),
+    ('ど', "do"),
+    ('な', "na"),
+    ('に', "ni"),
+    ('ぬ', "nu"),
+    ('ね', "ne"),
+    ('の', "no"),
+    ('は', "ha"),
+    ('ひ', "hi"),
+    ('ふ', "fu"),
+    ('へ', "he"),
+    ('ほ', "ho"),
+    ('ば', "ba"),
+    ('び', "bi"),
+    ('ぶ', "bu"),
+    ('べ', "be"),
+    ('ぼ', "bo"),
+    ('ぱ', "pa"),
+    ('ぴ', "pi"),
+    ('ぷ', "pu"),
+    ('ぺ', "pe"),
+    ('ぽ', "po"),
+    ('ま', "ma"),
+    ('み', "mi"),
+    ('む', "mu"),
+    ('め', "me"),
+    ('も', "mo"),
+    ('や', "ya"),
+    ('ゆ', "yu"),
+    ('よ', "yo"),
+    ('ら', "ra"),
+    ('り', "ri"),
+    ('る', "ru"),
+    ('れ', "re"),
+    ('ろ', "ro"),
+    ('わ', "wa"),
+    ('を', "o"),
+    ('ん', "n"),
+];
+
+fn kana(c: char) -> Option<&'static str> {
+    // Katakana sits 0x60 above the matching hiragana
+    let hiragana = match c {
+        'ァ'..='ヶ' => char::from_u32(c as u32 - 0x60)?,
+        _ => c,
+    };
+    let hiragana = match hiragana {
+        'ぁ' => 'あ',
+        'ぃ' => 'い',
+        'ぅ' => 'う',
+        'ぇ' => 'え',
+        'ぉ' => 'お',
+        'ゔ' => return Some("vu"),
+        other => other,
+    };
+
+    ROMAJI
+        .iter()
+        .find(|(kana, _)| *kana == hiragana)
+        .map(|(_, romaji)| *romaji)
+}
+
+// ゃ, ゅ and ょ (and their katakana) after an i-row kana: き + ゃ = kya, し + ゃ = sha
+fn small_y(c: char) -> Option<char> {
+    match c {
+        'ゃ' | 'ャ' => Some('a'),
+        'ゅ' | 'ュ' => Some('u'),
+        'ょ' | 'ョ' => Some('o'),
+        _ => None,
+    }
+}
+
+fn romaji(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut double_next = false;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            // っ doubles the consonant that follows
+            'っ' | 'ッ' => {
+                double_next = true;
+                continue;
+            }
+            // ー stretches the previous vowel
+            'ー' => {
+                if let Some(vowel) = out.chars().last().filter(|c| "aiueo".contains(*c)) {
+                    out.push(vowel);
+                }
+                continue;
+            }
+            _ => {}
+        }
+
+        let mut syllable = match kana(c) {
+            Some(romaji) => romaji.to_string(),
+            None => {
+                double_next = false;
+                out.push(c);
+                continue;
+            }
+        };
+
+        if let Some(vowel) = chars.peek().copied().and_then(small_y) {
+            if syllable.len() > 1 && syllable.ends_with('i') {
+                chars.next();
+                syllable.pop();
+                // shi + ya = sha, chi + ya = cha, ji + ya = ja
+                if !(syllable.ends_with("sh") || syllable.ends_with("ch") || syllable == "j") {
+                    syllable.push('y');
+                }
+                syllable.push(vowel);
+            }
+        }
+
+        if double_next {
+            double_next = false;
+            if let Some(first) = syllable.chars().next().filter(|c| !"aiueon".contains(*c)) {
+                // っち is written tch
+                out.push(if first == 'c' { 't' } else { first });
+            }
+        }
+
+        out.push_str(&syllable);
+    }
+
+    out
+}
+
+/// Formats a translation with the chatbox template and sends it to VRChat.
+#[tauri::command]
+pub fn send_translation(
+    app: AppHandle,
+    settings: State<'_, SettingsState>,
+    chatbox: State<'_, ChatboxState>,
+    original: String,
+    translation: String,
+    source_language: String,
+    target_language: String,
+) -> Result<(), String> {
+    // Every output below is held the same way the chatbox is
+    if headset::paused(&app) || osc::output_held(&app) {
+        return Ok(());
+    }
+
+    let settings = settings.get();
+    let vrchat = settings.vrchat_settings;
+    let message = format(
+        &template(&vrchat),
+        &original,
+        &translation,
+        &source_language,
+        &target_language,
+    );
+
+    if settings.kat.enabled {
+        kat::show(&app, &message);
+        if !settings.kat.also_chatbox {
+            return Ok(());
+        }
+    }
+
+    // The widget scheduler puts it in the chatbox along with the other widgets
+    if widgets::translated(&app, message.clone()) {
+        return Ok(());
+    }
+
+    if vrchat.typewriter {
+        typewriter::reveal(
+            &app,
+            message,
+            vrchat.typewriter_words,
+            vrchat.osc_address,
+            vrchat.osc_port.to_string(),
+        );
+        return Ok(());
+    }
+
+    osc::send_message(
+        app,
+        chatbox,
+        message,
+        vrchat.osc_address,
+        vrchat.osc_port.to_string(),
+    )
+}
@@ -0,0 +1,328 @@
+//! Google Cloud Speech-to-Text v2 streaming recognition over gRPC, as a backend ASR provider.
+//!
+//! Only the fields we use of `google.cloud.speech.v2` are written out below, with the tags
+//! from Google's protos. Oneofs with a single member we set are declared as plain optional
+//! fields, they encode the same.
+
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::codec::ProstCodec;
+use tonic::metadata::MetadataValue;
+use tonic::transport::{ClientTlsConfig, Endpoint};
+use tonic::Streaming;
+
+use crate::quota;
+use crate::secrets;
+use crate::settings::SettingsState;
+use crate::usage::{self, UsageState};
+
+const STREAMING_RECOGNIZE: &str = "/google.cloud.speech.v2.Speech/StreamingRecognize";
+const SAMPLE_RATE: i32 = 16000;
+const LINEAR16: i32 = 1;
+
+// Google ends streams after about five minutes, reopen a little before that
+const STREAM_LIMIT: Duration = Duration::from_secs(280);
+
+const AUDIO_BUFFER: usize = 50;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GoogleSttSettings {
+    pub project: String,
+    /// `global`, or a region such as `us-central1` or `asia-northeast1`.
+    pub location: String,
+    /// Recognizer id, `_` uses the configuration sent with each stream.
+    pub recognizer: String,
+    pub model: String,
+    pub automatic_punctuation: bool,
+    /// Names and jargon to bias recognition towards.
+    pub phrases: Vec<String>,
+    pub phrase_boost: f32,
+}
+
+impl Default for GoogleSttSettings {
+    fn default() -> Self {
+        GoogleSttSettings {
+            project: String::new(),
+            location: "global".to_string(),
+            recognizer: "_".to_string(),
+            model: "long".to_string(),
+            automatic_punctuation: true,
+            phrases: Vec::new(),
+            phrase_boost: 10.0,
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct StreamingRecognizeRequest {
+    #[prost(string, tag = "3")]
+    pub recognizer: String,
+    #[prost(bytes = "vec", tag = "5")]
+    pub audio: Vec<u8>,
+    #[prost(message, optional, tag = "6")]
+    pub streaming_config: Option<StreamingRecognitionConfig>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct StreamingRecognitionConfig {
+    #[prost(message, optional, tag = "1")]
+    pub config: Option<RecognitionConfig>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct RecognitionConfig {
+    #[prost(message, optional, tag = "2")]
+    pub features: Option<RecognitionFeatures>,
+    #[prost(message, optional, tag = "6")]
+    pub adaptation: Option<SpeechAdaptation>,
+    #[prost(message, optional, tag = "8")]
+    pub explicit_decoding_config: Option<ExplicitDecodingConfig>,
+    #[prost(string, tag = "9")]
+    pub model: String,
+    #[prost(string, repeated, tag = "10")]
+    pub language_codes: Vec<String>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct ExplicitDecodingConfig {
+    #[prost(int32, tag = "1")]
+    pub encoding: i32,
+    #[prost(int32, tag = "2")]
+    pub sample_rate_hertz: i32,
+    #[prost(int32, tag = "3")]
+    pub audio_channel_count: i32,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct RecognitionFeatures {
+    #[prost(bool, tag = "4")]
+    pub enable_automatic_punctuation: bool,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct SpeechAdaptation {
+    #[prost(message, repeated, tag = "1")]
+    pub phrase_sets: Vec<AdaptationPhraseSet>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct AdaptationPhraseSet {
+    #[prost(message, optional, tag = "2")]
+    pub inline_phrase_set: Option<PhraseSet>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct PhraseSet {
+    #[prost(message, repeated, tag = "3")]
+    pub phrases: Vec<Phrase>,
+    #[prost(float, tag = "4")]
+    pub boost: f32,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Phrase {
+    #[prost(string, tag = "1")]
+    pub value: String,
+    #[prost(float, tag = "2")]
+    pub boost: f32,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct StreamingRecognizeResponse {
+    #[prost(message, repeated, tag = "6")]
+    pub results: Vec<StreamingRecognitionResult>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct StreamingRecognitionResult {
+    #[prost(message, repeated, tag = "1")]
+    pub alternatives: Vec<SpeechRecognitionAlternative>,
+    #[prost(bool, tag = "2")]
+    pub is_final: bool,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct SpeechRecognitionAlternative {
+    #[prost(string, tag = "1")]
+    pub transcript: String,
+}
+
+fn host(location: &str) -> String {
+    if location.is_empty() || location == "global" {
+        "https://speech.googleapis.com".to_string()
+    } else {
+        format!("https://{}-speech.googleapis.com", location)
+    }
+}
+
+fn config(settings: &GoogleSttSettings, language: &str) -> StreamingRecognitionConfig {
+    let adaptation = (!settings.phrases.is_empty()).then(|| SpeechAdaptation {
+        phrase_sets: vec![AdaptationPhraseSet {
+            inline_phrase_set: Some(PhraseSet {
+                phrases: settings
+                    .phrases
+                    .iter()
+                    .map(|phrase| Phrase {
+                        value: phrase.clone(),
+                        boost: 0.0,
+                    })
+                    .collect(),
+                boost: settings.phrase_boost,
+            }),
+        }],
+    });
+
+    StreamingRecognitionConfig {
+        config: Some(RecognitionConfig {
+            features: Some(RecognitionFeatures {
+                enable_automatic_punctuation: settings.automatic_punctuation,
+            }),
+            adaptation,
+            explicit_decoding_config: Some(ExplicitDecodingConfig {
+                encoding: LINEAR16,
+                sample_rate_hertz: SAMPLE_RATE,
+                audio_channel_count: 1,
+            }),
+            model: settings.model.clone(),
+            language_codes: vec![language.to_string()],
+        }),
+    }
+}
+
+async fn open(
+    settings: &GoogleSttSettings,
+    api_key: &str,
+    language: &str,
+) -> Result<
+    (
+        mpsc::Sender<StreamingRecognizeRequest>,
+        Streaming<StreamingRecognizeResponse>,
+    ),
+    String,
+> {
+    if settings.project.is_empty() {
+        return Err("Google Speech-to-Text needs a Cloud project".to_string());
+    }
+
+    let channel = Endpoint::from_shared(host(&settings.location))
+        .map_err(|e| format!("Invalid Speech-to-Text location: {}", e))?
+        .tls_config(ClientTlsConfig::new().with_native_roots())
+        .map_err(|e| format!("Failed to set up TLS: {}", e))?
+        .connect()
+        .await
+        .map_err(|e| format!("Failed to connect to Google Speech-to-Text: {}", e))?;
+
+    let mut client = tonic::client::Grpc::new(channel);
+    client
+        .ready()
+        .await
+        .map_err(|e| format!("Google Speech-to-Text is not ready: {}", e))?;
+
+    let recognizer = format!(
+        "projects/{}/locations/{}/recognizers/{}",
+        settings.project, settings.location, settings.recognizer
+    );
+
+    // The first message only carries the configuration
+    let (sender, receiver) = mpsc::channel(AUDIO_BUFFER);
+    sender
+        .send(StreamingRecognizeRequest {
+            recognizer: recognizer.clone(),
+            audio: Vec::new(),
+            streaming_config: Some(config(settings, language)),
+        })
+        .await
+        .map_err(|_| "Failed to start the recognition stream".to_string())?;
+
+    let mut request = tonic::Request::new(ReceiverStream::new(receiver));
+    let metadata = request.metadata_mut();
+    metadata.insert(
+        "x-goog-api-key",
+        api_key
+            .parse::<MetadataValue<_>>()
+            .map_err(|_| "Invalid Google API key".to_string())?,
+    );
+    // Regional endpoints route on this header
+    metadata.insert(
+        "x-goog-request-params",
+        format!("recognizer={}", recognizer.replace('/', "%2F"))
+            .parse::<MetadataValue<_>>()
+            .map_err(|_| "Invalid Speech-to-Text recognizer".to_string())?,
+    );
+
+    let codec: ProstCodec<StreamingRecognizeRequest, StreamingRecognizeResponse> =
+        ProstCodec::default();
+    let responses = client
+        .streaming(
+            request,
+            http::uri::PathAndQuery::from_static(STREAMING_RECOGNIZE),
+            codec,
+        )
+        .await
+        .map_err(|e| format!("Google Speech-to-Text rejected the stream: {}", e.message()))?
+        .into_inner();
+
+    Ok((sender, responses))
+}
+
+/// Recognizes 16 kHz mono PCM16 chunks from `audio` and sends each finished utterance to
+/// `transcripts`, reopening the stream before Google's length limit.
+pub async fn transcripts(
+    app: &AppHandle,
+    language: &str,
+    mut audio: mpsc::Receiver<Vec<u8>>,
+    transcripts: mpsc::Sender<String>,
+) -> Result<(), String> {
+    let api_key = secrets::get(secrets::GOOGLE_STT_API_KEY)?
+        .ok_or_else(|| "Google Speech-to-Text API key is not set".to_string())?;
+
+    loop {
+        let settings = app.state::<SettingsState>().get().google_stt;
+        let (sender, mut responses) = open(&settings, &api_key, language).await?;
+        log::info!("[GOOGLE-STT] Opened a recognition stream");
+
+        let limit = tokio::time::sleep(STREAM_LIMIT);
+        tokio::pin!(limit);
+
+        loop {
+            tokio::select! {
+                _ = &mut limit => break,
+                chunk = audio.recv() => {
+                    let chunk = match chunk {
+                        Some(chunk) => chunk,
+                        None => return Ok(()),
+                    };
+
+                    quota::ensure_available(app, usage::GOOGLE_STT)?;
+                    let seconds = chunk.len() as f64 / (SAMPLE_RATE as f64 * 2.0);
+                    let request = StreamingRecognizeRequest { audio: chunk, ..Default::default() };
+                    if sender.try_send(request).is_err() {
+                        log::debug!("[GOOGLE-STT] Falling behind, dropped an audio chunk");
+                        continue;
+                    }
+
+                    app.state::<UsageState>().add(usage::GOOGLE_STT, 0, seconds);
+                }
+                response = responses.message() => match response {
+                    Ok(Some(response)) => {
+                        for result in response.results.into_iter().filter(|result| result.is_final) {
+                            if let Some(alternative) = result.alternatives.into_iter().next() {
+                                let text = alternative.transcript.trim().to_string();
+                                if !text.is_empty() {
+                                    let _ = transcripts.send(text).await;
+                                }
+                            }
+                        }
+                    }
+                    // Google closed the stream at its limit, open the next one
+                    Ok(None) => break,
+                    Err(e) => return Err(format!("Google Speech-to-Text failed: {}", e.message())),
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,254 @@
+//! Client for speech recognition engines running in a separate process, speaking the
+//! `Recognizer` service from `proto/asr.proto` over gRPC.
+//!
+//! The messages are written out by hand instead of generated so building doesn't need `protoc`,
+//! keep them in sync with the proto file.
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::sync::Mutex;
+use tauri::async_runtime::JoinHandle;
+use tauri::{AppHandle, Manager, State};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::codec::ProstCodec;
+use tonic::transport::Endpoint;
+use tonic::Streaming;
+
+use crate::errors::{AppError, ErrorCode};
+use crate::events;
+use crate::headset;
+use crate::language_switch;
+use crate::osc;
+use crate::settings::SettingsState;
+use crate::shutdown;
+use crate::telemetry;
+use crate::tray;
+use crate::vr_controller;
+
+const RECOGNIZE: &str = "/kikitan.asr.v1.Recognizer/Recognize";
+const SAMPLE_RATE: u32 = 16000;
+
+/// `task` of the first request asking for English text instead of a transcript.
+pub const TRANSLATE_TASK: &str = "translate";
+
+// About 10 seconds of the frontend's 4096 sample chunks before audio gets dropped
+const AUDIO_BUFFER: usize = 40;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GrpcAsrSettings {
+    /// Use the external engine instead of Qwen ASR or WebSpeech.
+    pub enabled: bool,
+    pub endpoint: String,
+    /// Use the engine's translate task, such as Whisper's, when the target language is English.
+    pub translate_to_english: bool,
+}
+
+impl Default for GrpcAsrSettings {
+    fn default() -> Self {
+        GrpcAsrSettings {
+            enabled: false,
+            endpoint: "http://127.0.0.1:50051".to_string(),
+            translate_to_english: false,
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct AudioRequest {
+    #[prost(string, tag = "1")]
+    pub language: String,
+    #[prost(uint32, tag = "2")]
+    pub sample_rate: u32,
+    #[prost(bytes = "vec", tag = "3")]
+    pub audio: Vec<u8>,
+    #[prost(string, tag = "4")]
+    pub task: String,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct TranscriptResponse {
+    #[prost(string, tag = "1")]
+    pub text: String,
+    #[prost(bool, tag = "2")]
+    pub r#final: bool,
+    #[prost(string, tag = "3")]
+    pub translation: String,
+}
+
+#[derive(Default)]
+pub struct GrpcAsrState {
+    sender: Mutex<Option<mpsc::Sender<AudioRequest>>>,
+    reader: Mutex<Option<JoinHandle<()>>>,
+}
+
+fn not_connected() -> AppError {
+    AppError::new(ErrorCode::NotConnected).with("provider", "ASR engine")
+}
+
+/// Opens a recognition stream on the engine at `endpoint`. Audio chunks go into the returned
+/// sender, the language, sample rate and task are already sent.
+pub async fn recognize(
+    endpoint: &str,
+    language: String,
+    task: &str,
+) -> Result<(mpsc::Sender<AudioRequest>, Streaming<TranscriptResponse>), AppError> {
+    let channel = Endpoint::from_shared(endpoint.to_string())
+        .map_err(|e| format!("Invalid ASR engine endpoint: {}", e))?
+        .connect()
+        .await
+        .map_err(|e| {
+            AppError::new(ErrorCode::ConnectFailed)
+                .with("provider", endpoint)
+                .with("reason", e)
+        })?;
+
+    let mut client = tonic::client::Grpc::new(channel);
+    client
+        .ready()
+        .await
+        .map_err(|e| format!("ASR engine is not ready: {}", e))?;
+
+    let (sender, receiver) = mpsc::channel(AUDIO_BUFFER);
+    sender
+        .send(AudioRequest {
+            language,
+            sample_rate: SAMPLE_RATE,
+            audio: Vec::new(),
+            task: task.to_string(),
+        })
+        .await
+        .map_err(|_| "Failed to start the recognition stream".to_string())?;
+
+    let codec: ProstCodec<AudioRequest, TranscriptResponse> = ProstCodec::default();
+    let transcripts = client
+        .streaming(
+            tonic::Request::new(ReceiverStream::new(receiver)),
+            http::uri::PathAndQuery::from_static(RECOGNIZE),
+            codec,
+        )
+        .await
+        .map_err(|e| format!("ASR engine rejected the stream: {}", e.message()))?
+        .into_inner();
+
+    Ok((sender, transcripts))
+}
+
+/// Opens a recognition stream, transcripts arrive as `grpc-asr-transcript` events.
+#[tauri::command]
+pub async fn grpc_asr_connect(
+    app: AppHandle,
+    state: State<'_, GrpcAsrState>,
+    language: String,
+) -> Result<(), AppError> {
+    let settings = app.state::<SettingsState>().get();
+    let endpoint = settings.grpc_asr.endpoint;
+
+    // Speech straight to English skips the translation provider, other targets need it anyway
+    let task =
+        if settings.grpc_asr.translate_to_english && settings.target_language.starts_with("en") {
+            TRANSLATE_TASK
+        } else {
+            ""
+        };
+    let (sender, mut transcripts) = recognize(&endpoint, language, task).await?;
+
+    log::info!("[GRPC-ASR] Connected to {}", endpoint);
+    tray::set_connection(&app, "Connected");
+    *state.sender.lock().unwrap() = Some(sender);
+
+    let app_clone = app.clone();
+    let reader = shutdown::spawn(&app, "grpc_asr", async move {
+        loop {
+            match transcripts.message().await {
+                Ok(Some(transcript)) => {
+                    if transcript.r#final {
+                        language_switch::utterance_finished(&app_clone, &transcript.text);
+                    }
+                    let _ = events::emit_transcript(
+                        &app_clone,
+                        "grpc-asr-transcript",
+                        json!({
+                            "text": transcript.text,
+                            "final": transcript.r#final,
+                            "translation": transcript.translation,
+                        }),
+                        transcript.r#final,
+                    );
+                }
+                Ok(None) => {
+                    tray::set_connection(&app_clone, "Disconnected");
+                    let _ = events::emit(&app_clone, "grpc-asr-close", ());
+                    break;
+                }
+                Err(e) => {
+                    tray::set_connection(&app_clone, "Connection error");
+                    telemetry::record_error(&app_clone, "grpc_asr");
+                    let _ = events::emit(&app_clone, "grpc-asr-error", e.message().to_string());
+                    break;
+                }
+            }
+        }
+    });
+
+    if let Some(previous) = state.reader.lock().unwrap().replace(reader) {
+        previous.abort();
+    }
+
+    Ok(())
+}
+
+/// Forwards a chunk of base64 encoded 16 bit PCM to the engine.
+#[tauri::command]
+pub fn grpc_asr_send(
+    app: AppHandle,
+    state: State<'_, GrpcAsrState>,
+    audio: String,
+) -> Result<(), AppError> {
+    if app
+        .try_state::<tray::TrayState>()
+        .map_or(false, |tray| tray.capture_muted())
+        || headset::paused(&app)
+        || osc::capture_paused(&app)
+        || vr_controller::capture_gated(&app)
+    {
+        return Ok(());
+    }
+
+    let audio = BASE64
+        .decode(audio)
+        .map_err(|e| format!("Invalid audio chunk: {}", e))?;
+    language_switch::audio(&app, &audio);
+
+    let sender = state.sender.lock().unwrap();
+    let sender = sender.as_ref().ok_or_else(not_connected)?;
+
+    // A slow engine loses audio rather than stalling the frontend
+    sender
+        .try_send(AudioRequest {
+            audio,
+            ..Default::default()
+        })
+        .or_else(|e| match e {
+            mpsc::error::TrySendError::Full(_) => {
+                log::warn!("[GRPC-ASR] Engine is falling behind, dropped an audio chunk");
+                Ok(())
+            }
+            mpsc::error::TrySendError::Closed(_) => Err(not_connected()),
+        })
+}
+
+/// Ends the audio stream, the engine can still send its last transcripts.
+#[tauri::command]
+pub fn grpc_asr_close(state: State<'_, GrpcAsrState>) -> Result<(), AppError> {
+    state
+        .sender
+        .lock()
+        .unwrap()
+        .take()
+        .map(drop)
+        .ok_or_else(not_connected)
+}
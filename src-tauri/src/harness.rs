@@ -0,0 +1,274 @@
+//! In-process stand-ins for VRChat's OSC and the realtime ASR service, so the whole
+//! pipeline can be driven in CI without external services. Only functional when built
+//! with the `test-harness` feature, the commands return an error otherwise.
+
+use serde::Serialize;
+use tauri::AppHandle;
+
+#[derive(Clone, Debug, Serialize)]
+#[cfg_attr(not(feature = "test-harness"), allow(dead_code))]
+pub struct ReceivedOsc {
+    pub address: String,
+    pub args: Vec<String>,
+}
+
+#[cfg(feature = "test-harness")]
+use mock as imp;
+
+#[cfg(not(feature = "test-harness"))]
+use disabled as imp;
+
+/// URL of the mock ASR server when it is running, `qwen_ws_connect` connects there instead.
+#[cfg(feature = "test-harness")]
+pub fn asr_url(app: &AppHandle) -> Option<String> {
+    mock::asr_url(app)
+}
+
+/// Starts a fake VRChat that records every OSC message sent to it, returns its port.
+#[tauri::command]
+pub async fn harness_start_osc_peer(app: AppHandle) -> Result<u16, String> {
+    imp::start_osc_peer(&app).await
+}
+
+#[tauri::command]
+pub fn harness_osc_messages(app: AppHandle) -> Result<Vec<ReceivedOsc>, String> {
+    imp::osc_messages(&app)
+}
+
+/// Sends `MuteSelf` to our OSC listener the way VRChat does.
+#[tauri::command]
+pub fn harness_send_vrchat_mute(muted: bool) -> Result<(), String> {
+    imp::send_vrchat_mute(muted)
+}
+
+/// Starts a fake realtime ASR server that answers audio with `transcript`, returns its URL.
+#[tauri::command]
+pub async fn harness_start_asr_server(
+    app: AppHandle,
+    transcript: String,
+) -> Result<String, String> {
+    imp::start_asr_server(&app, transcript).await
+}
+
+#[tauri::command]
+pub fn harness_stop(app: AppHandle) -> Result<(), String> {
+    imp::stop(&app)
+}
+
+#[cfg(not(feature = "test-harness"))]
+mod disabled {
+    use super::ReceivedOsc;
+    use tauri::AppHandle;
+
+    const DISABLED: &str = "Built without the test-harness feature";
+
+    pub async fn start_osc_peer(_: &AppHandle) -> Result<u16, String> {
+        Err(DISABLED.to_string())
+    }
+
+    pub fn osc_messages(_: &AppHandle) -> Result<Vec<ReceivedOsc>, String> {
+        Err(DISABLED.to_string())
+    }
+
+    pub fn send_vrchat_mute(_: bool) -> Result<(), String> {
+        Err(DISABLED.to_string())
+    }
+
+    pub async fn start_asr_server(_: &AppHandle, _: String) -> Result<String, String> {
+        Err(DISABLED.to_string())
+    }
+
+    pub fn stop(_: &AppHandle) -> Result<(), String> {
+        Err(DISABLED.to_string())
+    }
+}
+
+#[cfg(feature = "test-harness")]
+mod mock {
+    use futures_util::{SinkExt, StreamExt};
+    use rosc::{encoder, OscMessage, OscPacket, OscType};
+    use serde_json::{json, Value};
+    use std::net::UdpSocket as StdUdpSocket;
+    use std::sync::{Arc, Mutex};
+    use tauri::async_runtime::JoinHandle;
+    use tauri::{AppHandle, Manager};
+    use tokio::net::{TcpListener, TcpStream, UdpSocket};
+    use tokio_tungstenite::tungstenite::protocol::Message;
+
+    use super::ReceivedOsc;
+    use crate::shutdown;
+
+    // Our OSC listener, where VRChat sends avatar parameters
+    const VRCHAT_OUTPUT: &str = "127.0.0.1:9001";
+
+    // About two seconds of audio in the 4096 sample chunks the recognizer sends
+    const APPENDS_PER_UTTERANCE: usize = 8;
+
+    struct Running {
+        osc_peer: Option<JoinHandle<()>>,
+        received: Arc<Mutex<Vec<ReceivedOsc>>>,
+        asr_server: Option<(String, JoinHandle<()>)>,
+    }
+
+    pub struct HarnessState {
+        running: Mutex<Running>,
+    }
+
+    fn state(app: &AppHandle) -> tauri::State<'_, HarnessState> {
+        if app.try_state::<HarnessState>().is_none() {
+            app.manage(HarnessState {
+                running: Mutex::new(Running {
+                    osc_peer: None,
+                    received: Arc::new(Mutex::new(Vec::new())),
+                    asr_server: None,
+                }),
+            });
+        }
+        app.state::<HarnessState>()
+    }
+
+    pub fn asr_url(app: &AppHandle) -> Option<String> {
+        let state = app.try_state::<HarnessState>()?;
+        let running = state.running.lock().unwrap();
+        running.asr_server.as_ref().map(|(url, _)| url.clone())
+    }
+
+    pub async fn start_osc_peer(app: &AppHandle) -> Result<u16, String> {
+        let socket = UdpSocket::bind("127.0.0.1:0")
+            .await
+            .map_err(|e| format!("Failed to open mock OSC peer: {}", e))?;
+        let port = socket.local_addr().map_err(|e| e.to_string())?.port();
+
+        let state = state(app);
+        let mut running = state.running.lock().unwrap();
+        let received = running.received.clone();
+        received.lock().unwrap().clear();
+
+        let task = shutdown::spawn(app, "harness", async move {
+            let mut buf = [0u8; rosc::decoder::MTU];
+
+            while let Ok((size, _)) = socket.recv_from(&mut buf).await {
+                if let Ok((_, OscPacket::Message(msg))) = rosc::decoder::decode_udp(&buf[..size]) {
+                    received.lock().unwrap().push(ReceivedOsc {
+                        address: msg.addr,
+                        args: msg.args.iter().map(|arg| format!("{:?}", arg)).collect(),
+                    });
+                }
+            }
+        });
+
+        if let Some(previous) = running.osc_peer.replace(task) {
+            previous.abort();
+        }
+
+        log::info!("[HARNESS] Mock OSC peer listening on port {}", port);
+        Ok(port)
+    }
+
+    pub fn osc_messages(app: &AppHandle) -> Result<Vec<ReceivedOsc>, String> {
+        let state = state(app);
+        let running = state.running.lock().unwrap();
+        let received = running.received.lock().unwrap();
+        Ok(received.clone())
+    }
+
+    pub fn send_vrchat_mute(muted: bool) -> Result<(), String> {
+        let packet = encoder::encode(&OscPacket::Message(OscMessage {
+            addr: "/avatar/parameters/MuteSelf".to_string(),
+            args: vec![OscType::Bool(muted)],
+        }))
+        .map_err(|e| format!("Failed to encode OSC message: {:?}", e))?;
+
+        StdUdpSocket::bind("127.0.0.1:0")
+            .and_then(|socket| socket.send_to(&packet, VRCHAT_OUTPUT))
+            .map_err(|e| format!("Failed to send to {}: {}", VRCHAT_OUTPUT, e))?;
+
+        Ok(())
+    }
+
+    pub async fn start_asr_server(app: &AppHandle, transcript: String) -> Result<String, String> {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .map_err(|e| format!("Failed to open mock ASR server: {}", e))?;
+        let url = format!(
+            "ws://{}/",
+            listener.local_addr().map_err(|e| e.to_string())?
+        );
+
+        let app_clone = app.clone();
+        let task = shutdown::spawn(app, "harness", async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let transcript = transcript.clone();
+                shutdown::spawn(&app_clone, "harness", async move {
+                    if let Err(e) = serve_asr(stream, transcript).await {
+                        log::debug!("[HARNESS] {}", e);
+                    }
+                });
+            }
+        });
+
+        let state = state(app);
+        let mut running = state.running.lock().unwrap();
+        if let Some((_, previous)) = running.asr_server.replace((url.clone(), task)) {
+            previous.abort();
+        }
+
+        log::info!("[HARNESS] Mock ASR server listening on {}", url);
+        Ok(url)
+    }
+
+    // Mimics the realtime API closely enough for QwenASR.ts, every few audio chunks make an utterance
+    async fn serve_asr(stream: TcpStream, transcript: String) -> Result<(), String> {
+        let ws = tokio_tungstenite::accept_async(stream)
+            .await
+            .map_err(|e| format!("Rejected mock ASR client: {}", e))?;
+        let (mut write, mut read) = ws.split();
+        let mut appends = 0;
+
+        while let Some(message) = read.next().await {
+            let text = match message {
+                Ok(Message::Text(text)) => text,
+                Ok(Message::Close(_)) | Err(_) => break,
+                _ => continue,
+            };
+
+            let event: Value = serde_json::from_str(&text).unwrap_or_default();
+            let reply = match event["type"].as_str() {
+                Some("session.update") => Some(json!({ "type": "session.updated" })),
+                Some("input_audio_buffer.append") => {
+                    appends += 1;
+                    (appends % APPENDS_PER_UTTERANCE == 0).then(|| {
+                        json!({
+                            "type": "conversation.item.input_audio_transcription.completed",
+                            "transcript": transcript,
+                        })
+                    })
+                }
+                _ => None,
+            };
+
+            if let Some(reply) = reply {
+                write
+                    .send(Message::Text(reply.to_string()))
+                    .await
+                    .map_err(|e| format!("Failed to answer mock ASR client: {}", e))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn stop(app: &AppHandle) -> Result<(), String> {
+        let state = state(app);
+        let mut running = state.running.lock().unwrap();
+
+        if let Some(task) = running.osc_peer.take() {
+            task.abort();
+        }
+        if let Some((_, task)) = running.asr_server.take() {
+            task.abort();
+        }
+        running.received.lock().unwrap().clear();
+        Ok(())
+    }
+}
@@ -0,0 +1,140 @@
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::{AppHandle, Manager};
+
+use crate::events;
+use crate::settings::SettingsState;
+use crate::vr_controller;
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HeadsetSettings {
+    /// Pause capture and chatbox output while the HMD is off the user's head.
+    pub pause_when_removed: bool,
+    /// Same while the SteamVR dashboard is open.
+    pub pause_on_dashboard: bool,
+}
+
+#[derive(Default)]
+pub struct HeadsetState {
+    removed: AtomicBool,
+    dashboard: AtomicBool,
+}
+
+#[derive(Clone, Serialize)]
+struct HeadsetEvent {
+    removed: bool,
+    dashboard: bool,
+    paused: bool,
+}
+
+/// Whether capture and chatbox output should be held back because of the headset.
+pub fn paused(app: &AppHandle) -> bool {
+    let Some(state) = app.try_state::<HeadsetState>() else {
+        return false;
+    };
+    let (removed, dashboard) = (
+        state.removed.load(Ordering::Relaxed),
+        state.dashboard.load(Ordering::Relaxed),
+    );
+
+    app.state::<SettingsState>().read(|settings| {
+        (settings.headset.pause_when_removed && removed)
+            || (settings.headset.pause_on_dashboard && dashboard)
+    })
+}
+
+fn update(app: &AppHandle, removed: Option<bool>, dashboard: Option<bool>) {
+    let state = app.state::<HeadsetState>();
+    if let Some(removed) = removed {
+        state.removed.store(removed, Ordering::Relaxed);
+    }
+    if let Some(dashboard) = dashboard {
+        state.dashboard.store(dashboard, Ordering::Relaxed);
+    }
+
+    let event = HeadsetEvent {
+        removed: state.removed.load(Ordering::Relaxed),
+        dashboard: state.dashboard.load(Ordering::Relaxed),
+        paused: paused(app),
+    };
+
+    log::info!(
+        "[HEADSET] Removed: {}, dashboard: {}, paused: {}",
+        event.removed,
+        event.dashboard,
+        event.paused
+    );
+    let _ = events::emit(app, "headset-state", event);
+}
+
+/// Watches SteamVR for the headset being taken off or put on, the dashboard opening and the
+/// push-to-translate controller button.
+#[cfg(any(target_os = "windows", target_os = "linux"))]
+pub fn start(app: &AppHandle) {
+    use openvr::system::event::Event;
+    use std::thread;
+    use std::time::Duration;
+
+    // SteamVR may start long after us, and background apps can't launch it
+    const RETRY_INTERVAL: Duration = Duration::from_secs(10);
+    // Short enough that push-to-translate on a controller button doesn't clip the first word
+    const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+    let app = app.clone();
+    thread::spawn(move || loop {
+        let context = match unsafe { openvr::init(openvr::ApplicationType::Background) } {
+            Ok(context) => context,
+            Err(_) => {
+                thread::sleep(RETRY_INTERVAL);
+                continue;
+            }
+        };
+
+        let system = match context.system() {
+            Ok(system) => system,
+            Err(e) => {
+                log::warn!("[HEADSET] Failed to open the OpenVR system: {}", e);
+                thread::sleep(RETRY_INTERVAL);
+                continue;
+            }
+        };
+
+        log::info!("[HEADSET] Connected to SteamVR");
+
+        'session: loop {
+            while let Some((info, _)) =
+                system.poll_next_event_with_pose(openvr::TrackingUniverseOrigin::Standing)
+            {
+                let hmd = info.tracked_device_index == openvr::tracked_device_index::HMD;
+                match info.event {
+                    Event::TrackedDeviceUserInteractionStarted if hmd => {
+                        update(&app, Some(false), None)
+                    }
+                    Event::TrackedDeviceUserInteractionEnded if hmd => {
+                        update(&app, Some(true), None)
+                    }
+                    Event::DashboardActivated => update(&app, None, Some(true)),
+                    Event::DashboardDeactivated => update(&app, None, Some(false)),
+                    Event::Quit(_) => {
+                        system.acknowledge_quit_exiting();
+                        break 'session;
+                    }
+                    event => {
+                        vr_controller::handle(&app, &system, info.tracked_device_index, &event)
+                    }
+                }
+            }
+
+            vr_controller::tick(&app);
+            thread::sleep(POLL_INTERVAL);
+        }
+
+        log::info!("[HEADSET] SteamVR exited");
+        update(&app, Some(false), Some(false));
+        vr_controller::reset(&app);
+    });
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
+pub fn start(_app: &AppHandle) {}
@@ -0,0 +1,325 @@
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use tauri::{AppHandle, State};
+use tokio::sync::broadcast;
+
+use crate::database;
+use crate::paths;
+
+const HISTORY_FILE: &str = "history.db";
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    #[serde(default)]
+    pub id: i64,
+    #[serde(default)]
+    pub session_id: String,
+    /// Unix time in milliseconds, filled in by the backend when zero.
+    #[serde(default)]
+    pub timestamp: i64,
+    pub source_language: String,
+    pub target_language: String,
+    pub asr_provider: String,
+    pub translation_provider: String,
+    pub original: String,
+    pub translation: String,
+}
+
+impl HistoryEntry {
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(HistoryEntry {
+            id: row.get(0)?,
+            session_id: row.get(1)?,
+            timestamp: row.get(2)?,
+            source_language: row.get(3)?,
+            target_language: row.get(4)?,
+            asr_provider: row.get(5)?,
+            translation_provider: row.get(6)?,
+            original: row.get(7)?,
+            translation: row.get(8)?,
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct HistoryQuery {
+    #[serde(default)]
+    pub page: u32,
+    #[serde(default = "default_page_size")]
+    pub page_size: u32,
+    pub search: Option<String>,
+    pub session_id: Option<String>,
+}
+
+fn default_page_size() -> u32 {
+    50
+}
+
+#[derive(Serialize)]
+pub struct HistoryPage {
+    pub entries: Vec<HistoryEntry>,
+    pub total: u32,
+}
+
+#[derive(Serialize)]
+pub struct HistorySession {
+    pub session_id: String,
+    pub started: i64,
+    pub ended: i64,
+    pub count: u32,
+}
+
+// Millisecond resolution, a new session started right after another must not merge into it
+fn new_session_id() -> String {
+    chrono::Local::now().format("%Y%m%d-%H%M%S-%3f").to_string()
+}
+
+/// Matches `text` literally in a `LIKE ... ESCAPE '\'` pattern.
+fn like_pattern(text: &str) -> String {
+    let escaped = text
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+    format!("%{}%", escaped)
+}
+
+pub struct HistoryState {
+    conn: Mutex<Connection>,
+    session_id: Mutex<String>,
+    // Every recorded entry is also published here for the output integrations
+    recorded: broadcast::Sender<HistoryEntry>,
+}
+
+impl HistoryState {
+    pub fn open(app: &AppHandle) -> Self {
+        let path = paths::data_dir(app).join(HISTORY_FILE);
+
+        let conn = match database::open(&path, create_schema) {
+            Ok(conn) => conn,
+            Err(e) => {
+                // Keep the app usable, history just won't outlive this run
+                log::error!("[HISTORY] Failed to open {}: {}", path.display(), e);
+                let conn = Connection::open_in_memory().expect("failed to open in-memory database");
+                create_schema(&conn).expect("failed to create history schema");
+                conn
+            }
+        };
+
+        HistoryState {
+            conn: Mutex::new(conn),
+            session_id: Mutex::new(new_session_id()),
+            recorded: broadcast::channel(64).0,
+        }
+    }
+
+    pub fn session_id(&self) -> String {
+        self.session_id.lock().unwrap().clone()
+    }
+
+    /// Entries recorded from now on go into a new session, returns its id.
+    pub fn start_session(&self) -> String {
+        let mut session_id = self.session_id.lock().unwrap();
+        *session_id = new_session_id();
+        session_id.clone()
+    }
+
+    /// Receives every entry recorded from now on.
+    pub fn subscribe(&self) -> broadcast::Receiver<HistoryEntry> {
+        self.recorded.subscribe()
+    }
+
+    pub fn record(&self, mut entry: HistoryEntry) -> Result<HistoryEntry, String> {
+        if entry.session_id.is_empty() {
+            entry.session_id = self.session_id();
+        }
+        if entry.timestamp == 0 {
+            entry.timestamp = chrono::Utc::now().timestamp_millis();
+        }
+
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO history (session_id, timestamp, source_language, target_language, asr_provider, translation_provider, original, translation)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                entry.session_id,
+                entry.timestamp,
+                entry.source_language,
+                entry.target_language,
+                entry.asr_provider,
+                entry.translation_provider,
+                entry.original,
+                entry.translation
+            ],
+        )
+        .map_err(|e| format!("Failed to record history: {}", e))?;
+
+        entry.id = conn.last_insert_rowid();
+
+        // Nobody listening is fine
+        let _ = self.recorded.send(entry.clone());
+        Ok(entry)
+    }
+
+    /// Returns the most recently recorded entry, from any session.
+    pub fn latest(&self) -> Result<Option<HistoryEntry>, String> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row(
+            "SELECT id, session_id, timestamp, source_language, target_language, asr_provider, translation_provider, original, translation
+             FROM history ORDER BY id DESC LIMIT 1",
+            [],
+            HistoryEntry::from_row,
+        )
+        .optional()
+        .map_err(|e| format!("Failed to read history: {}", e))
+    }
+
+    /// Returns all entries of a session in chronological order.
+    pub fn session_entries(&self, session_id: &str) -> Result<Vec<HistoryEntry>, String> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn
+            .prepare(
+                "SELECT id, session_id, timestamp, source_language, target_language, asr_provider, translation_provider, original, translation
+                 FROM history WHERE session_id = ?1 ORDER BY timestamp ASC, id ASC",
+            )
+            .map_err(|e| e.to_string())?;
+
+        let entries = stmt
+            .query_map(params![session_id], HistoryEntry::from_row)
+            .and_then(|rows| rows.collect::<rusqlite::Result<Vec<_>>>())
+            .map_err(|e| format!("Failed to read history: {}", e))?;
+
+        Ok(entries)
+    }
+}
+
+fn create_schema(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS history (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            session_id TEXT NOT NULL,
+            timestamp INTEGER NOT NULL,
+            source_language TEXT NOT NULL,
+            target_language TEXT NOT NULL,
+            asr_provider TEXT NOT NULL,
+            translation_provider TEXT NOT NULL,
+            original TEXT NOT NULL,
+            translation TEXT NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS history_session ON history (session_id, timestamp);",
+    )
+}
+
+#[tauri::command]
+pub fn record_history(
+    state: State<'_, HistoryState>,
+    entry: HistoryEntry,
+) -> Result<HistoryEntry, String> {
+    state.record(entry)
+}
+
+#[tauri::command]
+pub fn query_history(
+    state: State<'_, HistoryState>,
+    query: HistoryQuery,
+) -> Result<HistoryPage, String> {
+    let page_size = query.page_size.clamp(1, 500);
+    let search = query
+        .search
+        .filter(|s| !s.trim().is_empty())
+        .map(|s| like_pattern(s.trim()));
+
+    let conn = state.conn.lock().unwrap();
+
+    let total: u32 = conn
+        .query_row(
+            "SELECT COUNT(*) FROM history
+             WHERE (?1 IS NULL OR session_id = ?1) AND (?2 IS NULL OR original LIKE ?2 ESCAPE '\\' OR translation LIKE ?2 ESCAPE '\\')",
+            params![query.session_id, search],
+            |row| row.get(0),
+        )
+        .map_err(|e| format!("Failed to query history: {}", e))?;
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, session_id, timestamp, source_language, target_language, asr_provider, translation_provider, original, translation
+             FROM history
+             WHERE (?1 IS NULL OR session_id = ?1) AND (?2 IS NULL OR original LIKE ?2 ESCAPE '\\' OR translation LIKE ?2 ESCAPE '\\')
+             ORDER BY timestamp DESC, id DESC
+             LIMIT ?3 OFFSET ?4",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let entries = stmt
+        .query_map(
+            params![
+                query.session_id,
+                search,
+                page_size,
+                query.page as i64 * page_size as i64
+            ],
+            HistoryEntry::from_row,
+        )
+        .and_then(|rows| rows.collect::<rusqlite::Result<Vec<_>>>())
+        .map_err(|e| format!("Failed to query history: {}", e))?;
+
+    Ok(HistoryPage { entries, total })
+}
+
+#[tauri::command]
+pub fn list_history_sessions(
+    state: State<'_, HistoryState>,
+) -> Result<Vec<HistorySession>, String> {
+    let conn = state.conn.lock().unwrap();
+    let mut stmt = conn
+        .prepare(
+            "SELECT session_id, MIN(timestamp), MAX(timestamp), COUNT(*) FROM history
+             GROUP BY session_id ORDER BY MIN(timestamp) DESC",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let sessions = stmt
+        .query_map([], |row| {
+            Ok(HistorySession {
+                session_id: row.get(0)?,
+                started: row.get(1)?,
+                ended: row.get(2)?,
+                count: row.get(3)?,
+            })
+        })
+        .and_then(|rows| rows.collect::<rusqlite::Result<Vec<_>>>())
+        .map_err(|e| format!("Failed to list sessions: {}", e))?;
+
+    Ok(sessions)
+}
+
+#[tauri::command]
+pub fn current_history_session(state: State<'_, HistoryState>) -> String {
+    state.session_id()
+}
+
+#[tauri::command]
+pub fn delete_history(state: State<'_, HistoryState>, ids: Vec<i64>) -> Result<(), String> {
+    let mut conn = state.conn.lock().unwrap();
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    for id in ids {
+        tx.execute("DELETE FROM history WHERE id = ?1", params![id])
+            .map_err(|e| format!("Failed to delete history: {}", e))?;
+    }
+    tx.commit().map_err(|e| e.to_string())
+}
+
+/// Deletes one session, or the whole history when no session is given.
+#[tauri::command]
+pub fn clear_history(
+    state: State<'_, HistoryState>,
+    session_id: Option<String>,
+) -> Result<(), String> {
+    let conn = state.conn.lock().unwrap();
+    conn.execute(
+        "DELETE FROM history WHERE ?1 IS NULL OR session_id = ?1",
+        params![session_id],
+    )
+    .map_err(|e| format!("Failed to clear history: {}", e))?;
+    Ok(())
+}
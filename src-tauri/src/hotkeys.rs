@@ -0,0 +1,182 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::plugin::TauriPlugin;
+use tauri::{AppHandle, Manager, State, Wry};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutState};
+
+use crate::events;
+use crate::settings::SettingsState;
+
+pub const ACTIONS: &[&str] = &[
+    "toggle_translation",
+    "push_to_talk",
+    "clear_chatbox",
+    "switch_language_pair",
+    "toggle_click_through",
+];
+
+#[derive(Default)]
+pub struct HotkeyState {
+    bindings: Mutex<HashMap<String, Shortcut>>,
+}
+
+#[derive(Clone, Serialize)]
+struct HotkeyEvent {
+    action: String,
+    pressed: bool,
+}
+
+#[derive(Serialize)]
+pub struct HotkeyBinding {
+    pub action: String,
+    pub accelerator: String,
+}
+
+pub fn plugin() -> TauriPlugin<Wry> {
+    tauri_plugin_global_shortcut::Builder::new()
+        .with_handler(|app, shortcut, event| {
+            let action = app
+                .state::<HotkeyState>()
+                .bindings
+                .lock()
+                .unwrap()
+                .iter()
+                .find(|(_, bound)| bound.id() == shortcut.id())
+                .map(|(action, _)| action.clone());
+
+            if let Some(action) = action {
+                let _ = events::emit(
+                    app,
+                    "hotkey",
+                    HotkeyEvent {
+                        action,
+                        pressed: matches!(event.state(), ShortcutState::Pressed),
+                    },
+                );
+            }
+        })
+        .build()
+}
+
+/// Registers the hotkeys saved in the settings, logging the ones that can't be claimed.
+pub fn register_saved(app: &AppHandle) {
+    for (action, accelerator) in app.state::<SettingsState>().get().hotkeys {
+        if let Err(e) = bind(app, &action, &accelerator) {
+            log::warn!(
+                "[HOTKEYS] Could not register {} for {}: {}",
+                accelerator,
+                action,
+                e
+            );
+        }
+    }
+}
+
+/// Swaps the registered hotkeys for the saved ones after the settings were replaced.
+pub fn reload(app: &AppHandle) {
+    let bound: Vec<Shortcut> = app
+        .state::<HotkeyState>()
+        .bindings
+        .lock()
+        .unwrap()
+        .drain()
+        .map(|(_, shortcut)| shortcut)
+        .collect();
+    for shortcut in bound {
+        let _ = app.global_shortcut().unregister(shortcut);
+    }
+
+    register_saved(app);
+}
+
+fn bind(app: &AppHandle, action: &str, accelerator: &str) -> Result<(), String> {
+    if !ACTIONS.contains(&action) {
+        return Err(format!("Unknown hotkey action {}", action));
+    }
+
+    let shortcut: Shortcut = accelerator
+        .parse()
+        .map_err(|e| format!("Invalid hotkey {}: {}", accelerator, e))?;
+
+    let state = app.state::<HotkeyState>();
+
+    // Don't hold the lock while talking to the plugin, the shortcut handler needs it too
+    let previous = {
+        let bindings = state.bindings.lock().unwrap();
+        if let Some((other, _)) = bindings
+            .iter()
+            .find(|(other, bound)| bound.id() == shortcut.id() && other.as_str() != action)
+        {
+            return Err(format!("{} is already used for {}", accelerator, other));
+        }
+
+        bindings.get(action).copied()
+    };
+
+    if previous.is_some_and(|previous| previous.id() == shortcut.id()) {
+        return Ok(());
+    }
+
+    // Claim the new combination first, a failure keeps the old one working
+    app.global_shortcut()
+        .register(shortcut)
+        .map_err(|e| format!("{} is in use by another application: {}", accelerator, e))?;
+
+    state
+        .bindings
+        .lock()
+        .unwrap()
+        .insert(action.to_string(), shortcut);
+
+    if let Some(previous) = previous {
+        let _ = app.global_shortcut().unregister(previous);
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn register_hotkey(
+    app: AppHandle,
+    state: State<'_, SettingsState>,
+    action: String,
+    accelerator: String,
+) -> Result<(), String> {
+    bind(&app, &action, &accelerator)?;
+
+    let mut settings = state.get();
+    settings.hotkeys.insert(action, accelerator);
+    state.set(settings)
+}
+
+#[tauri::command]
+pub fn unregister_hotkey(
+    app: AppHandle,
+    state: State<'_, SettingsState>,
+    hotkeys: State<'_, HotkeyState>,
+    action: String,
+) -> Result<(), String> {
+    let shortcut = hotkeys.bindings.lock().unwrap().remove(&action);
+    if let Some(shortcut) = shortcut {
+        app.global_shortcut()
+            .unregister(shortcut)
+            .map_err(|e| format!("Failed to unregister hotkey: {}", e))?;
+    }
+
+    let mut settings = state.get();
+    settings.hotkeys.remove(&action);
+    state.set(settings)
+}
+
+#[tauri::command]
+pub fn list_hotkeys(state: State<'_, SettingsState>) -> Vec<HotkeyBinding> {
+    state
+        .get()
+        .hotkeys
+        .into_iter()
+        .map(|(action, accelerator)| HotkeyBinding {
+            action,
+            accelerator,
+        })
+        .collect()
+}
@@ -0,0 +1,176 @@
+//! iFlytek realtime speech transcription (RTASR) over WebSocket, as a backend ASR provider.
+//!
+//! The handshake is signed in the URL: `signa` is base64 of an HMAC-SHA1, keyed with the API
+//! key, over the hex MD5 of the app id and the current timestamp.
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use futures_util::{SinkExt, StreamExt};
+use hmac::{Hmac, Mac};
+use md5::{Digest, Md5};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha1::Sha1;
+use tauri::{AppHandle, Manager};
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::protocol::Message;
+
+use crate::encoding;
+use crate::languages;
+use crate::network;
+use crate::quota;
+use crate::secrets;
+use crate::settings::SettingsState;
+use crate::usage::{self, UsageState};
+
+const URL: &str = "wss://rtasr.xfyun.cn/v1/ws";
+const BYTES_PER_SECOND: f64 = 16000.0 * 2.0;
+
+#[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct IflytekSettings {
+    /// The API key is kept in the credential store.
+    pub app_id: String,
+}
+
+fn signature(app_id: &str, api_key: &str, timestamp: i64) -> String {
+    let base = encoding::hex(&Md5::digest(format!("{}{}", app_id, timestamp)));
+    let mut mac =
+        Hmac::<Sha1>::new_from_slice(api_key.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(base.as_bytes());
+    BASE64.encode(mac.finalize().into_bytes())
+}
+
+// RTASR only knows Mandarin and English, everything else is sent as Mandarin
+fn language_code(language: &str) -> &'static str {
+    if languages::primary(language) == "en" {
+        "en"
+    } else {
+        "cn"
+    }
+}
+
+fn url(app_id: &str, api_key: &str, language: &str) -> String {
+    let timestamp = chrono::Utc::now().timestamp();
+    let signa: String = signature(app_id, api_key, timestamp)
+        .bytes()
+        .map(|byte| match byte {
+            b'+' => "%2B".to_string(),
+            b'/' => "%2F".to_string(),
+            b'=' => "%3D".to_string(),
+            _ => (byte as char).to_string(),
+        })
+        .collect();
+
+    format!(
+        "{}?appid={}&ts={}&signa={}&lang={}",
+        URL,
+        app_id,
+        timestamp,
+        signa,
+        language_code(language)
+    )
+}
+
+// `data` is itself JSON, with the words of a sentence spread over `rt[].ws[].cw[0].w`
+fn final_text(data: &str) -> Option<String> {
+    let data: Value = serde_json::from_str(data).ok()?;
+    let sentence = &data["cn"]["st"];
+
+    // Type 0 is a finished sentence, 1 an intermediate guess
+    if sentence["type"] != "0" {
+        return None;
+    }
+
+    let text: String = sentence["rt"]
+        .as_array()?
+        .iter()
+        .flat_map(|rt| rt["ws"].as_array().cloned().unwrap_or_default())
+        .filter_map(|ws| {
+            ws.pointer("/cw/0/w")
+                .and_then(Value::as_str)
+                .map(str::to_string)
+        })
+        .collect();
+
+    Some(text.trim().to_string()).filter(|text| !text.is_empty())
+}
+
+/// Recognizes 16 kHz mono PCM16 chunks from `audio` and sends each finished sentence to
+/// `transcripts`.
+pub async fn transcripts(
+    app: &AppHandle,
+    language: &str,
+    mut audio: mpsc::Receiver<Vec<u8>>,
+    transcripts: mpsc::Sender<String>,
+) -> Result<(), String> {
+    let settings = app.state::<SettingsState>().get().iflytek;
+    if settings.app_id.is_empty() {
+        return Err("iFlytek needs an app id".to_string());
+    }
+    let api_key = secrets::get(secrets::IFLYTEK_API_KEY)?
+        .ok_or_else(|| "iFlytek API key is not set".to_string())?;
+
+    let (ws, _) = network::connect_websocket(app, url(&settings.app_id, &api_key, language))
+        .await
+        .map_err(|e| format!("Failed to connect to iFlytek: {}", e))?;
+    let (mut write, mut read) = ws.split();
+
+    log::info!("[IFLYTEK] Connected");
+
+    let mut ping = network::keepalive_interval(app);
+
+    loop {
+        tokio::select! {
+            _ = ping.tick() => {
+                write
+                    .send(Message::Ping(Vec::new()))
+                    .await
+                    .map_err(|e| format!("Failed to ping iFlytek: {}", e))?;
+            }
+            chunk = audio.recv() => {
+                let chunk = match chunk {
+                    Some(chunk) => chunk,
+                    None => {
+                        let _ = write.send(Message::Text(json!({ "end": true }).to_string())).await;
+                        return Ok(());
+                    }
+                };
+
+                quota::ensure_available(app, usage::IFLYTEK)?;
+                let seconds = chunk.len() as f64 / BYTES_PER_SECOND;
+                write
+                    .send(Message::Binary(chunk))
+                    .await
+                    .map_err(|e| format!("Failed to send audio to iFlytek: {}", e))?;
+
+                app.state::<UsageState>().add(usage::IFLYTEK, 0, seconds);
+            }
+            message = read.next() => match message {
+                Some(Ok(Message::Text(text))) => {
+                    let message: Value = serde_json::from_str(&text)
+                        .map_err(|e| format!("Invalid message from iFlytek: {}", e))?;
+
+                    match message["action"].as_str() {
+                        Some("result") => {
+                            if let Some(text) = message["data"].as_str().and_then(final_text) {
+                                let _ = transcripts.send(text).await;
+                            }
+                        }
+                        Some("error") => {
+                            return Err(format!(
+                                "iFlytek failed: {} {}",
+                                message["code"].as_str().unwrap_or_default(),
+                                message["desc"].as_str().unwrap_or_default()
+                            ))
+                        }
+                        _ => {}
+                    }
+                }
+                Some(Ok(Message::Close(_))) | None => return Err("iFlytek closed the connection".to_string()),
+                Some(Err(e)) => return Err(format!("iFlytek connection failed: {}", e)),
+                Some(Ok(_)) => {}
+            }
+        }
+    }
+}
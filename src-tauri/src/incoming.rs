@@ -0,0 +1,654 @@
+//! "Understand others": captures what the PC plays, e.g. VRChat's voice chat, recognizes and
+//! translates it entirely in the backend, and shows the result in the subtitle window and
+//! the overlay. Independent of the webview's microphone pipeline and its settings.
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{FromSample, Sample, SampleFormat, SizedSample};
+use futures_util::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use tauri::async_runtime::JoinHandle;
+use tauri::{AppHandle, Manager};
+use tokio::sync::{broadcast, mpsc, oneshot};
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::protocol::Message;
+
+use crate::assemblyai;
+use crate::autocorrect;
+use crate::aws_transcribe;
+use crate::comparison::{self, ComparisonSettings};
+use crate::events;
+use crate::google_stt;
+use crate::grpc_asr::{self, AudioRequest};
+use crate::history::{HistoryEntry, HistoryState};
+use crate::iflytek;
+use crate::languages;
+use crate::network;
+use crate::power;
+use crate::quota;
+use crate::secrets;
+use crate::settings::SettingsState;
+use crate::shutdown;
+use crate::soniox;
+use crate::symbols;
+use crate::telemetry;
+use crate::tencent_asr;
+use crate::translation_memory;
+use crate::usage::{self, UsageState};
+
+pub const SAMPLE_RATE: u32 = 16000;
+
+// 100 ms of audio per chunk, and a few seconds of them before audio gets dropped
+pub const CHUNK_SAMPLES: usize = 1600;
+pub const AUDIO_BUFFER: usize = 50;
+
+const QWEN_URL: &str =
+    "wss://dashscope.aliyuncs.com/api-ws/v1/realtime?model=qwen3-asr-flash-realtime";
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IncomingProvider {
+    Qwen,
+    /// The external engine from the `grpc_asr` settings.
+    Grpc,
+    /// Google Cloud Speech-to-Text v2 with the `google_stt` settings.
+    GoogleStt,
+    /// Amazon Transcribe streaming with the `aws_transcribe` settings.
+    AwsTranscribe,
+    /// iFlytek realtime transcription with the `iflytek` settings.
+    Iflytek,
+    /// Tencent Cloud realtime ASR with the `tencent_asr` settings.
+    TencentAsr,
+    /// AssemblyAI universal streaming, picks the multilingual model for non-English sources.
+    AssemblyAi,
+    /// Soniox realtime transcription with the `soniox` settings.
+    Soniox,
+}
+
+impl IncomingProvider {
+    /// Name recorded as the ASR provider in history entries.
+    pub fn name(&self) -> &'static str {
+        match self {
+            IncomingProvider::Qwen => "qwen",
+            IncomingProvider::Grpc => "grpc",
+            IncomingProvider::GoogleStt => usage::GOOGLE_STT,
+            IncomingProvider::AwsTranscribe => usage::AWS_TRANSCRIBE,
+            IncomingProvider::Iflytek => usage::IFLYTEK,
+            IncomingProvider::TencentAsr => usage::TENCENT_ASR,
+            IncomingProvider::AssemblyAi => usage::ASSEMBLYAI,
+            IncomingProvider::Soniox => usage::SONIOX,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct IncomingSettings {
+    pub enabled: bool,
+    /// Output device to capture, or an input device such as a monitor source.
+    /// Empty captures the default output device.
+    pub device: String,
+    pub provider: IncomingProvider,
+    /// What the others speak.
+    pub source_language: String,
+    /// What to translate it into, usually my own language.
+    pub target_language: String,
+    /// A second recognizer to run on the same audio, see `comparison`.
+    pub comparison: ComparisonSettings,
+}
+
+impl Default for IncomingSettings {
+    fn default() -> Self {
+        IncomingSettings {
+            enabled: false,
+            device: String::new(),
+            provider: IncomingProvider::Qwen,
+            source_language: "en".to_string(),
+            target_language: "ja".to_string(),
+            comparison: ComparisonSettings::default(),
+        }
+    }
+}
+
+struct Pipeline {
+    stop_capture: Arc<AtomicBool>,
+    task: JoinHandle<()>,
+}
+
+pub struct IncomingState {
+    pipeline: Mutex<Option<(IncomingSettings, Pipeline)>>,
+    translated: broadcast::Sender<HistoryEntry>,
+}
+
+impl Default for IncomingState {
+    fn default() -> Self {
+        IncomingState {
+            pipeline: Mutex::new(None),
+            translated: broadcast::channel(64).0,
+        }
+    }
+}
+
+impl IncomingState {
+    /// Receives every translation of other players' speech from now on.
+    pub fn subscribe(&self) -> broadcast::Receiver<HistoryEntry> {
+        self.translated.subscribe()
+    }
+}
+
+/// Starts, restarts or stops the pipeline to match the current settings.
+pub fn apply(app: &AppHandle) {
+    let settings = app.state::<SettingsState>().get().incoming;
+    let state = app.state::<IncomingState>();
+    let mut pipeline = state.pipeline.lock().unwrap();
+
+    // Low-power mode closes the session without touching the settings
+    let enabled = settings.enabled && !power::sessions_closed(app);
+    let wanted = enabled.then_some(&settings);
+    if pipeline.as_ref().map(|(current, _)| current) == wanted {
+        return;
+    }
+
+    if let Some((_, running)) = pipeline.take() {
+        running.stop_capture.store(true, Ordering::Relaxed);
+        running.task.abort();
+        log::info!("[INCOMING] Stopped incoming speech translation");
+    }
+
+    if enabled {
+        let (audio_tx, audio_rx) = mpsc::channel(AUDIO_BUFFER);
+        let (failed_tx, failed_rx) = oneshot::channel();
+        let stop_capture = Arc::new(AtomicBool::new(false));
+        capture(
+            settings.device.clone(),
+            audio_tx,
+            stop_capture.clone(),
+            failed_tx,
+        );
+
+        let task = shutdown::spawn(
+            app,
+            "incoming",
+            run(
+                app.clone(),
+                settings.clone(),
+                audio_rx,
+                stop_capture.clone(),
+                failed_rx,
+            ),
+        );
+        *pipeline = Some((settings, Pipeline { stop_capture, task }));
+    }
+}
+
+/// Averages the captured frames down to 16 kHz mono.
+pub struct Downsampler {
+    step: f64,
+    position: f64,
+    sum: f32,
+    count: u32,
+}
+
+impl Downsampler {
+    pub fn new(rate: u32) -> Self {
+        Downsampler {
+            step: rate as f64 / SAMPLE_RATE as f64,
+            position: 0.0,
+            sum: 0.0,
+            count: 0,
+        }
+    }
+
+    pub fn push(&mut self, samples: &[f32], channels: usize, out: &mut Vec<i16>) {
+        for frame in samples.chunks(channels) {
+            self.sum += frame.iter().sum::<f32>() / channels as f32;
+            self.count += 1;
+            self.position += 1.0;
+
+            if self.position >= self.step {
+                self.position -= self.step;
+                let mono = (self.sum / self.count as f32).clamp(-1.0, 1.0);
+                out.push((mono * i16::MAX as f32) as i16);
+                self.sum = 0.0;
+                self.count = 0;
+            }
+        }
+    }
+}
+
+fn build_stream<T>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    audio: mpsc::Sender<Vec<u8>>,
+) -> Result<cpal::Stream, String>
+where
+    T: SizedSample,
+    f32: FromSample<T>,
+{
+    let channels = config.channels as usize;
+    let mut downsampler = Downsampler::new(config.sample_rate.0);
+    let mut pending = Vec::with_capacity(CHUNK_SAMPLES * 2);
+    // Reused between callbacks, the realtime thread shouldn't allocate for every buffer
+    let mut samples: Vec<f32> = Vec::new();
+
+    device
+        .build_input_stream(
+            config,
+            move |data: &[T], _| {
+                samples.clear();
+                samples.extend(data.iter().map(|sample| sample.to_sample::<f32>()));
+                downsampler.push(&samples, channels, &mut pending);
+
+                if pending.len() >= CHUNK_SAMPLES {
+                    let chunk = pending.drain(..).flat_map(i16::to_le_bytes).collect();
+                    // Falling behind loses audio instead of piling it up
+                    let _ = audio.try_send(chunk);
+                }
+            },
+            |e| log::warn!("[INCOMING] Capture error: {}", e),
+            None,
+        )
+        .map_err(|e| format!("Failed to start capturing: {}", e))
+}
+
+fn find_device(
+    mut devices: impl Iterator<Item = cpal::Device>,
+    name: &str,
+) -> Option<cpal::Device> {
+    devices.find(|device| device.name().ok().as_deref() == Some(name))
+}
+
+fn open_stream(device_name: &str, audio: mpsc::Sender<Vec<u8>>) -> Result<cpal::Stream, String> {
+    let host = cpal::default_host();
+
+    // Capturing from an output device records what it plays (WASAPI loopback)
+    let (device, config) = if device_name.is_empty() {
+        let device = host
+            .default_output_device()
+            .ok_or("No output device to capture")?;
+        let config = device.default_output_config();
+        (device, config)
+    } else if let Some(device) = host
+        .output_devices()
+        .ok()
+        .and_then(|devices| find_device(devices, device_name))
+    {
+        let config = device.default_output_config();
+        (device, config)
+    } else if let Some(device) = host
+        .input_devices()
+        .ok()
+        .and_then(|devices| find_device(devices, device_name))
+    {
+        let config = device.default_input_config();
+        (device, config)
+    } else {
+        return Err(format!("Audio device {} not found", device_name));
+    };
+    let config = config.map_err(|e| format!("Failed to read the device format: {}", e))?;
+
+    let stream = match config.sample_format() {
+        SampleFormat::F32 => build_stream::<f32>(&device, &config.config(), audio),
+        SampleFormat::I16 => build_stream::<i16>(&device, &config.config(), audio),
+        SampleFormat::U16 => build_stream::<u16>(&device, &config.config(), audio),
+        other => Err(format!("Unsupported sample format {:?}", other)),
+    }?;
+
+    stream
+        .play()
+        .map_err(|e| format!("Failed to start capturing: {}", e))?;
+    Ok(stream)
+}
+
+// Streams aren't Send, so each capture lives on its own thread until stopped. A device that
+// can't be opened goes to `failed` before `audio` closes, so the pipeline ends with its error.
+fn capture(
+    device_name: String,
+    audio: mpsc::Sender<Vec<u8>>,
+    stop: Arc<AtomicBool>,
+    failed: oneshot::Sender<String>,
+) {
+    thread::spawn(move || {
+        let stream = match open_stream(&device_name, audio.clone()) {
+            Ok(stream) => stream,
+            Err(e) => {
+                let _ = failed.send(e);
+                return;
+            }
+        };
+        drop(audio);
+
+        log::info!(
+            "[INCOMING] Capturing {}",
+            if device_name.is_empty() {
+                "the default output device"
+            } else {
+                &device_name
+            }
+        );
+        while !stop.load(Ordering::Relaxed) {
+            thread::sleep(Duration::from_millis(200));
+        }
+        drop(stream);
+    });
+}
+
+async fn qwen_transcripts(
+    app: &AppHandle,
+    language: &str,
+    mut audio: mpsc::Receiver<Vec<u8>>,
+    transcripts: mpsc::Sender<String>,
+) -> Result<(), String> {
+    let api_key = secrets::get(secrets::QWEN_ASR_API_KEY)?
+        .ok_or_else(|| "Qwen ASR API key is not set".to_string())?;
+
+    let mut request = QWEN_URL
+        .into_client_request()
+        .map_err(|e| format!("Failed to build request: {}", e))?;
+    let headers = request.headers_mut();
+    headers.insert(
+        "Authorization",
+        format!("Bearer {}", api_key)
+            .parse()
+            .map_err(|_| "Invalid API key".to_string())?,
+    );
+    headers.insert("OpenAI-Beta", http::HeaderValue::from_static("realtime=v1"));
+
+    let (ws, _) = network::connect_websocket(app, request)
+        .await
+        .map_err(|e| format!("Failed to connect to Qwen ASR: {}", e))?;
+    let (mut write, mut read) = ws.split();
+
+    let session = json!({
+        "type": "session.update",
+        "session": {
+            "modalities": ["text"],
+            "input_audio_format": "pcm",
+            "sample_rate": SAMPLE_RATE,
+            "input_audio_transcription": { "language": languages::primary(language) },
+            "turn_detection": { "type": "server_vad", "threshold": 0.2, "silence_duration_ms": 800 },
+        }
+    });
+    write
+        .send(Message::Text(session.to_string()))
+        .await
+        .map_err(|e| format!("Failed to configure Qwen ASR: {}", e))?;
+
+    log::info!("[INCOMING] Connected to Qwen ASR");
+
+    let mut ping = network::keepalive_interval(app);
+
+    loop {
+        tokio::select! {
+            _ = ping.tick() => {
+                write
+                    .send(Message::Ping(Vec::new()))
+                    .await
+                    .map_err(|e| format!("Failed to ping Qwen ASR: {}", e))?;
+            }
+            chunk = audio.recv() => {
+                let chunk = match chunk {
+                    Some(chunk) => chunk,
+                    None => return Ok(()),
+                };
+
+                quota::ensure_available(app, usage::QWEN_ASR)?;
+                let seconds = chunk.len() as f64 / (SAMPLE_RATE as f64 * 2.0);
+                let append = json!({ "type": "input_audio_buffer.append", "audio": BASE64.encode(chunk) });
+                write
+                    .send(Message::Text(append.to_string()))
+                    .await
+                    .map_err(|e| format!("Failed to send audio to Qwen ASR: {}", e))?;
+
+                app.state::<UsageState>().add(usage::QWEN_ASR, 0, seconds);
+            }
+            message = read.next() => match message {
+                Some(Ok(Message::Text(text))) => {
+                    let event: Value = serde_json::from_str(&text).unwrap_or_default();
+                    if event["type"] == "conversation.item.input_audio_transcription.completed" {
+                        if let Some(transcript) = event["transcript"].as_str() {
+                            let _ = transcripts.send(transcript.to_string()).await;
+                        }
+                    } else if event["type"] == "error" {
+                        log::warn!("[INCOMING] Qwen ASR error: {}", event["error"]);
+                    }
+                }
+                Some(Ok(Message::Close(_))) | None => return Err("Qwen ASR closed the connection".to_string()),
+                Some(Err(e)) => return Err(format!("Qwen ASR connection failed: {}", e)),
+                Some(Ok(_)) => {}
+            }
+        }
+    }
+}
+
+async fn grpc_transcripts(
+    app: &AppHandle,
+    language: &str,
+    mut audio: mpsc::Receiver<Vec<u8>>,
+    transcripts: mpsc::Sender<String>,
+) -> Result<(), String> {
+    let endpoint = app.state::<SettingsState>().get().grpc_asr.endpoint;
+    let (sender, mut responses) = grpc_asr::recognize(&endpoint, language.to_string(), "").await?;
+
+    log::info!("[INCOMING] Connected to the ASR engine at {}", endpoint);
+
+    loop {
+        tokio::select! {
+            chunk = audio.recv() => match chunk {
+                Some(audio) => {
+                    let request = AudioRequest { audio, ..Default::default() };
+                    if sender.try_send(request).is_err() {
+                        log::debug!("[INCOMING] ASR engine is falling behind, dropped an audio chunk");
+                    }
+                }
+                None => return Ok(()),
+            },
+            response = responses.message() => match response {
+                Ok(Some(response)) if response.r#final => {
+                    let _ = transcripts.send(response.text).await;
+                }
+                Ok(Some(_)) => {}
+                Ok(None) => return Err("ASR engine closed the stream".to_string()),
+                Err(e) => return Err(format!("ASR engine failed: {}", e.message())),
+            }
+        }
+    }
+}
+
+async fn translate(
+    client: &reqwest::Client,
+    text: &str,
+    source: &str,
+    target: &str,
+) -> Result<String, String> {
+    let response: Value = client
+        .get("https://translate.googleapis.com/translate_a/single")
+        .query(&[
+            ("client", "gtx"),
+            ("sl", source),
+            ("tl", target),
+            ("dt", "t"),
+            ("dj", "1"),
+            ("q", text),
+        ])
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| format!("Translation failed: {}", e))?
+        .json()
+        .await
+        .map_err(|e| format!("Invalid translation response: {}", e))?;
+
+    Ok(response["sentences"]
+        .as_array()
+        .map(|sentences| {
+            sentences
+                .iter()
+                .filter_map(|sentence| sentence["trans"].as_str())
+                .collect::<String>()
+        })
+        .unwrap_or_default())
+}
+
+pub struct Translated {
+    /// The transcript after autocorrection.
+    pub original: String,
+    pub translation: String,
+    pub provider: &'static str,
+}
+
+/// Autocorrects and translates a finished utterance, from the translation memory when it
+/// remembers one.
+pub async fn translate_utterance(
+    app: &AppHandle,
+    client: &reqwest::Client,
+    original: &str,
+    source: &str,
+    target: &str,
+) -> Result<Translated, String> {
+    let original = autocorrect::correct(app, original, source);
+    if let Some(translation) = translation_memory::lookup(app, source, target, &original) {
+        return Ok(Translated {
+            original,
+            translation,
+            provider: "memory",
+        });
+    }
+
+    let protected = symbols::protect(&original);
+    let translation =
+        network::retry(app, || translate(client, &protected.text, source, target)).await?;
+
+    Ok(Translated {
+        translation: symbols::restore(&translation, &protected.tokens),
+        original,
+        provider: "google",
+    })
+}
+
+/// Runs `provider` on 16 kHz mono PCM16 chunks from `audio` until it ends, sending each finished
+/// utterance to `transcripts`.
+pub async fn recognize(
+    app: &AppHandle,
+    provider: &IncomingProvider,
+    language: &str,
+    audio: mpsc::Receiver<Vec<u8>>,
+    transcripts: mpsc::Sender<String>,
+) -> Result<(), String> {
+    match provider {
+        IncomingProvider::Qwen => qwen_transcripts(app, language, audio, transcripts).await,
+        IncomingProvider::Grpc => grpc_transcripts(app, language, audio, transcripts).await,
+        IncomingProvider::GoogleStt => {
+            google_stt::transcripts(app, language, audio, transcripts).await
+        }
+        IncomingProvider::AwsTranscribe => {
+            aws_transcribe::transcripts(app, language, audio, transcripts).await
+        }
+        IncomingProvider::Iflytek => iflytek::transcripts(app, language, audio, transcripts).await,
+        IncomingProvider::TencentAsr => {
+            tencent_asr::transcripts(app, language, audio, transcripts).await
+        }
+        IncomingProvider::AssemblyAi => {
+            assemblyai::transcripts(app, language, audio, transcripts).await
+        }
+        IncomingProvider::Soniox => soniox::transcripts(app, language, audio, transcripts).await,
+    }
+}
+
+async fn run(
+    app: AppHandle,
+    settings: IncomingSettings,
+    audio: mpsc::Receiver<Vec<u8>>,
+    stop_capture: Arc<AtomicBool>,
+    mut capture_failed: oneshot::Receiver<String>,
+) {
+    let (transcripts_tx, mut transcripts) = mpsc::channel(16);
+
+    let recognizer = {
+        let app = app.clone();
+        let settings = settings.clone();
+        async move {
+            let result = if settings.comparison.enabled {
+                comparison::recognize(
+                    &app,
+                    &settings.provider,
+                    &settings.comparison,
+                    &settings.source_language,
+                    audio,
+                    transcripts_tx,
+                )
+                .await
+            } else {
+                recognize(
+                    &app,
+                    &settings.provider,
+                    &settings.source_language,
+                    audio,
+                    transcripts_tx,
+                )
+                .await
+            };
+
+            // Without a device the recognizer only saw the audio end
+            let result = match capture_failed.try_recv() {
+                Ok(e) => Err(e),
+                Err(_) => result,
+            };
+
+            if let Err(e) = result {
+                log::error!("[INCOMING] {}", e);
+                telemetry::record_error(&app, "incoming");
+                let _ = events::emit(&app, "incoming-error", e);
+            }
+        }
+    };
+    shutdown::spawn(&app, "incoming", recognizer);
+
+    let client = network::http_client(&app);
+    while let Some(original) = transcripts.recv().await {
+        let original = original.trim().to_string();
+        if original.is_empty() {
+            continue;
+        }
+
+        let translated = match translate_utterance(
+            &app,
+            &client,
+            &original,
+            &settings.source_language,
+            &settings.target_language,
+        )
+        .await
+        {
+            Ok(translated) => translated,
+            Err(e) => {
+                log::warn!("[INCOMING] {}", e);
+                continue;
+            }
+        };
+
+        // Not recorded in the history, everything following it would treat it as my own speech
+        let entry = HistoryEntry {
+            id: 0,
+            session_id: app.state::<HistoryState>().session_id(),
+            timestamp: chrono::Utc::now().timestamp_millis(),
+            source_language: settings.source_language.clone(),
+            target_language: settings.target_language.clone(),
+            asr_provider: settings.provider.name().to_string(),
+            translation_provider: translated.provider.to_string(),
+            original: translated.original,
+            translation: translated.translation,
+        };
+
+        let _ = events::emit(&app, "incoming-translation", &entry);
+        let _ = app.state::<IncomingState>().translated.send(entry);
+    }
+
+    stop_capture.store(true, Ordering::Relaxed);
+}
@@ -0,0 +1,144 @@
+//! Takes text from other speech-to-text tools on this machine and translates it like my own
+//! speech.
+//!
+//! WebSocket clients have to pass `?token=` from `get_ingest_url`, and requests carrying an
+//! `Origin` header are refused, so web pages open in a browser can't post into the chatbox.
+//! UDP has no handshake to carry a token and is unauthenticated, but browsers can't send UDP and
+//! the socket only listens on loopback. Ingest as a whole stays off until enabled.
+
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tauri::{AppHandle, Manager, State};
+use tokio::net::{TcpListener, TcpStream, UdpSocket};
+use tokio_tungstenite::tungstenite::protocol::Message;
+
+use crate::events;
+use crate::local_server;
+use crate::settings::SettingsState;
+
+// Longer lines are cut, the chatbox holds 144 characters anyway
+const MAX_LINE: usize = 1024;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct IngestSettings {
+    pub enabled: bool,
+    /// Plain text lines over UDP, and over WebSocket on the same TCP port.
+    pub port: u16,
+    /// Required by WebSocket clients, generated on first start.
+    pub token: String,
+}
+
+impl Default for IngestSettings {
+    fn default() -> Self {
+        IngestSettings {
+            enabled: false,
+            port: 9010,
+            token: String::new(),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct IngestState {
+    server: local_server::Server,
+}
+
+/// Starts, restarts or stops the text ingest to match the current settings.
+pub fn apply(app: &AppHandle) {
+    let settings =
+        local_server::ensure_token(app, "ingest", |settings| &mut settings.ingest.token).ingest;
+    app.state::<IngestState>().server.follow(
+        app,
+        "ingest",
+        settings.enabled.then_some(settings.port),
+        &settings.token,
+        serve,
+    );
+}
+
+// Each line is handled like a finished recognition result
+fn submit(app: &AppHandle, text: &str) {
+    for line in text.lines() {
+        let line: String = line.trim().chars().take(MAX_LINE).collect();
+        if !line.is_empty() {
+            let _ = events::emit(
+                app,
+                "remote-control",
+                json!({ "action": "translate", "text": line }),
+            );
+        }
+    }
+}
+
+async fn serve(app: AppHandle, port: u16, connections: local_server::Connections) {
+    // Other speech-to-text tools run on this machine, nothing outside may inject text
+    let (udp, tcp) = match tokio::try_join!(
+        UdpSocket::bind(("127.0.0.1", port)),
+        TcpListener::bind(("127.0.0.1", port))
+    ) {
+        Ok(sockets) => sockets,
+        Err(e) => {
+            log::error!("[INGEST] Failed to listen on port {}: {}", port, e);
+            return;
+        }
+    };
+
+    log::info!(
+        "[INGEST] Accepting text on udp://127.0.0.1:{0} and ws://127.0.0.1:{0}",
+        port
+    );
+    let mut buf = [0u8; 4096];
+
+    loop {
+        tokio::select! {
+            received = udp.recv_from(&mut buf) => match received {
+                Ok((size, _)) => submit(&app, &String::from_utf8_lossy(&buf[..size])),
+                Err(e) => log::debug!("[INGEST] {}", e),
+            },
+            accepted = tcp.accept() => match accepted {
+                Ok((stream, _)) => {
+                    let connection = read_websocket(app.clone(), stream);
+                    local_server::spawn_connection(&app, "ingest", &connections, connection);
+                }
+                Err(e) => log::warn!("[INGEST] Failed to accept connection: {}", e),
+            },
+        }
+    }
+}
+
+async fn read_websocket(app: AppHandle, stream: TcpStream) -> Result<(), String> {
+    let token = app.state::<SettingsState>().get().ingest.token;
+    let mut ws = local_server::accept_websocket(stream, |request| {
+        // Browsers always send an Origin, tools talking to us directly don't
+        !request.headers().contains_key(http::header::ORIGIN)
+            && local_server::has_token(local_server::request_path(request), "token", &token)
+    })
+    .await
+    .map_err(|e| format!("Rejected ingest client: {}", e))?;
+
+    while let Some(message) = ws.next().await {
+        match message {
+            Ok(Message::Text(text)) => {
+                if !local_server::still_valid(&app, |settings| &settings.ingest.token, &token) {
+                    return Err("Closed ingest client, its token was replaced".to_string());
+                }
+                submit(&app, &text)
+            }
+            Ok(Message::Close(_)) | Err(_) => break,
+            Ok(_) => {}
+        }
+    }
+
+    Ok(())
+}
+
+/// WebSocket URL for other tools to send text to, `None` while ingest is disabled.
+#[tauri::command]
+pub fn get_ingest_url(state: State<'_, SettingsState>) -> Option<String> {
+    let ingest = state.get().ingest;
+    ingest
+        .enabled
+        .then(|| format!("ws://127.0.0.1:{}/?token={}", ingest.port, ingest.token))
+}
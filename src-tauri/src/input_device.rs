@@ -0,0 +1,53 @@
+use regex::RegexBuilder;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, State};
+
+use crate::settings::SettingsState;
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InputDeviceSettings {
+    /// Case-insensitive regexes matched against microphone names, e.g. `Index HMD|Quest`.
+    /// Earlier patterns win, no match keeps the system default.
+    pub preferred_patterns: Vec<String>,
+    /// Fixed delay between speaking and the audio reaching us, Bluetooth headsets add a few
+    /// hundred ms. Capture times are moved back by this much.
+    pub capture_offset_ms: u64,
+}
+
+/// When the speech captured at `captured_at` (Unix ms) was actually spoken.
+pub fn compensate(app: &AppHandle, captured_at: u64) -> u64 {
+    let offset = app
+        .state::<SettingsState>()
+        .get()
+        .input_device
+        .capture_offset_ms;
+    captured_at.saturating_sub(offset)
+}
+
+/// Index of the first name matching the earliest pattern that matches anything.
+pub fn pick(patterns: &[String], names: &[String]) -> Option<usize> {
+    patterns
+        .iter()
+        .filter(|pattern| !pattern.trim().is_empty())
+        .filter_map(|pattern| {
+            match RegexBuilder::new(pattern.trim())
+                .case_insensitive(true)
+                .build()
+            {
+                Ok(regex) => Some(regex),
+                Err(e) => {
+                    log::warn!("[INPUT-DEVICE] Ignoring invalid pattern {}: {}", pattern, e);
+                    None
+                }
+            }
+        })
+        .find_map(|regex| names.iter().position(|name| regex.is_match(name)))
+}
+
+/// Picks the microphone to capture from the labels the webview enumerated, `None` means the
+/// system default. Called at startup and whenever the device list changes.
+#[tauri::command]
+pub fn pick_input_device(state: State<'_, SettingsState>, labels: Vec<String>) -> Option<usize> {
+    pick(&state.get().input_device.preferred_patterns, &labels)
+}
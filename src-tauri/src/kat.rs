@@ -0,0 +1,195 @@
+//! KillFrenzy Avatar Text output: writes translations onto avatars with a KAT text panel.
+//!
+//! KAT has no string parameter, the text is synced a few characters at a time. Each
+//! `KAT_CharSync<n>` float carries one character, and `KAT_Pointer` says which block of the
+//! panel they belong to. VRChat only syncs parameters a few times a second, so blocks are
+//! sent one after another with a pause in between, and only those that changed.
+
+use rosc::OscType;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tokio::sync::watch;
+
+use crate::headset;
+use crate::osc;
+use crate::settings::SettingsState;
+use crate::shutdown;
+
+const PARAMETER_PREFIX: &str = "/avatar/parameters/";
+
+// Pointer value that wipes the panel
+const POINTER_CLEAR: i32 = 255;
+
+// Slower than this and remote players see blocks arrive out of order
+const SYNC_DELAY: Duration = Duration::from_millis(250);
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KatSettings {
+    pub enabled: bool,
+    /// Keep sending to the chatbox as well.
+    pub also_chatbox: bool,
+    /// `KAT_CharSync` parameters on the avatar, 4, 8 or 16 depending on how it was set up.
+    pub sync_params: usize,
+    pub line_length: usize,
+    pub line_count: usize,
+}
+
+impl Default for KatSettings {
+    fn default() -> Self {
+        KatSettings {
+            enabled: false,
+            also_chatbox: true,
+            sync_params: 4,
+            line_length: 32,
+            line_count: 4,
+        }
+    }
+}
+
+pub struct KatState {
+    text: watch::Sender<String>,
+}
+
+impl Default for KatState {
+    fn default() -> Self {
+        KatState {
+            text: watch::channel(String::new()).0,
+        }
+    }
+}
+
+/// Replaces what the panel shows, the sync picks it up between blocks.
+pub fn show(app: &AppHandle, text: &str) {
+    if headset::paused(app) || osc::output_held(app) {
+        return;
+    }
+
+    app.state::<KatState>().text.send_replace(text.to_string());
+}
+
+pub fn start(app: &AppHandle) {
+    let mut text = app.state::<KatState>().text.subscribe();
+    let app = app.clone();
+
+    shutdown::spawn(&app.clone(), "kat", async move {
+        // What the panel shows as far as we know, a fresh avatar starts out blank
+        let mut shown: Vec<u8> = Vec::new();
+
+        while text.changed().await.is_ok() {
+            let settings = app.state::<SettingsState>().get();
+            let kat = settings.kat;
+            let vrchat = settings.vrchat_settings;
+            let port = vrchat.osc_port.to_string();
+            let target = (vrchat.osc_address.as_str(), port.as_str());
+
+            let wanted = layout(&text.borrow_and_update(), &kat);
+            if shown.len() != wanted.len() {
+                shown = vec![0; wanted.len()];
+            }
+
+            if wanted.iter().all(|key| *key == 0) {
+                let _ = parameter(target, "KAT_Pointer", OscType::Int(POINTER_CLEAR));
+                let _ = parameter(target, "KAT_Visible", OscType::Bool(false));
+                shown.iter_mut().for_each(|key| *key = 0);
+                continue;
+            }
+
+            if let Err(e) = parameter(target, "KAT_Visible", OscType::Bool(true)) {
+                log::warn!("[KAT] {}", e);
+                continue;
+            }
+
+            let sync_params = kat.sync_params.max(1);
+            for (block, keys) in wanted.chunks(sync_params).enumerate() {
+                let start = block * sync_params;
+                if shown[start..start + keys.len()] == *keys {
+                    continue;
+                }
+
+                // A newer translation arrived, start over with that one
+                if text.has_changed().unwrap_or(true) {
+                    break;
+                }
+
+                for (index, key) in keys.iter().enumerate() {
+                    let _ = parameter(
+                        target,
+                        &format!("KAT_CharSync{}", index),
+                        OscType::Float(value(*key)),
+                    );
+                }
+                let _ = parameter(target, "KAT_Pointer", OscType::Int(block as i32 + 1));
+                shown[start..start + keys.len()].copy_from_slice(keys);
+
+                tokio::time::sleep(SYNC_DELAY).await;
+            }
+        }
+    });
+}
+
+fn parameter(target: (&str, &str), name: &str, value: OscType) -> Result<(), String> {
+    let target = osc::resolve(target.0, target.1)?;
+    osc::send(
+        target,
+        &format!("{}{}", PARAMETER_PREFIX, name),
+        vec![value],
+    )
+}
+
+// Lays the text out on the panel, wrapping words and padding every line to full length
+fn layout(text: &str, settings: &KatSettings) -> Vec<u8> {
+    let line_length = settings.line_length.max(1);
+    let mut lines: Vec<Vec<u8>> = Vec::new();
+
+    for paragraph in text.lines() {
+        let mut line: Vec<u8> = Vec::new();
+        for word in paragraph.split_whitespace() {
+            let word: Vec<u8> = word.chars().map(key).collect();
+            if !line.is_empty() && line.len() + 1 + word.len() > line_length {
+                lines.push(std::mem::take(&mut line));
+            }
+            if !line.is_empty() {
+                line.push(0);
+            }
+            line.extend(word);
+            // Words longer than a line are cut wherever the line ends
+            while line.len() > line_length {
+                let rest = line.split_off(line_length);
+                lines.push(std::mem::replace(&mut line, rest));
+            }
+        }
+        lines.push(line);
+    }
+
+    let mut keys: Vec<u8> = lines
+        .into_iter()
+        .take(settings.line_count.max(1))
+        .flat_map(|mut line| {
+            line.resize(line_length, 0);
+            line
+        })
+        .collect();
+    keys.resize(line_length * settings.line_count.max(1), 0);
+    keys
+}
+
+// KAT's character table starts at the space and follows printable ASCII, characters the
+// panel has no glyph for become question marks
+fn key(c: char) -> u8 {
+    match c {
+        ' '..='~' => c as u8 - b' ',
+        _ => b'?' - b' ',
+    }
+}
+
+// Keys are sent as floats between -1 and 1, the upper half of the table wraps to negative
+fn value(key: u8) -> f32 {
+    let key = if key > 127 {
+        key as i32 - 256
+    } else {
+        key as i32
+    };
+    key as f32 / 127.0
+}
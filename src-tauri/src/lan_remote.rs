@@ -0,0 +1,233 @@
+use futures_util::{SinkExt, StreamExt};
+use qrcode::render::svg;
+use qrcode::QrCode;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::net::{IpAddr, UdpSocket};
+use std::time::Duration;
+use tauri::{AppHandle, Manager, State};
+use tokio::net::TcpStream;
+use tokio::sync::broadcast::error::RecvError;
+use tokio_tungstenite::tungstenite::protocol::Message;
+
+use crate::events;
+use crate::history::HistoryState;
+use crate::local_server;
+use crate::osc::{self, ChatboxState};
+use crate::settings::SettingsState;
+use crate::tray::TrayState;
+
+const REMOTE_PAGE: &str = include_str!("lan_remote.html");
+
+// The paused flag only reaches the backend through the tray, poll rather than wire up another event
+const STATUS_INTERVAL: Duration = Duration::from_secs(2);
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LanRemoteSettings {
+    pub enabled: bool,
+    pub port: u16,
+    /// Part of the pairing URL, generated on first start.
+    pub token: String,
+    /// Shown as buttons on the phone.
+    pub quick_messages: Vec<String>,
+}
+
+impl Default for LanRemoteSettings {
+    fn default() -> Self {
+        LanRemoteSettings {
+            enabled: false,
+            port: 7880,
+            token: String::new(),
+            quick_messages: vec![
+                "brb".to_string(),
+                "AFK for a bit".to_string(),
+                "Can't talk right now".to_string(),
+            ],
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct LanRemoteState {
+    server: local_server::Server,
+}
+
+/// What the phone page sends over the WebSocket.
+#[derive(Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+enum PhoneCommand {
+    Pause,
+    Resume,
+    Chatbox { text: String },
+    SetLanguages { source: String, target: String },
+}
+
+/// Starts, restarts or stops the LAN remote to match the current settings.
+pub fn apply(app: &AppHandle) {
+    let settings =
+        local_server::ensure_token(app, "lan_remote", |settings| &mut settings.lan_remote.token)
+            .lan_remote;
+    app.state::<LanRemoteState>().server.follow(
+        app,
+        "lan_remote",
+        settings.enabled.then_some(settings.port),
+        &settings.token,
+        serve,
+    );
+}
+
+/// Address other devices on the network reach this machine at.
+fn lan_address() -> Option<IpAddr> {
+    // Nothing is sent, connecting a UDP socket only picks the interface that routes outside
+    let socket = UdpSocket::bind("0.0.0.0:0").ok()?;
+    socket.connect("192.0.2.1:80").ok()?;
+    socket.local_addr().ok().map(|address| address.ip())
+}
+
+async fn serve(app: AppHandle, port: u16, connections: local_server::Connections) {
+    // Unlike the overlay and control API this is meant for the phone, so all interfaces
+    local_server::accept(app, "lan_remote", "0.0.0.0", port, connections, handle).await
+}
+
+async fn handle(app: AppHandle, mut stream: TcpStream) -> Result<(), String> {
+    let token = app.state::<SettingsState>().get().lan_remote.token;
+    let request = local_server::peek_request(&stream).await?;
+
+    if local_server::is_websocket(&request) {
+        return remote_session(app, stream, token).await;
+    }
+
+    let path = request.split_whitespace().nth(1).unwrap_or("/");
+    let allowed = local_server::has_token(path, "token", &token);
+    local_server::send_page(&mut stream, REMOTE_PAGE, allowed).await
+}
+
+fn status(app: &AppHandle) -> Value {
+    let settings = app.state::<SettingsState>().get();
+    let latest = app
+        .state::<HistoryState>()
+        .latest()
+        .ok()
+        .flatten()
+        .map(|entry| json!({ "original": entry.original, "translation": entry.translation }));
+
+    json!({
+        "paused": app.try_state::<TrayState>().map_or(false, |tray| tray.paused()),
+        "source_language": settings.source_language,
+        "target_language": settings.target_language,
+        "quick_messages": settings.lan_remote.quick_messages,
+        "latest": latest,
+    })
+}
+
+fn run_command(app: &AppHandle, command: PhoneCommand) -> Result<(), String> {
+    let remote_control = |payload: Value| {
+        let _ = events::emit(app, "remote-control", payload);
+    };
+
+    match command {
+        PhoneCommand::Pause => remote_control(json!({ "action": "pause", "text": null })),
+        PhoneCommand::Resume => remote_control(json!({ "action": "resume", "text": null })),
+        PhoneCommand::SetLanguages { source, target } => remote_control(json!({
+            "action": "set_languages",
+            "text": null,
+            "source": source,
+            "target": target,
+        })),
+        PhoneCommand::Chatbox { text } => {
+            let vrchat = app.state::<SettingsState>().get().vrchat_settings;
+            osc::send_message(
+                app.clone(),
+                app.state::<ChatboxState>(),
+                text,
+                vrchat.osc_address,
+                vrchat.osc_port.to_string(),
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+async fn remote_session(app: AppHandle, stream: TcpStream, token: String) -> Result<(), String> {
+    let ws = local_server::accept_websocket(stream, |request| {
+        local_server::has_token(local_server::request_path(request), "token", &token)
+    })
+    .await
+    .map_err(|e| format!("Rejected remote client: {}", e))?;
+    let (mut write, mut read) = ws.split();
+
+    log::info!("[LAN REMOTE] Phone connected");
+
+    let mut recorded = app.state::<HistoryState>().subscribe();
+    let mut ticker = tokio::time::interval(STATUS_INTERVAL);
+
+    loop {
+        let reply = tokio::select! {
+            _ = ticker.tick() => status(&app),
+            entry = recorded.recv() => match entry {
+                Ok(_) | Err(RecvError::Lagged(_)) => status(&app),
+                Err(RecvError::Closed) => return Ok(()),
+            },
+            message = read.next() => match message {
+                Some(Ok(Message::Text(text))) => {
+                    if !local_server::still_valid(&app, |settings| &settings.lan_remote.token, &token) {
+                        return Err("Unpaired phone, its token was replaced".to_string());
+                    }
+
+                    let result = serde_json::from_str::<PhoneCommand>(&text)
+                        .map_err(|e| format!("Unknown command: {}", e))
+                        .and_then(|command| run_command(&app, command));
+                    match result {
+                        Ok(()) => status(&app),
+                        Err(e) => json!({ "error": e }),
+                    }
+                }
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => {
+                    log::info!("[LAN REMOTE] Phone disconnected");
+                    return Ok(());
+                }
+                Some(Ok(_)) => continue,
+            }
+        };
+
+        write
+            .send(Message::Text(reply.to_string()))
+            .await
+            .map_err(|e| format!("Remote client dropped: {}", e))?;
+    }
+}
+
+/// Pairing URL for the phone plus the same URL as an SVG QR code, `None` while the remote is disabled.
+#[tauri::command]
+pub fn get_lan_remote_pairing(state: State<'_, SettingsState>) -> Result<Option<Value>, String> {
+    let remote = state.get().lan_remote;
+    if !remote.enabled {
+        return Ok(None);
+    }
+
+    let address = lan_address().ok_or("Not connected to a network")?;
+    let url = format!("http://{}:{}/?token={}", address, remote.port, remote.token);
+    let qr = QrCode::new(url.as_bytes())
+        .map_err(|e| format!("Failed to create the QR code: {}", e))?
+        .render::<svg::Color>()
+        .min_dimensions(240, 240)
+        .build();
+
+    Ok(Some(json!({ "url": url, "qr_svg": qr })))
+}
+
+/// Unpairs every phone, they need to scan the new QR code.
+#[tauri::command]
+pub fn regenerate_lan_remote_token(
+    app: AppHandle,
+    state: State<'_, SettingsState>,
+) -> Result<Option<Value>, String> {
+    let mut settings = state.get();
+    settings.lan_remote.token = local_server::new_token();
+    state.set(settings)?;
+    apply(&app);
+
+    get_lan_remote_pairing(state)
+}
@@ -0,0 +1,376 @@
+//! Follows me when I switch between the languages I speak, swapping the recognizer language
+//! and the translation direction without going through the language menus.
+//!
+//! Every utterance gets a small spectral embedding of how I sound: the average shape and spread
+//! of the spectrum over the voiced frames, plus how noisy they are. Most people hold their voice
+//! differently in each language, so each language gets a profile learned from what I say while
+//! it's selected, so switch by hand a few times at first. A transcript in the script of another
+//! of the languages, like kana or hangul from a multilingual recognizer, decides on its own and
+//! trains that language's profile too.
+//!
+//! A switch needs `switch_after` utterances in a row that sound closer to the other language by
+//! `margin`, so a single odd sentence doesn't flip the direction back and forth.
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, State};
+
+use crate::events;
+use crate::paths;
+use crate::settings::SettingsState;
+
+const PROFILE_FILE: &str = "voice_profiles.json";
+
+const SAMPLE_RATE: f32 = 16000.0;
+// 25 ms frames
+const FRAME: usize = 400;
+const BANDS: usize = 20;
+const LOWEST_BAND: f32 = 150.0;
+const HIGHEST_BAND: f32 = 4000.0;
+/// RMS of 16 bit samples below which a frame counts as silence.
+const SILENCE: f32 = 300.0;
+/// Voiced frames an utterance needs before its embedding means anything, half a second.
+const MIN_FRAMES: u32 = 20;
+/// Utterances a profile needs before it's compared against.
+const MIN_UTTERANCES: u32 = 5;
+/// Later utterances keep moving a profile at least this much, so it follows a changing voice.
+const MAX_WEIGHT: u32 = 200;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LanguageSwitchSettings {
+    pub enabled: bool,
+    /// Recognizer language codes I switch between, such as `en-US` and `ja-JP`.
+    pub languages: Vec<String>,
+    /// Utterances in a row that have to sound like another language before switching to it.
+    pub switch_after: u32,
+    /// How much closer, as a fraction, the other language's profile has to be.
+    pub margin: f32,
+}
+
+impl Default for LanguageSwitchSettings {
+    fn default() -> Self {
+        LanguageSwitchSettings {
+            enabled: false,
+            languages: Vec::new(),
+            switch_after: 2,
+            margin: 0.1,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct VoiceProfile {
+    pub centroid: Vec<f32>,
+    pub utterances: u32,
+}
+
+#[derive(Default)]
+struct Utterance {
+    // Samples left over from the last chunk that didn't fill a frame
+    pending: Vec<i16>,
+    sum: [f32; BANDS],
+    squares: [f32; BANDS],
+    crossings: f32,
+    frames: u32,
+}
+
+#[derive(Default)]
+pub struct LanguageSwitchState {
+    utterance: Mutex<Utterance>,
+    profiles: Mutex<Option<BTreeMap<String, VoiceProfile>>>,
+    /// Language the last utterances sounded like instead of the selected one, and how many.
+    streak: Mutex<Option<(String, u32)>>,
+}
+
+fn profile_path(app: &AppHandle) -> PathBuf {
+    paths::data_dir(app).join(PROFILE_FILE)
+}
+
+fn band_frequencies() -> [f32; BANDS] {
+    let ratio = (HIGHEST_BAND / LOWEST_BAND).powf(1.0 / (BANDS - 1) as f32);
+    std::array::from_fn(|i| LOWEST_BAND * ratio.powi(i as i32))
+}
+
+/// Power of `samples` at `frequency`, using the Goertzel algorithm instead of a full FFT.
+fn goertzel(samples: &[f32], frequency: f32) -> f32 {
+    let coefficient = 2.0 * (2.0 * std::f32::consts::PI * frequency / SAMPLE_RATE).cos();
+    let (mut previous, mut before) = (0.0, 0.0);
+    for sample in samples {
+        let current = sample + coefficient * previous - before;
+        before = previous;
+        previous = current;
+    }
+
+    previous * previous + before * before - coefficient * previous * before
+}
+
+impl Utterance {
+    fn push(&mut self, audio: &[u8]) {
+        self.pending.extend(
+            audio
+                .chunks_exact(2)
+                .map(|pair| i16::from_le_bytes([pair[0], pair[1]])),
+        );
+
+        let frequencies = band_frequencies();
+        let frames = self.pending.len() / FRAME;
+        for frame in self.pending[..frames * FRAME].chunks_exact(FRAME) {
+            let samples: Vec<f32> = frame.iter().map(|&s| s as f32).collect();
+            let rms = (samples.iter().map(|s| s * s).sum::<f32>() / FRAME as f32).sqrt();
+            if rms < SILENCE {
+                continue;
+            }
+
+            // Relative to the frame's own level, so speaking louder doesn't look like a language
+            let powers = frequencies.map(|f| (goertzel(&samples, f) + 1.0).ln());
+            let level = powers.iter().sum::<f32>() / BANDS as f32;
+            for (band, power) in powers.iter().enumerate() {
+                let shape = power - level;
+                self.sum[band] += shape;
+                self.squares[band] += shape * shape;
+            }
+
+            let crossings = frame
+                .windows(2)
+                .filter(|pair| (pair[0] < 0) != (pair[1] < 0))
+                .count();
+            self.crossings += crossings as f32 / FRAME as f32;
+            self.frames += 1;
+        }
+        self.pending.drain(..frames * FRAME);
+    }
+
+    /// Mean and spread of each band's share of the spectrum, then the zero crossing rate.
+    fn embedding(&self) -> Option<Vec<f32>> {
+        if self.frames < MIN_FRAMES {
+            return None;
+        }
+
+        let frames = self.frames as f32;
+        let means = self.sum.map(|sum| sum / frames);
+        let spreads: Vec<f32> = self
+            .squares
+            .iter()
+            .zip(means)
+            .map(|(squares, mean)| (squares / frames - mean * mean).max(0.0).sqrt())
+            .collect();
+
+        let mut embedding = means.to_vec();
+        embedding.extend(spreads);
+        embedding.push(self.crossings / frames * 10.0);
+        Some(embedding)
+    }
+}
+
+fn distance(a: &[f32], b: &[f32]) -> f32 {
+    a.iter()
+        .zip(b)
+        .map(|(a, b)| (a - b) * (a - b))
+        .sum::<f32>()
+        .sqrt()
+}
+
+/// The configured language a transcript has to be in going by its script alone, if any.
+fn script_language(text: &str, languages: &[String]) -> Option<String> {
+    let script = if text.chars().any(|c| matches!(c, '\u{3040}'..='\u{30ff}')) {
+        "ja"
+    } else if text.chars().any(|c| matches!(c, '\u{ac00}'..='\u{d7af}')) {
+        "ko"
+    } else if text.chars().any(|c| matches!(c, '\u{4e00}'..='\u{9fff}')) {
+        "zh"
+    } else if text.chars().any(|c| matches!(c, '\u{0400}'..='\u{04ff}')) {
+        "ru"
+    } else if text.chars().any(|c| matches!(c, '\u{0e00}'..='\u{0e7f}')) {
+        "th"
+    } else {
+        return None;
+    };
+
+    let mut matching = languages
+        .iter()
+        .filter(|language| language.to_lowercase().starts_with(script));
+    match (matching.next(), matching.next()) {
+        (Some(language), None) => Some(language.clone()),
+        _ => None,
+    }
+}
+
+/// Translation target for a recognizer language, the same mapping the language swap button uses.
+fn target_language(source: &str) -> String {
+    if source.starts_with("en-") {
+        "en".to_string()
+    } else if source.starts_with("es-") {
+        "es".to_string()
+    } else {
+        source.to_string()
+    }
+}
+
+fn load_profiles(app: &AppHandle) -> BTreeMap<String, VoiceProfile> {
+    fs::read_to_string(profile_path(app))
+        .ok()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+fn save_profiles(app: &AppHandle, profiles: &BTreeMap<String, VoiceProfile>) {
+    let result = serde_json::to_string(profiles)
+        .map_err(|e| e.to_string())
+        .and_then(|json| fs::write(profile_path(app), json).map_err(|e| e.to_string()));
+    if let Err(e) = result {
+        log::warn!("[LANGUAGE SWITCH] Failed to save voice profiles: {}", e);
+    }
+}
+
+fn train(profile: &mut VoiceProfile, embedding: &[f32]) {
+    if profile.centroid.len() != embedding.len() {
+        *profile = VoiceProfile::default();
+        profile.centroid = vec![0.0; embedding.len()];
+    }
+
+    profile.utterances += 1;
+    let weight = profile.utterances.min(MAX_WEIGHT) as f32;
+    for (value, sample) in profile.centroid.iter_mut().zip(embedding) {
+        *value += (sample - *value) / weight;
+    }
+}
+
+/// Feeds a chunk of 16 kHz mono PCM16 audio on its way to the recognizer.
+pub fn audio(app: &AppHandle, audio: &[u8]) {
+    if !app
+        .state::<SettingsState>()
+        .read(|settings| settings.language_switch.enabled)
+    {
+        return;
+    }
+
+    app.state::<LanguageSwitchState>()
+        .utterance
+        .lock()
+        .unwrap()
+        .push(audio);
+}
+
+/// Ends the utterance the audio so far belonged to, switching languages if it's time to.
+pub fn utterance_finished(app: &AppHandle, transcript: &str) {
+    let state = app.state::<LanguageSwitchState>();
+    let utterance = std::mem::take(&mut *state.utterance.lock().unwrap());
+
+    let settings = app.state::<SettingsState>().get();
+    let switch = settings.language_switch;
+    if !switch.enabled || switch.languages.len() < 2 || transcript.trim().is_empty() {
+        return;
+    }
+    let Some(embedding) = utterance.embedding() else {
+        return;
+    };
+
+    let current = settings.source_language;
+    let mut profiles = state.profiles.lock().unwrap();
+    let profiles = profiles.get_or_insert_with(|| load_profiles(app));
+
+    // A recognizer writes its own script whatever it hears, only another one tells anything
+    let scripted = script_language(transcript, &switch.languages).filter(|l| *l != current);
+    let heard = scripted.clone().or_else(|| {
+        let current_distance = profiles
+            .get(&current)
+            .filter(|profile| profile.utterances >= MIN_UTTERANCES)
+            .map(|profile| distance(&profile.centroid, &embedding))?;
+
+        switch
+            .languages
+            .iter()
+            .filter(|language| **language != current)
+            .filter_map(|language| {
+                let profile = profiles.get(language)?;
+                (profile.utterances >= MIN_UTTERANCES)
+                    .then(|| (language, distance(&profile.centroid, &embedding)))
+            })
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .filter(|(_, distance)| *distance <= current_distance * (1.0 - switch.margin))
+            .map(|(language, _)| language.clone())
+    });
+
+    // Only learn from what's known to be in a language, guesses would reinforce themselves
+    let label = match (scripted, &heard) {
+        (Some(language), _) => Some(language),
+        (None, None) if switch.languages.contains(&current) => Some(current.clone()),
+        _ => None,
+    };
+    if let Some(label) = label {
+        train(profiles.entry(label).or_default(), &embedding);
+        save_profiles(app, profiles);
+    }
+
+    let mut streak = state.streak.lock().unwrap();
+    let language = match heard {
+        Some(language) if language != current => language,
+        _ => {
+            *streak = None;
+            return;
+        }
+    };
+
+    let count = match streak.as_ref() {
+        Some((previous, count)) if *previous == language => count + 1,
+        _ => 1,
+    };
+    if count < switch.switch_after.max(1) {
+        *streak = Some((language, count));
+        return;
+    }
+    *streak = None;
+
+    // Keep translating into the same language unless I'm now speaking it
+    let target = if target_language(&language) == settings.target_language {
+        target_language(&current)
+    } else {
+        settings.target_language
+    };
+
+    log::info!(
+        "[LANGUAGE SWITCH] Sounds like {} now, translating into {}",
+        language,
+        target
+    );
+    let _ = events::emit(
+        app,
+        "remote-control",
+        json!({ "action": "set_languages", "text": null, "source": language, "target": target }),
+    );
+}
+
+/// How many utterances each language's voice profile has learned from.
+#[tauri::command]
+pub fn voice_profiles(
+    app: AppHandle,
+    state: State<'_, LanguageSwitchState>,
+) -> BTreeMap<String, u32> {
+    let mut profiles = state.profiles.lock().unwrap();
+    profiles
+        .get_or_insert_with(|| load_profiles(&app))
+        .iter()
+        .map(|(language, profile)| (language.clone(), profile.utterances))
+        .collect()
+}
+
+#[tauri::command]
+pub fn reset_voice_profiles(
+    app: AppHandle,
+    state: State<'_, LanguageSwitchState>,
+) -> Result<(), String> {
+    *state.profiles.lock().unwrap() = Some(BTreeMap::new());
+    *state.streak.lock().unwrap() = None;
+
+    match fs::remove_file(profile_path(&app)) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+            Err(format!("Failed to reset voice profiles: {}", e))
+        }
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,5 @@
+/// The language without its region or script, "en" for "en-US". Providers map this to the
+/// codes they want when they don't take full tags.
+pub fn primary(language: &str) -> &str {
+    language.split('-').next().unwrap_or(language)
+}
@@ -0,0 +1,130 @@
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use tauri::{AppHandle, State};
+
+use crate::events;
+use crate::input_device;
+use crate::session_log;
+
+// Enough utterances to cover a few minutes of conversation
+const WINDOW: usize = 200;
+
+/// Millisecond timestamps the webview takes as an utterance moves through the pipeline.
+#[derive(Clone, Debug, Deserialize)]
+pub struct LatencySample {
+    /// When the recognizer first reported speech, moved back by the capture offset on arrival.
+    pub captured_at: u64,
+    /// When the recognizer finalized the text.
+    pub recognized_at: u64,
+    /// When the translation came back, includes the time spent in the queue.
+    pub translated_at: u64,
+    /// When the chatbox message left through OSC.
+    pub sent_at: u64,
+}
+
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct LatencyBreakdown {
+    pub recognition_ms: u64,
+    pub translation_ms: u64,
+    pub send_ms: u64,
+    pub total_ms: u64,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct Percentiles {
+    pub p50: u64,
+    pub p90: u64,
+    pub p99: u64,
+    pub max: u64,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct LatencyStats {
+    pub samples: usize,
+    pub recognition: Percentiles,
+    pub translation: Percentiles,
+    pub send: Percentiles,
+    pub total: Percentiles,
+}
+
+#[derive(Default)]
+pub struct LatencyState {
+    recent: Mutex<VecDeque<LatencyBreakdown>>,
+}
+
+impl LatencySample {
+    // Clocks only go forward within one webview, but a reordered stage shouldn't underflow
+    pub fn breakdown(&self) -> LatencyBreakdown {
+        LatencyBreakdown {
+            recognition_ms: self.recognized_at.saturating_sub(self.captured_at),
+            translation_ms: self.translated_at.saturating_sub(self.recognized_at),
+            send_ms: self.sent_at.saturating_sub(self.translated_at),
+            total_ms: self.sent_at.saturating_sub(self.captured_at),
+        }
+    }
+}
+
+fn percentiles(mut values: Vec<u64>) -> Percentiles {
+    if values.is_empty() {
+        return Percentiles::default();
+    }
+
+    values.sort_unstable();
+    let at = |p: f64| values[((values.len() - 1) as f64 * p).round() as usize];
+
+    Percentiles {
+        p50: at(0.5),
+        p90: at(0.9),
+        p99: at(0.99),
+        max: values[values.len() - 1],
+    }
+}
+
+/// Stores one utterance's timings and emits its breakdown as `latency`. `utterance_id` is
+/// the history id of the translation they belong to.
+#[tauri::command]
+pub fn record_latency(
+    app: AppHandle,
+    state: State<'_, LatencyState>,
+    mut sample: LatencySample,
+    utterance_id: Option<i64>,
+) {
+    sample.captured_at = input_device::compensate(&app, sample.captured_at);
+    let breakdown = sample.breakdown();
+    if let Some(id) = utterance_id {
+        session_log::latency(&app, id, &sample);
+    }
+
+    {
+        let mut recent = state.recent.lock().unwrap();
+        if recent.len() == WINDOW {
+            recent.pop_front();
+        }
+        recent.push_back(breakdown);
+    }
+
+    log::debug!(
+        "[LATENCY] Recognition {}ms - Translation {}ms - Send {}ms - Total {}ms",
+        breakdown.recognition_ms,
+        breakdown.translation_ms,
+        breakdown.send_ms,
+        breakdown.total_ms
+    );
+    let _ = events::emit(&app, "latency", breakdown);
+}
+
+/// Rolling percentiles over the last few hundred utterances.
+#[tauri::command]
+pub fn latency_stats(state: State<'_, LatencyState>) -> LatencyStats {
+    let recent = state.recent.lock().unwrap();
+    let stage = |f: fn(&LatencyBreakdown) -> u64| percentiles(recent.iter().map(f).collect());
+
+    LatencyStats {
+        samples: recent.len(),
+        recognition: stage(|b| b.recognition_ms),
+        translation: stage(|b| b.translation_ms),
+        send: stage(|b| b.send_ms),
+        total: stage(|b| b.total_ms),
+    }
+}
@@ -0,0 +1,286 @@
+//! What the servers other programs and devices connect to have in common: the overlay,
+//! control API, text ingest, LAN remote and companion. Each one is a task that follows its
+//! settings, and clients show they may connect with a secret kept in the settings.
+
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use tauri::async_runtime::JoinHandle;
+use tauri::{AppHandle, Manager};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio_tungstenite::tungstenite::handshake::server::{ErrorResponse, Request, Response};
+use tokio_tungstenite::WebSocketStream;
+use tokio_util::sync::CancellationToken;
+
+use crate::settings::{Settings, SettingsState};
+use crate::shutdown;
+
+const FORBIDDEN: &str = "HTTP/1.1 403 Forbidden\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+
+/// The task of one server, with the port it listens on.
+#[derive(Default)]
+pub struct Server {
+    running: Mutex<Option<(u16, JoinHandle<()>)>>,
+    /// Secret the open connections were let in with.
+    secret: Mutex<String>,
+    connections: Connections,
+}
+
+impl Server {
+    /// Starts, restarts or stops the server so it listens on `port`, `None` stopping it.
+    /// Nothing happens while it already runs on that port. Open connections are closed when
+    /// the server stops or `secret` changes, the handshake only checked the old one.
+    pub fn follow<F, Fut>(
+        &self,
+        app: &AppHandle,
+        name: &'static str,
+        port: Option<u16>,
+        secret: &str,
+        serve: F,
+    ) where
+        F: FnOnce(AppHandle, u16, Connections) -> Fut,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let mut running = self.running.lock().unwrap();
+
+        let mut current = self.secret.lock().unwrap();
+        if *current != secret {
+            if !current.is_empty() {
+                self.connections.close_all();
+                log::info!("[{}] Closed connections using the old secret", tag(name));
+            }
+            *current = secret.to_string();
+        }
+
+        if running.as_ref().map(|(port, _)| *port) == port {
+            return;
+        }
+
+        if let Some((_, task)) = running.take() {
+            task.abort();
+            self.connections.close_all();
+            log::info!("[{}] Stopped {}", tag(name), name.replace('_', " "));
+        }
+
+        if let Some(port) = port {
+            let serve = serve(app.clone(), port, self.connections.clone());
+            *running = Some((port, shutdown::spawn(app, name, serve)));
+        }
+    }
+}
+
+/// The connections of one server, all cancelled together.
+#[derive(Clone, Default)]
+pub struct Connections(Arc<Mutex<CancellationToken>>);
+
+impl Connections {
+    fn current(&self) -> CancellationToken {
+        self.0.lock().unwrap().clone()
+    }
+
+    // Connections opened from now on get a fresh token
+    fn close_all(&self) {
+        std::mem::take(&mut *self.0.lock().unwrap()).cancel();
+    }
+}
+
+// "lan_remote" logs as [LAN REMOTE]
+fn tag(name: &str) -> String {
+    name.replace('_', " ").to_uppercase()
+}
+
+pub fn new_token() -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(32)
+        .map(char::from)
+        .collect()
+}
+
+/// The settings with a token in the field `token` picks, generating and saving one on first
+/// start.
+pub fn ensure_token(
+    app: &AppHandle,
+    name: &'static str,
+    token: fn(&mut Settings) -> &mut String,
+) -> Settings {
+    ensure_secret(app, name, token, |token| !token.is_empty(), new_token)
+}
+
+/// Like `ensure_token`, for secrets with their own format. `usable` decides whether the one
+/// in the settings can stay.
+pub fn ensure_secret(
+    app: &AppHandle,
+    name: &'static str,
+    secret: fn(&mut Settings) -> &mut String,
+    usable: fn(&str) -> bool,
+    generate: fn() -> String,
+) -> Settings {
+    let settings_state = app.state::<SettingsState>();
+    let mut settings = settings_state.get();
+
+    if !usable(secret(&mut settings)) {
+        *secret(&mut settings) = generate();
+        if let Err(e) = settings_state.set(settings.clone()) {
+            log::error!(
+                "[{}] Failed to save the {} secret: {}",
+                tag(name),
+                name.replace('_', " "),
+                e
+            );
+        }
+    }
+
+    settings
+}
+
+pub fn query_param<'a>(path: &'a str, name: &str) -> Option<&'a str> {
+    path.split_once('?')?.1.split('&').find_map(|pair| {
+        let (key, value) = pair.split_once('=')?;
+        (key == name).then_some(value)
+    })
+}
+
+/// Compares without stopping at the first difference, so response times don't reveal how
+/// much of a guessed token was right.
+pub fn tokens_match(given: &str, expected: &str) -> bool {
+    given.len() == expected.len()
+        && given
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+/// Whether the query of `path` carries `expected` as `name`.
+pub fn has_token(path: &str, name: &str, expected: &str) -> bool {
+    query_param(path, name).is_some_and(|given| tokens_match(given, expected))
+}
+
+pub fn request_path(request: &Request) -> &str {
+    request
+        .uri()
+        .path_and_query()
+        .map(|path| path.as_str())
+        .unwrap_or_default()
+}
+
+/// Listens on `host` and handles every connection in a task of its own until the server is
+/// stopped.
+pub async fn accept<F, Fut>(
+    app: AppHandle,
+    name: &'static str,
+    host: &str,
+    port: u16,
+    connections: Connections,
+    handle: F,
+) where
+    F: Fn(AppHandle, TcpStream) -> Fut,
+    Fut: Future<Output = Result<(), String>> + Send + 'static,
+{
+    let listener = match TcpListener::bind((host, port)).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            log::error!("[{}] Failed to listen on port {}: {}", tag(name), port, e);
+            return;
+        }
+    };
+
+    log::info!("[{}] Listening on {}:{}", tag(name), host, port);
+
+    loop {
+        match listener.accept().await {
+            Ok((stream, _)) => {
+                spawn_connection(&app, name, &connections, handle(app.clone(), stream))
+            }
+            Err(e) => log::warn!("[{}] Failed to accept connection: {}", tag(name), e),
+        }
+    }
+}
+
+/// Runs one client's connection until it ends or `connections` are closed. Failures are
+/// mostly clients going away, so they only go to the debug log.
+pub fn spawn_connection<Fut>(
+    app: &AppHandle,
+    name: &'static str,
+    connections: &Connections,
+    connection: Fut,
+) where
+    Fut: Future<Output = Result<(), String>> + Send + 'static,
+{
+    let closed = connections.current();
+    shutdown::spawn(app, name, async move {
+        tokio::select! {
+            _ = closed.cancelled() => {}
+            result = connection => {
+                if let Err(e) = result {
+                    log::debug!("[{}] {}", tag(name), e);
+                }
+            }
+        }
+    });
+}
+
+/// Whether `given` is still the secret in the settings, for connections to check before
+/// acting on what a client sends.
+pub fn still_valid(app: &AppHandle, secret: fn(&Settings) -> &String, given: &str) -> bool {
+    app.state::<SettingsState>()
+        .read(|settings| tokens_match(given, secret(settings)))
+}
+
+/// The HTTP request waiting on `stream`, left in place so a WebSocket handshake can still
+/// read it.
+pub async fn peek_request(stream: &TcpStream) -> Result<String, String> {
+    let mut buffer = [0u8; 4096];
+    let read = stream
+        .peek(&mut buffer)
+        .await
+        .map_err(|e| format!("Failed to read request: {}", e))?;
+    Ok(String::from_utf8_lossy(&buffer[..read]).to_string())
+}
+
+pub fn is_websocket(request: &str) -> bool {
+    request.to_ascii_lowercase().contains("upgrade: websocket")
+}
+
+/// Answers the peeked request with `page`, or with 403 unless `allowed`.
+pub async fn send_page(stream: &mut TcpStream, page: &str, allowed: bool) -> Result<(), String> {
+    let mut buffer = [0u8; 4096];
+    let _ = stream.read(&mut buffer).await;
+
+    let response = if allowed {
+        format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n{}",
+            page.len(),
+            page
+        )
+    } else {
+        FORBIDDEN.to_string()
+    };
+
+    stream
+        .write_all(response.as_bytes())
+        .await
+        .map_err(|e| format!("Failed to write response: {}", e))
+}
+
+/// Completes the WebSocket handshake if `allow` accepts the request, refusing it with 403
+/// otherwise.
+pub async fn accept_websocket(
+    stream: TcpStream,
+    allow: impl FnOnce(&Request) -> bool + Unpin,
+) -> Result<WebSocketStream<TcpStream>, tokio_tungstenite::tungstenite::Error> {
+    let check = |request: &Request, response: Response| -> Result<Response, ErrorResponse> {
+        if allow(request) {
+            Ok(response)
+        } else {
+            let mut error = ErrorResponse::new(None);
+            *error.status_mut() = http::StatusCode::FORBIDDEN;
+            Err(error)
+        }
+    };
+
+    tokio_tungstenite::accept_hdr_async(stream, check).await
+}
@@ -0,0 +1,228 @@
+use chrono::{Local, NaiveDateTime, TimeZone};
+use regex::Regex;
+use serde::Serialize;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+use tauri::plugin::TauriPlugin;
+use tauri::{AppHandle, Manager, Runtime, State};
+use tauri_plugin_log::{RotationStrategy, Target, TargetKind};
+
+use crate::paths;
+use crate::redact;
+use crate::settings::SettingsState;
+
+// A debug session fills a few megabytes quickly, keep enough history to cover one
+const MAX_FILE_SIZE: u128 = 5 * 1024 * 1024;
+const KEEP_FILES: usize = 5;
+
+// Newest entries a query returns when no limit is given
+const DEFAULT_QUERY_LIMIT: usize = 1000;
+
+#[derive(Serialize)]
+pub struct LogTargets {
+    level: String,
+    directory: Option<PathBuf>,
+    max_file_size: u128,
+    kept_files: usize,
+}
+
+pub fn plugin<R: Runtime>() -> TauriPlugin<R> {
+    tauri_plugin_log::Builder::new()
+        .targets([
+            Target::new(TargetKind::Stdout),
+            Target::new(match paths::portable_log_dir() {
+                Some(path) => TargetKind::Folder {
+                    path,
+                    file_name: None,
+                },
+                None => TargetKind::LogDir { file_name: None },
+            }),
+        ])
+        .max_file_size(MAX_FILE_SIZE)
+        .rotation_strategy(RotationStrategy::KeepSome(KEEP_FILES))
+        // The effective level comes from the settings, see settings::apply_live
+        .level(log::LevelFilter::Trace)
+        .format(|out, message, record| {
+            out.finish(format_args!(
+                "{}[{}][{}] {}",
+                chrono::Local::now().format("[%Y-%m-%d][%H:%M:%S]"),
+                record.target(),
+                record.level(),
+                redact::redact(&message.to_string())
+            ))
+        })
+        .build()
+}
+
+pub fn log_dir(app: &AppHandle) -> Option<PathBuf> {
+    paths::portable_log_dir().or_else(|| app.path().app_log_dir().ok())
+}
+
+/// The current and rotated log files, oldest first.
+pub fn log_files(app: &AppHandle) -> Vec<PathBuf> {
+    let Some(dir) = log_dir(app) else {
+        return Vec::new();
+    };
+
+    let mut files: Vec<(std::time::SystemTime, PathBuf)> = fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .filter(|entry| entry.path().extension().map_or(false, |ext| ext == "log"))
+                .filter_map(|entry| Some((entry.metadata().ok()?.modified().ok()?, entry.path())))
+                .collect()
+        })
+        .unwrap_or_default();
+    files.sort();
+    files.into_iter().map(|(_, path)| path).collect()
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct LogEntry {
+    /// Unix ms, from the local time the line was written at.
+    pub timestamp: i64,
+    pub level: String,
+    pub target: String,
+    /// The `[TAG]` a message starts with, e.g. `OSC`.
+    pub subsystem: Option<String>,
+    pub message: String,
+}
+
+// "[2024-05-01][12:34:56][kikitan_translator][INFO] [OSC] Listening on 127.0.0.1:9001"
+fn parse_line(line: &str) -> Option<LogEntry> {
+    static LINE: OnceLock<Regex> = OnceLock::new();
+    let line_pattern = LINE.get_or_init(|| {
+        Regex::new(
+            r"^\[(\d{4}-\d{2}-\d{2})\]\[(\d{2}:\d{2}:\d{2})\]\[([^\]]*)\]\[([A-Z]+)\] ?(.*)$",
+        )
+        .expect("invalid log line pattern")
+    });
+    static TAG: OnceLock<Regex> = OnceLock::new();
+    let tag_pattern =
+        TAG.get_or_init(|| Regex::new(r"^\[([A-Z0-9_-]+)\]").expect("invalid log tag pattern"));
+
+    let captures = line_pattern.captures(line)?;
+    let time = NaiveDateTime::parse_from_str(
+        &format!("{} {}", &captures[1], &captures[2]),
+        "%Y-%m-%d %H:%M:%S",
+    )
+    .ok()?;
+    let message = captures[5].to_string();
+
+    Some(LogEntry {
+        timestamp: Local
+            .from_local_datetime(&time)
+            .earliest()?
+            .timestamp_millis(),
+        level: captures[4].to_string(),
+        target: captures[3].to_string(),
+        subsystem: tag_pattern.captures(&message).map(|tag| tag[1].to_string()),
+        message,
+    })
+}
+
+/// Entries from the log files, newest last. `level` is the least severe level to include,
+/// `subsystem` matches the message tag, `since` is Unix ms and `text` a case-insensitive
+/// substring. Only the newest `limit` matches are returned.
+#[tauri::command]
+pub fn query_logs(
+    app: AppHandle,
+    level: Option<String>,
+    subsystem: Option<String>,
+    since: Option<i64>,
+    text: Option<String>,
+    limit: Option<usize>,
+) -> Result<Vec<LogEntry>, String> {
+    let level = level
+        .map(|level| {
+            level
+                .trim()
+                .parse::<log::LevelFilter>()
+                .map_err(|_| format!("Unknown log level {}", level))
+        })
+        .transpose()?
+        .unwrap_or(log::LevelFilter::Trace);
+    let text = text.map(|text| text.to_lowercase());
+
+    let matches = |entry: &LogEntry| {
+        entry
+            .level
+            .parse::<log::Level>()
+            .map_or(true, |entry_level| entry_level <= level)
+            && subsystem.as_ref().map_or(true, |subsystem| {
+                entry
+                    .subsystem
+                    .as_ref()
+                    .map_or(false, |tag| tag.eq_ignore_ascii_case(subsystem))
+            })
+            && since.map_or(true, |since| entry.timestamp >= since)
+            && text
+                .as_ref()
+                .map_or(true, |text| entry.message.to_lowercase().contains(text))
+    };
+
+    let mut entries = Vec::new();
+    for file in log_files(&app) {
+        let contents = match fs::read(&file) {
+            Ok(contents) => contents,
+            Err(e) => {
+                log::debug!("[LOG] Skipping {}: {}", file.display(), e);
+                continue;
+            }
+        };
+
+        let mut current: Option<LogEntry> = None;
+        for line in String::from_utf8_lossy(&contents).lines() {
+            match parse_line(line) {
+                Some(entry) => {
+                    if let Some(done) = current.replace(entry) {
+                        if matches(&done) {
+                            entries.push(done);
+                        }
+                    }
+                }
+                // Multi-line messages such as backtraces continue on lines without a prefix
+                None => {
+                    if let Some(entry) = current.as_mut() {
+                        entry.message.push('\n');
+                        entry.message.push_str(line);
+                    }
+                }
+            }
+        }
+        if let Some(done) = current.filter(|entry| matches(entry)) {
+            entries.push(done);
+        }
+    }
+
+    let limit = limit.unwrap_or(DEFAULT_QUERY_LIMIT);
+    let skip = entries.len().saturating_sub(limit);
+    Ok(entries.split_off(skip))
+}
+
+/// Changes the log level right away and keeps it for the next start.
+#[tauri::command]
+pub fn set_log_level(state: State<'_, SettingsState>, level: String) -> Result<(), String> {
+    let level = level.trim().to_lowercase();
+    level
+        .parse::<log::LevelFilter>()
+        .map_err(|_| format!("Unknown log level {}", level))?;
+
+    let mut settings = state.get();
+    settings.log_level = level;
+    state.set(settings)?;
+
+    log::info!("[LOG] Log level set to {}", log::max_level());
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_log_targets(app: AppHandle, state: State<'_, SettingsState>) -> LogTargets {
+    LogTargets {
+        level: state.get().log_level().to_string().to_lowercase(),
+        directory: log_dir(&app),
+        max_file_size: MAX_FILE_SIZE,
+        kept_files: KEEP_FILES,
+    }
+}
@@ -1,77 +1,383 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-use rosc::encoder;
-use rosc::{OscMessage, OscPacket, OscType};
-use std::net::{Ipv4Addr, UdpSocket};
-use std::thread;
-use tauri::{AppHandle, Emitter, State};
-use std::sync::{Arc, Mutex};
-use tokio_tungstenite::{connect_async, tungstenite::protocol::Message};
+use tauri::{AppHandle, Manager, RunEvent, State, WindowEvent};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use tokio_tungstenite::tungstenite::protocol::Message;
 use futures_util::{SinkExt, StreamExt};
 use http::Request;
 
-#[cfg(target_os = "windows")]
+use errors::{AppError, ErrorCode};
+
+#[cfg(any(target_os = "windows", target_os = "macos", target_os = "linux"))]
 use std::process::Command;
 
-static mut LISTENER_STARTED: bool = false;
+mod asr_providers;
+mod assemblyai;
+mod autocorrect;
+mod autostart;
+mod aws_transcribe;
+mod backups;
+mod clipboard;
+mod companion;
+mod comparison;
+mod config_watch;
+mod control_api;
+mod crash;
+mod database;
+mod diagnostics;
+mod diagnostics_bundle;
+mod discord;
+mod encoding;
+mod errors;
+mod events;
+mod file_transcription;
+mod formatting;
+mod google_stt;
+mod grpc_asr;
+mod harness;
+mod headset;
+mod history;
+mod hotkeys;
+mod iflytek;
+mod incoming;
+mod ingest;
+mod input_device;
+mod kat;
+mod lan_remote;
+mod language_switch;
+mod languages;
+mod latency;
+mod local_server;
+mod mqtt;
+mod logging;
+mod markdown_export;
+mod network;
+mod notifications;
+mod now_playing;
+mod obs;
+mod osc;
+mod overlay;
+mod paths;
+mod player_languages;
+mod plugins;
+mod portable_update;
+mod power;
+mod profiles;
+mod quota;
+mod redact;
+mod resources;
+mod runtime;
+mod secrets;
+mod session_log;
+mod settings;
+mod settings_bundle;
+mod shutdown;
+mod soniox;
+mod subtitle_export;
+mod subtitles;
+mod symbols;
+mod telemetry;
+mod tencent_asr;
+mod translation_memory;
+mod tray;
+mod twitch;
+mod typewriter;
+mod updater;
+mod usage;
+mod voice_commands;
+mod vr_controller;
+mod vr_notifications;
+mod vrchat;
+mod vrchat_log;
+mod watchdog;
+mod webhooks;
+mod widgets;
+mod window;
+mod youtube;
+
+// A message for the writer task, with where to report whether it went out
+type QwenOutgoing = (Message, tokio::sync::oneshot::Sender<Result<(), String>>);
 
 // WebSocket connection state
 struct QwenWsState {
-    sender: Arc<Mutex<Option<futures_util::stream::SplitSink<tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>>, Message>>>>,
+    // Feeds the writer task, which owns the sink so audio and keepalive pings queue up for it
+    // instead of taking turns holding it
+    sender: Mutex<Option<tokio::sync::mpsc::Sender<QwenOutgoing>>>,
+    reader: Mutex<Option<tauri::async_runtime::JoinHandle<()>>>,
+    writer: Mutex<Option<tauri::async_runtime::JoinHandle<()>>>,
+    // Bumped whenever the connection is replaced, dropped or closed, so the reader of the old
+    // connection can tell we hung up rather than the server
+    generation: AtomicU64,
 }
 
 fn main() {
+    let context = tauri::generate_context!();
+    let _runtime = runtime::install(&settings::read_early(&context.config().identifier).runtime);
+
     tauri::Builder::default()
+        // Must come first so a second launch exits before binding the OSC listener
+        .plugin(tauri_plugin_single_instance::init(|app, _args, _cwd| {
+            log::info!("[APP] Another instance was launched, focusing this one");
+            tray::show_main_window(app);
+        }))
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_fs::init())
-        .plugin(
-            tauri_plugin_log::Builder::new()
-                .level(log::LevelFilter::Info)
-                .build()
-        )
+        .plugin(hotkeys::plugin())
+        .plugin(tauri_plugin_updater::Builder::new().build())
+        .plugin(tauri_plugin_notification::init())
+        .plugin(tauri_plugin_clipboard_manager::init())
+        .plugin(logging::plugin())
         .manage(QwenWsState {
-            sender: Arc::new(Mutex::new(None)),
+            sender: Mutex::new(None),
+            reader: Mutex::new(None),
+            writer: Mutex::new(None),
+            generation: AtomicU64::new(0),
+        })
+        .manage(shutdown::ShutdownState::default())
+        .manage(events::EventState::default())
+        .manage(quota::QuotaState::default())
+        .manage(hotkeys::HotkeyState::default())
+        .manage(updater::UpdaterState::default())
+        .manage(overlay::OverlayState::default())
+        .manage(control_api::ControlApiState::default())
+        .manage(ingest::IngestState::default())
+        .manage(incoming::IncomingState::default())
+        .manage(lan_remote::LanRemoteState::default())
+        .manage(companion::CompanionState::default())
+        .manage(window::WindowState::default())
+        .manage(headset::HeadsetState::default())
+        .manage(vr_controller::VrControllerState::default())
+        .manage(osc::OscListenerState::default())
+        .manage(osc::ChatboxState::default())
+        .manage(kat::KatState::default())
+        .manage(now_playing::NowPlayingState::default())
+        .manage(widgets::WidgetState::default())
+        .manage(typewriter::TypewriterState::default())
+        .manage(vrchat_log::VrchatLogState::default())
+        .manage(player_languages::PlayerLanguageState::default())
+        .manage(language_switch::LanguageSwitchState::default())
+        .manage(file_transcription::FileTranscriptionState::default())
+        .manage(latency::LatencyState::default())
+        .manage(session_log::SessionLogState::default())
+        .manage(watchdog::WatchdogState::default())
+        .manage(resources::ResourceState::default())
+        .manage(grpc_asr::GrpcAsrState::default())
+        .manage(plugins::PluginState::default())
+        .manage(autocorrect::AutocorrectState::default())
+        .manage(telemetry::TelemetryState::default())
+        .manage(power::PowerState::default())
+        .setup(|app| {
+            // Portable copies swap in a downloaded update before anything holds on to their
+            // files. Only here, past the single-instance check, so a second launch can't swap
+            // them out from under the running one
+            if portable_update::apply_staged() {
+                app.handle().restart();
+            }
+
+            app.manage(crash::install(app.handle()));
+            app.manage(settings::SettingsState::load(app.handle()));
+            app.manage(history::HistoryState::open(app.handle()));
+            app.manage(usage::UsageState::open(app.handle()));
+            app.manage(translation_memory::TranslationMemoryState::open(app.handle()));
+
+            match config_watch::start(app.handle()) {
+                Ok(watcher) => {
+                    app.manage(watcher);
+                }
+                Err(e) => log::warn!("[CONFIG] {}", e),
+            }
+
+            hotkeys::register_saved(app.handle());
+            usage::start(app.handle());
+            obs::start(app.handle());
+            session_log::start(app.handle());
+            overlay::apply(app.handle());
+            control_api::apply(app.handle());
+            ingest::apply(app.handle());
+            incoming::apply(app.handle());
+            lan_remote::apply(app.handle());
+            companion::apply(app.handle());
+            discord::start(app.handle());
+            telemetry::start(app.handle());
+            mqtt::start(app.handle());
+            webhooks::start(app.handle());
+            kat::start(app.handle());
+            now_playing::start(app.handle());
+            widgets::start(app.handle());
+            twitch::start(app.handle());
+            youtube::start(app.handle());
+            vr_notifications::start(app.handle());
+            subtitles::start(app.handle());
+            vrchat::start(app.handle());
+            power::start(app.handle());
+            vrchat_log::start(app.handle());
+            player_languages::start(app.handle());
+            headset::start(app.handle());
+            watchdog::start(app.handle());
+            resources::start(app.handle());
+            window::restore(app.handle());
+
+            match tray::create(app.handle()) {
+                Ok(tray) => {
+                    app.manage(tray);
+                }
+                Err(e) => log::warn!("[TRAY] Failed to create tray icon: {}", e),
+            }
+
+            if autostart::started_minimized() {
+                if let Some(window) = app.get_webview_window("main") {
+                    let _ = window.minimize();
+                }
+            }
+
+            Ok(())
+        })
+        .on_window_event(|window, event| {
+            if let WindowEvent::CloseRequested { api, .. } = event {
+                let app = window.app_handle();
+                if window.label() == "main"
+                    && app.try_state::<tray::TrayState>().is_some()
+                    && app.state::<settings::SettingsState>().get().close_to_tray
+                {
+                    let _ = window.hide();
+                    api.prevent_close();
+                }
+            }
         })
         .invoke_handler(tauri::generate_handler![
-            send_typing,
-            send_message,
+            osc::send_typing,
+            osc::send_message,
+            formatting::send_translation,
+            voice_commands::run_voice_command,
+            autocorrect::autocorrect,
+            autocorrect::list_dictionaries,
+            autocorrect::reload_dictionaries,
+            symbols::protect_symbols,
+            symbols::restore_symbols,
             show_windows_audio_settings,
-            start_vrc_listener,
+            osc::start_vrc_listener,
+            osc::stop_vrc_listener,
+            osc::vrc_listener_running,
             qwen_ws_connect,
             qwen_ws_send,
-            qwen_ws_close
+            qwen_ws_close,
+            grpc_asr::grpc_asr_connect,
+            grpc_asr::grpc_asr_send,
+            grpc_asr::grpc_asr_close,
+            asr_providers::list_asr_providers,
+            input_device::pick_input_device,
+            diagnostics::diagnose_microphone,
+            diagnostics::diagnose_osc,
+            diagnostics::diagnose_provider,
+            diagnostics::run_setup_diagnostics,
+            diagnostics_bundle::create_diagnostics_bundle,
+            telemetry::preview_telemetry,
+            power::is_low_power,
+            settings::get_settings,
+            settings::set_settings,
+            logging::set_log_level,
+            logging::get_log_targets,
+            logging::query_logs,
+            secrets::has_secret,
+            secrets::set_secret,
+            secrets::delete_secret,
+            profiles::list_profiles,
+            profiles::save_profile,
+            profiles::delete_profile,
+            profiles::switch_profile,
+            settings_bundle::export_settings,
+            settings_bundle::import_settings,
+            history::record_history,
+            history::query_history,
+            history::list_history_sessions,
+            history::current_history_session,
+            history::delete_history,
+            history::clear_history,
+            subtitle_export::export_history_subtitles,
+            markdown_export::export_history_markdown,
+            file_transcription::transcribe_file,
+            file_transcription::cancel_transcribe_file,
+            usage::record_usage,
+            usage::usage_summary,
+            translation_memory::remember_correction,
+            translation_memory::lookup_translation_memory,
+            translation_memory::list_translation_memory,
+            translation_memory::delete_translation_memory,
+            latency::record_latency,
+            latency::latency_stats,
+            resources::resource_usage,
+            harness::harness_start_osc_peer,
+            harness::harness_osc_messages,
+            harness::harness_send_vrchat_mute,
+            harness::harness_start_asr_server,
+            harness::harness_stop,
+            autostart::set_autostart,
+            autostart::get_autostart,
+            paths::is_portable,
+            plugins::list_plugins,
+            plugins::plugin_request,
+            plugins::plugin_translate,
+            backups::list_config_backups,
+            backups::restore_config,
+            hotkeys::register_hotkey,
+            hotkeys::unregister_hotkey,
+            hotkeys::list_hotkeys,
+            tray::get_capture_muted,
+            tray::set_tray_status,
+            updater::check_for_update,
+            updater::install_update_and_restart,
+            obs::obs_test_connection,
+            webhooks::test_webhook,
+            overlay::get_overlay_url,
+            ingest::get_ingest_url,
+            overlay::get_transcript_stream_url,
+            overlay::regenerate_overlay_token,
+            control_api::get_control_api,
+            control_api::regenerate_control_api_token,
+            lan_remote::get_lan_remote_pairing,
+            lan_remote::regenerate_lan_remote_token,
+            companion::get_companion_pairing,
+            companion::regenerate_companion_code,
+            quota::get_quota_status,
+            quota::set_budget,
+            quota::check_quota,
+            errors::get_error_messages,
+            vrchat::is_vrchat_running,
+            vrchat::get_vrchat_launch_option,
+            vrchat::set_launch_with_vrchat,
+            vrchat::get_launch_with_vrchat,
+            vrchat::detect_vrchat_paths,
+            vrchat_log::get_vrchat_instance,
+            player_languages::set_player_language,
+            language_switch::voice_profiles,
+            language_switch::reset_voice_profiles,
+            window::get_window_mode,
+            window::set_always_on_top,
+            window::set_click_through,
+            window::toggle_click_through,
+            window::set_compact_mode,
+            clipboard::copy_last_translation,
+            clipboard::send_clipboard_to_chatbox,
+            crash::list_crash_reports,
+            crash::submit_crash_report,
+            subtitles::open_subtitle_window,
+            subtitles::close_subtitle_window
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
-}
-
-#[tauri::command]
-fn send_typing(address: String, port: String) {
-    let sock = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).unwrap();
-    let msg_buf = encoder::encode(&OscPacket::Message(OscMessage {
-        addr: "/chatbox/typing".to_string(),
-        args: vec![OscType::Bool(true)],
-    }))
-    .unwrap();
-
-    sock.send_to(&msg_buf, address + ":" + &port).unwrap();
-}
-
-#[tauri::command]
-fn send_message(msg: String, address: String, port: String) {
-    let sock = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).unwrap();
-    let msg_buf = encoder::encode(&OscPacket::Message(OscMessage {
-        addr: "/chatbox/input".to_string(),
-        args: vec![OscType::String(msg), OscType::Bool(true)],
-    }))
-    .unwrap();
-
-    sock.send_to(&msg_buf, address + ":" + &port).unwrap();
+        .build(context)
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            if let RunEvent::Exit = event {
+                // Say goodbye to the ASR server before the reader task is dropped
+                let _ = tauri::async_runtime::block_on(close_qwen_ws(&app.state::<QwenWsState>()));
+                shutdown::run(app);
+            }
+        });
 }
 
 #[tauri::command]
-fn show_windows_audio_settings() {
+fn show_windows_audio_settings() -> Result<(), String> {
     #[cfg(target_os = "windows")]
     {
         use std::os::windows::process::CommandExt;
@@ -80,68 +386,40 @@ fn show_windows_audio_settings() {
             .arg("ms-settings:sound")
             .creation_flags(0x08000000_u32)
             .spawn()
-            .unwrap()
-            .wait()
-            .unwrap();
+            .and_then(|mut child| child.wait())
+            .map(|_| ())
+            .map_err(|e| format!("Failed to open sound settings: {}", e))
     }
-    
-    #[cfg(not(target_os = "windows"))]
+
+    #[cfg(target_os = "macos")]
     {
-        println!("Audio settings only available on Windows");
+        Command::new("open")
+            .arg("x-apple.systempreferences:com.apple.preference.sound")
+            .spawn()
+            .map(|_| ())
+            .map_err(|e| format!("Failed to open sound settings: {}", e))
     }
-}
 
-#[tauri::command]
-fn start_vrc_listener(app: AppHandle) {
-    unsafe {
-        if LISTENER_STARTED {
-            return;
-        }
+    // There is no standard sound settings panel, try the common desktops' ones
+    #[cfg(target_os = "linux")]
+    {
+        const PANELS: &[&[&str]] = &[
+            &["gnome-control-center", "sound"],
+            &["systemsettings", "kcm_pulseaudio"],
+            &["pavucontrol"],
+        ];
 
-        LISTENER_STARTED = true;
+        PANELS
+            .iter()
+            .find(|panel| Command::new(panel[0]).args(&panel[1..]).spawn().is_ok())
+            .map(|_| ())
+            .ok_or_else(|| "No sound settings application found".to_string())
     }
 
-    thread::spawn(move || {
-        let sock = UdpSocket::bind("127.0.0.1:9001");
-        match sock {
-            Ok(sock) => {
-                println!("Starting OSC listener...");
-                let mut buf = [0u8; rosc::decoder::MTU];
-
-                loop {
-                    match sock.recv_from(&mut buf) {
-                        Ok((size, _)) => {
-                            let (_, packet) = rosc::decoder::decode_udp(&buf[..size]).unwrap();
-
-                            match packet {
-                                OscPacket::Message(msg) => {
-                                    // println!("{:?}", msg);
-                                    if msg.addr.as_str() == "/avatar/parameters/MuteSelf" {
-                                        if let Some(mute) = msg.args.first().unwrap().clone().bool()
-                                        {
-                                            app.emit("vrchat-mute", mute).unwrap();
-                                        }
-                                    }
-                                }
-
-                                OscPacket::Bundle(bundle) => {
-                                    println!("OSC Bundle: {:?}", bundle);
-                                }
-                            }
-                        }
-                        Err(e) => {
-                            println!("Error receiving from socket: {}", e);
-                            break;
-                        }
-                    }
-                }
-            }
-
-            Err(e) => {
-                println!("Error binding to 9001: {:?}", e);
-            }
-        }
-    });
+    #[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
+    {
+        Err("Sound settings are not available on this platform".to_string())
+    }
 }
 
 // Qwen ASR WebSocket proxy commands
@@ -149,10 +427,24 @@ fn start_vrc_listener(app: AppHandle) {
 async fn qwen_ws_connect(
     app: AppHandle,
     state: State<'_, QwenWsState>,
-    api_key: String,
     model: String,
-) -> Result<(), String> {
-    let url = format!("wss://dashscope.aliyuncs.com/api-ws/v1/realtime?model={}", model);
+) -> Result<(), AppError> {
+    quota::ensure_available(&app, usage::QWEN_ASR)?;
+
+    // Integration tests point the recognizer at the in-process mock, which needs no key
+    #[cfg(feature = "test-harness")]
+    let mock_url = harness::asr_url(&app);
+    #[cfg(not(feature = "test-harness"))]
+    let mock_url: Option<String> = None;
+
+    let (url, api_key) = match mock_url {
+        Some(url) => (url, String::new()),
+        None => (
+            format!("wss://dashscope.aliyuncs.com/api-ws/v1/realtime?model={}", model),
+            secrets::get(secrets::QWEN_ASR_API_KEY)?
+                .ok_or_else(|| AppError::new(ErrorCode::ApiKeyMissing).with("provider", "Qwen ASR"))?,
+        ),
+    };
     
     // Parse URL to get authority/host
     let uri = url.parse::<http::Uri>()
@@ -184,33 +476,93 @@ async fn qwen_ws_connect(
         .map_err(|e| format!("Failed to build request: {}", e))?;
 
     // Connect to WebSocket
-    let (ws_stream, _) = connect_async(request)
+    let (ws_stream, _) = network::connect_websocket(&app, request)
         .await
-        .map_err(|e| format!("Failed to connect: {}", e))?;
+        .map_err(|e| {
+            AppError::new(ErrorCode::ConnectFailed)
+                .with("provider", "Qwen ASR")
+                .with("reason", redact::redact(&e.to_string()))
+        })?;
 
-    let (write, mut read) = ws_stream.split();
+    tray::set_connection(&app, "Connected");
+
+    let (mut write, mut read) = ws_stream.split();
+    let (sender, mut outgoing) = tokio::sync::mpsc::channel::<QwenOutgoing>(32);
     
     // Store the sender for later use
-    {
-        let mut sender = state.sender.lock().unwrap();
-        *sender = Some(write);
-    }
+    let generation = {
+        let mut sender_lock = state.sender.lock().unwrap();
+        *sender_lock = Some(sender);
+        state.generation.fetch_add(1, Ordering::SeqCst) + 1
+    };
+    watchdog::reset(&app);
+
+    // Pings keep the connection from being dropped while capture is muted, they go out through
+    // the same sink as audio
+    let app_clone = app.clone();
+    let writer = shutdown::spawn(&app, "qwen_asr", async move {
+        let mut ping = network::keepalive_interval(&app_clone);
+        loop {
+            tokio::select! {
+                _ = ping.tick() => {
+                    if let Err(e) = write.send(Message::Ping(Vec::new())).await {
+                        log::debug!("[QWEN-ASR] Failed to ping: {}", redact::redact(&e.to_string()));
+                    }
+                }
+                message = outgoing.recv() => match message {
+                    Some((message, done)) => {
+                        let _ = done.send(write.send(message).await.map_err(|e| e.to_string()));
+                    }
+                    None => break,
+                },
+            }
+        }
+    });
 
     // Spawn task to handle incoming messages
     let app_clone = app.clone();
-    tokio::spawn(async move {
+    let reader = shutdown::spawn(&app, "qwen_asr", async move {
         while let Some(msg) = read.next().await {
             match msg {
                 Ok(Message::Text(text)) => {
-                    // Emit message to frontend
-                    let _ = app_clone.emit("qwen-ws-message", text);
+                    if text.contains("\"conversation.item.input_audio_transcription.") {
+                        watchdog::result_received(&app_clone);
+                    }
+
+                    // Emit message to frontend, a completed transcription ends the utterance
+                    let completed = text.contains("\"conversation.item.input_audio_transcription.completed\"");
+                    if completed {
+                        let transcript = serde_json::from_str::<serde_json::Value>(&text)
+                            .ok()
+                            .and_then(|event| event["transcript"].as_str().map(String::from))
+                            .unwrap_or_default();
+                        language_switch::utterance_finished(&app_clone, &transcript);
+                    }
+                    let _ = events::emit_transcript(&app_clone, "qwen-ws-message", text, completed);
                 }
                 Ok(Message::Close(_)) => {
-                    let _ = app_clone.emit("qwen-ws-close", ());
+                    tray::set_connection(&app_clone, "Disconnected");
+
+                    // qwen_ws_close bumps the generation first, anything else is the server hanging up
+                    if app_clone.state::<QwenWsState>().generation.load(Ordering::SeqCst) == generation {
+                        notifications::critical(
+                            &app_clone,
+                            "Speech recognition disconnected",
+                            "The Qwen ASR connection was closed by the server.",
+                        );
+                    }
+                    let _ = events::emit(&app_clone, "qwen-ws-close", ());
                     break;
                 }
                 Err(e) => {
-                    let _ = app_clone.emit("qwen-ws-error", format!("{}", e));
+                    tray::set_connection(&app_clone, "Connection error");
+                    notifications::critical(
+                        &app_clone,
+                        "Speech recognition disconnected",
+                        &redact::redact(&e.to_string()),
+                    );
+                    telemetry::record_error(&app_clone, "qwen_asr");
+                    let _ = events::emit(&app_clone, "qwen-ws-error", redact::redact(&e.to_string()));
                     break;
                 }
                 _ => {}
@@ -218,48 +570,117 @@ async fn qwen_ws_connect(
         }
     });
 
+    // A reconnect replaces the previous connection, its reader must not report on the new one
+    if let Some(previous) = state.reader.lock().unwrap().replace(reader) {
+        previous.abort();
+    }
+    if let Some(previous) = state.writer.lock().unwrap().replace(writer) {
+        previous.abort();
+    }
+
     Ok(())
 }
 
+/// Queues `message` for the writer task and waits until it went out.
+async fn qwen_ws_write(sender: tokio::sync::mpsc::Sender<QwenOutgoing>, message: Message) -> Result<(), String> {
+    let (done, result) = tokio::sync::oneshot::channel();
+    sender
+        .send((message, done))
+        .await
+        .map_err(|_| "Connection closed".to_string())?;
+    result.await.map_err(|_| "Connection closed".to_string())?
+}
+
 #[tauri::command]
 async fn qwen_ws_send(
+    app: AppHandle,
     state: State<'_, QwenWsState>,
+    usage: State<'_, usage::UsageState>,
     message: String,
-) -> Result<(), String> {
-    let sender_opt = {
-        let mut sender_lock = state.sender.lock().unwrap();
-        sender_lock.take()
-    };
+) -> Result<(), AppError> {
+    // Decoded once here, every consumer below gets the samples
+    let audio = usage::audio_event(&message);
+    if let Some(audio) = &audio {
+        // Muting from the tray or taking the headset off drops audio but keeps the session open
+        if app
+            .try_state::<tray::TrayState>()
+            .map_or(false, |tray| tray.capture_muted())
+            || headset::paused(&app)
+            || osc::capture_paused(&app)
+            || vr_controller::capture_gated(&app)
+        {
+            return Ok(());
+        }
+
+        quota::ensure_available(&app, usage::QWEN_ASR)?;
+        watchdog::audio_sent(&app, audio);
+        language_switch::audio(&app, audio);
+    }
+
+    let sender_opt = state.sender.lock().unwrap().clone();
     
-    if let Some(mut sender) = sender_opt {
-        let result = sender
-            .send(Message::Text(message))
+    if let Some(sender) = sender_opt {
+        watchdog::send_started(&app);
+        let result = qwen_ws_write(sender, Message::Text(message))
             .await
-            .map_err(|e| format!("Failed to send message: {}", e));
-        
-        // Put sender back
-        let mut sender_lock = state.sender.lock().unwrap();
-        *sender_lock = Some(sender);
+            .map_err(|e| {
+                AppError::new(ErrorCode::SendFailed)
+                    .with("provider", "Qwen ASR")
+                    .with("reason", redact::redact(&e))
+            });
+        watchdog::send_finished(&app);
+
+        if let (Ok(()), Some(audio)) = (&result, &audio) {
+            usage.add(usage::QWEN_ASR, 0, usage::audio_seconds(audio.len()));
+        }
         
         result
     } else {
-        Err("WebSocket not connected".to_string())
+        Err(AppError::new(ErrorCode::NotConnected).with("provider", "Qwen ASR"))
     }
 }
 
 #[tauri::command]
-async fn qwen_ws_close(state: State<'_, QwenWsState>) -> Result<(), String> {
+async fn qwen_ws_close(app: AppHandle, state: State<'_, QwenWsState>) -> Result<(), AppError> {
+    watchdog::reset(&app);
+    usage::flush(&app);
+    close_qwen_ws(&state).await
+}
+
+/// Drops a stalled connection without a close handshake, the frontend reconnects on `qwen-ws-error`.
+fn restart_qwen_ws(app: &AppHandle, reason: &str) {
+    let state = app.state::<QwenWsState>();
+
+    if let Some(reader) = state.reader.lock().unwrap().take() {
+        reader.abort();
+    }
+    // Sends stuck on the stalled sink fail once the writer is gone
+    if let Some(writer) = state.writer.lock().unwrap().take() {
+        writer.abort();
+    }
+    {
+        let mut sender = state.sender.lock().unwrap();
+        state.generation.fetch_add(1, Ordering::SeqCst);
+        sender.take();
+    }
+
+    tray::set_connection(app, "Reconnecting");
+    telemetry::record_error(app, "qwen_asr");
+    let _ = events::emit(app, "qwen-ws-error", reason.to_string());
+}
+
+async fn close_qwen_ws(state: &QwenWsState) -> Result<(), AppError> {
     let sender_opt = {
         let mut sender_lock = state.sender.lock().unwrap();
+        state.generation.fetch_add(1, Ordering::SeqCst);
         sender_lock.take()
     };
     
-    if let Some(mut sender) = sender_opt {
-        sender
-            .send(Message::Close(None))
+    if let Some(sender) = sender_opt {
+        qwen_ws_write(sender, Message::Close(None))
             .await
-            .map_err(|e| format!("Failed to close connection: {}", e))
+            .map_err(|e| AppError::from(format!("Failed to close connection: {}", e)))
     } else {
-        Err("WebSocket not connected".to_string())
+        Err(AppError::new(ErrorCode::NotConnected).with("provider", "Qwen ASR"))
     }
 }
@@ -0,0 +1,124 @@
+use chrono::{Local, TimeZone};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use tauri::State;
+
+use crate::history::{HistoryEntry, HistoryState};
+
+// Wrap each session so exporting it again replaces it instead of appending a copy
+const SECTION_START: &str = "<!-- kikitan:session ";
+const SECTION_END: &str = "<!-- /kikitan:session -->";
+
+fn local_time(ms: i64) -> chrono::DateTime<Local> {
+    Local
+        .timestamp_millis_opt(ms)
+        .single()
+        .unwrap_or_else(Local::now)
+}
+
+fn quote(text: &str) -> String {
+    text.lines()
+        .map(|line| format!("> {}", line))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Renders one day's part of a session, ready to go into that day's note.
+fn render(session_id: &str, entries: &[&HistoryEntry], speaker: &str) -> String {
+    let first = entries[0];
+    let mut out = format!(
+        "{}{} -->\n## Conversation at {} ({} → {})\n\n",
+        SECTION_START,
+        session_id,
+        local_time(first.timestamp).format("%H:%M"),
+        first.source_language,
+        first.target_language
+    );
+
+    for entry in entries {
+        out.push_str(&format!(
+            "**{} {}**: {}\n{}\n\n",
+            local_time(entry.timestamp).format("%H:%M:%S"),
+            speaker,
+            entry.original.trim(),
+            quote(entry.translation.trim())
+        ));
+    }
+
+    out.push_str(SECTION_END);
+    out.push('\n');
+    out
+}
+
+/// Puts the section into the note, replacing an earlier export of the same session.
+fn merge(existing: &str, session_id: &str, section: &str) -> String {
+    let marker = format!("{}{} -->", SECTION_START, session_id);
+
+    if let Some(start) = existing.find(&marker) {
+        if let Some(length) = existing[start..].find(SECTION_END) {
+            let end = start + length + SECTION_END.len();
+            let rest = existing[end..]
+                .strip_prefix('\n')
+                .unwrap_or(&existing[end..]);
+            return format!("{}{}{}", &existing[..start], section, rest);
+        }
+    }
+
+    if existing.is_empty() {
+        section.to_string()
+    } else {
+        format!("{}\n\n{}", existing.trim_end(), section)
+    }
+}
+
+/// Writes a session into daily Markdown notes in `folder`, e.g. an Obsidian vault.
+/// Each day gets a `YYYY-MM-DD.md` file, sessions past midnight go into both days.
+/// Returns the files that were written.
+#[tauri::command]
+pub fn export_history_markdown(
+    state: State<'_, HistoryState>,
+    session_id: String,
+    folder: String,
+    speaker: Option<String>,
+) -> Result<Vec<String>, String> {
+    let entries = state.session_entries(&session_id)?;
+    if entries.is_empty() {
+        return Err(format!("Session {} has no entries", session_id));
+    }
+
+    let speaker = speaker
+        .filter(|speaker| !speaker.trim().is_empty())
+        .unwrap_or_else(|| "Me".to_string());
+
+    let mut days: BTreeMap<String, Vec<&HistoryEntry>> = BTreeMap::new();
+    for entry in &entries {
+        let day = local_time(entry.timestamp).format("%Y-%m-%d").to_string();
+        days.entry(day).or_default().push(entry);
+    }
+
+    fs::create_dir_all(&folder).map_err(|e| format!("Failed to create {}: {}", folder, e))?;
+
+    let mut written = Vec::new();
+    for (day, day_entries) in days {
+        let path = Path::new(&folder).join(format!("{}.md", day));
+        let existing = fs::read_to_string(&path).unwrap_or_default();
+        let contents = merge(
+            &existing,
+            &session_id,
+            &render(&session_id, &day_entries, &speaker),
+        );
+
+        fs::write(&path, contents)
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        written.push(path.display().to_string());
+    }
+
+    log::info!(
+        "[HISTORY] Exported {} entries of session {} as Markdown to {}",
+        entries.len(),
+        session_id,
+        folder
+    );
+    Ok(written)
+}
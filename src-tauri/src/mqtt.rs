@@ -0,0 +1,192 @@
+use rumqttc::{AsyncClient, Event, EventLoop, LastWill, MqttOptions, Packet, QoS};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::time::Duration;
+use tauri::{AppHandle, Listener, Manager};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::mpsc;
+
+use crate::events;
+use crate::history::{HistoryEntry, HistoryState};
+use crate::network;
+use crate::secrets;
+use crate::settings::SettingsState;
+use crate::shutdown;
+use crate::tray::TrayState;
+
+const STATUS_INTERVAL: Duration = Duration::from_secs(30);
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MqttSettings {
+    pub enabled: bool,
+    pub host: String,
+    pub port: u16,
+    pub client_id: String,
+    /// The password lives in the credential store, see `secrets::MQTT_PASSWORD`.
+    pub username: String,
+    /// Topics are `<prefix>/transcript`, `<prefix>/translation`, `<prefix>/status` and `<prefix>/quota`.
+    pub topic_prefix: String,
+}
+
+impl Default for MqttSettings {
+    fn default() -> Self {
+        MqttSettings {
+            enabled: false,
+            host: "127.0.0.1".to_string(),
+            port: 1883,
+            client_id: "kikitan-translator".to_string(),
+            username: String::new(),
+            topic_prefix: "kikitan".to_string(),
+        }
+    }
+}
+
+fn topic(settings: &MqttSettings, name: &str) -> String {
+    format!("{}/{}", settings.topic_prefix.trim_end_matches('/'), name)
+}
+
+fn connect(settings: &MqttSettings, keepalive: Duration) -> (AsyncClient, EventLoop) {
+    let mut options = MqttOptions::new(&settings.client_id, &settings.host, settings.port);
+    options.set_keep_alive(keepalive);
+
+    // Retained, so automations see the translator as offline once it quits or crashes
+    options.set_last_will(LastWill::new(
+        topic(settings, "status"),
+        json!({ "online": false }).to_string(),
+        QoS::AtLeastOnce,
+        true,
+    ));
+
+    if !settings.username.is_empty() {
+        match secrets::get(secrets::MQTT_PASSWORD) {
+            Ok(password) => {
+                options.set_credentials(&settings.username, password.unwrap_or_default());
+            }
+            Err(e) => log::warn!("[MQTT] {}", e),
+        }
+    }
+
+    AsyncClient::new(options, 64)
+}
+
+fn status(app: &AppHandle) -> Value {
+    let tray = app.try_state::<TrayState>();
+
+    json!({
+        "online": true,
+        "paused": tray.as_ref().map_or(false, |tray| tray.paused()),
+        "connection": tray.as_ref().map(|tray| tray.connection()),
+    })
+}
+
+// Queued without waiting, the event loop below sends it. A full queue drops the message.
+fn publish(client: &AsyncClient, topic: String, payload: Value, retain: bool) {
+    if let Err(e) = client.try_publish(topic, QoS::AtLeastOnce, retain, payload.to_string()) {
+        log::debug!("[MQTT] Dropped a message: {}", e);
+    }
+}
+
+fn publish_entry(client: &AsyncClient, settings: &MqttSettings, entry: &HistoryEntry) {
+    publish(
+        client,
+        topic(settings, "transcript"),
+        json!({
+            "text": entry.original,
+            "language": entry.source_language,
+            "timestamp": entry.timestamp,
+        }),
+        false,
+    );
+    publish(
+        client,
+        topic(settings, "translation"),
+        json!({
+            "original": entry.original,
+            "translation": entry.translation,
+            "source_language": entry.source_language,
+            "target_language": entry.target_language,
+            "timestamp": entry.timestamp,
+        }),
+        false,
+    );
+}
+
+/// Publishes transcripts, translations, status and quota warnings while MQTT is enabled.
+pub fn start(app: &AppHandle) {
+    let mut recorded = app.state::<HistoryState>().subscribe();
+
+    // Quota warnings are only emitted as events, forward them into the publishing task
+    let (quota_tx, mut quota_rx) = mpsc::unbounded_channel();
+    for event in ["quota-warning", "quota-exhausted"] {
+        let quota_tx = quota_tx.clone();
+        app.listen_any(event, move |emitted| {
+            let status = events::payload(&emitted);
+            let _ = quota_tx.send(json!({ "event": event, "status": status }));
+        });
+    }
+
+    let app = app.clone();
+    shutdown::spawn(&app.clone(), "mqtt", async move {
+        let mut connected: Option<(MqttSettings, AsyncClient, EventLoop)> = None;
+        let mut interval = tokio::time::interval(STATUS_INTERVAL);
+
+        loop {
+            let settings = app.state::<SettingsState>().get().mqtt;
+
+            if connected.as_ref().map(|(current, _, _)| current) != Some(&settings) {
+                if let Some((_, client, _)) = connected.take() {
+                    let _ = client.try_disconnect();
+                }
+                if settings.enabled {
+                    let (client, eventloop) =
+                        connect(&settings, network::settings(&app).keepalive());
+                    connected = Some((settings.clone(), client, eventloop));
+                }
+            }
+
+            let (client, eventloop) = match connected.as_mut() {
+                Some((_, client, eventloop)) => (client.clone(), eventloop),
+                None => {
+                    // Nothing to publish to, keep draining so the channels don't back up
+                    tokio::select! {
+                        entry = recorded.recv() => {
+                            if let Err(RecvError::Closed) = entry {
+                                break;
+                            }
+                        }
+                        _ = quota_rx.recv() => {}
+                        _ = interval.tick() => {}
+                    }
+                    continue;
+                }
+            };
+
+            tokio::select! {
+                entry = recorded.recv() => match entry {
+                    Ok(entry) => publish_entry(&client, &settings, &entry),
+                    Err(RecvError::Lagged(_)) => {}
+                    Err(RecvError::Closed) => break,
+                },
+                Some(quota) = quota_rx.recv() => {
+                    publish(&client, topic(&settings, "quota"), quota, false);
+                }
+                _ = interval.tick() => {
+                    publish(&client, topic(&settings, "status"), status(&app), true);
+                }
+                event = eventloop.poll() => match event {
+                    Ok(Event::Incoming(Packet::ConnAck(_))) => {
+                        log::info!("[MQTT] Connected to {}:{}", settings.host, settings.port);
+                        publish(&client, topic(&settings, "status"), status(&app), true);
+                    }
+                    Ok(_) => {}
+                    Err(e) => {
+                        log::warn!("[MQTT] {}", e);
+                        tokio::time::sleep(RECONNECT_DELAY).await;
+                    }
+                },
+            }
+        }
+    });
+}
@@ -0,0 +1,149 @@
+//! Timeouts, retries and keepalive for every outgoing HTTP and WebSocket connection, so a
+//! flaky connection can be tuned in one place instead of per provider.
+//!
+//! Long-lived WebSockets ping on `keepalive_interval` so NATs and proxies don't drop them
+//! while nobody talks. Requests that are safe to repeat go through `retry` or `retry_if`.
+
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+use std::future::Future;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tokio::net::TcpStream;
+use tokio::time::{Interval, MissedTickBehavior};
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::handshake::client::Response;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+
+use crate::settings::SettingsState;
+
+// Waits before each retry, the last one repeats when more retries are configured
+const BACKOFF: [Duration; 3] = [
+    Duration::from_secs(1),
+    Duration::from_secs(5),
+    Duration::from_secs(30),
+];
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NetworkSettings {
+    pub connect_timeout_secs: u64,
+    /// How long a response may go quiet, streaming connections are not affected.
+    pub read_timeout_secs: u64,
+    /// Attempts after the first for requests that are safe to repeat.
+    pub retries: usize,
+    /// TCP keepalive for HTTP, and how often long-lived WebSockets ping.
+    pub keepalive_secs: u64,
+}
+
+impl Default for NetworkSettings {
+    fn default() -> Self {
+        NetworkSettings {
+            connect_timeout_secs: 10,
+            read_timeout_secs: 30,
+            retries: 3,
+            keepalive_secs: 15,
+        }
+    }
+}
+
+impl NetworkSettings {
+    pub fn connect_timeout(&self) -> Duration {
+        Duration::from_secs(self.connect_timeout_secs.max(1))
+    }
+
+    pub fn read_timeout(&self) -> Duration {
+        Duration::from_secs(self.read_timeout_secs.max(1))
+    }
+
+    pub fn keepalive(&self) -> Duration {
+        Duration::from_secs(self.keepalive_secs.max(1))
+    }
+
+    pub fn retry_delays(&self) -> Vec<Duration> {
+        (0..self.retries)
+            .map(|n| BACKOFF[n.min(BACKOFF.len() - 1)])
+            .collect()
+    }
+}
+
+pub fn settings(app: &AppHandle) -> NetworkSettings {
+    app.state::<SettingsState>().get().network
+}
+
+/// A client with the configured timeouts. Build one per task and reuse it, changes apply
+/// to clients built afterwards.
+pub fn http_client(app: &AppHandle) -> reqwest::Client {
+    let settings = settings(app);
+
+    reqwest::Client::builder()
+        .connect_timeout(settings.connect_timeout())
+        .read_timeout(settings.read_timeout())
+        .tcp_keepalive(settings.keepalive())
+        .build()
+        .unwrap_or_else(|e| {
+            log::warn!("[NETWORK] Falling back to the default client: {}", e);
+            reqwest::Client::new()
+        })
+}
+
+/// Runs `attempt` until it succeeds or the configured retries run out, returning the last error.
+pub async fn retry<T, F, Fut>(app: &AppHandle, attempt: F) -> Result<T, String>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, String>>,
+{
+    retry_if(app, attempt, |_| true).await
+}
+
+/// Like `retry`, but gives up right away on errors `retryable` says won't go away.
+pub async fn retry_if<T, E, F, Fut>(
+    app: &AppHandle,
+    mut attempt: F,
+    retryable: impl Fn(&E) -> bool,
+) -> Result<T, E>
+where
+    E: Display,
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+{
+    let mut delays = settings(app).retry_delays().into_iter();
+    loop {
+        match attempt().await {
+            Ok(value) => return Ok(value),
+            Err(e) if retryable(&e) => match delays.next() {
+                Some(delay) => {
+                    log::debug!("[NETWORK] {}, retrying in {:?}", e, delay);
+                    tokio::time::sleep(delay).await;
+                }
+                None => return Err(e),
+            },
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Ticks every keepalive interval, starting one interval from now, for a WebSocket to send
+/// a ping on.
+pub fn keepalive_interval(app: &AppHandle) -> Interval {
+    let period = settings(app).keepalive();
+    let mut interval = tokio::time::interval_at(tokio::time::Instant::now() + period, period);
+    interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    interval
+}
+
+/// `connect_async` giving up after the connect timeout instead of the OS default.
+pub async fn connect_websocket<R>(
+    app: &AppHandle,
+    request: R,
+) -> Result<(WebSocketStream<MaybeTlsStream<TcpStream>>, Response), String>
+where
+    R: IntoClientRequest + Unpin,
+{
+    let timeout = settings(app).connect_timeout();
+
+    match tokio::time::timeout(timeout, tokio_tungstenite::connect_async(request)).await {
+        Ok(connected) => connected.map_err(|e| e.to_string()),
+        Err(_) => Err(format!("timed out after {:?}", timeout)),
+    }
+}
@@ -0,0 +1,31 @@
+use tauri::{AppHandle, Manager};
+use tauri_plugin_notification::NotificationExt;
+
+use crate::settings::SettingsState;
+
+/// Shows a system notification for a failure the user would otherwise miss.
+/// Skipped while the main window has focus, the frontend already shows the error there.
+pub fn critical(app: &AppHandle, title: &str, body: &str) {
+    show(app, title, body);
+}
+
+/// Like `critical`, for things worth knowing that don't stop anything.
+pub fn notice(app: &AppHandle, title: &str, body: &str) {
+    show(app, title, body);
+}
+
+fn show(app: &AppHandle, title: &str, body: &str) {
+    if !app.state::<SettingsState>().get().notifications {
+        return;
+    }
+
+    if let Some(window) = app.get_webview_window("main") {
+        if window.is_focused().unwrap_or(false) {
+            return;
+        }
+    }
+
+    if let Err(e) = app.notification().builder().title(title).body(body).show() {
+        log::warn!("[NOTIFY] Failed to show notification: {}", e);
+    }
+}
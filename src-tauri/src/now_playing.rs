@@ -0,0 +1,104 @@
+//! The playing track for the now playing chatbox widget, read from the Windows media session
+//! that media keys and the volume flyout use, so it works with Spotify, browsers and most
+//! players alike.
+
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+use crate::power;
+use crate::widgets;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Track {
+    pub artist: String,
+    pub title: String,
+}
+
+#[derive(Default)]
+pub struct NowPlayingState {
+    track: Mutex<Option<Track>>,
+}
+
+/// The track that's playing right now, `None` when paused or nothing has a media session.
+pub fn current(app: &AppHandle) -> Option<Track> {
+    app.state::<NowPlayingState>().track.lock().unwrap().clone()
+}
+
+/// Supports `{artist}` and `{title}`.
+pub fn format_track(template: &str, track: &Track) -> String {
+    // Players without artist metadata would leave "♪  – title"
+    let template = if track.artist.is_empty() {
+        template
+            .replace("{artist} – ", "")
+            .replace("{artist} - ", "")
+    } else {
+        template.to_string()
+    };
+
+    template
+        .replace("{artist}", &track.artist)
+        .replace("{title}", &track.title)
+}
+
+pub fn start(app: &AppHandle) {
+    let app = app.clone();
+
+    thread::spawn(move || loop {
+        // Nothing reads the media session unless a now playing widget is shown
+        let track = if widgets::uses_now_playing(&app) {
+            current_track()
+        } else {
+            None
+        };
+        *app.state::<NowPlayingState>().track.lock().unwrap() = track;
+
+        thread::sleep(power::interval(&app, POLL_INTERVAL));
+    });
+}
+
+#[cfg(target_os = "windows")]
+fn current_track() -> Option<Track> {
+    use windows::Media::Control::{
+        GlobalSystemMediaTransportControlsSessionManager as SessionManager,
+        GlobalSystemMediaTransportControlsSessionPlaybackStatus as PlaybackStatus,
+    };
+
+    let read = || -> windows::core::Result<Option<Track>> {
+        let manager = SessionManager::RequestAsync()?.get()?;
+        let session = match manager.GetCurrentSession() {
+            Ok(session) => session,
+            // No app has a media session open
+            Err(_) => return Ok(None),
+        };
+
+        if session.GetPlaybackInfo()?.PlaybackStatus()? != PlaybackStatus::Playing {
+            return Ok(None);
+        }
+
+        let properties = session.TryGetMediaPropertiesAsync()?.get()?;
+        let title = properties.Title()?.to_string();
+        if title.is_empty() {
+            return Ok(None);
+        }
+
+        Ok(Some(Track {
+            artist: properties.Artist()?.to_string(),
+            title,
+        }))
+    };
+
+    read().unwrap_or_else(|e| {
+        log::debug!("[NOW PLAYING] Failed to read the media session: {}", e);
+        None
+    })
+}
+
+// Only Windows has a system wide media session we can read
+#[cfg(not(target_os = "windows"))]
+fn current_track() -> Option<Track> {
+    None
+}
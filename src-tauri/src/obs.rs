@@ -0,0 +1,217 @@
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use futures_util::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use tauri::{AppHandle, Manager};
+use tokio::net::TcpStream;
+use tokio::sync::broadcast::error::RecvError;
+use tokio_tungstenite::tungstenite::protocol::Message;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+
+use crate::history::{HistoryEntry, HistoryState};
+use crate::network;
+use crate::secrets;
+use crate::settings::SettingsState;
+use crate::shutdown;
+
+type ObsSocket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ObsSettings {
+    pub enabled: bool,
+    pub address: String,
+    pub port: u16,
+    /// Text source (GDI+ / FreeType) that receives the latest translation.
+    pub source_name: String,
+    pub update_text_source: bool,
+    /// Send the translation as a CEA-608 stream caption as well.
+    pub send_captions: bool,
+    pub include_original: bool,
+}
+
+impl Default for ObsSettings {
+    fn default() -> Self {
+        ObsSettings {
+            enabled: false,
+            address: "127.0.0.1".to_string(),
+            port: 4455,
+            source_name: "Kikitan Subtitles".to_string(),
+            update_text_source: true,
+            send_captions: false,
+            include_original: false,
+        }
+    }
+}
+
+/// Pushes every recorded translation to OBS while the integration is enabled.
+pub fn start(app: &AppHandle) {
+    let mut recorded = app.state::<HistoryState>().subscribe();
+    let app = app.clone();
+
+    shutdown::spawn(&app.clone(), "obs", async move {
+        let mut socket: Option<ObsSocket> = None;
+        let mut ping = network::keepalive_interval(&app);
+
+        loop {
+            let entry = tokio::select! {
+                // Keeps the connection up between translations
+                _ = ping.tick() => {
+                    if let Some(ws) = socket.as_mut() {
+                        if let Err(e) = ws.send(Message::Ping(Vec::new())).await {
+                            log::warn!("[OBS] Failed to ping OBS: {}", e);
+                            socket = None;
+                        }
+                    }
+                    continue;
+                }
+                entry = recorded.recv() => match entry {
+                    Ok(entry) => entry,
+                    Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => break,
+                },
+            };
+
+            let settings = app.state::<SettingsState>().get().obs;
+            if !settings.enabled {
+                socket = None;
+                continue;
+            }
+
+            // Connect lazily so OBS can be started after the translator
+            if socket.is_none() {
+                match connect(&app, &settings).await {
+                    Ok(ws) => {
+                        log::info!("[OBS] Connected to {}:{}", settings.address, settings.port);
+                        socket = Some(ws);
+                    }
+                    Err(e) => log::warn!("[OBS] {}", e),
+                }
+            }
+
+            if let Some(ws) = socket.as_mut() {
+                if let Err(e) = push(ws, &settings, &entry).await {
+                    log::warn!("[OBS] {}", e);
+                    socket = None;
+                }
+            }
+        }
+    });
+}
+
+async fn connect(app: &AppHandle, settings: &ObsSettings) -> Result<ObsSocket, String> {
+    let url = format!("ws://{}:{}", settings.address, settings.port);
+    let (mut ws, _) = network::connect_websocket(app, url)
+        .await
+        .map_err(|e| format!("Failed to connect to OBS: {}", e))?;
+
+    let hello = next_message(&mut ws).await?;
+    let mut identify = json!({ "op": 1, "d": { "rpcVersion": 1, "eventSubscriptions": 0 } });
+
+    if let Some(auth) = hello["d"].get("authentication") {
+        let password = secrets::get(secrets::OBS_PASSWORD)?
+            .ok_or_else(|| "OBS requires a password but none is set".to_string())?;
+        let (salt, challenge) = (
+            auth["salt"].as_str().unwrap_or_default(),
+            auth["challenge"].as_str().unwrap_or_default(),
+        );
+
+        identify["d"]["authentication"] = json!(auth_response(&password, salt, challenge));
+    }
+
+    ws.send(Message::Text(identify.to_string()))
+        .await
+        .map_err(|e| format!("Failed to identify with OBS: {}", e))?;
+
+    let identified = next_message(&mut ws).await?;
+    if identified["op"] != 2 {
+        return Err("OBS rejected the connection, check the password".to_string());
+    }
+
+    Ok(ws)
+}
+
+// obs-websocket 5 authentication: base64(sha256(base64(sha256(password + salt)) + challenge))
+fn auth_response(password: &str, salt: &str, challenge: &str) -> String {
+    let secret = BASE64.encode(Sha256::digest(format!("{}{}", password, salt)));
+    BASE64.encode(Sha256::digest(format!("{}{}", secret, challenge)))
+}
+
+async fn next_message(ws: &mut ObsSocket) -> Result<Value, String> {
+    loop {
+        match ws.next().await {
+            Some(Ok(Message::Text(text))) => {
+                return serde_json::from_str(&text)
+                    .map_err(|e| format!("Invalid message from OBS: {}", e))
+            }
+            Some(Ok(Message::Close(_))) | None => {
+                return Err("OBS closed the connection".to_string())
+            }
+            Some(Err(e)) => return Err(format!("OBS connection failed: {}", e)),
+            Some(Ok(_)) => {}
+        }
+    }
+}
+
+async fn request(ws: &mut ObsSocket, request_type: &str, data: Value) -> Result<(), String> {
+    let request_id = format!("kikitan-{}", chrono::Utc::now().timestamp_millis());
+    let message = json!({
+        "op": 6,
+        "d": { "requestType": request_type, "requestId": request_id, "requestData": data }
+    });
+
+    ws.send(Message::Text(message.to_string()))
+        .await
+        .map_err(|e| format!("Failed to send {} to OBS: {}", request_type, e))?;
+
+    // Only request responses arrive since no events are subscribed
+    let response = next_message(ws).await?;
+    let status = &response["d"]["requestStatus"];
+    if status["result"] != true {
+        return Err(format!(
+            "OBS rejected {}: {}",
+            request_type,
+            status["comment"].as_str().unwrap_or("unknown error")
+        ));
+    }
+
+    Ok(())
+}
+
+async fn push(
+    ws: &mut ObsSocket,
+    settings: &ObsSettings,
+    entry: &HistoryEntry,
+) -> Result<(), String> {
+    let text = if settings.include_original {
+        format!("{}\n{}", entry.original, entry.translation)
+    } else {
+        entry.translation.clone()
+    };
+
+    if settings.update_text_source {
+        request(
+            ws,
+            "SetInputSettings",
+            json!({ "inputName": settings.source_name, "inputSettings": { "text": text }, "overlay": true }),
+        )
+        .await?;
+    }
+
+    if settings.send_captions {
+        request(ws, "SendStreamCaption", json!({ "captionText": text })).await?;
+    }
+
+    Ok(())
+}
+
+/// Connects and authenticates with the current settings to check them.
+#[tauri::command]
+pub async fn obs_test_connection(app: AppHandle) -> Result<(), String> {
+    let settings = app.state::<SettingsState>().get().obs;
+    let mut ws = connect(&app, &settings).await?;
+    let _ = ws.close(None).await;
+    Ok(())
+}
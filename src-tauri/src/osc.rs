@@ -0,0 +1,476 @@
+use rosc::{encoder, OscMessage, OscPacket, OscType};
+use serde::{Deserialize, Serialize};
+use std::net::{Ipv4Addr, SocketAddr, ToSocketAddrs, UdpSocket};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Sender, TryRecvError};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager, State};
+
+use crate::events;
+use crate::headset;
+use crate::notifications;
+use crate::settings::SettingsState;
+use crate::shutdown;
+use crate::telemetry;
+
+const LISTEN_ADDRESS: &str = "127.0.0.1:9001";
+
+// How often the listener looks at the shutdown channel between packets
+const SHUTDOWN_POLL: Duration = Duration::from_millis(250);
+
+// VRChat drops chatbox updates that arrive faster than this
+pub const CHATBOX_INTERVAL: Duration = Duration::from_millis(1500);
+
+#[derive(Clone, Serialize)]
+struct OscError {
+    command: String,
+    message: String,
+}
+
+struct Listener {
+    shutdown: Sender<()>,
+    thread: JoinHandle<()>,
+}
+
+struct PendingMessage {
+    target: SocketAddr,
+    msg: String,
+}
+
+#[derive(Default)]
+struct Chatbox {
+    last_sent: Option<Instant>,
+    /// Only the newest text waiting for the interval to pass, older ones are outdated.
+    pending: Option<PendingMessage>,
+}
+
+/// Coalesces chatbox messages so bursts of partial results don't get dropped by VRChat.
+#[derive(Default)]
+pub struct ChatboxState {
+    chatbox: Mutex<Chatbox>,
+}
+
+#[derive(Default)]
+pub struct OscListenerState {
+    running: Arc<AtomicBool>,
+    listener: Mutex<Option<Listener>>,
+    /// Last MuteSelf reported by VRChat.
+    muted: AtomicBool,
+    afk: AtomicBool,
+    afk_reply: Mutex<Option<tauri::async_runtime::JoinHandle<()>>>,
+    /// When the last packet from VRChat arrived.
+    last_packet: Mutex<Option<Instant>>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AfkSettings {
+    /// Post `message` to the chatbox while VRChat reports me as AFK.
+    pub enabled: bool,
+    pub message: String,
+    /// Seconds between repeats, VRChat hides chatbox messages after a while.
+    pub interval: u64,
+}
+
+impl Default for AfkSettings {
+    fn default() -> Self {
+        AfkSettings {
+            enabled: false,
+            message: "AFK — back soon / 離席中".to_string(),
+            interval: 25,
+        }
+    }
+}
+
+/// What happens to my own speech while I'm muted in VRChat.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MuteBehavior {
+    /// Drop the audio, nothing gets recognized until unmuted.
+    Pause,
+    /// Keep recognizing and translating for the history and subtitles, but keep it out of
+    /// the chatbox.
+    Transcribe,
+    Ignore,
+}
+
+impl Default for MuteBehavior {
+    fn default() -> Self {
+        MuteBehavior::Ignore
+    }
+}
+
+/// Whether VRChat says I'm muted, never while its OSC output isn't being listened to.
+pub fn vrchat_muted(app: &AppHandle) -> bool {
+    let state = app.state::<OscListenerState>();
+    state.running.load(Ordering::SeqCst) && state.muted.load(Ordering::Relaxed)
+}
+
+/// Whether captured audio should be dropped before it reaches speech recognition.
+pub fn capture_paused(app: &AppHandle) -> bool {
+    vrchat_muted(app) && mute_behavior(app) == MuteBehavior::Pause
+}
+
+/// Whether chatbox output should be held back.
+pub fn output_held(app: &AppHandle) -> bool {
+    vrchat_muted(app) && mute_behavior(app) != MuteBehavior::Ignore
+}
+
+fn mute_behavior(app: &AppHandle) -> MuteBehavior {
+    app.state::<SettingsState>()
+        .get()
+        .vrchat_settings
+        .mute_behavior
+}
+
+/// Starts listening for VRChat's OSC output, does nothing if already listening.
+pub fn start_listener(app: &AppHandle) -> Result<(), String> {
+    let state = app.state::<OscListenerState>();
+    let mut listener = state.listener.lock().unwrap();
+
+    if state.running.load(Ordering::SeqCst) {
+        return Ok(());
+    }
+
+    // A listener that stopped on its own still needs joining before the port is reused
+    if let Some(stopped) = listener.take() {
+        let _ = stopped.thread.join();
+    }
+
+    let socket = UdpSocket::bind(LISTEN_ADDRESS)
+        .and_then(|socket| {
+            socket.set_read_timeout(Some(SHUTDOWN_POLL))?;
+            Ok(socket)
+        })
+        .map_err(|e| {
+            notifications::critical(
+                app,
+                "VRChat OSC unavailable",
+                "Port 9001 is in use, mute detection won't work.",
+            );
+            format!("Failed to listen on {}: {}", LISTEN_ADDRESS, e)
+        })?;
+
+    let (shutdown, shutdown_rx) = mpsc::channel();
+    let running = state.running.clone();
+    let app = app.clone();
+
+    running.store(true, Ordering::SeqCst);
+    let thread = thread::spawn(move || {
+        log::info!("[OSC] Listening on {}", LISTEN_ADDRESS);
+        let mut buf = [0u8; rosc::decoder::MTU];
+
+        loop {
+            match shutdown_rx.try_recv() {
+                Ok(()) | Err(TryRecvError::Disconnected) => break,
+                Err(TryRecvError::Empty) => {}
+            }
+
+            match socket.recv_from(&mut buf) {
+                Ok((size, _)) => handle_packet(&app, &buf[..size]),
+                Err(e)
+                    if matches!(
+                        e.kind(),
+                        std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+                    ) => {}
+                Err(e) => {
+                    log::error!("[OSC] Error receiving from socket: {}", e);
+                    break;
+                }
+            }
+        }
+
+        running.store(false, Ordering::SeqCst);
+        log::info!("[OSC] Listener stopped");
+    });
+
+    *listener = Some(Listener { shutdown, thread });
+    Ok(())
+}
+
+/// Stops the listener and waits for it to release the port.
+pub fn stop_listener(app: &AppHandle) {
+    let state = app.state::<OscListenerState>();
+    let listener = state.listener.lock().unwrap().take();
+
+    if let Some(listener) = listener {
+        let _ = listener.shutdown.send(());
+        let _ = listener.thread.join();
+    }
+}
+
+fn handle_packet(app: &AppHandle, data: &[u8]) {
+    *app.state::<OscListenerState>().last_packet.lock().unwrap() = Some(Instant::now());
+
+    let packet = match rosc::decoder::decode_udp(data) {
+        Ok((_, packet)) => packet,
+        Err(e) => {
+            log::debug!("[OSC] Ignoring malformed packet: {:?}", e);
+            return;
+        }
+    };
+
+    match packet {
+        OscPacket::Message(msg) => {
+            if msg.addr.as_str() == "/avatar/parameters/MuteSelf" {
+                if let Some(mute) = msg.args.first().and_then(|arg| arg.clone().bool()) {
+                    app.state::<OscListenerState>()
+                        .muted
+                        .store(mute, Ordering::Relaxed);
+                    let _ = events::emit(app, "vrchat-mute", mute);
+                }
+            } else if msg.addr.as_str() == "/avatar/parameters/AFK" {
+                if let Some(afk) = msg.args.first().and_then(|arg| arg.clone().bool()) {
+                    set_afk(app, afk);
+                }
+            }
+        }
+
+        OscPacket::Bundle(bundle) => {
+            log::debug!("[OSC] Bundle: {:?}", bundle);
+        }
+    }
+}
+
+/// Whether VRChat says I'm AFK, the chatbox belongs to the AFK reply then.
+pub fn afk(app: &AppHandle) -> bool {
+    let state = app.state::<OscListenerState>();
+    state.running.load(Ordering::SeqCst) && state.afk.load(Ordering::Relaxed)
+}
+
+// VRChat repeats avatar parameters, only the changes matter
+fn set_afk(app: &AppHandle, afk: bool) {
+    let state = app.state::<OscListenerState>();
+    if state.afk.swap(afk, Ordering::Relaxed) == afk {
+        return;
+    }
+
+    let mut reply = state.afk_reply.lock().unwrap();
+    if let Some(task) = reply.take() {
+        task.abort();
+
+        // Back from AFK, don't leave the reply up until VRChat hides it
+        let vrchat = app.state::<SettingsState>().get().vrchat_settings;
+        if let Ok(target) = resolve(&vrchat.osc_address, &vrchat.osc_port.to_string()) {
+            let _ = queue_chatbox(app, &app.state::<ChatboxState>(), target, String::new());
+        }
+        log::info!("[OSC] Back from AFK, stopped the auto-reply");
+    }
+
+    let settings = app.state::<SettingsState>().get().afk;
+    if !afk || !settings.enabled || settings.message.is_empty() {
+        return;
+    }
+
+    log::info!("[OSC] AFK, posting the auto-reply");
+    let app = app.clone();
+    *reply = Some(shutdown::spawn(&app.clone(), "osc", async move {
+        let interval = Duration::from_secs(settings.interval.max(5));
+        loop {
+            let vrchat = app.state::<SettingsState>().get().vrchat_settings;
+            match resolve(&vrchat.osc_address, &vrchat.osc_port.to_string()) {
+                // Not gated on mute or the headset, being AFK usually means both
+                Ok(target) => {
+                    let _ = queue_chatbox(
+                        &app,
+                        &app.state::<ChatboxState>(),
+                        target,
+                        settings.message.clone(),
+                    );
+                }
+                Err(e) => log::warn!("[OSC] AFK reply: {}", e),
+            }
+
+            tokio::time::sleep(interval).await;
+        }
+    }));
+}
+
+#[tauri::command]
+pub fn start_vrc_listener(app: AppHandle) -> Result<(), String> {
+    start_listener(&app)
+}
+
+#[tauri::command]
+pub fn stop_vrc_listener(app: AppHandle) {
+    stop_listener(&app)
+}
+
+pub fn listener_running(app: &AppHandle) -> bool {
+    app.state::<OscListenerState>()
+        .running
+        .load(Ordering::SeqCst)
+}
+
+#[tauri::command]
+pub fn vrc_listener_running(app: AppHandle) -> bool {
+    listener_running(&app)
+}
+
+/// How long ago the listener last heard from VRChat, `None` if it never did.
+pub fn last_heard(app: &AppHandle) -> Option<Duration> {
+    app.state::<OscListenerState>()
+        .last_packet
+        .lock()
+        .unwrap()
+        .map(|at| at.elapsed())
+}
+
+// Rejects things like "localhost:abc" up front instead of failing deep in the socket code
+pub fn resolve(address: &str, port: &str) -> Result<SocketAddr, String> {
+    let port: u16 = port
+        .trim()
+        .parse()
+        .map_err(|_| format!("Invalid OSC port {}", port))?;
+
+    (address.trim(), port)
+        .to_socket_addrs()
+        .map_err(|e| format!("Invalid OSC address {}: {}", address, e))?
+        .next()
+        .ok_or_else(|| format!("OSC address {} did not resolve", address))
+}
+
+pub fn send(target: SocketAddr, addr: &str, args: Vec<OscType>) -> Result<(), String> {
+    let msg_buf = encoder::encode(&OscPacket::Message(OscMessage {
+        addr: addr.to_string(),
+        args,
+    }))
+    .map_err(|e| format!("Failed to encode OSC message: {:?}", e))?;
+
+    let sock = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))
+        .map_err(|e| format!("Failed to open OSC socket: {}", e))?;
+    sock.send_to(&msg_buf, target)
+        .map_err(|e| format!("Failed to send OSC message to {}: {}", target, e))?;
+
+    Ok(())
+}
+
+// The frontend fires these without awaiting them, so failures are also emitted as `osc-error`
+fn report<T>(app: &AppHandle, command: &str, result: Result<T, String>) -> Result<T, String> {
+    if let Err(message) = &result {
+        log::warn!("[OSC] {}: {}", command, message);
+        telemetry::record_error(app, "osc");
+        let _ = events::emit(
+            app,
+            "osc-error",
+            OscError {
+                command: command.to_string(),
+                message: message.clone(),
+            },
+        );
+    }
+
+    result
+}
+
+#[tauri::command]
+pub fn send_typing(app: AppHandle, address: String, port: String) -> Result<(), String> {
+    if headset::paused(&app) || output_held(&app) {
+        return Ok(());
+    }
+
+    let result = resolve(&address, &port)
+        .and_then(|target| send(target, "/chatbox/typing", vec![OscType::Bool(true)]));
+    report(&app, "send_typing", result)
+}
+
+fn send_chatbox(target: SocketAddr, msg: String) -> Result<(), String> {
+    send(
+        target,
+        "/chatbox/input",
+        vec![OscType::String(msg), OscType::Bool(true)],
+    )
+}
+
+// Sends whatever is pending once the interval since the last message has passed
+fn schedule_flush(app: &AppHandle, delay: Duration) {
+    let app = app.clone();
+
+    shutdown::spawn(&app.clone(), "osc", async move {
+        tokio::time::sleep(delay).await;
+
+        let pending = {
+            let state = app.state::<ChatboxState>();
+            let mut chatbox = state.chatbox.lock().unwrap();
+            chatbox.last_sent = Some(Instant::now());
+            chatbox.pending.take()
+        };
+
+        if let Some(pending) = pending {
+            let _ = report(
+                &app,
+                "send_message",
+                send_chatbox(pending.target, pending.msg),
+            );
+        }
+    });
+}
+
+/// Sends the message still waiting for the interval right away, the delayed flush stops with
+/// the other background tasks on exit.
+pub fn flush_pending(app: &AppHandle) {
+    let Some(state) = app.try_state::<ChatboxState>() else {
+        return;
+    };
+    let pending = state.chatbox.lock().unwrap().pending.take();
+
+    if let Some(pending) = pending {
+        log::info!("[OSC] Sending the pending chatbox message before exiting");
+        let _ = report(
+            app,
+            "send_message",
+            send_chatbox(pending.target, pending.msg),
+        );
+    }
+}
+
+/// Sends right away if the chatbox is idle, otherwise replaces the pending message.
+/// Errors of delayed messages are only reported through `osc-error`.
+#[tauri::command]
+pub fn send_message(
+    app: AppHandle,
+    state: State<'_, ChatboxState>,
+    msg: String,
+    address: String,
+    port: String,
+) -> Result<(), String> {
+    if headset::paused(&app) || output_held(&app) {
+        return Ok(());
+    }
+
+    let target = report(&app, "send_message", resolve(&address, &port))?;
+    queue_chatbox(&app, &state, target, msg)
+}
+
+fn queue_chatbox(
+    app: &AppHandle,
+    state: &ChatboxState,
+    target: SocketAddr,
+    msg: String,
+) -> Result<(), String> {
+    let mut chatbox = state.chatbox.lock().unwrap();
+
+    let wait = chatbox.last_sent.map_or(Duration::ZERO, |last| {
+        CHATBOX_INTERVAL.saturating_sub(last.elapsed())
+    });
+
+    if chatbox.pending.is_none() && wait.is_zero() {
+        chatbox.last_sent = Some(Instant::now());
+        drop(chatbox);
+        return report(app, "send_message", send_chatbox(target, msg));
+    }
+
+    if chatbox
+        .pending
+        .replace(PendingMessage { target, msg })
+        .is_none()
+    {
+        schedule_flush(app, wait);
+    } else {
+        log::debug!("[OSC] Replaced a pending chatbox message");
+    }
+
+    Ok(())
+}
@@ -0,0 +1,283 @@
+use futures_util::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tauri::{AppHandle, Manager, State};
+use tokio::net::TcpStream;
+use tokio::sync::broadcast::error::RecvError;
+use tokio_tungstenite::tungstenite::protocol::Message;
+
+use crate::history::{HistoryEntry, HistoryState};
+use crate::incoming::IncomingState;
+use crate::local_server;
+use crate::settings::SettingsState;
+
+const OVERLAY_PAGE: &str = include_str!("overlay.html");
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OverlaySettings {
+    pub enabled: bool,
+    pub port: u16,
+    /// Required in the overlay URL, generated on first start.
+    pub token: String,
+    pub include_original: bool,
+}
+
+impl Default for OverlaySettings {
+    fn default() -> Self {
+        OverlaySettings {
+            enabled: false,
+            port: 7878,
+            token: String::new(),
+            include_original: true,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct OverlayState {
+    server: local_server::Server,
+}
+
+/// Starts, restarts or stops the overlay server to match the current settings.
+pub fn apply(app: &AppHandle) {
+    let settings =
+        local_server::ensure_token(app, "overlay", |settings| &mut settings.overlay.token).overlay;
+    app.state::<OverlayState>().server.follow(
+        app,
+        "overlay",
+        settings.enabled.then_some(settings.port),
+        &settings.token,
+        serve,
+    );
+}
+
+async fn serve(app: AppHandle, port: u16, connections: local_server::Connections) {
+    // Only reachable from this machine, OBS and a second monitor don't need more
+    local_server::accept(app, "overlay", "127.0.0.1", port, connections, handle).await
+}
+
+async fn handle(app: AppHandle, mut stream: TcpStream) -> Result<(), String> {
+    let token = app.state::<SettingsState>().get().overlay.token;
+    let request = local_server::peek_request(&stream).await?;
+
+    if local_server::is_websocket(&request) {
+        return stream_transcripts(app, stream, token).await;
+    }
+
+    let path = request.split_whitespace().nth(1).unwrap_or("/");
+    let allowed = path.starts_with("/?") && local_server::has_token(path, "token", &token);
+    local_server::send_page(&mut stream, OVERLAY_PAGE, allowed).await
+}
+
+/// Message format of the transcript stream, picked with `&format=`.
+#[derive(Clone, Copy, PartialEq)]
+enum StreamFormat {
+    /// Our own JSON, see `stream_transcripts`.
+    Kikitan,
+    /// Bare text frames, what most hand-written browser source captioners expect.
+    Text,
+    /// `{"transcript": ..., "sequence": ...}` like Web Captioner's webhook channel, for
+    /// overlays built around it.
+    WebCaptioner,
+}
+
+impl Default for StreamFormat {
+    fn default() -> Self {
+        StreamFormat::Kikitan
+    }
+}
+
+impl StreamFormat {
+    fn from_query(value: Option<&str>) -> Self {
+        match value {
+            Some("text") => StreamFormat::Text,
+            Some("webcaptioner") => StreamFormat::WebCaptioner,
+            _ => StreamFormat::Kikitan,
+        }
+    }
+
+    fn render(
+        self,
+        entry: HistoryEntry,
+        include_original: bool,
+        incoming: bool,
+        sequence: u64,
+    ) -> String {
+        let original = if include_original {
+            entry.original
+        } else {
+            String::new()
+        };
+        let text = if original.is_empty() {
+            entry.translation.clone()
+        } else {
+            format!("{}\n{}", original, entry.translation)
+        };
+
+        match self {
+            StreamFormat::Kikitan => json!({
+                "id": entry.id,
+                "session_id": entry.session_id,
+                "timestamp": entry.timestamp,
+                "original": original,
+                "translation": entry.translation,
+                "source_language": entry.source_language,
+                "target_language": entry.target_language,
+                "incoming": incoming,
+            })
+            .to_string(),
+            StreamFormat::Text => text,
+            StreamFormat::WebCaptioner => {
+                json!({ "transcript": text, "sequence": sequence }).to_string()
+            }
+        }
+    }
+}
+
+/// What a client asked for in its URL, e.g. `&source=ja,ko&target=en&original=0&format=text&incoming=1`.
+#[derive(Default)]
+struct ClientFilter {
+    /// Language prefixes, `en` matches `en-US`. Empty accepts every language.
+    source: Vec<String>,
+    target: Vec<String>,
+    /// Overrides the overlay's include original setting.
+    original: Option<bool>,
+    format: StreamFormat,
+    /// Also stream translations of what other players say.
+    incoming: bool,
+}
+
+impl ClientFilter {
+    fn from_path(path: &str) -> Self {
+        let languages = |name| {
+            local_server::query_param(path, name)
+                .map(|value| {
+                    value
+                        .split(',')
+                        .filter(|language| !language.is_empty())
+                        .map(|language| language.to_ascii_lowercase())
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+
+        ClientFilter {
+            source: languages("source"),
+            target: languages("target"),
+            original: local_server::query_param(path, "original")
+                .map(|value| value != "0" && value != "false"),
+            format: StreamFormat::from_query(local_server::query_param(path, "format")),
+            incoming: local_server::query_param(path, "incoming") == Some("1"),
+        }
+    }
+
+    fn accepts(&self, entry: &HistoryEntry) -> bool {
+        let matches = |wanted: &Vec<String>, language: &str| {
+            let language = language.to_ascii_lowercase();
+            wanted.is_empty()
+                || wanted
+                    .iter()
+                    .any(|prefix| language.starts_with(prefix.as_str()))
+        };
+
+        matches(&self.source, &entry.source_language)
+            && matches(&self.target, &entry.target_language)
+    }
+}
+
+async fn stream_transcripts(
+    app: AppHandle,
+    stream: TcpStream,
+    token: String,
+) -> Result<(), String> {
+    let mut filter = ClientFilter::default();
+    let ws = local_server::accept_websocket(stream, |request| {
+        let path = local_server::request_path(request);
+        let allowed = local_server::has_token(path, "token", &token);
+        if allowed {
+            filter = ClientFilter::from_path(path);
+        }
+        allowed
+    })
+    .await
+    .map_err(|e| format!("Rejected overlay client: {}", e))?;
+    let (mut write, mut read) = ws.split();
+
+    let mut recorded = app.state::<HistoryState>().subscribe();
+    let mut incoming = app.state::<IncomingState>().subscribe();
+    let mut sequence = 0;
+
+    loop {
+        let (entry, from_others) = tokio::select! {
+            entry = recorded.recv() => (entry, false),
+            entry = incoming.recv(), if filter.incoming => (entry, true),
+            message = read.next() => match message {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => return Ok(()),
+                Some(Ok(_)) => continue,
+            }
+        };
+
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(RecvError::Lagged(_)) => continue,
+            Err(RecvError::Closed) => return Ok(()),
+        };
+
+        if !local_server::still_valid(&app, |settings| &settings.overlay.token, &token) {
+            return Err("Closed overlay client, its token was replaced".to_string());
+        }
+
+        if !filter.accepts(&entry) {
+            continue;
+        }
+
+        let include_original = filter
+            .original
+            .unwrap_or_else(|| app.state::<SettingsState>().get().overlay.include_original);
+        sequence += 1;
+        let message = filter
+            .format
+            .render(entry, include_original, from_others, sequence);
+
+        write
+            .send(Message::Text(message))
+            .await
+            .map_err(|e| format!("Overlay client dropped: {}", e))?;
+    }
+}
+
+/// URL to add as an OBS browser source, `None` while the overlay is disabled.
+#[tauri::command]
+pub fn get_overlay_url(state: State<'_, SettingsState>) -> Option<String> {
+    let overlay = state.get().overlay;
+    overlay
+        .enabled
+        .then(|| format!("http://127.0.0.1:{}/?token={}", overlay.port, overlay.token))
+}
+
+/// WebSocket URL that streams every translation as JSON to companion apps and logging tools.
+/// Clients can narrow it down with `source`, `target` and `original` query parameters, add
+/// what other players say with `incoming=1`, and existing caption overlays can ask for their
+/// own message format with `format`.
+#[tauri::command]
+pub fn get_transcript_stream_url(state: State<'_, SettingsState>) -> Option<String> {
+    let overlay = state.get().overlay;
+    overlay
+        .enabled
+        .then(|| format!("ws://127.0.0.1:{}/?token={}", overlay.port, overlay.token))
+}
+
+/// Invalidates the current overlay URL.
+#[tauri::command]
+pub fn regenerate_overlay_token(
+    app: AppHandle,
+    state: State<'_, SettingsState>,
+) -> Result<Option<String>, String> {
+    let mut settings = state.get();
+    settings.overlay.token = local_server::new_token();
+    state.set(settings)?;
+    apply(&app);
+
+    Ok(get_overlay_url(state))
+}
@@ -0,0 +1,44 @@
+use std::env;
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager};
+
+/// A file with this name next to the executable keeps all app files beside it.
+const PORTABLE_MARKER: &str = "portable";
+const PORTABLE_DATA_DIR: &str = "data";
+
+/// The directory holding everything in portable mode, `None` for a regular install.
+pub fn portable_dir() -> Option<PathBuf> {
+    let exe_dir = env::current_exe().ok()?.parent()?.to_path_buf();
+
+    if exe_dir.join(PORTABLE_MARKER).exists() {
+        Some(exe_dir.join(PORTABLE_DATA_DIR))
+    } else {
+        None
+    }
+}
+
+pub fn config_dir(app: &AppHandle) -> PathBuf {
+    portable_dir().unwrap_or_else(|| {
+        app.path()
+            .app_config_dir()
+            .unwrap_or_else(|_| PathBuf::from("."))
+    })
+}
+
+pub fn data_dir(app: &AppHandle) -> PathBuf {
+    portable_dir().unwrap_or_else(|| {
+        app.path()
+            .app_data_dir()
+            .unwrap_or_else(|_| PathBuf::from("."))
+    })
+}
+
+/// Log directory when running portable. Regular installs use the log plugin's default.
+pub fn portable_log_dir() -> Option<PathBuf> {
+    portable_dir().map(|dir| dir.join("logs"))
+}
+
+#[tauri::command]
+pub fn is_portable() -> bool {
+    portable_dir().is_some()
+}
@@ -0,0 +1,121 @@
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use tauri::{AppHandle, Listener, Manager, State};
+
+use crate::events;
+use crate::settings::SettingsState;
+use crate::vrchat_log;
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PlayerLanguageSettings {
+    pub enabled: bool,
+    /// VRChat display name to the target language to translate into while they are around.
+    pub preferences: BTreeMap<String, String>,
+}
+
+#[derive(Default)]
+pub struct PlayerLanguageState {
+    /// Player whose language is in use.
+    active: Mutex<Option<String>>,
+    /// Target language from before any preference applied, restored once those players leave.
+    previous_target: Mutex<Option<String>>,
+}
+
+/// Player the current target language was picked for, shown next to subtitles.
+pub fn active_player(app: &AppHandle) -> Option<String> {
+    app.state::<PlayerLanguageState>()
+        .active
+        .lock()
+        .unwrap()
+        .clone()
+}
+
+fn set_target(app: &AppHandle, source: &str, target: &str) {
+    let _ = events::emit(
+        app,
+        "remote-control",
+        json!({ "action": "set_languages", "text": null, "source": source, "target": target }),
+    );
+}
+
+// The player who joined last wins when several with preferences are in the instance
+fn refresh(app: &AppHandle) {
+    let settings = app.state::<SettingsState>().get();
+    let state = app.state::<PlayerLanguageState>();
+    let mut active = state.active.lock().unwrap();
+    let mut previous_target = state.previous_target.lock().unwrap();
+
+    let wanted = if settings.player_languages.enabled {
+        vrchat_log::current_players(app)
+            .iter()
+            .rev()
+            .find_map(|player| {
+                let language = settings.player_languages.preferences.get(&player.name)?;
+                Some((player.name.clone(), language.clone()))
+            })
+    } else {
+        None
+    };
+
+    if active.as_ref() == wanted.as_ref().map(|(name, _)| name) {
+        return;
+    }
+
+    match wanted {
+        Some((name, language)) => {
+            log::info!(
+                "[PLAYER LANGUAGES] {} is here, translating into {}",
+                name,
+                language
+            );
+            previous_target.get_or_insert(settings.target_language.clone());
+            set_target(app, &settings.source_language, &language);
+            *active = Some(name);
+        }
+        None => {
+            if let Some(target) = previous_target.take() {
+                log::info!("[PLAYER LANGUAGES] Back to translating into {}", target);
+                set_target(app, &settings.source_language, &target);
+            }
+            *active = None;
+        }
+    }
+}
+
+/// Switches the target language as players with a preferred language come and go.
+pub fn start(app: &AppHandle) {
+    for event in [
+        "vrchat-player-joined",
+        "vrchat-player-left",
+        "vrchat-instance-changed",
+    ] {
+        let handle = app.clone();
+        // Emitted while the log state is locked, read it once the emit has returned
+        app.listen_any(event, move |_| {
+            let handle = handle.clone();
+            tauri::async_runtime::spawn(async move { refresh(&handle) });
+        });
+    }
+}
+
+/// Remembers the language to translate into while `name` is in the instance, `None` forgets it.
+#[tauri::command]
+pub fn set_player_language(
+    app: AppHandle,
+    state: State<'_, SettingsState>,
+    name: String,
+    language: Option<String>,
+) -> Result<(), String> {
+    let mut settings = state.get();
+    match language {
+        Some(language) => settings.player_languages.preferences.insert(name, language),
+        None => settings.player_languages.preferences.remove(&name),
+    };
+    state.set(settings)?;
+
+    refresh(&app);
+    Ok(())
+}
@@ -0,0 +1,280 @@
+//! Third-party providers that run as separate processes and talk JSON over stdio.
+//!
+//! A plugin is a folder in `<data dir>/plugins` with a `plugin.json` manifest. The process
+//! reads one request per line, `{"id": 1, "method": "translate", "params": {...}}`, and
+//! answers each with `{"id": 1, "result": ...}` or `{"id": 1, "error": "..."}`.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{AppHandle, State};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::process::{Child, ChildStdin, ChildStdout, Command};
+use tokio::sync::Mutex;
+
+use crate::paths;
+
+const PLUGIN_DIR: &str = "plugins";
+const MANIFEST: &str = "plugin.json";
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+// A plugin printing garbage shouldn't be able to exhaust memory
+const MAX_RESPONSE: usize = 1024 * 1024;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PluginKind {
+    Asr,
+    Translation,
+    Tts,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PluginManifest {
+    pub name: String,
+    #[serde(default)]
+    pub version: String,
+    pub provides: Vec<PluginKind>,
+    /// Executable, relative to the plugin folder unless absolute.
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PluginSettings {
+    /// Plugin used instead of Google Translate, empty for the built-in provider.
+    pub translation_provider: String,
+}
+
+struct PluginProcess {
+    // Killed when dropped, e.g. after a timeout
+    _child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+    next_id: u64,
+}
+
+// Each plugin has its own lock, so a slow one doesn't hold up requests to the others
+type PluginSlot = Arc<Mutex<Option<PluginProcess>>>;
+
+#[derive(Default)]
+pub struct PluginState {
+    running: Mutex<HashMap<String, PluginSlot>>,
+}
+
+fn plugin_dir(app: &AppHandle) -> PathBuf {
+    paths::data_dir(app).join(PLUGIN_DIR)
+}
+
+fn load_manifests(dir: &Path) -> Vec<(PathBuf, PluginManifest)> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+
+    entries
+        .flatten()
+        .filter_map(|entry| {
+            let path = entry.path().join(MANIFEST);
+            let contents = fs::read_to_string(&path).ok()?;
+            match serde_json::from_str::<PluginManifest>(&contents) {
+                Ok(manifest) => Some((entry.path(), manifest)),
+                Err(e) => {
+                    log::warn!("[PLUGINS] Ignoring {}: {}", path.display(), e);
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+fn spawn(dir: &Path, manifest: &PluginManifest) -> Result<PluginProcess, String> {
+    let program = if Path::new(&manifest.command).is_absolute() {
+        PathBuf::from(&manifest.command)
+    } else {
+        dir.join(&manifest.command)
+    };
+
+    // Plugins get their own folder and a bare environment, no API keys or tokens of ours
+    let mut command = Command::new(program);
+    command
+        .args(&manifest.args)
+        .current_dir(dir)
+        .env_clear()
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .kill_on_drop(true);
+
+    for inherited in ["PATH", "SYSTEMROOT", "TEMP", "TMP", "TMPDIR"] {
+        if let Some(value) = std::env::var_os(inherited) {
+            command.env(inherited, value);
+        }
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+        command.creation_flags(CREATE_NO_WINDOW);
+    }
+
+    let mut child = command
+        .spawn()
+        .map_err(|e| format!("Failed to start plugin {}: {}", manifest.name, e))?;
+
+    let stdin = child.stdin.take().ok_or("Plugin stdin unavailable")?;
+    let stdout = child.stdout.take().ok_or("Plugin stdout unavailable")?;
+
+    log::info!("[PLUGINS] Started {} {}", manifest.name, manifest.version);
+    Ok(PluginProcess {
+        _child: child,
+        stdin,
+        stdout: BufReader::new(stdout),
+        next_id: 1,
+    })
+}
+
+async fn exchange(
+    process: &mut PluginProcess,
+    method: &str,
+    params: Value,
+) -> Result<Value, String> {
+    let id = process.next_id;
+    process.next_id += 1;
+
+    let request = json!({ "id": id, "method": method, "params": params });
+    process
+        .stdin
+        .write_all(format!("{}\n", request).as_bytes())
+        .await
+        .map_err(|e| format!("Failed to write to plugin: {}", e))?;
+
+    loop {
+        let line = read_line(&mut process.stdout).await?;
+
+        // Anything that isn't the answer, like debug output, is skipped
+        let response: Value = match serde_json::from_slice(&line) {
+            Ok(response) => response,
+            Err(_) => continue,
+        };
+        if response["id"] != id {
+            continue;
+        }
+
+        if let Some(error) = response.get("error").filter(|error| !error.is_null()) {
+            return Err(error
+                .as_str()
+                .map_or_else(|| error.to_string(), str::to_string));
+        }
+        return Ok(response["result"].clone());
+    }
+}
+
+// Stops reading at MAX_RESPONSE instead of buffering whatever the plugin prints
+async fn read_line(stdout: &mut BufReader<ChildStdout>) -> Result<Vec<u8>, String> {
+    let mut line = Vec::new();
+    let read = stdout
+        .take(MAX_RESPONSE as u64 + 1)
+        .read_until(b'\n', &mut line)
+        .await
+        .map_err(|e| format!("Failed to read from plugin: {}", e))?;
+
+    if read == 0 {
+        return Err("Plugin exited".to_string());
+    }
+    if line.len() > MAX_RESPONSE && line.last() != Some(&b'\n') {
+        return Err("Plugin response too large".to_string());
+    }
+    Ok(line)
+}
+
+/// Sends a request to a plugin, starting it first if needed. A plugin that fails or
+/// times out is stopped and started fresh on the next request.
+pub async fn request(
+    app: &AppHandle,
+    state: &PluginState,
+    name: &str,
+    method: &str,
+    params: Value,
+) -> Result<Value, String> {
+    let slot = state
+        .running
+        .lock()
+        .await
+        .entry(name.to_string())
+        .or_default()
+        .clone();
+    let mut process = slot.lock().await;
+
+    if process.is_none() {
+        let (dir, manifest) = load_manifests(&plugin_dir(app))
+            .into_iter()
+            .find(|(_, manifest)| manifest.name == name)
+            .ok_or_else(|| format!("Plugin {} is not installed", name))?;
+        *process = Some(spawn(&dir, &manifest)?);
+    }
+
+    let result = tokio::time::timeout(
+        REQUEST_TIMEOUT,
+        exchange(process.as_mut().unwrap(), method, params),
+    )
+    .await
+    .unwrap_or_else(|_| Err(format!("Plugin {} did not answer in time", name)));
+
+    if result.is_err() {
+        *process = None;
+    }
+    result
+}
+
+#[tauri::command]
+pub fn list_plugins(app: AppHandle) -> Vec<PluginManifest> {
+    load_manifests(&plugin_dir(&app))
+        .into_iter()
+        .map(|(_, manifest)| manifest)
+        .collect()
+}
+
+#[tauri::command]
+pub async fn plugin_request(
+    app: AppHandle,
+    state: State<'_, PluginState>,
+    plugin: String,
+    method: String,
+    params: Value,
+) -> Result<Value, String> {
+    request(&app, &state, &plugin, &method, params).await
+}
+
+/// Translates through a plugin that provides `translation`.
+#[tauri::command]
+pub async fn plugin_translate(
+    app: AppHandle,
+    state: State<'_, PluginState>,
+    plugin: String,
+    text: String,
+    source: String,
+    target: String,
+) -> Result<String, String> {
+    let result = request(
+        &app,
+        &state,
+        &plugin,
+        "translate",
+        json!({ "text": text, "source": source, "target": target }),
+    )
+    .await?;
+
+    result
+        .as_str()
+        .or_else(|| result["text"].as_str())
+        .map(str::to_string)
+        .ok_or_else(|| format!("Plugin {} returned no translation", plugin))
+}
@@ -0,0 +1,160 @@
+//! Self-update for the portable zip, which the installer-based update can't replace.
+//!
+//! The archive is listed in the same manifest under `<platform>-portable` and its signature is
+//! checked against the update key while downloading. It's unpacked into `update` next to the
+//! executable, and the next launch swaps it in once it knows it's the only instance: the files
+//! being replaced, the running executable included, are moved into `update-old` (Windows
+//! won't overwrite a running executable but lets it be moved), the staged ones take their
+//! place and the app restarts into the new version. The launch after that removes `update-old`.
+
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, Cursor};
+use std::path::{Path, PathBuf};
+use tauri_plugin_updater::Update;
+
+use crate::paths;
+
+const STAGING_DIR: &str = "update";
+const REPLACED_DIR: &str = "update-old";
+// Written last, a staging folder without it is an interrupted download
+const COMPLETE_MARKER: &str = ".complete";
+// Settings, history and logs of a portable install, never part of the archive
+const DATA_DIR: &str = "data";
+
+/// The manifest key for this platform's portable archive, e.g. `windows-x86_64-portable`.
+pub fn target() -> String {
+    let os = match env::consts::OS {
+        "macos" => "darwin",
+        os => os,
+    };
+    format!("{}-{}-portable", os, env::consts::ARCH)
+}
+
+fn exe_dir() -> Option<PathBuf> {
+    Some(env::current_exe().ok()?.parent()?.to_path_buf())
+}
+
+fn remove(path: &Path) -> io::Result<()> {
+    if path.is_dir() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    }
+}
+
+fn extract(archive: &[u8], dir: &Path) -> Result<(), String> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(archive))
+        .map_err(|e| format!("Invalid archive: {}", e))?;
+
+    for i in 0..archive.len() {
+        let mut file = archive
+            .by_index(i)
+            .map_err(|e| format!("Invalid archive: {}", e))?;
+        // Entries pointing outside the folder are skipped along with the data folder
+        let Some(path) = file.enclosed_name() else {
+            continue;
+        };
+        if path.starts_with(DATA_DIR) {
+            continue;
+        }
+
+        let target = dir.join(path);
+        if file.is_dir() {
+            fs::create_dir_all(&target).map_err(|e| e.to_string())?;
+            continue;
+        }
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        let mut out = File::create(&target).map_err(|e| e.to_string())?;
+        io::copy(&mut file, &mut out).map_err(|e| e.to_string())?;
+    }
+
+    Ok(())
+}
+
+/// Downloads and verifies `update` and stages it for the next launch.
+pub async fn stage(
+    update: &Update,
+    on_chunk: impl FnMut(usize, Option<u64>),
+) -> Result<(), String> {
+    let dir = exe_dir()
+        .ok_or_else(|| "Could not find the program folder".to_string())?
+        .join(STAGING_DIR);
+
+    let archive = update
+        .download(on_chunk, || {})
+        .await
+        .map_err(|e| format!("Failed to download update: {}", e))?;
+
+    if dir.exists() {
+        remove(&dir).map_err(|e| format!("Failed to clear {}: {}", dir.display(), e))?;
+    }
+    extract(&archive, &dir).map_err(|e| format!("Failed to unpack update: {}", e))?;
+    fs::write(dir.join(COMPLETE_MARKER), &update.version).map_err(|e| e.to_string())?;
+
+    log::info!("[UPDATER] Staged portable update {}", update.version);
+    Ok(())
+}
+
+fn swap(dir: &Path, staging: &Path, replaced: &Path) -> io::Result<()> {
+    fs::create_dir_all(replaced)?;
+
+    for entry in fs::read_dir(staging)? {
+        let entry = entry?;
+        let name = entry.file_name();
+        if name == COMPLETE_MARKER {
+            continue;
+        }
+
+        let target = dir.join(&name);
+        if target.exists() {
+            fs::rename(&target, replaced.join(&name))?;
+        }
+        fs::rename(entry.path(), &target)?;
+    }
+
+    Ok(())
+}
+
+// Puts the replaced files back after a swap failed halfway
+fn rollback(dir: &Path, replaced: &Path) {
+    for entry in fs::read_dir(replaced).into_iter().flatten().flatten() {
+        let target = dir.join(entry.file_name());
+        if target.exists() {
+            let _ = remove(&target);
+        }
+        let _ = fs::rename(entry.path(), &target);
+    }
+}
+
+/// Swaps in a staged update, `true` when the app should restart into it. A failed swap keeps
+/// the current version running.
+pub fn apply_staged() -> bool {
+    let Some(dir) = exe_dir() else {
+        return false;
+    };
+
+    // No longer in use, the process that moved them here has exited
+    let replaced = dir.join(REPLACED_DIR);
+    if replaced.exists() {
+        let _ = remove(&replaced);
+    }
+
+    let staging = dir.join(STAGING_DIR);
+    if paths::portable_dir().is_none() || !staging.join(COMPLETE_MARKER).exists() {
+        return false;
+    }
+
+    let swapped = swap(&dir, &staging, &replaced);
+    let _ = remove(&staging);
+    if let Err(e) = swapped {
+        log::error!("[UPDATER] Failed to apply the staged update: {}", e);
+        rollback(&dir, &replaced);
+        return false;
+    }
+
+    log::info!("[UPDATER] Applied the staged update, restarting");
+    true
+}
@@ -0,0 +1,187 @@
+//! Low-power mode while the game isn't in front. Once VRChat, or the configured game, has
+//! been out of focus for a while or exits, capture is paused, the incoming speech session is
+//! closed and background polling slows down. Focusing the game again resumes within a second.
+//!
+//! Focus can only be read on Windows, elsewhere the game counts as focused while it runs.
+
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+use sysinfo::System;
+use tauri::{AppHandle, Manager, State};
+
+use crate::events;
+use crate::incoming;
+use crate::settings::SettingsState;
+
+const DEFAULT_PROCESS: &str = "VRChat.exe";
+
+// Short enough that coming back to the game feels instant
+#[cfg(windows)]
+const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+// Without focus information the whole process list is scanned, not worth doing every second
+#[cfg(not(windows))]
+const CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Background polling runs this many times less often in low-power mode.
+const POLL_SLOWDOWN: u32 = 4;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PowerSettings {
+    pub enabled: bool,
+    /// Executable of the game to follow, VRChat when empty.
+    pub process_name: String,
+    /// How long the game may be out of focus before downshifting, so alt-tabbing doesn't.
+    pub idle_secs: u64,
+    /// Stop capturing my own speech, which also closes its recognizer session.
+    pub pause_capture: bool,
+    /// Close the incoming speech session.
+    pub close_sessions: bool,
+    pub reduce_polling: bool,
+}
+
+impl Default for PowerSettings {
+    fn default() -> Self {
+        PowerSettings {
+            enabled: false,
+            process_name: String::new(),
+            idle_secs: 30,
+            pause_capture: true,
+            close_sessions: true,
+            reduce_polling: true,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct PowerState {
+    low_power: AtomicBool,
+}
+
+#[derive(Clone, Serialize)]
+struct PowerEvent {
+    low_power: bool,
+    pause_capture: bool,
+}
+
+fn settings(app: &AppHandle) -> PowerSettings {
+    app.state::<SettingsState>().get().power
+}
+
+pub fn low_power(app: &AppHandle) -> bool {
+    app.try_state::<PowerState>()
+        .is_some_and(|state| state.low_power.load(Ordering::Relaxed))
+}
+
+/// Whether idle sessions should stay closed right now.
+pub fn sessions_closed(app: &AppHandle) -> bool {
+    low_power(app) && settings(app).close_sessions
+}
+
+/// `normal`, stretched while in low-power mode, for loops polling in the background.
+pub fn interval(app: &AppHandle, normal: Duration) -> Duration {
+    if low_power(app) && settings(app).reduce_polling {
+        normal * POLL_SLOWDOWN
+    } else {
+        normal
+    }
+}
+
+fn game(settings: &PowerSettings) -> &str {
+    match settings.process_name.trim() {
+        "" => DEFAULT_PROCESS,
+        name => name,
+    }
+}
+
+/// Whether the game, or Kikitan itself, has the foreground window.
+#[cfg(windows)]
+fn focused(system: &mut System, game: &str) -> bool {
+    use sysinfo::Pid;
+    use windows::Win32::UI::WindowsAndMessaging::{GetForegroundWindow, GetWindowThreadProcessId};
+
+    let mut pid = 0u32;
+    unsafe {
+        let window = GetForegroundWindow();
+        if window.0.is_null() {
+            return false;
+        }
+        GetWindowThreadProcessId(window, Some(&mut pid));
+    }
+    if pid == 0 {
+        return false;
+    }
+    // Tweaking settings here shouldn't count as leaving the game
+    if pid == std::process::id() {
+        return true;
+    }
+
+    let pid = Pid::from_u32(pid);
+    system.refresh_process(pid);
+    system
+        .process(pid)
+        .is_some_and(|process| process.name().eq_ignore_ascii_case(game))
+}
+
+#[cfg(not(windows))]
+fn focused(system: &mut System, game: &str) -> bool {
+    system.refresh_processes();
+    system
+        .processes()
+        .values()
+        .any(|process| process.name().eq_ignore_ascii_case(game))
+}
+
+fn set_low_power(app: &AppHandle, low_power: bool) {
+    let state = app.state::<PowerState>();
+    if state.low_power.swap(low_power, Ordering::Relaxed) == low_power {
+        return;
+    }
+
+    let settings = settings(app);
+    log::info!(
+        "[POWER] {} low-power mode",
+        if low_power { "Entering" } else { "Leaving" }
+    );
+
+    incoming::apply(app);
+    let _ = events::emit(
+        app,
+        "power-state",
+        PowerEvent {
+            low_power,
+            pause_capture: settings.pause_capture,
+        },
+    );
+}
+
+/// Follows the game's focus and switches low-power mode on and off.
+pub fn start(app: &AppHandle) {
+    let app = app.clone();
+
+    thread::spawn(move || {
+        let mut system = System::new();
+        let mut last_focused = Instant::now();
+
+        loop {
+            let settings = settings(&app);
+
+            if !settings.enabled || focused(&mut system, game(&settings)) {
+                set_low_power(&app, false);
+                last_focused = Instant::now();
+            } else if last_focused.elapsed() >= Duration::from_secs(settings.idle_secs) {
+                set_low_power(&app, true);
+            }
+
+            thread::sleep(CHECK_INTERVAL);
+        }
+    });
+}
+
+#[tauri::command]
+pub fn is_low_power(state: State<'_, PowerState>) -> bool {
+    state.low_power.load(Ordering::Relaxed)
+}
@@ -0,0 +1,136 @@
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, State};
+
+use crate::events;
+use crate::grpc_asr::GrpcAsrSettings;
+use crate::incoming::IncomingSettings;
+use crate::input_device::InputDeviceSettings;
+use crate::plugins::PluginSettings;
+use crate::settings::{self, LanguageSettings, Settings, SettingsState, VrchatSettings};
+use crate::widgets::WidgetSettings;
+
+/// The subset of settings that can be swapped as a unit, e.g. "JP event" or "EN streaming".
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Profile {
+    pub source_language: String,
+    pub target_language: String,
+    pub mode: u32,
+    pub language_settings: LanguageSettings,
+    pub vrchat_settings: VrchatSettings,
+    /// Profiles saved before widgets existed get the defaults, as do those saved before the
+    /// provider and audio settings below were part of profiles.
+    #[serde(default)]
+    pub widgets: WidgetSettings,
+    #[serde(default)]
+    pub plugins: PluginSettings,
+    /// Picks the external engine over Qwen ASR or WebSpeech for my own speech.
+    #[serde(default)]
+    pub grpc_asr: GrpcAsrSettings,
+    /// Provider for what other players say.
+    #[serde(default)]
+    pub incoming: IncomingSettings,
+    #[serde(default)]
+    pub input_device: InputDeviceSettings,
+}
+
+impl Profile {
+    pub fn capture(settings: &Settings) -> Self {
+        Profile {
+            source_language: settings.source_language.clone(),
+            target_language: settings.target_language.clone(),
+            mode: settings.mode,
+            language_settings: settings.language_settings.clone(),
+            vrchat_settings: settings.vrchat_settings.clone(),
+            widgets: settings.widgets.clone(),
+            plugins: settings.plugins.clone(),
+            grpc_asr: settings.grpc_asr.clone(),
+            incoming: settings.incoming.clone(),
+            input_device: settings.input_device.clone(),
+        }
+    }
+
+    pub fn apply(&self, settings: &mut Settings) {
+        settings.source_language = self.source_language.clone();
+        settings.target_language = self.target_language.clone();
+        settings.mode = self.mode;
+        settings.language_settings = self.language_settings.clone();
+        settings.vrchat_settings = self.vrchat_settings.clone();
+        settings.widgets = self.widgets.clone();
+        settings.plugins = self.plugins.clone();
+        settings.grpc_asr = self.grpc_asr.clone();
+        settings.incoming = self.incoming.clone();
+        settings.input_device = self.input_device.clone();
+    }
+}
+
+#[derive(Serialize)]
+pub struct ProfileList {
+    pub active: Option<String>,
+    pub profiles: Vec<String>,
+}
+
+#[tauri::command]
+pub fn list_profiles(state: State<'_, SettingsState>) -> ProfileList {
+    let settings = state.get();
+
+    ProfileList {
+        active: settings.active_profile,
+        profiles: settings.profiles.into_keys().collect(),
+    }
+}
+
+/// Saves the current settings under `name`, overwriting an existing profile of the same name.
+#[tauri::command]
+pub fn save_profile(state: State<'_, SettingsState>, name: String) -> Result<(), String> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err("Profile name cannot be empty".to_string());
+    }
+
+    let mut settings = state.get();
+    settings
+        .profiles
+        .insert(name.clone(), Profile::capture(&settings));
+    settings.active_profile = Some(name);
+
+    state.set(settings)
+}
+
+#[tauri::command]
+pub fn delete_profile(state: State<'_, SettingsState>, name: String) -> Result<(), String> {
+    let mut settings = state.get();
+    if settings.profiles.remove(&name).is_none() {
+        return Err(format!("Profile {} does not exist", name));
+    }
+
+    if settings.active_profile.as_deref() == Some(name.as_str()) {
+        settings.active_profile = None;
+    }
+
+    state.set(settings)
+}
+
+#[tauri::command]
+pub fn switch_profile(
+    app: AppHandle,
+    state: State<'_, SettingsState>,
+    name: String,
+) -> Result<Settings, String> {
+    let mut settings = state.get();
+    let profile = settings
+        .profiles
+        .get(&name)
+        .cloned()
+        .ok_or_else(|| format!("Profile {} does not exist", name))?;
+
+    profile.apply(&mut settings);
+    settings.active_profile = Some(name.clone());
+    state.set(settings.clone())?;
+    settings::apply(&app);
+
+    log::info!("[PROFILES] Switched to profile {}", name);
+
+    let settings = settings::for_webview(settings);
+    let _ = events::emit(&app, "profile-switched", settings.clone());
+    Ok(settings)
+}
@@ -0,0 +1,244 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, State};
+
+use crate::errors::{AppError, ErrorCode};
+use crate::events;
+use crate::notifications;
+use crate::settings::SettingsState;
+use crate::usage::{UsageState, UsageSummary};
+
+/// Monthly limits for a provider. Unset limits are not enforced.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Budget {
+    pub monthly_characters: Option<u64>,
+    pub monthly_seconds: Option<f64>,
+    /// Fractions of the budget at which a warning is emitted, e.g. `[0.8, 0.95]`.
+    pub warning_thresholds: Vec<f64>,
+    /// Refuse further requests to the provider once the budget is used up.
+    pub hard_stop: bool,
+}
+
+impl Default for Budget {
+    fn default() -> Self {
+        Budget {
+            monthly_characters: None,
+            monthly_seconds: None,
+            warning_thresholds: vec![0.8, 0.95],
+            hard_stop: false,
+        }
+    }
+}
+
+impl Budget {
+    fn used_fraction(&self, usage: &UsageSummary) -> f64 {
+        let characters = self
+            .monthly_characters
+            .map(|limit| usage.characters as f64 / limit.max(1) as f64)
+            .unwrap_or(0.0);
+        let seconds = self
+            .monthly_seconds
+            .map(|limit| usage.seconds / limit.max(1.0))
+            .unwrap_or(0.0);
+
+        characters.max(seconds)
+    }
+}
+
+#[derive(Clone, Serialize)]
+pub struct QuotaStatus {
+    pub provider: String,
+    pub budget: Budget,
+    pub usage: UsageSummary,
+    pub fraction: f64,
+    pub exhausted: bool,
+}
+
+#[derive(Default)]
+pub struct QuotaState {
+    // Highest warning already emitted per provider, keyed with the month it applies to
+    warned: Mutex<HashMap<String, (String, usize)>>,
+    // Whether each provider is hard stopped, with the month it applies to. Refreshed when its
+    // usage is flushed, so checks on every audio chunk don't query the database.
+    stopped: Mutex<HashMap<String, (String, bool)>>,
+}
+
+fn month() -> String {
+    chrono::Local::now().format("%Y-%m").to_string()
+}
+
+/// Queries the provider's usage and caches whether it's hard stopped.
+fn status(app: &AppHandle, provider: &str) -> Result<Option<QuotaStatus>, String> {
+    let budget = app
+        .state::<SettingsState>()
+        .read(|settings| settings.budgets.get(provider).cloned());
+
+    let status = match budget {
+        Some(budget) => {
+            let usage = app.state::<UsageState>().this_month(provider)?;
+            let fraction = budget.used_fraction(&usage);
+            Some(QuotaStatus {
+                provider: provider.to_string(),
+                exhausted: fraction >= 1.0,
+                budget,
+                usage,
+                fraction,
+            })
+        }
+        None => None,
+    };
+
+    let stopped = status
+        .as_ref()
+        .is_some_and(|status| status.exhausted && status.budget.hard_stop);
+    app.state::<QuotaState>()
+        .stopped
+        .lock()
+        .unwrap()
+        .insert(provider.to_string(), (month(), stopped));
+
+    Ok(status)
+}
+
+/// Fails when the provider's budget is used up and it is configured to hard stop.
+pub fn ensure_available(app: &AppHandle, provider: &str) -> Result<(), AppError> {
+    let cached = app
+        .state::<QuotaState>()
+        .stopped
+        .lock()
+        .unwrap()
+        .get(provider)
+        .filter(|(month_of, _)| *month_of == month())
+        .map(|(_, stopped)| *stopped);
+
+    let stopped = match cached {
+        Some(stopped) => stopped,
+        None => {
+            status(app, provider)?.is_some_and(|status| status.exhausted && status.budget.hard_stop)
+        }
+    };
+
+    if stopped {
+        return Err(AppError::new(ErrorCode::BudgetExhausted).with("provider", provider));
+    }
+    Ok(())
+}
+
+/// Drops the cached budget checks, for when budgets may have changed.
+pub fn forget(app: &AppHandle) {
+    app.state::<QuotaState>().stopped.lock().unwrap().clear();
+}
+
+/// Emits warning events for thresholds crossed since the last check.
+pub fn evaluate(app: &AppHandle, provider: &str) {
+    let status = match status(app, provider) {
+        Ok(Some(status)) => status,
+        Ok(None) => return,
+        Err(e) => {
+            log::warn!("[QUOTA] {}", e);
+            return;
+        }
+    };
+
+    let crossed = status
+        .budget
+        .warning_thresholds
+        .iter()
+        .filter(|&&threshold| status.fraction >= threshold)
+        .count();
+
+    let state = app.state::<QuotaState>();
+    let mut warned = state.warned.lock().unwrap();
+    let entry = warned
+        .entry(provider.to_string())
+        .or_insert_with(|| (status.usage.period.clone(), 0));
+
+    if entry.0 != status.usage.period {
+        *entry = (status.usage.period.clone(), 0);
+    }
+
+    if status.exhausted && entry.1 <= status.budget.warning_thresholds.len() {
+        entry.1 = status.budget.warning_thresholds.len() + 1;
+
+        if status.budget.hard_stop {
+            log::warn!("[QUOTA] Monthly budget for {} is exhausted", provider);
+            notifications::critical(
+                app,
+                "Translation stopped",
+                &format!("The monthly budget for {} is used up.", provider),
+            );
+        } else {
+            log::info!(
+                "[QUOTA] Monthly budget for {} is exhausted, translation continues",
+                provider
+            );
+            notifications::notice(
+                app,
+                "Budget used up",
+                &format!(
+                    "The monthly budget for {} is used up, translation continues.",
+                    provider
+                ),
+            );
+        }
+        let _ = events::emit(app, "quota-exhausted", status);
+    } else if crossed > entry.1 {
+        entry.1 = crossed;
+
+        log::warn!(
+            "[QUOTA] {} has used {:.0}% of its monthly budget",
+            provider,
+            status.fraction * 100.0
+        );
+        let _ = events::emit(app, "quota-warning", status);
+    }
+}
+
+#[tauri::command]
+pub fn get_quota_status(app: AppHandle) -> Result<Vec<QuotaStatus>, String> {
+    let providers: Vec<String> = app
+        .state::<SettingsState>()
+        .get()
+        .budgets
+        .into_keys()
+        .collect();
+
+    let mut statuses = Vec::new();
+    for provider in providers {
+        if let Some(status) = status(&app, &provider)? {
+            statuses.push(status);
+        }
+    }
+
+    Ok(statuses)
+}
+
+/// Sets the budget of a provider, or removes it when `budget` is null.
+#[tauri::command]
+pub fn set_budget(
+    app: AppHandle,
+    state: State<'_, SettingsState>,
+    provider: String,
+    budget: Option<Budget>,
+) -> Result<(), String> {
+    let mut settings = state.get();
+    match budget {
+        Some(budget) => settings.budgets.insert(provider.clone(), budget),
+        None => settings.budgets.remove(&provider),
+    };
+    state.set(settings)?;
+
+    // Let a raised budget warn again from scratch
+    let quota = app.state::<QuotaState>();
+    quota.warned.lock().unwrap().remove(&provider);
+    quota.stopped.lock().unwrap().remove(&provider);
+
+    Ok(())
+}
+
+#[tauri::command]
+pub fn check_quota(app: AppHandle, provider: String) -> Result<(), AppError> {
+    ensure_available(&app, &provider)
+}
@@ -0,0 +1,90 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::Duration;
+use sysinfo::{Pid, System};
+use tauri::{AppHandle, Manager, State};
+
+use crate::events;
+use crate::power;
+use crate::shutdown;
+
+const REPORT_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ResourceUsage {
+    /// Share of the whole machine, 100 means every core is busy.
+    pub cpu_percent: f32,
+    pub memory_bytes: u64,
+    /// `None` where the platform doesn't report threads.
+    pub threads: Option<usize>,
+    pub tasks: BTreeMap<&'static str, usize>,
+}
+
+pub struct ResourceState {
+    system: Mutex<System>,
+    pid: Option<Pid>,
+}
+
+impl Default for ResourceState {
+    fn default() -> Self {
+        ResourceState {
+            system: Mutex::new(System::new()),
+            pid: sysinfo::get_current_pid().ok(),
+        }
+    }
+}
+
+impl ResourceState {
+    // CPU usage is measured since the previous refresh, so the first sample reads zero
+    fn sample(&self, app: &AppHandle) -> Result<ResourceUsage, String> {
+        let pid = self
+            .pid
+            .ok_or_else(|| "Could not determine the process id".to_string())?;
+
+        let mut system = self.system.lock().unwrap();
+        system.refresh_process(pid);
+
+        let process = system
+            .process(pid)
+            .ok_or_else(|| "Could not read the process information".to_string())?;
+
+        let cores = std::thread::available_parallelism().map_or(1, |n| n.get());
+
+        Ok(ResourceUsage {
+            cpu_percent: process.cpu_usage() / cores as f32,
+            memory_bytes: process.memory(),
+            threads: process.tasks().map(|tasks| tasks.len()),
+            tasks: shutdown::running_tasks(app),
+        })
+    }
+}
+
+/// Emits `resource-usage` periodically so it can be watched while playing.
+pub fn start(app: &AppHandle) {
+    let app = app.clone();
+
+    shutdown::spawn(&app.clone(), "resources", async move {
+        loop {
+            tokio::time::sleep(power::interval(&app, REPORT_INTERVAL)).await;
+
+            match app.state::<ResourceState>().sample(&app) {
+                Ok(usage) => {
+                    let _ = events::emit(&app, "resource-usage", usage);
+                }
+                Err(e) => {
+                    log::warn!("[RESOURCES] {}", e);
+                    break;
+                }
+            }
+        }
+    });
+}
+
+#[tauri::command]
+pub fn resource_usage(
+    app: AppHandle,
+    state: State<'_, ResourceState>,
+) -> Result<ResourceUsage, String> {
+    state.sample(&app)
+}
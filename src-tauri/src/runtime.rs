@@ -0,0 +1,35 @@
+use serde::{Deserialize, Serialize};
+use tokio::runtime::{Builder, Runtime};
+
+/// Thread limits for the backend. They are applied at startup, `0` keeps the default.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RuntimeSettings {
+    /// Async worker threads, one per core by default.
+    pub worker_threads: usize,
+    /// Threads for blocking work like file and database access, 512 by default.
+    pub max_blocking_threads: usize,
+}
+
+/// Replaces Tauri's default async runtime with one sized from the settings.
+/// The returned runtime has to be kept alive for as long as the app runs.
+pub fn install(settings: &RuntimeSettings) -> Option<Runtime> {
+    if settings.worker_threads == 0 && settings.max_blocking_threads == 0 {
+        return None;
+    }
+
+    let mut builder = Builder::new_multi_thread();
+    builder.enable_all();
+
+    if settings.worker_threads > 0 {
+        builder.worker_threads(settings.worker_threads);
+    }
+    if settings.max_blocking_threads > 0 {
+        builder.max_blocking_threads(settings.max_blocking_threads);
+    }
+
+    // Logging isn't set up yet, a broken runtime config falls back to Tauri's default
+    let runtime = builder.build().ok()?;
+    tauri::async_runtime::set(runtime.handle().clone());
+    Some(runtime)
+}
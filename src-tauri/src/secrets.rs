@@ -0,0 +1,93 @@
+use keyring::Entry;
+
+use crate::redact;
+
+const SERVICE: &str = "kikitan-translator";
+
+pub const QWEN_ASR_API_KEY: &str = "qwen_asr_api_key";
+pub const OBS_PASSWORD: &str = "obs_websocket_password";
+pub const MQTT_PASSWORD: &str = "mqtt_password";
+pub const TWITCH_OAUTH_TOKEN: &str = "twitch_oauth_token";
+pub const YOUTUBE_API_KEY: &str = "youtube_api_key";
+pub const GOOGLE_STT_API_KEY: &str = "google_stt_api_key";
+pub const AWS_SECRET_ACCESS_KEY: &str = "aws_secret_access_key";
+pub const IFLYTEK_API_KEY: &str = "iflytek_api_key";
+pub const TENCENT_SECRET_KEY: &str = "tencent_secret_key";
+pub const ASSEMBLYAI_API_KEY: &str = "assemblyai_api_key";
+pub const SONIOX_API_KEY: &str = "soniox_api_key";
+/// Key for the `X-Kikitan-Signature` HMAC on webhook deliveries.
+pub const WEBHOOK_SECRET: &str = "webhook_secret";
+
+/// Every secret the app stores, for settings exports that include them.
+pub const ALL: [&str; 12] = [
+    QWEN_ASR_API_KEY,
+    OBS_PASSWORD,
+    MQTT_PASSWORD,
+    TWITCH_OAUTH_TOKEN,
+    YOUTUBE_API_KEY,
+    GOOGLE_STT_API_KEY,
+    AWS_SECRET_ACCESS_KEY,
+    IFLYTEK_API_KEY,
+    TENCENT_SECRET_KEY,
+    ASSEMBLYAI_API_KEY,
+    SONIOX_API_KEY,
+    WEBHOOK_SECRET,
+];
+
+fn entry(name: &str) -> Result<Entry, String> {
+    Entry::new(SERVICE, name).map_err(|e| format!("Failed to open credential store: {}", e))
+}
+
+pub fn get(name: &str) -> Result<Option<String>, String> {
+    match entry(name)?.get_password() {
+        Ok(value) => {
+            redact::register_secret(&value);
+            Ok(Some(value))
+        }
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(format!("Failed to read secret {}: {}", name, e)),
+    }
+}
+
+/// Stores a secret, removing it from the credential store when the value is empty.
+pub fn set(name: &str, value: &str) -> Result<(), String> {
+    if value.is_empty() {
+        return delete(name);
+    }
+
+    redact::register_secret(value);
+
+    entry(name)?
+        .set_password(value)
+        .map_err(|e| format!("Failed to store secret {}: {}", name, e))
+}
+
+pub fn delete(name: &str) -> Result<(), String> {
+    match entry(name)?.delete_credential() {
+        Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+        Err(e) => Err(format!("Failed to delete secret {}: {}", name, e)),
+    }
+}
+
+// Windows only get to touch the app's own entries, not anything else in the credential store
+fn known(name: &str) -> Result<&str, String> {
+    ALL.into_iter()
+        .find(|known| *known == name)
+        .ok_or_else(|| format!("Unknown secret {}", name))
+}
+
+/// Whether a secret is stored. Windows never get the secret itself.
+#[tauri::command]
+pub fn has_secret(name: String) -> Result<bool, String> {
+    Ok(get(known(&name)?)?.is_some_and(|value| !value.is_empty()))
+}
+
+#[tauri::command]
+pub fn set_secret(name: String, value: String) -> Result<(), String> {
+    set(known(&name)?, &value)
+}
+
+#[tauri::command]
+pub fn delete_secret(name: String) -> Result<(), String> {
+    delete(known(&name)?)
+}
@@ -0,0 +1,136 @@
+//! Append-only JSONL record of every utterance, one file per history session, for people
+//! who analyze their sessions with their own tools. Independent of the SQLite history,
+//! deleting history doesn't touch these files.
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::history::{HistoryEntry, HistoryState};
+use crate::latency::LatencySample;
+use crate::paths;
+use crate::settings::SettingsState;
+use crate::shutdown;
+
+// Translations that never go to the chatbox report no latency, they're written without
+const LATENCY_WAIT: Duration = Duration::from_secs(10);
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SessionLogSettings {
+    pub enabled: bool,
+    /// Defaults to `sessions` in the data directory.
+    pub folder: String,
+}
+
+/// Entries waiting for their latency, by history id.
+#[derive(Default)]
+pub struct SessionLogState {
+    pending: Mutex<HashMap<i64, HistoryEntry>>,
+}
+
+pub fn start(app: &AppHandle) {
+    let mut recorded = app.state::<HistoryState>().subscribe();
+    let app = app.clone();
+
+    shutdown::spawn(&app.clone(), "session-log", async move {
+        loop {
+            let entry = match recorded.recv().await {
+                Ok(entry) => entry,
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => break,
+            };
+
+            if !app.state::<SettingsState>().get().session_log.enabled {
+                continue;
+            }
+
+            let id = entry.id;
+            app.state::<SessionLogState>()
+                .pending
+                .lock()
+                .unwrap()
+                .insert(id, entry);
+
+            let app = app.clone();
+            shutdown::spawn(&app.clone(), "session-log", async move {
+                tokio::time::sleep(LATENCY_WAIT).await;
+                finish(&app, id, None);
+            });
+        }
+    });
+}
+
+/// Completes the utterance with the timings the webview took for it.
+pub fn latency(app: &AppHandle, id: i64, sample: &LatencySample) {
+    finish(app, id, Some(sample));
+}
+
+fn finish(app: &AppHandle, id: i64, sample: Option<&LatencySample>) {
+    let entry = match app
+        .state::<SessionLogState>()
+        .pending
+        .lock()
+        .unwrap()
+        .remove(&id)
+    {
+        Some(entry) => entry,
+        None => return,
+    };
+
+    if let Err(e) = write(app, &entry, sample) {
+        log::warn!("[SESSION LOG] {}", e);
+    }
+}
+
+fn folder(app: &AppHandle) -> PathBuf {
+    let folder = app.state::<SettingsState>().get().session_log.folder;
+    if folder.is_empty() {
+        paths::data_dir(app).join("sessions")
+    } else {
+        PathBuf::from(folder)
+    }
+}
+
+fn write(
+    app: &AppHandle,
+    entry: &HistoryEntry,
+    sample: Option<&LatencySample>,
+) -> Result<(), String> {
+    let folder = folder(app);
+    fs::create_dir_all(&folder)
+        .map_err(|e| format!("Failed to create {}: {}", folder.display(), e))?;
+
+    let line = json!({
+        "utterance_id": entry.id,
+        "session_id": entry.session_id,
+        "timestamp": entry.timestamp,
+        "captured_at": sample.map(|sample| sample.captured_at),
+        "recognized_at": sample.map(|sample| sample.recognized_at),
+        "translated_at": sample.map(|sample| sample.translated_at),
+        "sent_at": sample.map(|sample| sample.sent_at),
+        "source_language": entry.source_language,
+        "target_language": entry.target_language,
+        "asr_provider": entry.asr_provider,
+        "translation_provider": entry.translation_provider,
+        "asr_text": entry.original,
+        "translation": entry.translation,
+        "latency": sample.map(LatencySample::breakdown),
+    });
+
+    let path = folder.join(format!("{}.jsonl", entry.session_id));
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+
+    writeln!(file, "{}", line).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
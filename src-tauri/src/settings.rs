@@ -0,0 +1,595 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::{AppHandle, State};
+
+use crate::autocorrect::AutocorrectSettings;
+use crate::aws_transcribe::AwsTranscribeSettings;
+use crate::backups;
+use crate::companion::{self, CompanionSettings};
+use crate::control_api::{self, ControlApiSettings};
+use crate::discord::DiscordSettings;
+use crate::events;
+use crate::google_stt::GoogleSttSettings;
+use crate::grpc_asr::GrpcAsrSettings;
+use crate::headset::HeadsetSettings;
+use crate::hotkeys;
+use crate::iflytek::IflytekSettings;
+use crate::incoming::{self, IncomingSettings};
+use crate::ingest::{self, IngestSettings};
+use crate::input_device::InputDeviceSettings;
+use crate::kat::KatSettings;
+use crate::lan_remote::{self, LanRemoteSettings};
+use crate::language_switch::LanguageSwitchSettings;
+use crate::mqtt::MqttSettings;
+use crate::network::NetworkSettings;
+use crate::obs::ObsSettings;
+use crate::osc::{AfkSettings, MuteBehavior};
+use crate::overlay::{self, OverlaySettings};
+use crate::paths;
+use crate::player_languages::PlayerLanguageSettings;
+use crate::plugins::PluginSettings;
+use crate::power::PowerSettings;
+use crate::profiles::Profile;
+use crate::quota::{self, Budget};
+use crate::runtime::RuntimeSettings;
+use crate::secrets;
+use crate::session_log::SessionLogSettings;
+use crate::soniox::SonioxSettings;
+use crate::subtitles::SubtitleWindowSettings;
+use crate::telemetry::TelemetrySettings;
+use crate::tencent_asr::TencentAsrSettings;
+use crate::translation_memory::TranslationMemorySettings;
+use crate::twitch::TwitchSettings;
+use crate::voice_commands::VoiceCommandSettings;
+use crate::vr_controller::VrControllerSettings;
+use crate::vr_notifications::VrNotificationSettings;
+use crate::widgets::WidgetSettings;
+use crate::webhooks::WebhookSettings;
+use crate::window::WindowSettings;
+use crate::youtube::YoutubeSettings;
+
+pub const SETTINGS_VERSION: u32 = 2;
+const SETTINGS_FILE: &str = "settings.json";
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub version: u32,
+    pub source_language: String,
+    pub target_language: String,
+    pub mode: u32,
+    pub light_mode: bool,
+    /// Closing the main window hides it to the tray instead of quitting.
+    pub close_to_tray: bool,
+    /// System notifications for disconnects and other failures.
+    pub notifications: bool,
+    pub language_settings: LanguageSettings,
+    pub vrchat_settings: VrchatSettings,
+    pub api_settings: ApiSettings,
+    pub active_profile: Option<String>,
+    pub profiles: BTreeMap<String, Profile>,
+    pub budgets: BTreeMap<String, Budget>,
+    /// Hotkey action to accelerator, e.g. `"toggle_translation": "CommandOrControl+Shift+T"`.
+    pub hotkeys: BTreeMap<String, String>,
+    pub log_level: String,
+    /// `stable` or `beta`.
+    pub update_channel: String,
+    pub session_log: SessionLogSettings,
+    pub obs: ObsSettings,
+    pub overlay: OverlaySettings,
+    pub control_api: ControlApiSettings,
+    pub ingest: IngestSettings,
+    pub lan_remote: LanRemoteSettings,
+    pub companion: CompanionSettings,
+    pub discord: DiscordSettings,
+    pub mqtt: MqttSettings,
+    pub webhooks: WebhookSettings,
+    pub kat: KatSettings,
+    pub widgets: WidgetSettings,
+    pub afk: AfkSettings,
+    pub twitch: TwitchSettings,
+    pub youtube: YoutubeSettings,
+    pub player_languages: PlayerLanguageSettings,
+    pub language_switch: LanguageSwitchSettings,
+    pub voice_commands: VoiceCommandSettings,
+    pub plugins: PluginSettings,
+    pub autocorrect: AutocorrectSettings,
+    pub translation_memory: TranslationMemorySettings,
+    pub grpc_asr: GrpcAsrSettings,
+    pub google_stt: GoogleSttSettings,
+    pub aws_transcribe: AwsTranscribeSettings,
+    pub iflytek: IflytekSettings,
+    pub tencent_asr: TencentAsrSettings,
+    pub soniox: SonioxSettings,
+    pub incoming: IncomingSettings,
+    pub input_device: InputDeviceSettings,
+    pub telemetry: TelemetrySettings,
+    pub window: WindowSettings,
+    pub vr_notifications: VrNotificationSettings,
+    pub headset: HeadsetSettings,
+    pub vr_controller: VrControllerSettings,
+    pub power: PowerSettings,
+    pub subtitle_window: SubtitleWindowSettings,
+    /// Applied on the next start, see `runtime::install`.
+    pub runtime: RuntimeSettings,
+    pub network: NetworkSettings,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LanguageSettings {
+    pub japanese_omit_questionmark: bool,
+    pub english_gender_change: bool,
+    pub english_gender_change_gender: u32,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VrchatSettings {
+    pub translation_first: bool,
+    pub only_translation: bool,
+    pub mute_behavior: MuteBehavior,
+    pub send_typing_status_while_talking: bool,
+    pub chatbox_update_speed: u32,
+    /// How translations are written to the chatbox and chat outputs, see `formatting`.
+    /// Empty follows translation first and only translation.
+    pub message_template: String,
+    /// Reveal translations a few words per chatbox update instead of all at once.
+    pub typewriter: bool,
+    pub typewriter_words: usize,
+    pub osc_address: String,
+    pub osc_port: u16,
+    /// Start translating when VRChat launches and stop when it exits.
+    pub follow_vrchat: bool,
+    /// Start a new history session whenever VRChat changes instance.
+    pub session_per_instance: bool,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ApiSettings {
+    pub qwen_asr_api_key: String,
+    /// Whether a key is stored. Windows get this instead of the key, see `for_webview`.
+    pub qwen_asr_api_key_set: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            version: SETTINGS_VERSION,
+            source_language: "en-US".to_string(),
+            target_language: "ja".to_string(),
+            mode: 0,
+            light_mode: false,
+            close_to_tray: true,
+            notifications: true,
+            language_settings: LanguageSettings::default(),
+            vrchat_settings: VrchatSettings::default(),
+            api_settings: ApiSettings::default(),
+            active_profile: None,
+            profiles: BTreeMap::new(),
+            budgets: BTreeMap::new(),
+            hotkeys: BTreeMap::new(),
+            log_level: "info".to_string(),
+            update_channel: "stable".to_string(),
+            session_log: SessionLogSettings::default(),
+            obs: ObsSettings::default(),
+            overlay: OverlaySettings::default(),
+            control_api: ControlApiSettings::default(),
+            ingest: IngestSettings::default(),
+            lan_remote: LanRemoteSettings::default(),
+            companion: CompanionSettings::default(),
+            discord: DiscordSettings::default(),
+            mqtt: MqttSettings::default(),
+            webhooks: WebhookSettings::default(),
+            kat: KatSettings::default(),
+            widgets: WidgetSettings::default(),
+            afk: AfkSettings::default(),
+            twitch: TwitchSettings::default(),
+            youtube: YoutubeSettings::default(),
+            player_languages: PlayerLanguageSettings::default(),
+            language_switch: LanguageSwitchSettings::default(),
+            voice_commands: VoiceCommandSettings::default(),
+            plugins: PluginSettings::default(),
+            autocorrect: AutocorrectSettings::default(),
+            translation_memory: TranslationMemorySettings::default(),
+            grpc_asr: GrpcAsrSettings::default(),
+            google_stt: GoogleSttSettings::default(),
+            aws_transcribe: AwsTranscribeSettings::default(),
+            iflytek: IflytekSettings::default(),
+            tencent_asr: TencentAsrSettings::default(),
+            soniox: SonioxSettings::default(),
+            incoming: IncomingSettings::default(),
+            input_device: InputDeviceSettings::default(),
+            telemetry: TelemetrySettings::default(),
+            window: WindowSettings::default(),
+            vr_notifications: VrNotificationSettings::default(),
+            headset: HeadsetSettings::default(),
+            vr_controller: VrControllerSettings::default(),
+            power: PowerSettings::default(),
+            subtitle_window: SubtitleWindowSettings::default(),
+            runtime: RuntimeSettings::default(),
+            network: NetworkSettings::default(),
+        }
+    }
+}
+
+impl Settings {
+    pub fn log_level(&self) -> log::LevelFilter {
+        self.log_level.parse().unwrap_or(log::LevelFilter::Info)
+    }
+}
+
+impl Default for LanguageSettings {
+    fn default() -> Self {
+        LanguageSettings {
+            japanese_omit_questionmark: true,
+            english_gender_change: false,
+            english_gender_change_gender: 0,
+        }
+    }
+}
+
+impl Default for VrchatSettings {
+    fn default() -> Self {
+        VrchatSettings {
+            translation_first: true,
+            only_translation: false,
+            mute_behavior: MuteBehavior::default(),
+            send_typing_status_while_talking: true,
+            chatbox_update_speed: 60,
+            message_template: String::new(),
+            typewriter: false,
+            typewriter_words: 4,
+            osc_address: "127.0.0.1".to_string(),
+            osc_port: 9000,
+            follow_vrchat: false,
+            session_per_instance: true,
+        }
+    }
+}
+
+pub struct SettingsState {
+    settings: Mutex<Settings>,
+    path: PathBuf,
+    // What this process last wrote to or read from the file, to tell external edits apart
+    last_contents: Mutex<String>,
+}
+
+impl SettingsState {
+    pub fn load(app: &AppHandle) -> Self {
+        let path = paths::config_dir(app).join(SETTINGS_FILE);
+
+        let mut settings = match fs::read_to_string(&path) {
+            Ok(contents) => load_contents(&path, &contents),
+            Err(_) => Settings::default(),
+        };
+
+        // Keys saved by older versions are still in plaintext, move them to the keyring
+        if !settings.api_settings.qwen_asr_api_key.is_empty() {
+            match secrets::set(
+                secrets::QWEN_ASR_API_KEY,
+                &settings.api_settings.qwen_asr_api_key,
+            )
+            .and_then(|_| to_file_contents(&settings))
+            .and_then(|contents| write_file(&path, &contents))
+            {
+                Ok(()) => log::info!("[SETTINGS] Moved API keys to the credential store"),
+                Err(e) => log::error!("[SETTINGS] Failed to move API keys: {}", e),
+            }
+        }
+
+        match secrets::get(secrets::QWEN_ASR_API_KEY) {
+            Ok(key) => settings.api_settings.qwen_asr_api_key = key.unwrap_or_default(),
+            Err(e) => log::error!("[SETTINGS] {}", e),
+        }
+
+        apply_live(&settings);
+
+        SettingsState {
+            settings: Mutex::new(settings),
+            last_contents: Mutex::new(fs::read_to_string(&path).unwrap_or_default()),
+            path,
+        }
+    }
+
+    pub fn path(&self) -> &PathBuf {
+        &self.path
+    }
+
+    pub fn get(&self) -> Settings {
+        self.settings.lock().unwrap().clone()
+    }
+
+    /// Looks at the settings without cloning all of them, for checks that run on every audio chunk.
+    pub fn read<T>(&self, f: impl FnOnce(&Settings) -> T) -> T {
+        f(&self.settings.lock().unwrap())
+    }
+
+    pub fn set(&self, settings: Settings) -> Result<(), String> {
+        let key = &settings.api_settings.qwen_asr_api_key;
+        if *key != self.settings.lock().unwrap().api_settings.qwen_asr_api_key {
+            if key.is_empty() {
+                secrets::delete(secrets::QWEN_ASR_API_KEY)?;
+            } else {
+                secrets::set(secrets::QWEN_ASR_API_KEY, key)?;
+            }
+        }
+
+        let contents = to_file_contents(&settings)?;
+        *self.last_contents.lock().unwrap() = contents.clone();
+        write_file(&self.path, &contents)?;
+
+        apply_live(&settings);
+        *self.settings.lock().unwrap() = settings;
+        Ok(())
+    }
+
+    /// Picks up edits made to the settings file by something other than this process.
+    /// Returns `None` if the file still holds what was last written or read.
+    pub fn reload(&self) -> Result<Option<Settings>, String> {
+        let contents = fs::read_to_string(&self.path)
+            .map_err(|e| format!("Failed to read {}: {}", self.path.display(), e))?;
+
+        {
+            let mut last = self.last_contents.lock().unwrap();
+            if *last == contents {
+                return Ok(None);
+            }
+            *last = contents.clone();
+        }
+
+        let value =
+            serde_json::from_str(&contents).map_err(|e| format!("Invalid settings file: {}", e))?;
+        let mut settings = parse(value)?;
+
+        let mut current = self.settings.lock().unwrap();
+        settings.api_settings = current.api_settings.clone();
+        *current = settings.clone();
+
+        apply_live(&settings);
+        Ok(Some(settings))
+    }
+}
+
+/// Reads the saved settings before the app is built, for what has to be set up front.
+/// Nothing is migrated on disk or backed up here, `SettingsState::load` does that later.
+pub fn read_early(identifier: &str) -> Settings {
+    paths::portable_dir()
+        .or_else(|| dirs::config_dir().map(|dir| dir.join(identifier)))
+        .and_then(|dir| fs::read_to_string(dir.join(SETTINGS_FILE)).ok())
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .and_then(|value| parse(value).ok())
+        .unwrap_or_default()
+}
+
+fn load_contents(path: &PathBuf, contents: &str) -> Settings {
+    let result = serde_json::from_str(contents)
+        .map_err(|e| e.to_string())
+        .and_then(|value| {
+            if schema_version(&value) < SETTINGS_VERSION {
+                backups::create(path, "migration")?;
+            }
+            parse(value)
+        });
+
+    result.unwrap_or_else(|e| {
+        log::error!("[SETTINGS] Failed to load {}: {}", path.display(), e);
+
+        // The broken file gets replaced on the next save, keep a copy of it
+        if let Err(e) = backups::create(path, "unreadable") {
+            log::error!("[SETTINGS] {}", e);
+        }
+        Settings::default()
+    })
+}
+
+// Settings the backend can apply on the spot without restarting anything
+fn apply_live(settings: &Settings) {
+    log::set_max_level(settings.log_level());
+}
+
+fn to_file_contents(settings: &Settings) -> Result<String, String> {
+    // API keys live in the OS credential store and never touch the settings file
+    let mut settings = settings.clone();
+    settings.api_settings = ApiSettings::default();
+
+    serde_json::to_string_pretty(&settings)
+        .map_err(|e| format!("Failed to serialize settings: {}", e))
+}
+
+fn write_file(path: &PathBuf, contents: &str) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("Failed to create config directory: {}", e))?;
+    }
+
+    // Write to a temporary file first so a crash mid-write can't truncate the settings
+    let tmp = path.with_extension("json.tmp");
+    fs::write(&tmp, contents).map_err(|e| format!("Failed to write settings: {}", e))?;
+    fs::rename(&tmp, path).map_err(|e| format!("Failed to write settings: {}", e))
+}
+
+/// Brings a raw settings document up to the current schema and deserializes it.
+pub fn parse(value: Value) -> Result<Settings, String> {
+    let value = migrate(value)?;
+    serde_json::from_value(value).map_err(|e| format!("Invalid settings: {}", e))
+}
+
+fn schema_version(value: &Value) -> u32 {
+    value.get("version").and_then(Value::as_u64).unwrap_or(0) as u32
+}
+
+fn migrate(mut value: Value) -> Result<Value, String> {
+    if !value.is_object() {
+        return Err("Settings must be a JSON object".to_string());
+    }
+
+    let mut version = schema_version(&value);
+    if version > SETTINGS_VERSION {
+        return Err(format!(
+            "Settings version {} is newer than supported version {}",
+            version, SETTINGS_VERSION
+        ));
+    }
+
+    while version < SETTINGS_VERSION {
+        value = match version {
+            0 => migrate_v0(value),
+            1 => migrate_v1(value),
+            _ => unreachable!(),
+        };
+        version += 1;
+        value["version"] = Value::from(version);
+
+        log::info!("[SETTINGS] Migrated settings to version {}", version);
+    }
+
+    Ok(value)
+}
+
+// Version 0 is the unversioned config the frontend used to keep in localStorage
+fn migrate_v0(mut value: Value) -> Value {
+    if let Some(speed) = value.pointer_mut("/vrchat_settings/chatbox_update_speed") {
+        if !speed.is_u64() {
+            *speed = Value::from(VrchatSettings::default().chatbox_update_speed);
+        }
+    }
+
+    value
+}
+
+// Disabling Kikitan while muted became one of the mute behaviors, in saved profiles too
+fn migrate_v1(mut value: Value) -> Value {
+    migrate_mute_behavior(value.get_mut("vrchat_settings"));
+
+    if let Some(profiles) = value.get_mut("profiles").and_then(Value::as_object_mut) {
+        for profile in profiles.values_mut() {
+            migrate_mute_behavior(profile.get_mut("vrchat_settings"));
+        }
+    }
+
+    value
+}
+
+fn migrate_mute_behavior(vrchat: Option<&mut Value>) {
+    if let Some(vrchat) = vrchat.and_then(Value::as_object_mut) {
+        if let Some(disable) = vrchat.remove("disable_kikitan_when_muted") {
+            let behavior = if disable.as_bool() == Some(true) {
+                "pause"
+            } else {
+                "ignore"
+            };
+            vrchat.insert("mute_behavior".to_string(), Value::from(behavior));
+        }
+    }
+}
+
+/// The settings as they are sent to windows, with API keys replaced by whether they're set.
+pub fn for_webview(mut settings: Settings) -> Settings {
+    settings.api_settings.qwen_asr_api_key_set = !settings.api_settings.qwen_asr_api_key.is_empty();
+    settings.api_settings.qwen_asr_api_key.clear();
+    settings
+}
+
+#[tauri::command]
+pub fn get_settings(state: State<'_, SettingsState>) -> Settings {
+    for_webview(state.get())
+}
+
+/// Restarts the servers and workers that only read their settings when they start.
+pub fn apply(app: &AppHandle) {
+    overlay::apply(app);
+    control_api::apply(app);
+    ingest::apply(app);
+    incoming::apply(app);
+    lan_remote::apply(app);
+    companion::apply(app);
+}
+
+/// For settings replaced as a whole by an import or a restored backup, which also bring the
+/// hotkeys and budgets `set_settings` leaves to their own commands.
+pub fn apply_replaced(app: &AppHandle) {
+    apply(app);
+    hotkeys::reload(app);
+    quota::forget(app);
+}
+
+#[tauri::command]
+pub fn set_settings(
+    app: AppHandle,
+    state: State<'_, SettingsState>,
+    settings: Value,
+) -> Result<(), String> {
+    let mut settings = parse(settings)?;
+
+    // Profiles, budgets, hotkeys, the window mode and the log level are managed through their own commands, the webview's copy may be stale
+    let current = state.get();
+    settings.active_profile = current.active_profile;
+    settings.profiles = current.profiles;
+    settings.budgets = current.budgets;
+    settings.hotkeys = current.hotkeys;
+    settings.overlay.token = current.overlay.token;
+    settings.control_api.token = current.control_api.token;
+    settings.lan_remote.token = current.lan_remote.token;
+    settings.companion.pairing_code = current.companion.pairing_code;
+    settings.window = current.window;
+    settings.subtitle_window = current.subtitle_window;
+    settings.log_level = current.log_level;
+
+    // Windows never get the stored key, an empty one only replaces it when it was cleared
+    if settings.api_settings.qwen_asr_api_key.is_empty() && settings.api_settings.qwen_asr_api_key_set {
+        settings.api_settings.qwen_asr_api_key = current.api_settings.qwen_asr_api_key;
+    }
+
+    state.set(settings.clone())?;
+    apply(&app);
+
+    let _ = events::emit(&app, "settings-changed", for_webview(settings));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn v1_mute_switch_becomes_mute_behavior_in_profiles() {
+        let vrchat = json!({ "disable_kikitan_when_muted": true });
+        let settings = parse(json!({
+            "version": 1,
+            "vrchat_settings": vrchat,
+            "profiles": {
+                "Quest": {
+                    "source_language": "ja",
+                    "target_language": "en",
+                    "mode": 0,
+                    "language_settings": {},
+                    "vrchat_settings": vrchat,
+                },
+                "Desktop": {
+                    "source_language": "ja",
+                    "target_language": "en",
+                    "mode": 0,
+                    "language_settings": {},
+                    "vrchat_settings": { "disable_kikitan_when_muted": false },
+                },
+            },
+        }))
+        .unwrap();
+
+        assert_eq!(settings.vrchat_settings.mute_behavior, MuteBehavior::Pause);
+        assert_eq!(
+            settings.profiles["Quest"].vrchat_settings.mute_behavior,
+            MuteBehavior::Pause
+        );
+        assert_eq!(
+            settings.profiles["Desktop"].vrchat_settings.mute_behavior,
+            MuteBehavior::Ignore
+        );
+    }
+}
@@ -0,0 +1,136 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs;
+use tauri::{AppHandle, State};
+
+use crate::backups;
+use crate::events;
+use crate::secrets;
+use crate::settings::{self, ApiSettings, Settings, SettingsState};
+
+const BUNDLE_FORMAT: &str = "kikitan-settings";
+
+#[derive(Serialize, Deserialize)]
+struct SettingsBundle {
+    format: String,
+    app_version: String,
+    settings: Value,
+    /// Credential store entries by name, only in exports that include secrets.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    secrets: BTreeMap<String, String>,
+}
+
+#[tauri::command]
+pub fn export_settings(
+    app: AppHandle,
+    state: State<'_, SettingsState>,
+    path: String,
+    include_secrets: bool,
+) -> Result<(), String> {
+    let mut settings = state.get();
+    let mut exported_secrets = BTreeMap::new();
+    if include_secrets {
+        for name in secrets::ALL {
+            if let Some(value) = secrets::get(name)? {
+                exported_secrets.insert(name.to_string(), value);
+            }
+        }
+    } else {
+        strip_secrets(&mut settings);
+    }
+
+    let bundle = SettingsBundle {
+        format: BUNDLE_FORMAT.to_string(),
+        app_version: app.package_info().version.to_string(),
+        settings: serde_json::to_value(&settings)
+            .map_err(|e| format!("Failed to serialize settings: {}", e))?,
+        secrets: exported_secrets,
+    };
+
+    let contents = serde_json::to_string_pretty(&bundle)
+        .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+    fs::write(&path, contents).map_err(|e| format!("Failed to write {}: {}", path, e))?;
+
+    log::info!("[SETTINGS] Exported settings to {}", path);
+    Ok(())
+}
+
+// Tokens and URLs that let someone else in, or carry a token like Discord webhook URLs do
+fn strip_secrets(settings: &mut Settings) {
+    settings.api_settings = ApiSettings::default();
+    settings.overlay.token.clear();
+    settings.control_api.token.clear();
+    settings.ingest.token.clear();
+    settings.lan_remote.token.clear();
+    settings.companion.pairing_code.clear();
+    settings.webhooks.endpoints.clear();
+    settings.widgets.heart_rate_url.clear();
+    for profile in settings.profiles.values_mut() {
+        profile.widgets.heart_rate_url.clear();
+    }
+}
+
+#[tauri::command]
+pub fn import_settings(
+    app: AppHandle,
+    state: State<'_, SettingsState>,
+    path: String,
+) -> Result<Settings, String> {
+    let contents =
+        fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    let bundle: SettingsBundle = serde_json::from_str(&contents)
+        .map_err(|e| format!("{} is not a settings export: {}", path, e))?;
+
+    if bundle.format != BUNDLE_FORMAT {
+        return Err(format!("{} is not a settings export", path));
+    }
+
+    // Rejects bundles from newer versions and migrates older ones
+    let mut imported = settings::parse(bundle.settings)?;
+
+    // Bundles exported without secrets shouldn't wipe the keys on this machine
+    if let Some(key) = bundle.secrets.get(secrets::QWEN_ASR_API_KEY) {
+        imported.api_settings.qwen_asr_api_key = key.clone();
+    }
+    let current = state.get();
+    if imported.api_settings.qwen_asr_api_key.is_empty() {
+        imported.api_settings = current.api_settings;
+    }
+    if imported.webhooks.endpoints.is_empty() {
+        imported.webhooks.endpoints = current.webhooks.endpoints;
+    }
+    if imported.widgets.heart_rate_url.is_empty() {
+        imported.widgets.heart_rate_url = current.widgets.heart_rate_url;
+    }
+
+    // Another machine's tokens are known to whoever had its URLs, `settings::apply` below
+    // generates new ones
+    imported.overlay.token.clear();
+    imported.control_api.token.clear();
+    imported.ingest.token.clear();
+    imported.lan_remote.token.clear();
+    imported.companion.pairing_code.clear();
+
+    backups::create(state.path(), "import")?;
+    state.set(imported.clone())?;
+
+    // Unknown names could come from a newer version or a tampered file, only known ones are stored
+    for name in secrets::ALL {
+        if let Some(value) = bundle.secrets.get(name).filter(|value| !value.is_empty()) {
+            secrets::set(name, value)?;
+        }
+    }
+
+    settings::apply_replaced(&app);
+    let imported = settings::for_webview(state.get());
+
+    log::info!(
+        "[SETTINGS] Imported settings from {} (exported by {})",
+        path,
+        bundle.app_version
+    );
+
+    let _ = events::emit(&app, "settings-imported", imported.clone());
+    Ok(imported)
+}
@@ -0,0 +1,128 @@
+use futures_util::FutureExt;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::panic::AssertUnwindSafe;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::async_runtime::JoinHandle;
+use tauri::{AppHandle, Manager};
+use tokio_util::sync::CancellationToken;
+use tokio_util::task::TaskTracker;
+
+use crate::osc;
+use crate::usage;
+
+// Long enough to send close frames, short enough that quitting doesn't feel stuck
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Background tasks register here so they can be stopped and awaited on exit.
+#[derive(Default)]
+pub struct ShutdownState {
+    token: CancellationToken,
+    tracker: TaskTracker,
+    running: Arc<Mutex<BTreeMap<&'static str, usize>>>,
+}
+
+// Counts a task as running for its subsystem until the task is dropped, finished or aborted
+struct RunningGuard {
+    running: Arc<Mutex<BTreeMap<&'static str, usize>>>,
+    subsystem: &'static str,
+}
+
+impl RunningGuard {
+    fn new(running: Arc<Mutex<BTreeMap<&'static str, usize>>>, subsystem: &'static str) -> Self {
+        *running.lock().unwrap().entry(subsystem).or_default() += 1;
+        RunningGuard { running, subsystem }
+    }
+}
+
+impl Drop for RunningGuard {
+    fn drop(&mut self) {
+        let mut running = self.running.lock().unwrap();
+        if let Some(count) = running.get_mut(self.subsystem) {
+            *count -= 1;
+            if *count == 0 {
+                running.remove(self.subsystem);
+            }
+        }
+    }
+}
+
+/// Cancelled when the app exits, for tasks that need to clean up before stopping.
+pub fn token(app: &AppHandle) -> CancellationToken {
+    app.state::<ShutdownState>().token.clone()
+}
+
+/// Awaits the task during shutdown. The task has to watch `token` and finish by itself,
+/// for tasks that need to clean up, e.g. clear the Discord presence.
+pub fn track<F>(
+    app: &AppHandle,
+    subsystem: &'static str,
+    task: F,
+) -> impl Future<Output = ()> + Send + 'static
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    let state = app.state::<ShutdownState>();
+    let guard = RunningGuard::new(state.running.clone(), subsystem);
+
+    state.tracker.track_future(async move {
+        let _guard = guard;
+
+        // The panic hook has reported the details already, name the subsystem that stopped
+        if AssertUnwindSafe(task).catch_unwind().await.is_err() {
+            log::error!("[SHUTDOWN] The {} task stopped after a panic", subsystem);
+        }
+    })
+}
+
+/// Spawns a task that is dropped on exit, closing whatever sockets it owns.
+pub fn spawn<F>(app: &AppHandle, subsystem: &'static str, task: F) -> JoinHandle<()>
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    let token = token(app);
+    tauri::async_runtime::spawn(track(app, subsystem, async move {
+        tokio::select! {
+            _ = token.cancelled() => {}
+            _ = task => {}
+        }
+    }))
+}
+
+/// Number of background tasks currently running, per subsystem.
+pub fn running_tasks(app: &AppHandle) -> BTreeMap<&'static str, usize> {
+    app.state::<ShutdownState>().running.lock().unwrap().clone()
+}
+
+/// Stops every background task and the OSC listener, waiting briefly for them to finish.
+pub fn run(app: &AppHandle) {
+    log::info!("[SHUTDOWN] Stopping background tasks");
+
+    // Cancelling would drop it along with the timer that sends it
+    osc::flush_pending(app);
+
+    let state = app.state::<ShutdownState>();
+    state.token.cancel();
+    state.tracker.close();
+
+    osc::stop_listener(app);
+
+    let finished = tauri::async_runtime::block_on(async {
+        tokio::time::timeout(SHUTDOWN_TIMEOUT, state.tracker.wait())
+            .await
+            .is_ok()
+    });
+
+    // Recognizers count usage until they stop, the periodic flush is cancelled by now
+    usage::flush(app);
+
+    if finished {
+        log::info!("[SHUTDOWN] All background tasks stopped");
+    } else {
+        log::warn!(
+            "[SHUTDOWN] {} background tasks did not stop in time",
+            state.tracker.len()
+        );
+    }
+}
@@ -0,0 +1,142 @@
+//! Soniox realtime transcription over WebSocket, as a backend ASR provider.
+//!
+//! Soniox streams tokens rather than sentences. Final tokens are collected until the endpoint
+//! detector emits its `<end>` token, which closes the utterance.
+
+use futures_util::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tauri::{AppHandle, Manager};
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::protocol::Message;
+
+use crate::languages;
+use crate::network;
+use crate::quota;
+use crate::secrets;
+use crate::settings::SettingsState;
+use crate::usage::{self, UsageState};
+
+const URL: &str = "wss://stt-rt.soniox.com/transcribe-websocket";
+const SAMPLE_RATE: u32 = 16000;
+const END_TOKEN: &str = "<end>";
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SonioxSettings {
+    pub model: String,
+    /// Languages besides the source language that speakers may switch to.
+    pub language_hints: Vec<String>,
+}
+
+impl Default for SonioxSettings {
+    fn default() -> Self {
+        SonioxSettings {
+            model: "stt-rt-preview".to_string(),
+            language_hints: Vec::new(),
+        }
+    }
+}
+
+/// Recognizes 16 kHz mono PCM16 chunks from `audio` and sends each finished utterance to
+/// `transcripts`.
+pub async fn transcripts(
+    app: &AppHandle,
+    language: &str,
+    mut audio: mpsc::Receiver<Vec<u8>>,
+    transcripts: mpsc::Sender<String>,
+) -> Result<(), String> {
+    let settings = app.state::<SettingsState>().get().soniox;
+    let api_key = secrets::get(secrets::SONIOX_API_KEY)?
+        .ok_or_else(|| "Soniox API key is not set".to_string())?;
+
+    let (ws, _) = network::connect_websocket(app, URL)
+        .await
+        .map_err(|e| format!("Failed to connect to Soniox: {}", e))?;
+    let (mut write, mut read) = ws.split();
+
+    // The key travels in the first message instead of the handshake
+    let mut hints = vec![languages::primary(language).to_string()];
+    hints.extend(settings.language_hints.iter().cloned());
+    let config = json!({
+        "api_key": api_key,
+        "model": settings.model,
+        "audio_format": "pcm_s16le",
+        "sample_rate": SAMPLE_RATE,
+        "num_channels": 1,
+        "language_hints": hints,
+        "enable_endpoint_detection": true,
+    });
+    write
+        .send(Message::Text(config.to_string()))
+        .await
+        .map_err(|e| format!("Failed to configure Soniox: {}", e))?;
+
+    log::info!("[SONIOX] Connected");
+
+    let mut utterance = String::new();
+
+    let mut ping = network::keepalive_interval(app);
+
+    loop {
+        tokio::select! {
+            _ = ping.tick() => {
+                write
+                    .send(Message::Ping(Vec::new()))
+                    .await
+                    .map_err(|e| format!("Failed to ping Soniox: {}", e))?;
+            }
+            chunk = audio.recv() => {
+                let chunk = match chunk {
+                    Some(chunk) => chunk,
+                    None => {
+                        // An empty frame asks Soniox to finish up
+                        let _ = write.send(Message::Binary(Vec::new())).await;
+                        return Ok(());
+                    }
+                };
+
+                quota::ensure_available(app, usage::SONIOX)?;
+                let seconds = chunk.len() as f64 / (SAMPLE_RATE as f64 * 2.0);
+                write
+                    .send(Message::Binary(chunk))
+                    .await
+                    .map_err(|e| format!("Failed to send audio to Soniox: {}", e))?;
+
+                app.state::<UsageState>().add(usage::SONIOX, 0, seconds);
+            }
+            message = read.next() => match message {
+                Some(Ok(Message::Text(text))) => {
+                    let message: Value = serde_json::from_str(&text)
+                        .map_err(|e| format!("Invalid message from Soniox: {}", e))?;
+
+                    if let Some(error) = message["error_message"].as_str() {
+                        return Err(format!("Soniox failed: {} {}", message["error_code"], error));
+                    }
+
+                    // Non-final tokens are revised in later messages, skip them
+                    let tokens = message["tokens"].as_array().cloned().unwrap_or_default();
+                    for token in tokens.iter().filter(|token| token["is_final"] == true) {
+                        let text = token["text"].as_str().unwrap_or_default();
+                        if text != END_TOKEN {
+                            utterance.push_str(text);
+                            continue;
+                        }
+
+                        let finished = std::mem::take(&mut utterance);
+                        if !finished.trim().is_empty() {
+                            let _ = transcripts.send(finished.trim().to_string()).await;
+                        }
+                    }
+
+                    if message["finished"] == true {
+                        return Err("Soniox ended the session".to_string());
+                    }
+                }
+                Some(Ok(Message::Close(_))) | None => return Err("Soniox closed the connection".to_string()),
+                Some(Err(e)) => return Err(format!("Soniox connection failed: {}", e)),
+                Some(Ok(_)) => {}
+            }
+        }
+    }
+}
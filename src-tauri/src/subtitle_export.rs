@@ -0,0 +1,118 @@
+use serde::Deserialize;
+use std::fs;
+use tauri::State;
+
+use crate::history::{HistoryEntry, HistoryState};
+use crate::settings::SettingsState;
+
+// Entries only carry the time they were recorded, cues last until the next one up to this long
+const MAX_CUE_MS: i64 = 5000;
+const MIN_CUE_MS: i64 = 1000;
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SubtitleFormat {
+    Srt,
+    Vtt,
+}
+
+fn timestamp(ms: i64, format: SubtitleFormat) -> String {
+    let ms = ms.max(0);
+    let separator = match format {
+        SubtitleFormat::Srt => ',',
+        SubtitleFormat::Vtt => '.',
+    };
+
+    format!(
+        "{:02}:{:02}:{:02}{}{:03}",
+        ms / 3_600_000,
+        ms / 60_000 % 60,
+        ms / 1000 % 60,
+        separator,
+        ms % 1000
+    )
+}
+
+/// Renders the entries as subtitles, with times relative to `start_ms`. Cues start
+/// `capture_offset_ms` before each entry to line up with when it was spoken.
+pub fn render(
+    entries: &[HistoryEntry],
+    format: SubtitleFormat,
+    start_ms: i64,
+    capture_offset_ms: i64,
+    include_original: bool,
+) -> String {
+    let start_ms = start_ms + capture_offset_ms;
+
+    let mut out = match format {
+        SubtitleFormat::Srt => String::new(),
+        SubtitleFormat::Vtt => "WEBVTT\n\n".to_string(),
+    };
+
+    for (index, entry) in entries.iter().enumerate() {
+        let start = entry.timestamp - start_ms;
+        let end = entries
+            .get(index + 1)
+            .map(|next| next.timestamp - start_ms)
+            .unwrap_or(i64::MAX)
+            .min(start + MAX_CUE_MS)
+            .max(start + MIN_CUE_MS);
+
+        if let SubtitleFormat::Srt = format {
+            out.push_str(&format!("{}\n", index + 1));
+        }
+        out.push_str(&format!(
+            "{} --> {}\n",
+            timestamp(start, format),
+            timestamp(end, format)
+        ));
+
+        // Blank lines would end the cue early
+        if include_original && !entry.original.trim().is_empty() {
+            out.push_str(&single_paragraph(&entry.original));
+            out.push('\n');
+        }
+        out.push_str(&single_paragraph(&entry.translation));
+        out.push_str("\n\n");
+    }
+
+    out
+}
+
+fn single_paragraph(text: &str) -> String {
+    text.lines()
+        .filter(|line| !line.trim().is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Writes a session as SRT or WebVTT. Times count from `start_time` (Unix ms), e.g. when
+/// the stream started, or from the session's first entry when not given.
+#[tauri::command]
+pub fn export_history_subtitles(
+    state: State<'_, HistoryState>,
+    settings: State<'_, SettingsState>,
+    session_id: String,
+    path: String,
+    format: SubtitleFormat,
+    include_original: bool,
+    start_time: Option<i64>,
+) -> Result<usize, String> {
+    let entries = state.session_entries(&session_id)?;
+    if entries.is_empty() {
+        return Err(format!("Session {} has no entries", session_id));
+    }
+
+    let offset = settings.get().input_device.capture_offset_ms as i64;
+    let start_ms = start_time.unwrap_or(entries[0].timestamp - offset);
+    let contents = render(&entries, format, start_ms, offset, include_original);
+    fs::write(&path, contents).map_err(|e| format!("Failed to write {}: {}", path, e))?;
+
+    log::info!(
+        "[HISTORY] Exported {} entries of session {} to {}",
+        entries.len(),
+        session_id,
+        path
+    );
+    Ok(entries.len())
+}
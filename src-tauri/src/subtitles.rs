@@ -0,0 +1,140 @@
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::sync::Mutex;
+use tauri::{
+    AppHandle, Manager, PhysicalPosition, PhysicalSize, WebviewUrl, WebviewWindowBuilder,
+    WindowEvent,
+};
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::events;
+use crate::history::HistoryState;
+use crate::incoming::IncomingState;
+use crate::player_languages;
+use crate::settings::SettingsState;
+use crate::shutdown;
+
+const LABEL: &str = "subtitles";
+
+/// Geometry of the subtitle window in physical pixels, saved when it closes.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SubtitleWindowSettings {
+    pub x: Option<i32>,
+    pub y: Option<i32>,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Default for SubtitleWindowSettings {
+    fn default() -> Self {
+        SubtitleWindowSettings {
+            x: None,
+            y: None,
+            width: 900,
+            height: 160,
+        }
+    }
+}
+
+/// Forwards every translation, mine and the other players', to the subtitle window while it is open.
+pub fn start(app: &AppHandle) {
+    let mut recorded = app.state::<HistoryState>().subscribe();
+    let mut incoming = app.state::<IncomingState>().subscribe();
+    let app = app.clone();
+
+    shutdown::spawn(&app.clone(), "subtitles", async move {
+        loop {
+            let (entry, from_others) = tokio::select! {
+                entry = recorded.recv() => (entry, false),
+                entry = incoming.recv() => (entry, true),
+            };
+
+            match entry {
+                Ok(entry) => {
+                    if app.get_webview_window(LABEL).is_some() {
+                        let mut subtitle = serde_json::to_value(entry).unwrap_or_default();
+                        let player = if from_others {
+                            None
+                        } else {
+                            player_languages::active_player(&app)
+                        };
+                        subtitle["player"] = json!(player);
+                        subtitle["incoming"] = json!(from_others);
+                        let _ = events::emit_to(&app, LABEL, "subtitle", subtitle);
+                    }
+                }
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => break,
+            }
+        }
+    });
+}
+
+fn save_geometry(app: &AppHandle, geometry: SubtitleWindowSettings) {
+    let state = app.state::<SettingsState>();
+    let mut settings = state.get();
+    settings.subtitle_window = geometry;
+
+    if let Err(e) = state.set(settings) {
+        log::warn!("[SUBTITLES] Failed to save window position: {}", e);
+    }
+}
+
+#[tauri::command]
+pub fn open_subtitle_window(app: AppHandle) -> Result<(), String> {
+    if let Some(window) = app.get_webview_window(LABEL) {
+        let _ = window.set_focus();
+        return Ok(());
+    }
+
+    let geometry = app.state::<SettingsState>().get().subtitle_window;
+
+    let window =
+        WebviewWindowBuilder::new(&app, LABEL, WebviewUrl::App("index.html#subtitles".into()))
+            .title("Kikitan Subtitles")
+            .decorations(false)
+            .transparent(true)
+            .always_on_top(true)
+            .skip_taskbar(true)
+            .build()
+            .map_err(|e| format!("Failed to open subtitle window: {}", e))?;
+
+    // The builder takes logical units, the saved geometry is physical
+    let _ = window.set_size(PhysicalSize::new(geometry.width, geometry.height));
+    if let (Some(x), Some(y)) = (geometry.x, geometry.y) {
+        let _ = window.set_position(PhysicalPosition::new(x, y));
+    }
+
+    // Positions can't be read once the window is gone, so follow them as they change
+    let handle = app.clone();
+    let geometry = Mutex::new(geometry);
+    window.on_window_event(move |event| match event {
+        WindowEvent::Moved(position) => {
+            let mut geometry = geometry.lock().unwrap();
+            geometry.x = Some(position.x);
+            geometry.y = Some(position.y);
+        }
+        WindowEvent::Resized(size) => {
+            let mut geometry = geometry.lock().unwrap();
+            geometry.width = size.width;
+            geometry.height = size.height;
+        }
+        WindowEvent::Destroyed => save_geometry(&handle, geometry.lock().unwrap().clone()),
+        _ => {}
+    });
+
+    log::info!("[SUBTITLES] Opened subtitle window");
+    Ok(())
+}
+
+#[tauri::command]
+pub fn close_subtitle_window(app: AppHandle) -> Result<(), String> {
+    if let Some(window) = app.get_webview_window(LABEL) {
+        window
+            .close()
+            .map_err(|e| format!("Failed to close subtitle window: {}", e))?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,139 @@
+//! Keeps emoji and kaomoji intact through translation.
+//!
+//! Providers either drop them or "translate" the pieces, `(´・ω・`)` tends to come back with
+//! its ω spelled out. `protect` swaps each one for a numbered placeholder that translators
+//! leave alone, and `restore` puts them back in the translated text.
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+
+// Letters that only show up as eyes and mouths, `(ツ)` and `(・ω・)` are faces, `(笑)` is a word
+const FACE_LETTERS: &str = "ωツシДд∀ᴗ‿▽ー゜ﾟｰ";
+
+// Placed around the translation, so sentence punctuation after a face stays with the sentence
+const SENTENCE_PUNCTUATION: &str = ".,!?。、！？\"'「」";
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Protected {
+    /// Text to translate, with `[[n]]` in place of each symbol.
+    pub text: String,
+    pub tokens: Vec<String>,
+}
+
+fn symbols() -> &'static Regex {
+    static SYMBOLS: OnceLock<Regex> = OnceLock::new();
+
+    SYMBOLS.get_or_init(|| {
+        let face = format!(r"[^\s\p{{L}}\p{{N}}()（）]|[{}]", FACE_LETTERS);
+        let arms = format!(r"[^\s\p{{L}}\p{{N}}()（）{}]", regex::escape(SENTENCE_PUNCTUATION));
+
+        Regex::new(&format!(
+            // Emoji with their skin tone, variation selector and ZWJ sequences, then flags,
+            // then bracketed faces with whatever arms they have on either side
+            r"\p{{Extended_Pictographic}}(?:[\u{{FE0F}}\u{{20E3}}\u{{1F3FB}}-\u{{1F3FF}}]|\u{{200D}}\p{{Extended_Pictographic}})*|\p{{Regional_Indicator}}{{2}}|{arms}*[(（](?:{face})(?:{face}|\s)*[)）]{arms}*",
+            arms = arms,
+            face = face,
+        ))
+        .expect("invalid symbol pattern")
+    })
+}
+
+// Western emoticons are only taken as a whole word, `:3` in `10:30` is a time
+fn emoticon() -> &'static Regex {
+    static EMOTICON: OnceLock<Regex> = OnceLock::new();
+
+    EMOTICON.get_or_init(|| {
+        Regex::new(r"^(?:[:;=][-'^]?[)(\]\[DPpOo3/\\|*]|[(\[][-'^]?[:;=]|\^[_.-]?\^;*|[Tㅠ;][_.][Tㅠ;]|>[_.]?<|<3|[xX]D|[oO][_.][oO]|[uU][wW][uU]|[oO][wW][oO])$")
+            .expect("invalid emoticon pattern")
+    })
+}
+
+fn placeholder(index: usize) -> String {
+    format!("[[{}]]", index)
+}
+
+fn placeholders() -> &'static Regex {
+    static PLACEHOLDERS: OnceLock<Regex> = OnceLock::new();
+
+    // Translators sometimes space out the brackets or make them full width for CJK targets
+    PLACEHOLDERS.get_or_init(|| {
+        Regex::new(r"[\[［]\s*[\[［]\s*([0-9０-９]+)\s*[\]］]\s*[\]］]")
+            .expect("invalid placeholder pattern")
+    })
+}
+
+pub fn protect(text: &str) -> Protected {
+    let mut tokens = Vec::new();
+
+    let text = symbols().replace_all(text, |captures: &regex::Captures| {
+        tokens.push(captures[0].to_string());
+        placeholder(tokens.len() - 1)
+    });
+
+    let text = text
+        .split(' ')
+        .map(|word| {
+            if emoticon().is_match(word) {
+                tokens.push(word.to_string());
+                placeholder(tokens.len() - 1)
+            } else {
+                word.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    Protected { text, tokens }
+}
+
+/// Puts the symbols back, appending any whose placeholder the translator dropped.
+pub fn restore(translated: &str, tokens: &[String]) -> String {
+    if tokens.is_empty() {
+        return translated.to_string();
+    }
+
+    let mut used = vec![false; tokens.len()];
+    let mut text = placeholders()
+        .replace_all(translated, |captures: &regex::Captures| {
+            let index: Option<usize> = captures[1]
+                .chars()
+                .map(|c| match c {
+                    '０'..='９' => {
+                        char::from_u32(c as u32 - '０' as u32 + '0' as u32).unwrap_or(c)
+                    }
+                    _ => c,
+                })
+                .collect::<String>()
+                .parse()
+                .ok();
+
+            match index.and_then(|index| Some((index, tokens.get(index)?))) {
+                Some((index, token)) => {
+                    used[index] = true;
+                    token.clone()
+                }
+                None => String::new(),
+            }
+        })
+        .into_owned();
+
+    for (token, used) in tokens.iter().zip(used) {
+        if !used {
+            text.push(' ');
+            text.push_str(token);
+        }
+    }
+
+    text
+}
+
+#[tauri::command]
+pub fn protect_symbols(text: String) -> Protected {
+    protect(&text)
+}
+
+#[tauri::command]
+pub fn restore_symbols(text: String, tokens: Vec<String>) -> String {
+    restore(&text, &tokens)
+}
@@ -0,0 +1,208 @@
+//! Strictly opt-in anonymous usage counters: which providers are used, how long the app
+//! runs and which kinds of errors happen. Never any text, languages, names or ids.
+//!
+//! Nothing is counted while disabled, and builds without a telemetry endpoint never send.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager, State};
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::history::HistoryState;
+use crate::network;
+use crate::settings::SettingsState;
+use crate::shutdown;
+
+/// Where batches are posted, builds without it keep telemetry local.
+const ENDPOINT: Option<&str> = option_env!("KIKITAN_TELEMETRY_URL");
+
+const SUBMIT_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TelemetrySettings {
+    pub enabled: bool,
+}
+
+#[derive(Clone, Debug, Default)]
+struct Counters {
+    translations: u64,
+    asr_providers: BTreeMap<String, u64>,
+    translation_providers: BTreeMap<String, u64>,
+    errors: BTreeMap<&'static str, u64>,
+}
+
+/// Exactly what one submission contains.
+#[derive(Clone, Debug, Serialize)]
+pub struct TelemetryBatch {
+    pub app_version: String,
+    pub os: &'static str,
+    /// How long the app has been running.
+    pub session_seconds: u64,
+    /// Translations since the previous batch.
+    pub translations: u64,
+    pub asr_providers: BTreeMap<String, u64>,
+    pub translation_providers: BTreeMap<String, u64>,
+    /// Error categories such as `osc` or `incoming`, see [`record_error`].
+    pub errors: BTreeMap<&'static str, u64>,
+}
+
+pub struct TelemetryState {
+    started: Instant,
+    counters: Mutex<Counters>,
+}
+
+impl Default for TelemetryState {
+    fn default() -> Self {
+        TelemetryState {
+            started: Instant::now(),
+            counters: Mutex::new(Counters::default()),
+        }
+    }
+}
+
+impl TelemetryState {
+    fn batch(&self, app: &AppHandle) -> TelemetryBatch {
+        let counters = self.counters.lock().unwrap().clone();
+        TelemetryBatch {
+            app_version: app.package_info().version.to_string(),
+            os: std::env::consts::OS,
+            session_seconds: self.started.elapsed().as_secs(),
+            translations: counters.translations,
+            asr_providers: counters.asr_providers,
+            translation_providers: counters.translation_providers,
+            errors: counters.errors,
+        }
+    }
+}
+
+fn enabled(app: &AppHandle) -> bool {
+    app.state::<SettingsState>().get().telemetry.enabled
+}
+
+/// Counts one error of `category`. Categories are fixed names, never error messages.
+pub fn record_error(app: &AppHandle, category: &'static str) {
+    if !enabled(app) {
+        return;
+    }
+
+    if let Some(state) = app.try_state::<TelemetryState>() {
+        *state
+            .counters
+            .lock()
+            .unwrap()
+            .errors
+            .entry(category)
+            .or_default() += 1;
+    }
+}
+
+async fn submit(app: &AppHandle, client: &reqwest::Client) {
+    let state = app.state::<TelemetryState>();
+
+    // Turning it off also drops whatever was counted before
+    if !enabled(app) {
+        *state.counters.lock().unwrap() = Counters::default();
+        return;
+    }
+    let Some(endpoint) = ENDPOINT else {
+        return;
+    };
+
+    let batch = state.batch(app);
+    if batch.translations == 0 && batch.errors.is_empty() {
+        return;
+    }
+
+    let sent = client
+        .post(endpoint)
+        .json(&batch)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status());
+
+    match sent {
+        Ok(_) => {
+            // Counting went on during the request, only take off what was sent
+            let mut counters = state.counters.lock().unwrap();
+            counters.translations -= batch.translations.min(counters.translations);
+            for (provider, count) in &batch.asr_providers {
+                counters
+                    .asr_providers
+                    .entry(provider.clone())
+                    .and_modify(|n| *n -= (*count).min(*n));
+            }
+            for (provider, count) in &batch.translation_providers {
+                counters
+                    .translation_providers
+                    .entry(provider.clone())
+                    .and_modify(|n| *n -= (*count).min(*n));
+            }
+            for (category, count) in &batch.errors {
+                counters
+                    .errors
+                    .entry(category)
+                    .and_modify(|n| *n -= (*count).min(*n));
+            }
+            counters.asr_providers.retain(|_, n| *n > 0);
+            counters.translation_providers.retain(|_, n| *n > 0);
+            counters.errors.retain(|_, n| *n > 0);
+
+            log::debug!("[TELEMETRY] Submitted a batch");
+        }
+        // Kept for the next try, a missed batch is no reason to bother the user
+        Err(e) => log::debug!("[TELEMETRY] Failed to submit: {}", e),
+    }
+}
+
+/// Counts translations while enabled and submits a batch every hour and when quitting.
+pub fn start(app: &AppHandle) {
+    let mut recorded = app.state::<HistoryState>().subscribe();
+    let app = app.clone();
+    let exiting = shutdown::token(&app);
+
+    let task = async move {
+        let client = network::http_client(&app);
+        let mut interval = tokio::time::interval(SUBMIT_INTERVAL);
+        interval.tick().await;
+
+        loop {
+            tokio::select! {
+                _ = exiting.cancelled() => break,
+                _ = interval.tick() => submit(&app, &client).await,
+                entry = recorded.recv() => match entry {
+                    Ok(entry) => {
+                        if !enabled(&app) {
+                            continue;
+                        }
+
+                        let state = app.state::<TelemetryState>();
+                        let mut counters = state.counters.lock().unwrap();
+                        counters.translations += 1;
+                        *counters.asr_providers.entry(entry.asr_provider).or_default() += 1;
+                        *counters.translation_providers.entry(entry.translation_provider).or_default() += 1;
+                    }
+                    Err(RecvError::Lagged(_)) => {}
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        }
+
+        submit(&app, &client).await;
+    };
+
+    tauri::async_runtime::spawn(shutdown::track(&app, "telemetry", task));
+}
+
+/// The batch that would be sent next, so users can see exactly what leaves the machine.
+/// `endpoint` is `None` in builds that never send.
+#[tauri::command]
+pub fn preview_telemetry(app: AppHandle, state: State<'_, TelemetryState>) -> serde_json::Value {
+    serde_json::json!({
+        "enabled": enabled(&app),
+        "endpoint": ENDPOINT,
+        "batch": state.batch(&app),
+    })
+}
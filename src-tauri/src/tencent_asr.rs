@@ -0,0 +1,182 @@
+//! Tencent Cloud realtime speech recognition over WebSocket, as a backend ASR provider.
+//!
+//! The handshake is signed in the URL: `signature` is base64 of an HMAC-SHA1, keyed with the
+//! secret key, over the host, path and the alphabetically sorted query without it.
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use futures_util::{SinkExt, StreamExt};
+use hmac::{Hmac, Mac};
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha1::Sha1;
+use tauri::{AppHandle, Manager};
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::protocol::Message;
+
+use crate::languages;
+use crate::network;
+use crate::quota;
+use crate::secrets;
+use crate::settings::SettingsState;
+use crate::usage::{self, UsageState};
+
+const HOST: &str = "asr.cloud.tencent.com";
+const BYTES_PER_SECOND: f64 = 16000.0 * 2.0;
+
+// Raw PCM in the `voice_format` parameter
+const PCM: u8 = 1;
+
+// A slice of type 2 ends a sentence, 0 and 1 are partial results
+const SENTENCE_END: i64 = 2;
+
+#[derive(Clone, Debug, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TencentAsrSettings {
+    pub app_id: String,
+    /// The secret key is kept in the credential store.
+    pub secret_id: String,
+    /// Engine such as `16k_zh` or `16k_zh_dialect`, empty picks one from the source language.
+    pub engine_model_type: String,
+}
+
+fn engine_model_type(language: &str) -> String {
+    match languages::primary(language) {
+        "zh" if language.ends_with("HK") => "16k_yue".to_string(),
+        primary => format!("16k_{}", primary),
+    }
+}
+
+fn url(settings: &TencentAsrSettings, secret_key: &str, language: &str) -> String {
+    let timestamp = chrono::Utc::now().timestamp();
+    let engine = if settings.engine_model_type.is_empty() {
+        engine_model_type(language)
+    } else {
+        settings.engine_model_type.clone()
+    };
+    let voice_id: String = rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(16)
+        .map(char::from)
+        .collect();
+
+    let mut query = vec![
+        ("engine_model_type", engine),
+        ("expired", (timestamp + 24 * 60 * 60).to_string()),
+        ("needvad", "1".to_string()),
+        (
+            "nonce",
+            rand::thread_rng()
+                .gen_range(1..1_000_000_000u32)
+                .to_string(),
+        ),
+        ("secretid", settings.secret_id.clone()),
+        ("timestamp", timestamp.to_string()),
+        ("voice_format", PCM.to_string()),
+        ("voice_id", voice_id),
+    ];
+    query.sort();
+    let query = query
+        .iter()
+        .map(|(key, value)| format!("{}={}", key, value))
+        .collect::<Vec<_>>()
+        .join("&");
+
+    let path = format!("/asr/v2/{}", settings.app_id);
+    let mut mac = Hmac::<Sha1>::new_from_slice(secret_key.as_bytes())
+        .expect("HMAC accepts keys of any length");
+    mac.update(format!("{}{}?{}", HOST, path, query).as_bytes());
+    let signature: String = BASE64
+        .encode(mac.finalize().into_bytes())
+        .bytes()
+        .map(|byte| match byte {
+            b'+' => "%2B".to_string(),
+            b'/' => "%2F".to_string(),
+            b'=' => "%3D".to_string(),
+            _ => (byte as char).to_string(),
+        })
+        .collect();
+
+    format!("wss://{}{}?{}&signature={}", HOST, path, query, signature)
+}
+
+/// Recognizes 16 kHz mono PCM16 chunks from `audio` and sends each finished sentence to
+/// `transcripts`.
+pub async fn transcripts(
+    app: &AppHandle,
+    language: &str,
+    mut audio: mpsc::Receiver<Vec<u8>>,
+    transcripts: mpsc::Sender<String>,
+) -> Result<(), String> {
+    let settings = app.state::<SettingsState>().get().tencent_asr;
+    if settings.app_id.is_empty() || settings.secret_id.is_empty() {
+        return Err("Tencent Cloud ASR needs an app id and a secret id".to_string());
+    }
+    let secret_key = secrets::get(secrets::TENCENT_SECRET_KEY)?
+        .ok_or_else(|| "Tencent Cloud secret key is not set".to_string())?;
+
+    let (ws, _) = network::connect_websocket(app, url(&settings, &secret_key, language))
+        .await
+        .map_err(|e| format!("Failed to connect to Tencent Cloud ASR: {}", e))?;
+    let (mut write, mut read) = ws.split();
+
+    log::info!("[TENCENT-ASR] Connected");
+
+    let mut ping = network::keepalive_interval(app);
+
+    loop {
+        tokio::select! {
+            _ = ping.tick() => {
+                write
+                    .send(Message::Ping(Vec::new()))
+                    .await
+                    .map_err(|e| format!("Failed to ping Tencent ASR: {}", e))?;
+            }
+            chunk = audio.recv() => {
+                let chunk = match chunk {
+                    Some(chunk) => chunk,
+                    None => {
+                        let _ = write.send(Message::Text(json!({ "type": "end" }).to_string())).await;
+                        return Ok(());
+                    }
+                };
+
+                quota::ensure_available(app, usage::TENCENT_ASR)?;
+                let seconds = chunk.len() as f64 / BYTES_PER_SECOND;
+                write
+                    .send(Message::Binary(chunk))
+                    .await
+                    .map_err(|e| format!("Failed to send audio to Tencent Cloud ASR: {}", e))?;
+
+                app.state::<UsageState>().add(usage::TENCENT_ASR, 0, seconds);
+            }
+            message = read.next() => match message {
+                Some(Ok(Message::Text(text))) => {
+                    let message: Value = serde_json::from_str(&text)
+                        .map_err(|e| format!("Invalid message from Tencent Cloud ASR: {}", e))?;
+
+                    if message["code"] != 0 {
+                        return Err(format!(
+                            "Tencent Cloud ASR failed: {} {}",
+                            message["code"],
+                            message["message"].as_str().unwrap_or_default()
+                        ));
+                    }
+
+                    let result = &message["result"];
+                    if result["slice_type"].as_i64() == Some(SENTENCE_END) {
+                        let text = result["voice_text_str"].as_str().unwrap_or_default().trim();
+                        if !text.is_empty() {
+                            let _ = transcripts.send(text.to_string()).await;
+                        }
+                    }
+                }
+                Some(Ok(Message::Close(_))) | None => return Err("Tencent Cloud ASR closed the connection".to_string()),
+                Some(Err(e)) => return Err(format!("Tencent Cloud ASR connection failed: {}", e)),
+                Some(Ok(_)) => {}
+            }
+        }
+    }
+}
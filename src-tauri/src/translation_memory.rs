@@ -0,0 +1,330 @@
+//! Remembers how I corrected translations, so the same sentence comes out the way I wanted
+//! next time instead of making the same mistake again.
+//!
+//! Lookups match the normalized original exactly first, then fall back to the closest
+//! remembered sentence if it's at least `fuzzy_threshold` similar. Both run before any
+//! translation provider is called.
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, State};
+
+use crate::comparison;
+use crate::database;
+use crate::paths;
+use crate::settings::SettingsState;
+
+const MEMORY_FILE: &str = "memory.db";
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TranslationMemorySettings {
+    pub enabled: bool,
+    /// How similar a sentence has to be to a remembered one to reuse its translation, 0 to 1.
+    pub fuzzy_threshold: f64,
+}
+
+impl Default for TranslationMemorySettings {
+    fn default() -> Self {
+        TranslationMemorySettings {
+            enabled: true,
+            fuzzy_threshold: 0.9,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct MemoryEntry {
+    pub id: i64,
+    pub source_language: String,
+    pub target_language: String,
+    pub original: String,
+    pub translation: String,
+    pub uses: u64,
+    pub updated: i64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct MemoryMatch {
+    pub id: i64,
+    pub translation: String,
+    /// 1 for exact matches.
+    pub similarity: f64,
+}
+
+pub struct TranslationMemoryState {
+    conn: Mutex<Connection>,
+}
+
+/// Case, surrounding punctuation and repeated spaces don't change what a sentence means.
+fn normalize(text: &str) -> String {
+    text.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .trim_matches(|c: char| c.is_ascii_punctuation() || "。、！？…".contains(c))
+        .to_lowercase()
+}
+
+impl TranslationMemoryState {
+    pub fn open(app: &AppHandle) -> Self {
+        let path = paths::data_dir(app).join(MEMORY_FILE);
+
+        let conn = match database::open(&path, create_schema) {
+            Ok(conn) => conn,
+            Err(e) => {
+                log::error!("[MEMORY] Failed to open {}: {}", path.display(), e);
+                let conn = Connection::open_in_memory().expect("failed to open in-memory database");
+                create_schema(&conn).expect("failed to create translation memory schema");
+                conn
+            }
+        };
+
+        TranslationMemoryState {
+            conn: Mutex::new(conn),
+        }
+    }
+
+    pub fn remember(
+        &self,
+        source_language: &str,
+        target_language: &str,
+        original: &str,
+        translation: &str,
+    ) -> Result<(), String> {
+        let normalized = normalize(original);
+        if normalized.is_empty() || translation.trim().is_empty() {
+            return Err("Nothing to remember".to_string());
+        }
+
+        self.conn
+            .lock()
+            .unwrap()
+            .execute(
+                "INSERT INTO translation_memory
+                    (source_language, target_language, normalized, original, translation, uses, updated)
+                 VALUES (?1, ?2, ?3, ?4, ?5, 0, ?6)
+                 ON CONFLICT (source_language, target_language, normalized) DO UPDATE SET
+                    original = excluded.original,
+                    translation = excluded.translation,
+                    updated = excluded.updated",
+                params![
+                    source_language,
+                    target_language,
+                    normalized,
+                    original.trim(),
+                    translation.trim(),
+                    chrono::Utc::now().timestamp_millis()
+                ],
+            )
+            .map_err(|e| format!("Failed to remember correction: {}", e))?;
+
+        Ok(())
+    }
+
+    pub fn lookup(
+        &self,
+        source_language: &str,
+        target_language: &str,
+        text: &str,
+        fuzzy_threshold: f64,
+    ) -> Result<Option<MemoryMatch>, String> {
+        let normalized = normalize(text);
+        if normalized.is_empty() {
+            return Ok(None);
+        }
+
+        let conn = self.conn.lock().unwrap();
+        let exact = conn
+            .query_row(
+                "SELECT id, translation FROM translation_memory
+                 WHERE source_language = ?1 AND target_language = ?2 AND normalized = ?3",
+                params![source_language, target_language, normalized],
+                |row| {
+                    Ok(MemoryMatch {
+                        id: row.get(0)?,
+                        translation: row.get(1)?,
+                        similarity: 1.0,
+                    })
+                },
+            )
+            .optional()
+            .map_err(|e| format!("Failed to look up translation memory: {}", e))?;
+
+        let found = match exact {
+            Some(found) => Some(found),
+            None if fuzzy_threshold < 1.0 => {
+                // Sentences this much shorter or longer are too different to reuse anyway
+                let length = normalized.chars().count() as f64;
+                let mut stmt = conn
+                    .prepare(
+                        "SELECT id, normalized, translation FROM translation_memory
+                         WHERE source_language = ?1 AND target_language = ?2
+                            AND length(normalized) BETWEEN ?3 AND ?4",
+                    )
+                    .map_err(|e| e.to_string())?;
+
+                let candidates = stmt
+                    .query_map(
+                        params![
+                            source_language,
+                            target_language,
+                            (length * fuzzy_threshold).floor() as i64,
+                            (length / fuzzy_threshold.max(0.01)).ceil() as i64
+                        ],
+                        |row| {
+                            Ok((
+                                row.get::<_, i64>(0)?,
+                                row.get::<_, String>(1)?,
+                                row.get::<_, String>(2)?,
+                            ))
+                        },
+                    )
+                    .and_then(|rows| rows.collect::<rusqlite::Result<Vec<_>>>())
+                    .map_err(|e| format!("Failed to look up translation memory: {}", e))?;
+
+                candidates
+                    .into_iter()
+                    .map(|(id, remembered, translation)| MemoryMatch {
+                        id,
+                        translation,
+                        similarity: comparison::agreement(&normalized, &remembered),
+                    })
+                    .filter(|found| found.similarity >= fuzzy_threshold)
+                    .max_by(|a, b| a.similarity.total_cmp(&b.similarity))
+            }
+            None => None,
+        };
+
+        if let Some(found) = &found {
+            let _ = conn.execute(
+                "UPDATE translation_memory SET uses = uses + 1 WHERE id = ?1",
+                params![found.id],
+            );
+        }
+
+        Ok(found)
+    }
+
+    fn list(&self) -> Result<Vec<MemoryEntry>, String> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn
+            .prepare(
+                "SELECT id, source_language, target_language, original, translation, uses, updated
+                 FROM translation_memory
+                 ORDER BY updated DESC",
+            )
+            .map_err(|e| e.to_string())?;
+
+        let entries = stmt
+            .query_map([], |row| {
+                Ok(MemoryEntry {
+                    id: row.get(0)?,
+                    source_language: row.get(1)?,
+                    target_language: row.get(2)?,
+                    original: row.get(3)?,
+                    translation: row.get(4)?,
+                    uses: row.get::<_, i64>(5)? as u64,
+                    updated: row.get(6)?,
+                })
+            })
+            .and_then(|rows| rows.collect::<rusqlite::Result<Vec<_>>>())
+            .map_err(|e| format!("Failed to list translation memory: {}", e))?;
+
+        Ok(entries)
+    }
+
+    fn delete(&self, ids: &[i64]) -> Result<(), String> {
+        let conn = self.conn.lock().unwrap();
+        for id in ids {
+            conn.execute("DELETE FROM translation_memory WHERE id = ?1", params![id])
+                .map_err(|e| format!("Failed to delete translation memory: {}", e))?;
+        }
+
+        Ok(())
+    }
+}
+
+fn create_schema(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS translation_memory (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            source_language TEXT NOT NULL,
+            target_language TEXT NOT NULL,
+            normalized TEXT NOT NULL,
+            original TEXT NOT NULL,
+            translation TEXT NOT NULL,
+            uses INTEGER NOT NULL DEFAULT 0,
+            updated INTEGER NOT NULL,
+            UNIQUE (source_language, target_language, normalized)
+        );",
+    )
+}
+
+/// The remembered translation of `text`, if memory is enabled and has one.
+pub fn lookup(
+    app: &AppHandle,
+    source_language: &str,
+    target_language: &str,
+    text: &str,
+) -> Option<String> {
+    let settings = app.state::<SettingsState>().get().translation_memory;
+    if !settings.enabled {
+        return None;
+    }
+
+    match app.state::<TranslationMemoryState>().lookup(
+        source_language,
+        target_language,
+        text,
+        settings.fuzzy_threshold,
+    ) {
+        Ok(found) => found.map(|found| found.translation),
+        Err(e) => {
+            log::warn!("[MEMORY] {}", e);
+            None
+        }
+    }
+}
+
+#[tauri::command]
+pub fn remember_correction(
+    state: State<'_, TranslationMemoryState>,
+    source_language: String,
+    target_language: String,
+    original: String,
+    translation: String,
+) -> Result<(), String> {
+    state.remember(&source_language, &target_language, &original, &translation)?;
+    log::info!(
+        "[MEMORY] Remembered a correction for {} -> {}",
+        source_language,
+        target_language
+    );
+    Ok(())
+}
+
+#[tauri::command]
+pub fn lookup_translation_memory(
+    app: AppHandle,
+    source_language: String,
+    target_language: String,
+    text: String,
+) -> Option<String> {
+    lookup(&app, &source_language, &target_language, &text)
+}
+
+#[tauri::command]
+pub fn list_translation_memory(
+    state: State<'_, TranslationMemoryState>,
+) -> Result<Vec<MemoryEntry>, String> {
+    state.list()
+}
+
+#[tauri::command]
+pub fn delete_translation_memory(
+    state: State<'_, TranslationMemoryState>,
+    ids: Vec<i64>,
+) -> Result<(), String> {
+    state.delete(&ids)
+}
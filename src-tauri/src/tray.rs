@@ -0,0 +1,148 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use tauri::menu::{CheckMenuItem, Menu, MenuItem, PredefinedMenuItem};
+use tauri::tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent};
+use tauri::{AppHandle, Manager, Wry};
+
+use crate::events;
+
+pub struct TrayState {
+    status: MenuItem<Wry>,
+    pause: MenuItem<Wry>,
+    mute: CheckMenuItem<Wry>,
+    connection: Mutex<String>,
+    paused: AtomicBool,
+    muted: AtomicBool,
+}
+
+impl TrayState {
+    pub fn capture_muted(&self) -> bool {
+        self.muted.load(Ordering::Relaxed)
+    }
+
+    pub fn paused(&self) -> bool {
+        self.paused.load(Ordering::Relaxed)
+    }
+
+    pub fn connection(&self) -> String {
+        self.connection.lock().unwrap().clone()
+    }
+
+    fn refresh(&self) {
+        let paused = self.paused.load(Ordering::Relaxed);
+        let status = if paused {
+            "Paused".to_string()
+        } else {
+            self.connection.lock().unwrap().clone()
+        };
+
+        let _ = self.status.set_text(format!("Status: {}", status));
+        let _ = self.pause.set_text(if paused {
+            "Resume translation"
+        } else {
+            "Pause translation"
+        });
+    }
+}
+
+pub fn create(app: &AppHandle) -> tauri::Result<TrayState> {
+    let status = MenuItem::with_id(app, "status", "Status: Idle", false, None::<&str>)?;
+    let pause = MenuItem::with_id(app, "pause", "Pause translation", true, None::<&str>)?;
+    let mute = CheckMenuItem::with_id(app, "mute", "Mute capture", true, false, None::<&str>)?;
+    let open = MenuItem::with_id(app, "open", "Open window", true, None::<&str>)?;
+    let quit = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
+
+    let menu = Menu::with_items(
+        app,
+        &[
+            &status,
+            &PredefinedMenuItem::separator(app)?,
+            &pause,
+            &mute,
+            &PredefinedMenuItem::separator(app)?,
+            &open,
+            &quit,
+        ],
+    )?;
+
+    let mut builder = TrayIconBuilder::with_id("main")
+        .tooltip("Kikitan Translator")
+        .menu(&menu)
+        .menu_on_left_click(false)
+        .on_menu_event(|app, event| match event.id.as_ref() {
+            "pause" => {
+                let _ = events::emit(app, "tray-action", "toggle_translation");
+            }
+            "mute" => {
+                let state = app.state::<TrayState>();
+                let muted = !state.muted.load(Ordering::Relaxed);
+                state.muted.store(muted, Ordering::Relaxed);
+                let _ = state.mute.set_checked(muted);
+
+                log::info!("[TRAY] Capture {}", if muted { "muted" } else { "unmuted" });
+                let _ = events::emit(app, "capture-muted", muted);
+            }
+            "open" => show_main_window(app),
+            "quit" => app.exit(0),
+            _ => {}
+        })
+        .on_tray_icon_event(|tray, event| {
+            if let TrayIconEvent::Click {
+                button: MouseButton::Left,
+                button_state: MouseButtonState::Up,
+                ..
+            } = event
+            {
+                show_main_window(tray.app_handle());
+            }
+        });
+
+    if let Some(icon) = app.default_window_icon() {
+        builder = builder.icon(icon.clone());
+    }
+
+    builder.build(app)?;
+
+    Ok(TrayState {
+        status,
+        pause,
+        mute,
+        connection: Mutex::new("Idle".to_string()),
+        paused: AtomicBool::new(false),
+        muted: AtomicBool::new(false),
+    })
+}
+
+pub fn show_main_window(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.show();
+        let _ = window.unminimize();
+        let _ = window.set_focus();
+    }
+}
+
+/// Updates the connection part of the tray status, e.g. "Connected" or "Disconnected".
+pub fn set_connection(app: &AppHandle, connection: &str) {
+    if let Some(state) = app.try_state::<TrayState>() {
+        *state.connection.lock().unwrap() = connection.to_string();
+        state.refresh();
+    }
+}
+
+/// Whether "Mute capture" is checked, for recognizers started while it already was.
+#[tauri::command]
+pub fn get_capture_muted(app: AppHandle) -> bool {
+    app.try_state::<TrayState>()
+        .map_or(false, |tray| tray.capture_muted())
+}
+
+#[tauri::command]
+pub fn set_tray_status(app: AppHandle, paused: bool, connection: Option<String>) {
+    if let Some(state) = app.try_state::<TrayState>() {
+        state.paused.store(paused, Ordering::Relaxed);
+        if let Some(connection) = connection {
+            *state.connection.lock().unwrap() = connection;
+        }
+        state.refresh();
+    }
+}
@@ -0,0 +1,280 @@
+use futures_util::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashMap;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tokio::net::TcpStream;
+use tokio::sync::broadcast::error::RecvError;
+use tokio_tungstenite::tungstenite::protocol::Message;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+
+use crate::events;
+use crate::formatting;
+use crate::history::{HistoryEntry, HistoryState};
+use crate::network;
+use crate::secrets;
+use crate::settings::{SettingsState, VrchatSettings};
+use crate::shutdown;
+
+type TwitchSocket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+const IRC_URL: &str = "wss://irc-ws.chat.twitch.tv:443";
+const RECONNECT_DELAY: Duration = Duration::from_secs(10);
+
+// Twitch cuts chat messages at 500 characters
+const MAX_MESSAGE: usize = 500;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TwitchSettings {
+    pub enabled: bool,
+    /// Channel to join, without the `#`.
+    pub channel: String,
+    /// Account that posts, its OAuth token lives in the credential store.
+    pub username: String,
+    /// Post every translation to chat.
+    pub post_translations: bool,
+    /// Chat messages starting with this are translated and sent to the chatbox, empty disables it.
+    pub relay_prefix: String,
+    /// Only the broadcaster, moderators and VIPs may use the relay.
+    pub relay_privileged_only: bool,
+}
+
+impl Default for TwitchSettings {
+    fn default() -> Self {
+        TwitchSettings {
+            enabled: false,
+            channel: String::new(),
+            username: String::new(),
+            post_translations: true,
+            relay_prefix: "!say ".to_string(),
+            relay_privileged_only: true,
+        }
+    }
+}
+
+struct ChatMessage {
+    tags: HashMap<String, String>,
+    user: String,
+    text: String,
+}
+
+impl ChatMessage {
+    fn privileged(&self) -> bool {
+        let badges = self.tags.get("badges").map(String::as_str).unwrap_or("");
+        badges.split(',').any(|badge| {
+            badge.starts_with("broadcaster/")
+                || badge.starts_with("moderator/")
+                || badge.starts_with("vip/")
+        })
+    }
+}
+
+// `@tags :user!user@user.tmi.twitch.tv PRIVMSG #channel :text`
+fn parse_privmsg(line: &str) -> Option<ChatMessage> {
+    let (tags, rest) = match line.strip_prefix('@') {
+        Some(tagged) => tagged.split_once(' ')?,
+        None => ("", line),
+    };
+
+    let (prefix, rest) = rest.strip_prefix(':')?.split_once(' ')?;
+    let (command, rest) = rest.split_once(' ')?;
+    if command != "PRIVMSG" {
+        return None;
+    }
+
+    let text = rest.split_once(" :")?.1;
+    let tags = tags
+        .split(';')
+        .filter_map(|tag| tag.split_once('='))
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect();
+
+    Some(ChatMessage {
+        tags,
+        user: prefix.split('!').next().unwrap_or(prefix).to_string(),
+        text: text.to_string(),
+    })
+}
+
+async fn connect(app: &AppHandle, settings: &TwitchSettings) -> Result<TwitchSocket, String> {
+    let token = secrets::get(secrets::TWITCH_OAUTH_TOKEN)?
+        .ok_or_else(|| "Twitch OAuth token is not set".to_string())?;
+
+    let (mut ws, _) = network::connect_websocket(app, IRC_URL)
+        .await
+        .map_err(|e| format!("Failed to connect to Twitch chat: {}", e))?;
+
+    let login = [
+        "CAP REQ :twitch.tv/tags".to_string(),
+        format!("PASS oauth:{}", token.trim_start_matches("oauth:")),
+        format!("NICK {}", settings.username.to_lowercase()),
+        format!("JOIN #{}", channel(settings)),
+    ];
+    for line in login {
+        ws.send(Message::Text(line))
+            .await
+            .map_err(|e| format!("Failed to log in to Twitch chat: {}", e))?;
+    }
+
+    Ok(ws)
+}
+
+fn channel(settings: &TwitchSettings) -> String {
+    settings.channel.trim_start_matches('#').to_lowercase()
+}
+
+async fn post(
+    ws: &mut TwitchSocket,
+    settings: &TwitchSettings,
+    vrchat: &VrchatSettings,
+    entry: &HistoryEntry,
+) -> Result<(), String> {
+    let text: String = formatting::format_entry(vrchat, entry)
+        .replace(['\r', '\n'], " ")
+        .chars()
+        .take(MAX_MESSAGE)
+        .collect();
+
+    ws.send(Message::Text(format!(
+        "PRIVMSG #{} :{}",
+        channel(settings),
+        text
+    )))
+    .await
+    .map_err(|e| format!("Failed to post to Twitch chat: {}", e))
+}
+
+// Returns false once the connection is gone
+async fn handle_incoming(
+    app: &AppHandle,
+    ws: &mut TwitchSocket,
+    settings: &TwitchSettings,
+    text: &str,
+) -> bool {
+    for line in text.lines() {
+        if let Some(server) = line.strip_prefix("PING ") {
+            if ws
+                .send(Message::Text(format!("PONG {}", server)))
+                .await
+                .is_err()
+            {
+                return false;
+            }
+            continue;
+        }
+
+        if line.contains(" NOTICE * :Login authentication failed") {
+            log::warn!("[TWITCH] Login failed, check the username and OAuth token");
+            return false;
+        }
+
+        let message = match parse_privmsg(line) {
+            Some(message) => message,
+            None => continue,
+        };
+
+        if settings.relay_prefix.is_empty() {
+            continue;
+        }
+        let relayed = match message.text.strip_prefix(settings.relay_prefix.as_str()) {
+            Some(relayed) if !relayed.trim().is_empty() => relayed.trim().to_string(),
+            _ => continue,
+        };
+        if settings.relay_privileged_only && !message.privileged() {
+            continue;
+        }
+
+        log::info!("[TWITCH] Relaying a message from {}", message.user);
+        let _ = events::emit(
+            app,
+            "remote-control",
+            json!({ "action": "translate", "text": relayed }),
+        );
+    }
+
+    true
+}
+
+/// Posts translations to Twitch chat and relays prefixed chat messages into the translation queue.
+pub fn start(app: &AppHandle) {
+    let mut recorded = app.state::<HistoryState>().subscribe();
+    let app = app.clone();
+
+    shutdown::spawn(&app.clone(), "twitch", async move {
+        loop {
+            let settings = app.state::<SettingsState>().get().twitch;
+
+            if !settings.enabled || settings.channel.is_empty() || settings.username.is_empty() {
+                // Drain while disabled so enabling doesn't post a backlog
+                match tokio::time::timeout(RECONNECT_DELAY, recorded.recv()).await {
+                    Ok(Err(RecvError::Closed)) => break,
+                    _ => continue,
+                }
+            }
+
+            let mut ws = match connect(&app, &settings).await {
+                Ok(ws) => {
+                    log::info!("[TWITCH] Joined #{}", channel(&settings));
+                    ws
+                }
+                Err(e) => {
+                    log::warn!("[TWITCH] {}", e);
+                    tokio::time::sleep(RECONNECT_DELAY).await;
+                    continue;
+                }
+            };
+
+            let mut settings_check = tokio::time::interval(RECONNECT_DELAY);
+            let mut ping = network::keepalive_interval(&app);
+
+            loop {
+                tokio::select! {
+                    _ = ping.tick() => {
+                        if let Err(e) = ws.send(Message::Ping(Vec::new())).await {
+                            log::warn!("[TWITCH] Failed to ping chat: {}", e);
+                            break;
+                        }
+                    }
+                    // Reconnect with the new settings when they changed
+                    _ = settings_check.tick() => {
+                        if app.state::<SettingsState>().get().twitch != settings {
+                            break;
+                        }
+                    }
+                    entry = recorded.recv() => {
+                        let entry = match entry {
+                            Ok(entry) => entry,
+                            Err(RecvError::Lagged(_)) => continue,
+                            Err(RecvError::Closed) => return,
+                        };
+
+                        if settings.post_translations {
+                            let vrchat = app.state::<SettingsState>().get().vrchat_settings;
+                            if let Err(e) = post(&mut ws, &settings, &vrchat, &entry).await {
+                                log::warn!("[TWITCH] {}", e);
+                                break;
+                            }
+                        }
+                    }
+                    message = ws.next() => match message {
+                        Some(Ok(Message::Text(text))) => {
+                            if !handle_incoming(&app, &mut ws, &settings, &text).await {
+                                break;
+                            }
+                        }
+                        Some(Ok(Message::Close(_))) | Some(Err(_)) | None => {
+                            log::warn!("[TWITCH] Disconnected from chat");
+                            break;
+                        }
+                        Some(Ok(_)) => {}
+                    }
+                }
+            }
+
+            let _ = ws.close(None).await;
+            tokio::time::sleep(RECONNECT_DELAY).await;
+        }
+    });
+}
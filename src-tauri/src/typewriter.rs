@@ -0,0 +1,77 @@
+//! Reveals long translations a few words at a time, which listeners find easier to follow
+//! than a whole paragraph popping up at once.
+
+use std::sync::Mutex;
+use tauri::async_runtime::JoinHandle;
+use tauri::{AppHandle, Manager};
+
+use crate::osc::{self, ChatboxState, CHATBOX_INTERVAL};
+use crate::shutdown;
+
+// Languages written without spaces reveal this many characters per word setting
+const CHARACTERS_PER_WORD: usize = 2;
+
+#[derive(Default)]
+pub struct TypewriterState {
+    reveal: Mutex<Option<JoinHandle<()>>>,
+}
+
+// Byte offsets where each reveal step ends, the last one is the whole message
+fn steps(message: &str, words: usize) -> Vec<usize> {
+    let words = words.max(1);
+    let mut ends: Vec<usize> = if message.trim().contains(char::is_whitespace) {
+        message
+            .split_inclusive(char::is_whitespace)
+            .scan(0, |end, word| {
+                *end += word.len();
+                Some(*end)
+            })
+            .collect::<Vec<_>>()
+            .chunks(words)
+            .filter_map(|chunk| chunk.last().copied())
+            .collect()
+    } else {
+        message
+            .char_indices()
+            .map(|(start, c)| start + c.len_utf8())
+            .collect::<Vec<_>>()
+            .chunks(words * CHARACTERS_PER_WORD)
+            .filter_map(|chunk| chunk.last().copied())
+            .collect()
+    };
+
+    if ends.last() != Some(&message.len()) {
+        ends.push(message.len());
+    }
+    ends
+}
+
+/// Sends `message` to the chatbox in steps of `words` words, one step per chatbox interval.
+/// A newer message cancels the reveal that's still running.
+pub fn reveal(app: &AppHandle, message: String, words: usize, address: String, port: String) {
+    let state = app.state::<TypewriterState>();
+    let mut reveal = state.reveal.lock().unwrap();
+    if let Some(task) = reveal.take() {
+        task.abort();
+    }
+
+    let app = app.clone();
+    *reveal = Some(shutdown::spawn(&app.clone(), "typewriter", async move {
+        for end in steps(&message, words) {
+            let shown = message[..end].trim_end().to_string();
+            if osc::send_message(
+                app.clone(),
+                app.state::<ChatboxState>(),
+                shown,
+                address.clone(),
+                port.clone(),
+            )
+            .is_err()
+            {
+                return;
+            }
+
+            tokio::time::sleep(CHATBOX_INTERVAL).await;
+        }
+    }));
+}
@@ -0,0 +1,139 @@
+use serde::Serialize;
+use std::sync::Mutex;
+use tauri::{AppHandle, State};
+use tauri_plugin_updater::{Update, UpdaterExt};
+
+use crate::events;
+use crate::paths;
+use crate::portable_update;
+use crate::settings::SettingsState;
+
+// Manifests published by deploy.py, beta users also receive stable releases
+const STABLE_ENDPOINT: &str = "https://gist.githubusercontent.com/YusufOzmen01/2739ef70ee7f4e60add7e5d87a4c745b/raw/current_version.json";
+const BETA_ENDPOINT: &str = "https://gist.githubusercontent.com/YusufOzmen01/2739ef70ee7f4e60add7e5d87a4c745b/raw/beta_version.json";
+
+/// Public half of TAURI_SIGNING_PRIVATE_KEY, updates signed with anything else are rejected.
+const UPDATER_PUBKEY: Option<&str> = option_env!("KIKITAN_UPDATER_PUBKEY");
+
+#[derive(Default)]
+pub struct UpdaterState {
+    pending: Mutex<Option<Update>>,
+}
+
+#[derive(Serialize)]
+pub struct UpdateInfo {
+    pub version: String,
+    pub current_version: String,
+    pub notes: Option<String>,
+    pub date: Option<String>,
+}
+
+#[derive(Clone, Serialize)]
+struct UpdateProgress {
+    downloaded: u64,
+    total: Option<u64>,
+}
+
+#[tauri::command]
+pub async fn check_for_update(
+    app: AppHandle,
+    settings: State<'_, SettingsState>,
+    state: State<'_, UpdaterState>,
+) -> Result<Option<UpdateInfo>, String> {
+    let pubkey =
+        UPDATER_PUBKEY.ok_or_else(|| "This build was made without an update key".to_string())?;
+
+    let channel = settings.get().update_channel;
+    let endpoint = match channel.as_str() {
+        "beta" => BETA_ENDPOINT,
+        _ => STABLE_ENDPOINT,
+    };
+
+    let mut builder = app.updater_builder().pubkey(pubkey);
+    // The installer doesn't know about portable copies, they update from their own archive
+    if paths::portable_dir().is_some() {
+        builder = builder.target(portable_update::target());
+    }
+
+    let update = builder
+        .endpoints(vec![endpoint
+            .parse()
+            .map_err(|e| format!("Invalid update endpoint: {}", e))?])
+        .and_then(|builder| builder.build())
+        .map_err(|e| format!("Failed to set up the updater: {}", e))?
+        .check()
+        .await
+        .map_err(|e| format!("Failed to check for updates: {}", e))?;
+
+    let info = update.as_ref().map(|update| UpdateInfo {
+        version: update.version.clone(),
+        current_version: update.current_version.clone(),
+        notes: update.body.clone(),
+        date: update.date.map(|date| date.to_string()),
+    });
+
+    match &info {
+        Some(info) => log::info!(
+            "[UPDATER] Update {} available on the {} channel",
+            info.version,
+            channel
+        ),
+        None => log::info!("[UPDATER] No update available on the {} channel", channel),
+    }
+
+    *state.pending.lock().unwrap() = update;
+    Ok(info)
+}
+
+/// Downloads the update found by `check_for_update`, verifies its signature and restarts into it.
+/// Portable copies stage it next to the executable instead, see `portable_update`.
+#[tauri::command]
+pub async fn install_update_and_restart(
+    app: AppHandle,
+    state: State<'_, UpdaterState>,
+) -> Result<(), String> {
+    let update = state
+        .pending
+        .lock()
+        .unwrap()
+        .take()
+        .ok_or_else(|| "No update to install, check for updates first".to_string())?;
+
+    log::info!("[UPDATER] Installing update {}", update.version);
+
+    let mut downloaded = 0u64;
+    if paths::portable_dir().is_some() {
+        portable_update::stage(&update, |chunk, total| {
+            downloaded += chunk as u64;
+            let _ = events::emit(
+                &app,
+                "update-progress",
+                UpdateProgress { downloaded, total },
+            );
+        })
+        .await?;
+        let _ = events::emit(&app, "update-downloaded", ());
+
+        // Swapped in by the next launch
+        app.restart()
+    }
+
+    update
+        .download_and_install(
+            |chunk, total| {
+                downloaded += chunk as u64;
+                let _ = events::emit(
+                    &app,
+                    "update-progress",
+                    UpdateProgress { downloaded, total },
+                );
+            },
+            || {
+                let _ = events::emit(&app, "update-downloaded", ());
+            },
+        )
+        .await
+        .map_err(|e| format!("Failed to install update: {}", e))?;
+
+    app.restart()
+}
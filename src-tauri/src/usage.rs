@@ -0,0 +1,261 @@
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Manager, State};
+
+use crate::database;
+use crate::paths;
+use crate::quota;
+use crate::shutdown;
+
+const USAGE_FILE: &str = "usage.db";
+// Audio usage is added several times a second, it's written out in batches this often
+const FLUSH_INTERVAL: Duration = Duration::from_secs(10);
+
+pub const QWEN_ASR: &str = "qwen";
+pub const GOOGLE_STT: &str = "google_stt";
+pub const AWS_TRANSCRIBE: &str = "aws_transcribe";
+pub const IFLYTEK: &str = "iflytek";
+pub const TENCENT_ASR: &str = "tencent_asr";
+pub const ASSEMBLYAI: &str = "assemblyai";
+pub const SONIOX: &str = "soniox";
+
+/// Bytes per second of the 16 kHz mono PCM16 audio sent to realtime ASR providers.
+const PCM16_BYTES_PER_SECOND: f64 = 16000.0 * 2.0;
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct UsageSummary {
+    pub provider: String,
+    /// `YYYY-MM-DD` for daily summaries, `YYYY-MM` for monthly ones.
+    pub period: String,
+    pub characters: u64,
+    pub seconds: f64,
+    pub requests: u64,
+}
+
+#[derive(Default)]
+struct Pending {
+    characters: u64,
+    seconds: f64,
+    requests: u64,
+}
+
+pub struct UsageState {
+    conn: Mutex<Connection>,
+    /// Usage not written to the database yet, by provider and day.
+    pending: Mutex<HashMap<(String, String), Pending>>,
+}
+
+impl UsageState {
+    pub fn open(app: &AppHandle) -> Self {
+        let path = paths::data_dir(app).join(USAGE_FILE);
+
+        let conn = match database::open(&path, prepare) {
+            Ok(conn) => conn,
+            Err(e) => {
+                log::error!("[USAGE] Failed to open {}: {}", path.display(), e);
+                let conn = Connection::open_in_memory().expect("failed to open in-memory database");
+                create_schema(&conn).expect("failed to create usage schema");
+                conn
+            }
+        };
+
+        UsageState {
+            conn: Mutex::new(conn),
+            pending: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Counts usage in memory, it reaches the database with the next `flush`.
+    pub fn add(&self, provider: &str, characters: u64, seconds: f64) {
+        let day = chrono::Local::now().format("%Y-%m-%d").to_string();
+
+        let mut pending = self.pending.lock().unwrap();
+        let usage = pending.entry((provider.to_string(), day)).or_default();
+        usage.characters += characters;
+        usage.seconds += seconds;
+        usage.requests += 1;
+    }
+
+    /// Writes the pending usage to the database, returning the providers it was for.
+    fn flush(&self) -> Result<Vec<String>, String> {
+        let pending = std::mem::take(&mut *self.pending.lock().unwrap());
+        if pending.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        // Usage counted while writing sits next to what failed, both go out with the next flush
+        if let Err(e) = self.write(&pending) {
+            let mut current = self.pending.lock().unwrap();
+            for (key, usage) in pending {
+                let entry = current.entry(key).or_default();
+                entry.characters += usage.characters;
+                entry.seconds += usage.seconds;
+                entry.requests += usage.requests;
+            }
+            return Err(e);
+        }
+
+        let mut providers: Vec<String> =
+            pending.into_keys().map(|(provider, _)| provider).collect();
+        providers.sort();
+        providers.dedup();
+        Ok(providers)
+    }
+
+    fn write(&self, pending: &HashMap<(String, String), Pending>) -> Result<(), String> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn
+            .transaction()
+            .map_err(|e| format!("Failed to record usage: {}", e))?;
+        for ((provider, day), usage) in pending {
+            tx.execute(
+                "INSERT INTO usage (provider, day, characters, seconds, requests) VALUES (?1, ?2, ?3, ?4, ?5)
+                 ON CONFLICT (provider, day) DO UPDATE SET
+                    characters = characters + excluded.characters,
+                    seconds = seconds + excluded.seconds,
+                    requests = requests + excluded.requests",
+                params![provider, day, usage.characters as i64, usage.seconds, usage.requests as i64],
+            )
+            .map_err(|e| format!("Failed to record usage: {}", e))?;
+        }
+        tx.commit()
+            .map_err(|e| format!("Failed to record usage: {}", e))
+    }
+
+    /// Usage of a provider in the current calendar month.
+    pub fn this_month(&self, provider: &str) -> Result<UsageSummary, String> {
+        let month = chrono::Local::now().format("%Y-%m").to_string();
+        let summary = self
+            .summarize("%Y-%m", Some(&month))?
+            .into_iter()
+            .find(|s| s.provider == provider);
+
+        Ok(summary.unwrap_or(UsageSummary {
+            provider: provider.to_string(),
+            period: month,
+            ..Default::default()
+        }))
+    }
+
+    fn summarize(&self, format: &str, period: Option<&str>) -> Result<Vec<UsageSummary>, String> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn
+            .prepare(
+                "SELECT provider, strftime(?1, day) AS period, SUM(characters), SUM(seconds), SUM(requests)
+                 FROM usage
+                 WHERE ?2 IS NULL OR strftime(?1, day) = ?2
+                 GROUP BY provider, period
+                 ORDER BY period DESC, provider ASC",
+            )
+            .map_err(|e| e.to_string())?;
+
+        let summaries = stmt
+            .query_map(params![format, period], |row| {
+                Ok(UsageSummary {
+                    provider: row.get(0)?,
+                    period: row.get(1)?,
+                    characters: row.get::<_, i64>(2)? as u64,
+                    seconds: row.get(3)?,
+                    requests: row.get::<_, i64>(4)? as u64,
+                })
+            })
+            .and_then(|rows| rows.collect::<rusqlite::Result<Vec<_>>>())
+            .map_err(|e| format!("Failed to summarize usage: {}", e))?;
+
+        Ok(summaries)
+    }
+}
+
+fn prepare(conn: &Connection) -> rusqlite::Result<()> {
+    // Flushes still come every few seconds while recognizing, don't fsync every one of them
+    conn.execute_batch("PRAGMA journal_mode = WAL; PRAGMA synchronous = NORMAL;")?;
+    create_schema(conn)
+}
+
+fn create_schema(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS usage (
+            provider TEXT NOT NULL,
+            day TEXT NOT NULL,
+            characters INTEGER NOT NULL DEFAULT 0,
+            seconds REAL NOT NULL DEFAULT 0,
+            requests INTEGER NOT NULL DEFAULT 0,
+            PRIMARY KEY (provider, day)
+        );",
+    )
+}
+
+/// The PCM16 audio carried by a realtime ASR `input_audio_buffer.append` event.
+pub fn audio_event(message: &str) -> Option<Vec<u8>> {
+    let event: serde_json::Value = serde_json::from_str(message).ok()?;
+    if event["type"] != "input_audio_buffer.append" {
+        return None;
+    }
+
+    BASE64.decode(event["audio"].as_str()?).ok()
+}
+
+/// Seconds of 16 kHz mono PCM16 audio in `bytes`.
+pub fn audio_seconds(bytes: usize) -> f64 {
+    bytes as f64 / PCM16_BYTES_PER_SECOND
+}
+
+/// Writes the usage counted since the last flush and checks the budgets it touched.
+pub fn flush(app: &AppHandle) {
+    match app.state::<UsageState>().flush() {
+        Ok(providers) => {
+            for provider in providers {
+                quota::evaluate(app, &provider);
+            }
+        }
+        Err(e) => log::warn!("[USAGE] {}", e),
+    }
+}
+
+/// Flushes the counted usage every few seconds.
+pub fn start(app: &AppHandle) {
+    let app = app.clone();
+
+    shutdown::spawn(&app.clone(), "usage", async move {
+        let mut interval = tokio::time::interval(FLUSH_INTERVAL);
+
+        loop {
+            interval.tick().await;
+            flush(&app);
+        }
+    });
+}
+
+#[tauri::command]
+pub fn record_usage(
+    app: AppHandle,
+    state: State<'_, UsageState>,
+    provider: String,
+    characters: u64,
+    seconds: f64,
+) -> Result<(), String> {
+    state.add(&provider, characters, seconds);
+    flush(&app);
+    Ok(())
+}
+
+/// Summarizes usage per provider by day or by month, newest first.
+#[tauri::command]
+pub fn usage_summary(
+    app: AppHandle,
+    state: State<'_, UsageState>,
+    period: String,
+) -> Result<Vec<UsageSummary>, String> {
+    flush(&app);
+
+    match period.as_str() {
+        "daily" => state.summarize("%Y-%m-%d", None),
+        "monthly" => state.summarize("%Y-%m", None),
+        _ => Err(format!("Unknown usage period {}", period)),
+    }
+}
@@ -0,0 +1,208 @@
+use rosc::OscType;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+use crate::events;
+use crate::osc::{self, ChatboxState};
+use crate::settings::SettingsState;
+use crate::shutdown;
+
+/// Buttons of VRChat's `/input/*` OSC endpoints, for players who can't easily reach a controller.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum VrchatInput {
+    Jump,
+    MoveForward,
+    MoveBackward,
+    MoveLeft,
+    MoveRight,
+    LookLeft,
+    LookRight,
+    Run,
+    /// Toggles the microphone, or pushes to talk, depending on the VRChat setting.
+    Voice,
+}
+
+impl VrchatInput {
+    fn address(self) -> String {
+        format!("/input/{:?}", self)
+    }
+
+    // Long enough to walk a few steps, a press is enough for everything else
+    fn default_hold(self) -> Duration {
+        match self {
+            VrchatInput::MoveForward
+            | VrchatInput::MoveBackward
+            | VrchatInput::MoveLeft
+            | VrchatInput::MoveRight => Duration::from_millis(1500),
+            VrchatInput::LookLeft | VrchatInput::LookRight => Duration::from_millis(300),
+            _ => Duration::from_millis(100),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum VoiceAction {
+    ClearChatbox,
+    Pause,
+    SetTarget {
+        language: String,
+    },
+    SetSource {
+        language: String,
+    },
+    /// Presses a VRChat input and releases it after `hold_ms`.
+    Input {
+        input: VrchatInput,
+        #[serde(default)]
+        hold_ms: Option<u64>,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct VoiceCommand {
+    /// Matched against the whole utterance, ignoring case and punctuation.
+    pub phrase: String,
+    #[serde(flatten)]
+    pub action: VoiceAction,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VoiceCommandSettings {
+    pub enabled: bool,
+    pub commands: Vec<VoiceCommand>,
+}
+
+impl Default for VoiceCommandSettings {
+    fn default() -> Self {
+        let command = |phrase: &str, action| VoiceCommand {
+            phrase: phrase.to_string(),
+            action,
+        };
+        let target = |language: &str| VoiceAction::SetTarget {
+            language: language.to_string(),
+        };
+        let input = |input| VoiceAction::Input {
+            input,
+            hold_ms: None,
+        };
+
+        VoiceCommandSettings {
+            enabled: false,
+            commands: vec![
+                command("clear chatbox", VoiceAction::ClearChatbox),
+                command("pause translation", VoiceAction::Pause),
+                command("switch to english", target("en")),
+                command("switch to japanese", target("ja")),
+                command("switch to korean", target("ko")),
+                command("switch to chinese", target("zh")),
+                command("jump", input(VrchatInput::Jump)),
+                command("move forward", input(VrchatInput::MoveForward)),
+                command("move back", input(VrchatInput::MoveBackward)),
+                command("turn left", input(VrchatInput::LookLeft)),
+                command("turn right", input(VrchatInput::LookRight)),
+                command("toggle voice", input(VrchatInput::Voice)),
+            ],
+        }
+    }
+}
+
+// "Clear the chatbox." and "clear the chatbox" are the same command, ASR adds punctuation
+// and capitalization on its own
+fn normalize(text: &str) -> String {
+    text.chars()
+        .map(|c| {
+            if c.is_alphanumeric() {
+                c.to_lowercase().next().unwrap_or(c)
+            } else {
+                ' '
+            }
+        })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn run(app: &AppHandle, action: &VoiceAction) -> Result<(), String> {
+    let settings = app.state::<SettingsState>().get();
+    let set_languages = |source: &str, target: &str| {
+        let _ = events::emit(
+            app,
+            "remote-control",
+            json!({ "action": "set_languages", "text": null, "source": source, "target": target }),
+        );
+    };
+
+    match action {
+        VoiceAction::ClearChatbox => osc::send_message(
+            app.clone(),
+            app.state::<ChatboxState>(),
+            String::new(),
+            settings.vrchat_settings.osc_address,
+            settings.vrchat_settings.osc_port.to_string(),
+        )?,
+        VoiceAction::Pause => {
+            let _ = events::emit(
+                app,
+                "remote-control",
+                json!({ "action": "pause", "text": null }),
+            );
+        }
+        VoiceAction::SetTarget { language } => set_languages(&settings.source_language, language),
+        VoiceAction::SetSource { language } => set_languages(language, &settings.target_language),
+        VoiceAction::Input { input, hold_ms } => {
+            let target = osc::resolve(
+                &settings.vrchat_settings.osc_address,
+                &settings.vrchat_settings.osc_port.to_string(),
+            )?;
+            let hold = hold_ms.map_or(input.default_hold(), Duration::from_millis);
+            let address = input.address();
+
+            // VRChat only registers the next press after a release, so the button is always
+            // released, even for a toggle like Voice, and also when quitting mid-hold so the
+            // avatar doesn't keep walking
+            osc::send(target, &address, vec![OscType::Int(1)])?;
+            let exiting = shutdown::token(app);
+            let release = async move {
+                tokio::select! {
+                    _ = exiting.cancelled() => {}
+                    _ = tokio::time::sleep(hold) => {}
+                }
+                if let Err(e) = osc::send(target, &address, vec![OscType::Int(0)]) {
+                    log::warn!("[VOICE] Failed to release {}: {}", address, e);
+                }
+            };
+            tauri::async_runtime::spawn(shutdown::track(app, "voice-commands", release));
+        }
+    }
+
+    Ok(())
+}
+
+/// Runs the command the utterance is a trigger phrase for. Returns whether it was one, in
+/// which case it shouldn't be translated or sent anywhere.
+#[tauri::command]
+pub fn run_voice_command(app: AppHandle, text: String) -> Result<bool, String> {
+    let settings = app.state::<SettingsState>().get().voice_commands;
+    if !settings.enabled {
+        return Ok(false);
+    }
+
+    let text = normalize(&text);
+    let command = match settings
+        .commands
+        .iter()
+        .find(|command| normalize(&command.phrase) == text)
+    {
+        Some(command) => command,
+        None => return Ok(false),
+    };
+
+    log::info!("[VOICE] Running voice command \"{}\"", command.phrase);
+    run(&app, &command.action)?;
+    Ok(true)
+}
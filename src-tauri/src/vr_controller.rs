@@ -0,0 +1,217 @@
+//! Push-to-translate on a VR controller button, since keyboard hotkeys are out of reach with
+//! the headset on. Holding the button long enough opens the microphone capture for as long as
+//! it stays down, or flips it in toggle mode, so quick grabs in VRChat don't trigger it.
+//!
+//! Button events come from the OpenVR session in `headset`. The `openvr` crate only wraps the
+//! legacy controller input, not SteamVR Input action manifests, so the button is picked here
+//! instead of in the SteamVR bindings UI.
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+
+use crate::events;
+use crate::settings::SettingsState;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VrButton {
+    #[default]
+    Grip,
+    Trigger,
+    Menu,
+    /// A on Index controllers, X or A on Touch controllers.
+    A,
+    /// Touchpad or thumbstick click.
+    Touchpad,
+}
+
+impl VrButton {
+    /// `EVRButtonId` from `openvr.h`.
+    fn id(self) -> u32 {
+        match self {
+            VrButton::Menu => 1,
+            VrButton::Grip => 2,
+            VrButton::A => 7,
+            VrButton::Touchpad => 32,
+            VrButton::Trigger => 33,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VrHand {
+    #[default]
+    Any,
+    Left,
+    Right,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VrBindingMode {
+    /// Capture only while the button is held.
+    #[default]
+    Hold,
+    /// Each long press turns capture on or off.
+    Toggle,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VrControllerSettings {
+    pub enabled: bool,
+    pub button: VrButton,
+    pub hand: VrHand,
+    pub mode: VrBindingMode,
+    /// How long the button has to be held before it counts.
+    pub hold_ms: u64,
+}
+
+impl Default for VrControllerSettings {
+    fn default() -> Self {
+        VrControllerSettings {
+            enabled: false,
+            button: VrButton::Grip,
+            hand: VrHand::Any,
+            mode: VrBindingMode::Hold,
+            hold_ms: 400,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct VrControllerState {
+    pressed_at: Mutex<Option<Instant>>,
+    /// The current press was held long enough and already acted on.
+    engaged: AtomicBool,
+    /// Capture is open.
+    active: AtomicBool,
+}
+
+/// Whether capture is held back because the controller binding is waiting to be pressed.
+pub fn capture_gated(app: &AppHandle) -> bool {
+    let Some(state) = app.try_state::<VrControllerState>() else {
+        return false;
+    };
+
+    !state.active.load(Ordering::Relaxed)
+        && app
+            .state::<SettingsState>()
+            .read(|settings| settings.vr_controller.enabled)
+}
+
+fn set_active(app: &AppHandle, active: bool) {
+    let state = app.state::<VrControllerState>();
+    if state.active.swap(active, Ordering::Relaxed) == active {
+        return;
+    }
+
+    log::info!(
+        "[VR CONTROLLER] Push-to-translate {}",
+        if active { "pressed" } else { "released" }
+    );
+    // Same as the keyboard hotkey, so recognizers in the webview start and stop with it
+    let _ = events::emit(
+        app,
+        "hotkey",
+        json!({ "action": "push_to_talk", "pressed": active }),
+    );
+}
+
+fn pressed(app: &AppHandle) {
+    app.state::<VrControllerState>()
+        .pressed_at
+        .lock()
+        .unwrap()
+        .get_or_insert_with(Instant::now);
+}
+
+fn released(app: &AppHandle, settings: &VrControllerSettings) {
+    let state = app.state::<VrControllerState>();
+    *state.pressed_at.lock().unwrap() = None;
+
+    if state.engaged.swap(false, Ordering::Relaxed) && settings.mode == VrBindingMode::Hold {
+        set_active(app, false);
+    }
+}
+
+/// Acts on a press once it has been held for `hold_ms`, called on every poll of the session.
+pub fn tick(app: &AppHandle) {
+    let settings = app.state::<SettingsState>().get().vr_controller;
+    let state = app.state::<VrControllerState>();
+    if !settings.enabled {
+        *state.pressed_at.lock().unwrap() = None;
+        state.engaged.store(false, Ordering::Relaxed);
+        set_active(app, false);
+        return;
+    }
+
+    let held = state
+        .pressed_at
+        .lock()
+        .unwrap()
+        .is_some_and(|at| at.elapsed() >= Duration::from_millis(settings.hold_ms));
+    if !held || state.engaged.swap(true, Ordering::Relaxed) {
+        return;
+    }
+
+    match settings.mode {
+        VrBindingMode::Hold => set_active(app, true),
+        VrBindingMode::Toggle => set_active(app, !state.active.load(Ordering::Relaxed)),
+    }
+}
+
+/// Forgets the button state when SteamVR goes away, so capture doesn't stay open.
+pub fn reset(app: &AppHandle) {
+    let state = app.state::<VrControllerState>();
+    *state.pressed_at.lock().unwrap() = None;
+    state.engaged.store(false, Ordering::Relaxed);
+    set_active(app, false);
+}
+
+/// Handles a button event from the OpenVR session.
+#[cfg(any(target_os = "windows", target_os = "linux"))]
+pub fn handle(
+    app: &AppHandle,
+    system: &openvr::System,
+    device: openvr::TrackedDeviceIndex,
+    event: &openvr::system::event::Event,
+) {
+    use openvr::system::event::Event;
+    use openvr::TrackedControllerRole;
+
+    let settings = app.state::<SettingsState>().get().vr_controller;
+    if !settings.enabled {
+        return;
+    }
+
+    let (button, down) = match event {
+        Event::ButtonPress(controller) => (controller.button, true),
+        Event::ButtonUnpress(controller) => (controller.button, false),
+        _ => return,
+    };
+    if button != settings.button.id() {
+        return;
+    }
+
+    let role = system.get_controller_role_for_tracked_device_index(device);
+    let hand_matches = match settings.hand {
+        VrHand::Any => true,
+        VrHand::Left => role == Some(TrackedControllerRole::LeftHand),
+        VrHand::Right => role == Some(TrackedControllerRole::RightHand),
+    };
+    if !hand_matches {
+        return;
+    }
+
+    if down {
+        pressed(app);
+    } else {
+        released(app, &settings);
+    }
+}
@@ -0,0 +1,125 @@
+use futures_util::SinkExt;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tauri::{AppHandle, Manager};
+use tokio::net::{TcpStream, UdpSocket};
+use tokio::sync::broadcast::error::RecvError;
+use tokio_tungstenite::tungstenite::protocol::Message;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+
+use crate::history::{HistoryEntry, HistoryState};
+use crate::network;
+use crate::settings::SettingsState;
+use crate::shutdown;
+
+const XSOVERLAY_ADDRESS: &str = "127.0.0.1:42069";
+const OVR_TOOLKIT_URL: &str = "ws://127.0.0.1:11450/api";
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VrNotificationSettings {
+    pub xsoverlay: bool,
+    pub ovr_toolkit: bool,
+    pub timeout_seconds: f32,
+}
+
+impl Default for VrNotificationSettings {
+    fn default() -> Self {
+        VrNotificationSettings {
+            xsoverlay: false,
+            ovr_toolkit: false,
+            timeout_seconds: 4.0,
+        }
+    }
+}
+
+/// Pops up every translation as an XSOverlay and/or OVR Toolkit notification.
+pub fn start(app: &AppHandle) {
+    let mut recorded = app.state::<HistoryState>().subscribe();
+    let app = app.clone();
+
+    shutdown::spawn(&app.clone(), "vr_notifications", async move {
+        let mut ovr_toolkit: Option<WebSocketStream<MaybeTlsStream<TcpStream>>> = None;
+
+        loop {
+            let entry = match recorded.recv().await {
+                Ok(entry) => entry,
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => break,
+            };
+
+            let settings = app.state::<SettingsState>().get().vr_notifications;
+
+            if settings.xsoverlay {
+                if let Err(e) = send_xsoverlay(&settings, &entry).await {
+                    log::debug!("[VR NOTIFY] {}", e);
+                }
+            }
+
+            if !settings.ovr_toolkit {
+                ovr_toolkit = None;
+                continue;
+            }
+
+            // OVR Toolkit may be started after us, connect on demand
+            if ovr_toolkit.is_none() {
+                match network::connect_websocket(&app, OVR_TOOLKIT_URL).await {
+                    Ok((ws, _)) => ovr_toolkit = Some(ws),
+                    Err(e) => log::debug!("[VR NOTIFY] Could not reach OVR Toolkit: {}", e),
+                }
+            }
+
+            if let Some(ws) = ovr_toolkit.as_mut() {
+                let notification = json!({ "title": title(&entry), "body": entry.translation });
+                let message = json!({
+                    "messageType": "SendNotification",
+                    "json": notification.to_string(),
+                });
+
+                if let Err(e) = ws.send(Message::Text(message.to_string())).await {
+                    log::debug!("[VR NOTIFY] OVR Toolkit dropped: {}", e);
+                    ovr_toolkit = None;
+                }
+            }
+        }
+    });
+}
+
+fn title(entry: &HistoryEntry) -> String {
+    if entry.original.is_empty() {
+        "Kikitan Translator".to_string()
+    } else {
+        entry.original.clone()
+    }
+}
+
+// XSOverlay listens for fire-and-forget UDP packets, nothing to keep open
+async fn send_xsoverlay(
+    settings: &VrNotificationSettings,
+    entry: &HistoryEntry,
+) -> Result<(), String> {
+    let packet = json!({
+        "messageType": 1,
+        "index": 0,
+        "timeout": settings.timeout_seconds,
+        "height": 120.0,
+        "opacity": 1.0,
+        "volume": 0.0,
+        "audioPath": "",
+        "title": title(entry),
+        "content": entry.translation,
+        "useBase64Icon": false,
+        "icon": "",
+        "sourceApp": "Kikitan Translator",
+    });
+
+    let socket = UdpSocket::bind("127.0.0.1:0")
+        .await
+        .map_err(|e| format!("Failed to open UDP socket: {}", e))?;
+    socket
+        .send_to(packet.to_string().as_bytes(), XSOVERLAY_ADDRESS)
+        .await
+        .map_err(|e| format!("Failed to notify XSOverlay: {}", e))?;
+
+    Ok(())
+}
@@ -0,0 +1,242 @@
+use serde::Serialize;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+use sysinfo::System;
+use tauri::{AppHandle, Manager, State};
+
+use crate::autostart;
+use crate::events;
+use crate::osc;
+use crate::power;
+use crate::settings::{self, SettingsState};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+const STEAM_APP_ID: &str = "438100";
+
+// VRChat is Windows-only, under Proton the process keeps its .exe name
+const PROCESS_NAME: &str = "VRChat.exe";
+
+/// Polls for the VRChat process and emits `vrchat-running` whenever it starts or exits.
+/// With `follow_vrchat` enabled the OSC listener is started along with the game,
+/// the frontend starts and stops capture and the ASR session on the same event.
+pub fn start(app: &AppHandle) {
+    let app = app.clone();
+
+    thread::spawn(move || {
+        let mut system = System::new();
+        let mut was_running = false;
+
+        loop {
+            system.refresh_processes();
+            let running = system
+                .processes_by_exact_name(PROCESS_NAME)
+                .next()
+                .is_some();
+
+            if running != was_running {
+                was_running = running;
+                log::info!(
+                    "[VRCHAT] VRChat {}",
+                    if running { "started" } else { "exited" }
+                );
+
+                let settings = app.state::<SettingsState>().get();
+                if running && settings.vrchat_settings.follow_vrchat {
+                    if let Err(e) = osc::start_listener(&app) {
+                        log::warn!("[VRCHAT] {}", e);
+                    }
+                }
+
+                let _ = events::emit(&app, "vrchat-running", running);
+            }
+
+            thread::sleep(power::interval(&app, POLL_INTERVAL));
+        }
+    });
+}
+
+#[tauri::command]
+pub fn is_vrchat_running() -> bool {
+    let mut system = System::new();
+    system.refresh_processes();
+    system
+        .processes_by_exact_name(PROCESS_NAME)
+        .next()
+        .is_some()
+}
+
+/// Launch option to paste into VRChat's Steam properties so Steam starts the
+/// translator with the game. A second launch just focuses the running instance.
+#[tauri::command]
+pub fn get_vrchat_launch_option() -> Result<String, String> {
+    let exe = env::current_exe().map_err(|e| format!("Failed to locate executable: {}", e))?;
+
+    Ok(if cfg!(target_os = "windows") {
+        format!(
+            "cmd /c start \"\" \"{}\" {} & %command%",
+            exe.display(),
+            autostart::MINIMIZED_ARG
+        )
+    } else {
+        format!(
+            "\"{}\" {} & %command%",
+            exe.display(),
+            autostart::MINIMIZED_ARG
+        )
+    })
+}
+
+/// Companion mode, the translator waits in the tray from login on and
+/// starts translating whenever VRChat is running.
+#[tauri::command]
+pub fn set_launch_with_vrchat(
+    app: AppHandle,
+    state: State<'_, SettingsState>,
+    enabled: bool,
+) -> Result<(), String> {
+    autostart::set_autostart(enabled)?;
+
+    let mut settings = state.get();
+    settings.vrchat_settings.follow_vrchat = enabled;
+    state.set(settings.clone())?;
+
+    // The webview's copy would otherwise overwrite follow_vrchat on its next save
+    let _ = events::emit(&app, "config-changed", settings::for_webview(settings));
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_launch_with_vrchat(state: State<'_, SettingsState>) -> bool {
+    autostart::get_autostart() && state.get().vrchat_settings.follow_vrchat
+}
+
+#[derive(Serialize)]
+pub struct VrchatPaths {
+    pub install_dir: Option<PathBuf>,
+    /// Where VRChat writes its output logs.
+    pub log_dir: Option<PathBuf>,
+}
+
+fn steam_dir() -> Option<PathBuf> {
+    #[cfg(target_os = "windows")]
+    {
+        use winreg::enums::HKEY_CURRENT_USER;
+        use winreg::RegKey;
+
+        RegKey::predef(HKEY_CURRENT_USER)
+            .open_subkey(r"Software\Valve\Steam")
+            .and_then(|key| key.get_value::<String, _>("SteamPath"))
+            .ok()
+            .map(PathBuf::from)
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    {
+        let home = dirs::home_dir()?;
+        [
+            ".steam/steam",
+            ".local/share/Steam",
+            "Library/Application Support/Steam",
+        ]
+        .iter()
+        .map(|dir| home.join(dir))
+        .find(|dir| dir.exists())
+    }
+}
+
+// Every library listed in libraryfolders.vdf, the Steam folder itself included
+fn steam_libraries(steam_dir: &Path) -> Vec<PathBuf> {
+    let mut libraries = vec![steam_dir.to_path_buf()];
+
+    let folders = fs::read_to_string(steam_dir.join("steamapps").join("libraryfolders.vdf"))
+        .unwrap_or_default();
+    for line in folders.lines() {
+        if let Some(path) = vdf_value(line, "path") {
+            let path = PathBuf::from(path.replace("\\\\", "\\"));
+            if !libraries.contains(&path) {
+                libraries.push(path);
+            }
+        }
+    }
+
+    libraries
+}
+
+// Reads `"key"  "value"` lines, the only shape needed from Steam's VDF files
+fn vdf_value<'a>(line: &'a str, key: &str) -> Option<&'a str> {
+    let mut parts = line
+        .trim()
+        .split('"')
+        .filter(|part| !part.trim().is_empty());
+    if parts.next()? != key {
+        return None;
+    }
+    parts.next()
+}
+
+fn find_install_dir(libraries: &[PathBuf]) -> Option<PathBuf> {
+    libraries.iter().find_map(|library| {
+        let steamapps = library.join("steamapps");
+        let manifest =
+            fs::read_to_string(steamapps.join(format!("appmanifest_{}.acf", STEAM_APP_ID))).ok()?;
+        let install_dir = manifest
+            .lines()
+            .find_map(|line| vdf_value(line, "installdir"))?;
+
+        Some(steamapps.join("common").join(install_dir)).filter(|dir| dir.exists())
+    })
+}
+
+fn find_log_dir(libraries: &[PathBuf]) -> Option<PathBuf> {
+    let local_low = Path::new("AppData")
+        .join("LocalLow")
+        .join("VRChat")
+        .join("VRChat");
+
+    if cfg!(target_os = "windows") {
+        return dirs::home_dir()
+            .map(|home| home.join(local_low))
+            .filter(|dir| dir.exists());
+    }
+
+    // Under Proton the logs live in the game's Wine prefix
+    libraries.iter().find_map(|library| {
+        Some(
+            library
+                .join("steamapps")
+                .join("compatdata")
+                .join(STEAM_APP_ID)
+                .join("pfx")
+                .join("drive_c")
+                .join("users")
+                .join("steamuser")
+                .join(&local_low),
+        )
+        .filter(|dir| dir.exists())
+    })
+}
+
+pub fn detect_paths() -> VrchatPaths {
+    let libraries = steam_dir()
+        .map(|dir| steam_libraries(&dir))
+        .unwrap_or_default();
+
+    VrchatPaths {
+        install_dir: find_install_dir(&libraries),
+        log_dir: find_log_dir(&libraries),
+    }
+}
+
+#[tauri::command]
+pub fn detect_vrchat_paths() -> VrchatPaths {
+    let paths = detect_paths();
+    log::info!(
+        "[VRCHAT] Install: {:?}, logs: {:?}",
+        paths.install_dir,
+        paths.log_dir
+    );
+    paths
+}
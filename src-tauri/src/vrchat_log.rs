@@ -0,0 +1,288 @@
+use regex::Regex;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Listener, Manager, State};
+
+use crate::events;
+use crate::history::HistoryState;
+use crate::power;
+use crate::settings::SettingsState;
+use crate::vrchat;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+// Looked for again every so often, VRChat may be installed after we start
+const LOG_DIR_RETRY: Duration = Duration::from_secs(60);
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Player {
+    pub name: String,
+    /// `usr_...`, missing in logs of older VRChat builds.
+    pub user_id: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct Instance {
+    pub world_id: String,
+    pub instance_id: String,
+    pub world_name: String,
+    pub players: Vec<Player>,
+}
+
+#[derive(Default)]
+pub struct VrchatLogState {
+    instance: Mutex<Option<Instance>>,
+}
+
+enum LogEvent {
+    Joining {
+        world_id: String,
+        instance_id: String,
+    },
+    EnteredRoom {
+        world_name: String,
+    },
+    LeftRoom,
+    PlayerJoined(Player),
+    PlayerLeft(Player),
+}
+
+struct Parser {
+    joining: Regex,
+    entering: Regex,
+    player: Regex,
+}
+
+impl Parser {
+    fn new() -> Self {
+        Parser {
+            joining: Regex::new(r"\[Behaviour\] Joining (wrld_[0-9a-fA-F-]+):(\S+)").unwrap(),
+            entering: Regex::new(r"\[Behaviour\] Entering Room: (.+)$").unwrap(),
+            player: Regex::new(
+                r"\[Behaviour\] OnPlayer(Joined|Left) (.+?)(?: \((usr_[0-9a-fA-F-]+)\))?$",
+            )
+            .unwrap(),
+        }
+    }
+
+    fn parse(&self, line: &str) -> Option<LogEvent> {
+        let line = line.trim_end();
+
+        if let Some(captures) = self.joining.captures(line) {
+            return Some(LogEvent::Joining {
+                world_id: captures[1].to_string(),
+                instance_id: captures[2].to_string(),
+            });
+        }
+
+        if let Some(captures) = self.entering.captures(line) {
+            return Some(LogEvent::EnteredRoom {
+                world_name: captures[1].to_string(),
+            });
+        }
+
+        if line.contains("[Behaviour] OnLeftRoom") {
+            return Some(LogEvent::LeftRoom);
+        }
+
+        let captures = self.player.captures(line)?;
+        let player = Player {
+            name: captures[2].to_string(),
+            user_id: captures.get(3).map(|id| id.as_str().to_string()),
+        };
+
+        Some(match &captures[1] {
+            "Joined" => LogEvent::PlayerJoined(player),
+            _ => LogEvent::PlayerLeft(player),
+        })
+    }
+}
+
+struct Tail {
+    path: PathBuf,
+    offset: u64,
+    partial: String,
+}
+
+fn newest_log(dir: &Path) -> Option<PathBuf> {
+    fs::read_dir(dir)
+        .ok()?
+        .flatten()
+        .filter(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            name.starts_with("output_log_") && name.ends_with(".txt")
+        })
+        .max_by_key(|entry| entry.metadata().and_then(|m| m.modified()).ok())
+        .map(|entry| entry.path())
+}
+
+// Reads whatever was appended since the last call, keeping an unfinished last line for later
+fn read_lines(tail: &mut Tail) -> Vec<String> {
+    let mut file = match File::open(&tail.path) {
+        Ok(file) => file,
+        Err(_) => return Vec::new(),
+    };
+
+    let len = file.metadata().map(|m| m.len()).unwrap_or(0);
+    if len < tail.offset {
+        tail.offset = 0;
+        tail.partial.clear();
+    }
+
+    let mut appended = Vec::new();
+    if file.seek(SeekFrom::Start(tail.offset)).is_err() || file.read_to_end(&mut appended).is_err()
+    {
+        return Vec::new();
+    }
+    tail.offset += appended.len() as u64;
+
+    tail.partial.push_str(&String::from_utf8_lossy(&appended));
+    let mut lines: Vec<String> = tail.partial.split('\n').map(str::to_string).collect();
+    tail.partial = lines.pop().unwrap_or_default();
+    lines
+}
+
+fn apply(app: &AppHandle, event: LogEvent, replaying: bool) {
+    let state = app.state::<VrchatLogState>();
+    let mut instance = state.instance.lock().unwrap();
+
+    match event {
+        LogEvent::Joining {
+            world_id,
+            instance_id,
+        } => {
+            *instance = Some(Instance {
+                world_id,
+                instance_id,
+                ..Instance::default()
+            });
+        }
+        LogEvent::EnteredRoom { world_name } => {
+            let current = instance.get_or_insert_with(Instance::default);
+            current.world_name = world_name;
+
+            if replaying {
+                return;
+            }
+
+            log::info!(
+                "[VRCHAT LOG] Entered {} ({})",
+                current.world_name,
+                current.world_id
+            );
+            if app
+                .state::<SettingsState>()
+                .get()
+                .vrchat_settings
+                .session_per_instance
+            {
+                let session = app.state::<HistoryState>().start_session();
+                log::info!("[VRCHAT LOG] Started history session {}", session);
+            }
+            let _ = events::emit(app, "vrchat-instance-changed", current.clone());
+        }
+        LogEvent::LeftRoom => {
+            *instance = None;
+
+            if !replaying {
+                let _ = events::emit(app, "vrchat-instance-changed", ());
+            }
+        }
+        LogEvent::PlayerJoined(player) => {
+            if let Some(current) = instance.as_mut() {
+                current.players.retain(|p| p.name != player.name);
+                current.players.push(player.clone());
+            }
+
+            if !replaying {
+                let _ = events::emit(app, "vrchat-player-joined", player);
+            }
+        }
+        LogEvent::PlayerLeft(player) => {
+            if let Some(current) = instance.as_mut() {
+                current.players.retain(|p| p.name != player.name);
+            }
+
+            if !replaying {
+                let _ = events::emit(app, "vrchat-player-left", player);
+            }
+        }
+    }
+}
+
+/// Follows VRChat's output log and emits `vrchat-instance-changed`, `vrchat-player-joined`
+/// and `vrchat-player-left`. A log that already exists on start is replayed silently,
+/// so the current instance is known without announcing every past event.
+pub fn start(app: &AppHandle) {
+    // The last log still describes the instance after VRChat closed
+    let handle = app.clone();
+    app.listen_any("vrchat-running", move |event| {
+        if events::payload(&event).as_bool() == Some(false) {
+            *handle.state::<VrchatLogState>().instance.lock().unwrap() = None;
+        }
+    });
+
+    let app = app.clone();
+
+    thread::spawn(move || {
+        let parser = Parser::new();
+        let mut log_dir: Option<PathBuf> = None;
+        let mut since_lookup = LOG_DIR_RETRY;
+        let mut tail: Option<Tail> = None;
+
+        loop {
+            if log_dir.is_none() && since_lookup >= LOG_DIR_RETRY {
+                log_dir = vrchat::detect_paths().log_dir;
+                since_lookup = Duration::ZERO;
+            }
+
+            if let Some(newest) = log_dir.as_deref().and_then(newest_log) {
+                // A new file means VRChat was restarted, everything in it is news
+                let replaying = tail.is_none();
+                if tail.as_ref().map(|t| &t.path) != Some(&newest) {
+                    log::info!("[VRCHAT LOG] Following {}", newest.display());
+                    tail = Some(Tail {
+                        path: newest,
+                        offset: 0,
+                        partial: String::new(),
+                    });
+                }
+
+                if let Some(tail) = tail.as_mut() {
+                    for line in read_lines(tail) {
+                        if let Some(event) = parser.parse(&line) {
+                            apply(&app, event, replaying);
+                        }
+                    }
+                }
+            }
+
+            let interval = power::interval(&app, POLL_INTERVAL);
+            thread::sleep(interval);
+            since_lookup += interval;
+        }
+    });
+}
+
+/// Players in the current instance, in the order they joined.
+pub fn current_players(app: &AppHandle) -> Vec<Player> {
+    app.state::<VrchatLogState>()
+        .instance
+        .lock()
+        .unwrap()
+        .as_ref()
+        .map(|instance| instance.players.clone())
+        .unwrap_or_default()
+}
+
+/// The instance VRChat is in and who is there, `None` outside of VRChat.
+#[tauri::command]
+pub fn get_vrchat_instance(state: State<'_, VrchatLogState>) -> Option<Instance> {
+    state.instance.lock().unwrap().clone()
+}
@@ -0,0 +1,142 @@
+use serde::Serialize;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+
+use crate::events;
+use crate::restart_qwen_ws;
+use crate::shutdown;
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+// Qwen usually answers within a couple of seconds of the speaker pausing
+const RESULT_TIMEOUT: Duration = Duration::from_secs(30);
+const SEND_TIMEOUT: Duration = Duration::from_secs(10);
+
+// RMS of a PCM16 chunk, relative to full scale, above which it is treated as speech
+const SPEECH_LEVEL: f64 = 0.02;
+
+pub const QWEN_ASR: &str = "qwen-asr";
+
+#[derive(Clone, Serialize)]
+struct WatchdogEvent {
+    subsystem: String,
+    reason: String,
+}
+
+#[derive(Default)]
+struct Activity {
+    /// First speech sent since the last recognition result.
+    speech_since: Option<Instant>,
+    /// When the send that is currently in flight started.
+    sending_since: Option<Instant>,
+}
+
+#[derive(Default)]
+pub struct WatchdogState {
+    activity: Mutex<Activity>,
+}
+
+fn activity(app: &AppHandle) -> std::sync::MutexGuard<'_, Activity> {
+    app.state::<WatchdogState>()
+        .inner()
+        .activity
+        .lock()
+        .unwrap()
+}
+
+/// Notes a chunk of PCM16 audio sent to the recognizer, results are expected once it holds speech.
+pub fn audio_sent(app: &AppHandle, audio: &[u8]) {
+    if !is_speech(audio) {
+        return;
+    }
+
+    let mut activity = activity(app);
+    if activity.speech_since.is_none() {
+        activity.speech_since = Some(Instant::now());
+    }
+}
+
+pub fn result_received(app: &AppHandle) {
+    activity(app).speech_since = None;
+}
+
+pub fn send_started(app: &AppHandle) {
+    activity(app).sending_since = Some(Instant::now());
+}
+
+pub fn send_finished(app: &AppHandle) {
+    activity(app).sending_since = None;
+}
+
+/// Forgets the previous connection's activity, e.g. after connecting or closing.
+pub fn reset(app: &AppHandle) {
+    *activity(app) = Activity::default();
+}
+
+fn is_speech(audio: &[u8]) -> bool {
+    let samples: Vec<f64> = audio
+        .chunks_exact(2)
+        .map(|b| i16::from_le_bytes([b[0], b[1]]) as f64 / i16::MAX as f64)
+        .collect();
+
+    if samples.is_empty() {
+        return false;
+    }
+
+    let rms = (samples.iter().map(|s| s * s).sum::<f64>() / samples.len() as f64).sqrt();
+    rms > SPEECH_LEVEL
+}
+
+fn stall_reason(activity: &Activity) -> Option<String> {
+    if let Some(since) = activity.sending_since {
+        if since.elapsed() > SEND_TIMEOUT {
+            return Some(format!(
+                "Sending to the ASR connection has been blocked for {}s",
+                since.elapsed().as_secs()
+            ));
+        }
+    }
+
+    if let Some(since) = activity.speech_since {
+        if since.elapsed() > RESULT_TIMEOUT {
+            return Some(format!(
+                "No recognition results for {}s despite speech being sent",
+                since.elapsed().as_secs()
+            ));
+        }
+    }
+
+    None
+}
+
+/// Restarts the Qwen ASR connection when it stops responding, emitting `watchdog`.
+pub fn start(app: &AppHandle) {
+    let app = app.clone();
+
+    shutdown::spawn(&app.clone(), "watchdog", async move {
+        let mut interval = tokio::time::interval(CHECK_INTERVAL);
+
+        loop {
+            interval.tick().await;
+
+            let reason = match stall_reason(&activity(&app)) {
+                Some(reason) => reason,
+                None => continue,
+            };
+
+            log::warn!("[WATCHDOG] Restarting {}: {}", QWEN_ASR, reason);
+            reset(&app);
+            let _ = events::emit(
+                &app,
+                "watchdog",
+                WatchdogEvent {
+                    subsystem: QWEN_ASR.to_string(),
+                    reason: reason.clone(),
+                },
+            );
+
+            restart_qwen_ws(&app, &reason);
+        }
+    });
+}
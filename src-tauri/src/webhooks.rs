@@ -0,0 +1,257 @@
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::Sha256;
+use std::fmt;
+use tauri::{AppHandle, Listener, Manager};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::mpsc;
+
+use crate::encoding;
+use crate::events;
+use crate::history::HistoryState;
+use crate::local_server;
+use crate::network;
+use crate::secrets;
+use crate::settings::SettingsState;
+use crate::shutdown;
+
+// Backend events that are delivered as `error`
+const ERROR_EVENTS: [&str; 4] = [
+    "qwen-ws-error",
+    "grpc-asr-error",
+    "backend-fatal",
+    "watchdog",
+];
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WebhookSettings {
+    pub enabled: bool,
+    pub endpoints: Vec<WebhookEndpoint>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct WebhookEndpoint {
+    pub url: String,
+    /// `translation`, `error`, `session_start` and `session_end`. Empty receives all of them.
+    #[serde(default)]
+    pub events: Vec<String>,
+}
+
+impl WebhookEndpoint {
+    fn wants(&self, event: &str) -> bool {
+        self.events.is_empty() || self.events.iter().any(|wanted| wanted == event)
+    }
+}
+
+/// `sha256=<hex>` over `<timestamp>.<body>`, so a captured delivery can't be replayed with a new timestamp.
+fn signature(secret: &str, timestamp: i64, body: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(format!("{}.{}", timestamp, body).as_bytes());
+
+    format!("sha256={}", encoding::hex(&mac.finalize().into_bytes()))
+}
+
+struct Failure {
+    message: String,
+    /// Whether the endpoint might accept it on a later try.
+    retry: bool,
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+async fn post(
+    client: &reqwest::Client,
+    url: &str,
+    event: &str,
+    delivery: &str,
+    body: &str,
+    secret: Option<&str>,
+) -> Result<(), Failure> {
+    let timestamp = chrono::Utc::now().timestamp();
+    let mut request = client
+        .post(url)
+        .header("Content-Type", "application/json")
+        .header("X-Kikitan-Event", event)
+        .header("X-Kikitan-Delivery", delivery)
+        .header("X-Kikitan-Timestamp", timestamp.to_string())
+        .body(body.to_string());
+
+    if let Some(secret) = secret {
+        request = request.header("X-Kikitan-Signature", signature(secret, timestamp, body));
+    }
+
+    // Only failures that might go away are worth retrying
+    match request.send().await {
+        Ok(response) if response.status().is_success() => Ok(()),
+        Ok(response) => {
+            let status = response.status();
+            let retry = status.is_server_error() || status.as_u16() == 429;
+            Err(Failure {
+                message: format!("{} answered {}", url, status),
+                retry,
+            })
+        }
+        Err(e) => Err(Failure {
+            message: format!("Failed to reach {}: {}", url, e),
+            retry: true,
+        }),
+    }
+}
+
+// A delivery is dropped once the retry delays run out
+async fn deliver(
+    app: AppHandle,
+    client: reqwest::Client,
+    url: String,
+    event: String,
+    body: String,
+) {
+    let secret = match secrets::get(secrets::WEBHOOK_SECRET) {
+        Ok(secret) => secret.filter(|secret| !secret.is_empty()),
+        Err(e) => {
+            log::warn!("[WEBHOOKS] {}, sending unsigned", e);
+            None
+        }
+    };
+    let delivery = local_server::new_token();
+
+    let sent = network::retry_if(
+        &app,
+        || post(&client, &url, &event, &delivery, &body, secret.as_deref()),
+        |failure: &Failure| failure.retry,
+    )
+    .await;
+
+    match sent {
+        Ok(()) => {}
+        Err(e) if e.retry => log::warn!("[WEBHOOKS] {}, giving up on {} delivery", e, event),
+        Err(e) => log::warn!("[WEBHOOKS] {}", e),
+    }
+}
+
+fn payload(event: &str, data: Value) -> String {
+    json!({
+        "event": event,
+        "timestamp": chrono::Utc::now().timestamp_millis(),
+        "data": data,
+    })
+    .to_string()
+}
+
+/// Queues a delivery to every endpoint that subscribed to the event.
+fn dispatch(app: &AppHandle, client: &reqwest::Client, event: &str, data: Value) {
+    let settings = app.state::<SettingsState>().get().webhooks;
+    if !settings.enabled {
+        return;
+    }
+
+    let body = payload(event, data);
+    for endpoint in settings
+        .endpoints
+        .iter()
+        .filter(|endpoint| endpoint.wants(event))
+    {
+        shutdown::spawn(
+            app,
+            "webhooks",
+            deliver(
+                app.clone(),
+                client.clone(),
+                endpoint.url.clone(),
+                event.to_string(),
+                body.clone(),
+            ),
+        );
+    }
+}
+
+/// Delivers translations, errors and session changes while webhooks are enabled.
+pub fn start(app: &AppHandle) {
+    let mut recorded = app.state::<HistoryState>().subscribe();
+
+    // Errors are only emitted as events, forward them into the delivery task
+    let (error_tx, mut error_rx) = mpsc::unbounded_channel();
+    for event in ERROR_EVENTS {
+        let error_tx = error_tx.clone();
+        app.listen_any(event, move |emitted| {
+            let detail = events::payload(&emitted);
+            let _ = error_tx.send(json!({ "source": event, "detail": detail }));
+        });
+    }
+
+    let app = app.clone();
+    let exiting = shutdown::token(&app);
+
+    let task = async move {
+        let client = network::http_client(&app);
+        // Sessions have no explicit end, one ends when entries start arriving for the next
+        let mut session: Option<String> = None;
+
+        loop {
+            tokio::select! {
+                _ = exiting.cancelled() => break,
+                entry = recorded.recv() => match entry {
+                    Ok(entry) => {
+                        if session.as_deref() != Some(entry.session_id.as_str()) {
+                            if let Some(previous) = session.replace(entry.session_id.clone()) {
+                                dispatch(&app, &client, "session_end", json!({ "session_id": previous }));
+                            }
+                            dispatch(&app, &client, "session_start", json!({ "session_id": entry.session_id }));
+                        }
+                        dispatch(&app, &client, "translation", serde_json::to_value(&entry).unwrap_or_default());
+                    }
+                    Err(RecvError::Lagged(_)) => {}
+                    Err(RecvError::Closed) => break,
+                },
+                Some(error) = error_rx.recv() => dispatch(&app, &client, "error", error),
+            }
+        }
+
+        // Deliveries spawned now would be dropped on exit, send the last one inline and only once
+        let settings = app.state::<SettingsState>().get().webhooks;
+        if let (true, Some(session_id)) = (settings.enabled, session) {
+            let body = payload("session_end", json!({ "session_id": session_id }));
+            let secret = secrets::get(secrets::WEBHOOK_SECRET).ok().flatten();
+            for endpoint in settings
+                .endpoints
+                .iter()
+                .filter(|endpoint| endpoint.wants("session_end"))
+            {
+                let _ = post(
+                    &client,
+                    &endpoint.url,
+                    "session_end",
+                    &local_server::new_token(),
+                    &body,
+                    secret.as_deref(),
+                )
+                .await;
+            }
+        }
+    };
+
+    tauri::async_runtime::spawn(shutdown::track(&app, "webhooks", task));
+}
+
+/// Sends a `ping` to the URL once, without retries, to check it before saving.
+#[tauri::command]
+pub async fn test_webhook(app: AppHandle, url: String) -> Result<(), String> {
+    let secret = secrets::get(secrets::WEBHOOK_SECRET)?;
+    post(
+        &network::http_client(&app),
+        &url,
+        "ping",
+        &local_server::new_token(),
+        &payload("ping", Value::Null),
+        secret.as_deref(),
+    )
+    .await
+    .map_err(|e| e.message)
+}
@@ -0,0 +1,289 @@
+//! Chatbox widgets: the chatbox shows a set of widgets, e.g. the last translation, a clock, my
+//! heart rate and the playing track, instead of just translations. A scheduler composes them
+//! into one message or rotates through them, and only sends when the text changed or VRChat
+//! is about to hide it, so the widgets stay under the chatbox rate limit together.
+
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+use tokio::sync::Notify;
+use tokio_tungstenite::tungstenite::protocol::Message;
+
+use crate::network;
+use crate::now_playing;
+use crate::osc::{self, ChatboxState};
+use crate::settings::SettingsState;
+use crate::shutdown;
+
+const TICK: Duration = Duration::from_secs(2);
+
+// VRChat hides a chatbox message after a while, repeat it so the widgets stay visible
+const REFRESH_INTERVAL: Duration = Duration::from_secs(25);
+
+// A heart rate monitor that stopped reporting shouldn't show a reading from minutes ago
+const HEART_RATE_STALE: Duration = Duration::from_secs(30);
+const HEART_RATE_RECONNECT: Duration = Duration::from_secs(10);
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ChatboxWidget {
+    /// What I said last, formatted with the chatbox template.
+    Translation,
+    /// Local time in chrono's `strftime` format, e.g. `%H:%M`.
+    Clock { format: String },
+    /// Supports `{bpm}`.
+    HeartRate { template: String },
+    /// Supports `{artist}` and `{title}`.
+    NowPlaying { template: String },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WidgetLayout {
+    /// Every widget on its own line.
+    Compose,
+    /// One widget at a time, a fresh translation always goes first.
+    Rotate,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WidgetSettings {
+    pub enabled: bool,
+    pub widgets: Vec<ChatboxWidget>,
+    pub layout: WidgetLayout,
+    /// Seconds each widget is shown for when rotating.
+    pub rotate_interval: u64,
+    /// Seconds a translation stays in the chatbox.
+    pub translation_hold: u64,
+    /// WebSocket that sends heart rate readings, e.g. Pulsoid's real time API.
+    pub heart_rate_url: String,
+}
+
+impl Default for WidgetSettings {
+    fn default() -> Self {
+        WidgetSettings {
+            enabled: false,
+            widgets: vec![
+                ChatboxWidget::Translation,
+                ChatboxWidget::Clock {
+                    format: "%H:%M".to_string(),
+                },
+                ChatboxWidget::NowPlaying {
+                    template: "♪ {artist} – {title}".to_string(),
+                },
+            ],
+            layout: WidgetLayout::Rotate,
+            rotate_interval: 10,
+            translation_hold: 15,
+            heart_rate_url: String::new(),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct WidgetState {
+    translation: Mutex<Option<(String, Instant)>>,
+    heart_rate: Mutex<Option<(u32, Instant)>>,
+    // Wakes the scheduler when a translation shouldn't wait for the next tick
+    wake: Notify,
+}
+
+/// Hands a translation to the scheduler. Returns false while widgets are disabled, the
+/// caller sends it to the chatbox itself then.
+pub fn translated(app: &AppHandle, message: String) -> bool {
+    if !app.state::<SettingsState>().get().widgets.enabled {
+        return false;
+    }
+
+    let state = app.state::<WidgetState>();
+    *state.translation.lock().unwrap() = Some((message, Instant::now()));
+    state.wake.notify_one();
+    true
+}
+
+pub fn uses_now_playing(app: &AppHandle) -> bool {
+    let settings = app.state::<SettingsState>().get().widgets;
+    settings.enabled
+        && settings
+            .widgets
+            .iter()
+            .any(|widget| matches!(widget, ChatboxWidget::NowPlaying { .. }))
+}
+
+fn render(app: &AppHandle, settings: &WidgetSettings, widget: &ChatboxWidget) -> Option<String> {
+    let state = app.state::<WidgetState>();
+
+    match widget {
+        ChatboxWidget::Translation => state
+            .translation
+            .lock()
+            .unwrap()
+            .as_ref()
+            .filter(|(_, at)| at.elapsed() < Duration::from_secs(settings.translation_hold))
+            .map(|(message, _)| message.clone()),
+        ChatboxWidget::Clock { format } => Some(chrono::Local::now().format(format).to_string()),
+        ChatboxWidget::HeartRate { template } => state
+            .heart_rate
+            .lock()
+            .unwrap()
+            .filter(|(_, at)| at.elapsed() < HEART_RATE_STALE)
+            .map(|(bpm, _)| template.replace("{bpm}", &bpm.to_string())),
+        ChatboxWidget::NowPlaying { template } => {
+            now_playing::current(app).map(|track| now_playing::format_track(template, &track))
+        }
+    }
+}
+
+struct Rotation {
+    index: usize,
+    since: Instant,
+}
+
+fn compose(app: &AppHandle, settings: &WidgetSettings, rotation: &mut Rotation) -> Option<String> {
+    let rendered: Vec<(bool, String)> = settings
+        .widgets
+        .iter()
+        .filter_map(|widget| {
+            render(app, settings, widget).map(|text| (*widget == ChatboxWidget::Translation, text))
+        })
+        .collect();
+
+    match settings.layout {
+        WidgetLayout::Compose => {
+            let lines: Vec<String> = rendered.into_iter().map(|(_, text)| text).collect();
+            (!lines.is_empty()).then(|| lines.join("\n"))
+        }
+        WidgetLayout::Rotate => {
+            if let Some((_, translation)) = rendered.iter().find(|(translation, _)| *translation) {
+                return Some(translation.clone());
+            }
+
+            if rendered.is_empty() {
+                return None;
+            }
+            if rotation.since.elapsed() >= Duration::from_secs(settings.rotate_interval.max(1)) {
+                rotation.index += 1;
+                rotation.since = Instant::now();
+            }
+            Some(rendered[rotation.index % rendered.len()].1.clone())
+        }
+    }
+}
+
+pub fn start(app: &AppHandle) {
+    let scheduler = app.clone();
+    shutdown::spawn(app, "widgets", async move {
+        let app = scheduler;
+        let state = app.state::<WidgetState>();
+        let mut rotation = Rotation {
+            index: 0,
+            since: Instant::now(),
+        };
+        let mut shown: Option<(String, Instant)> = None;
+
+        loop {
+            tokio::select! {
+                _ = tokio::time::sleep(TICK) => {}
+                _ = state.wake.notified() => {}
+            }
+
+            let settings = app.state::<SettingsState>().get();
+            // The AFK reply has the chatbox to itself
+            if !settings.widgets.enabled || osc::afk(&app) {
+                shown = None;
+                continue;
+            }
+
+            let text = compose(&app, &settings.widgets, &mut rotation).unwrap_or_default();
+            let due = match &shown {
+                Some((last, at)) => {
+                    *last != text || (!text.is_empty() && at.elapsed() >= REFRESH_INTERVAL)
+                }
+                // Nothing to clear on the first round
+                None => !text.is_empty(),
+            };
+            if !due {
+                continue;
+            }
+
+            // osc coalesces messages that come faster than VRChat accepts them
+            let sent = osc::send_message(
+                app.clone(),
+                app.state::<ChatboxState>(),
+                text.clone(),
+                settings.vrchat_settings.osc_address,
+                settings.vrchat_settings.osc_port.to_string(),
+            );
+            if sent.is_ok() {
+                shown = Some((text, Instant::now()));
+            }
+        }
+    });
+
+    let heart_rate = app.clone();
+    shutdown::spawn(app, "widgets", async move {
+        let app = heart_rate;
+
+        loop {
+            let url = app.state::<SettingsState>().get().widgets.heart_rate_url;
+            if !url.is_empty() {
+                if let Err(e) = read_heart_rate(&app, &url).await {
+                    log::warn!("[WIDGETS] {}", e);
+                }
+            }
+
+            tokio::time::sleep(HEART_RATE_RECONNECT).await;
+        }
+    });
+}
+
+async fn read_heart_rate(app: &AppHandle, url: &str) -> Result<(), String> {
+    let (mut ws, _) = network::connect_websocket(app, url)
+        .await
+        .map_err(|e| format!("Failed to connect to the heart rate source: {}", e))?;
+    log::info!("[WIDGETS] Connected to the heart rate source");
+
+    loop {
+        let message = tokio::time::timeout(HEART_RATE_STALE, ws.next())
+            .await
+            .map_err(|_| "The heart rate source stopped sending".to_string())?;
+
+        let text = match message {
+            Some(Ok(Message::Text(text))) => text,
+            Some(Ok(Message::Close(_))) | None => {
+                return Err("The heart rate source closed the connection".to_string())
+            }
+            Some(Err(e)) => return Err(format!("Heart rate connection failed: {}", e)),
+            Some(Ok(_)) => continue,
+        };
+
+        // Reconnect with the new source when it was changed
+        if app.state::<SettingsState>().get().widgets.heart_rate_url != url {
+            return Ok(());
+        }
+
+        if let Some(bpm) = parse_heart_rate(&text) {
+            *app.state::<WidgetState>().heart_rate.lock().unwrap() = Some((bpm, Instant::now()));
+        }
+    }
+}
+
+// Pulsoid sends `{"data": {"heart_rate": 72}}`, simpler bridges a bare number or a flat object
+fn parse_heart_rate(text: &str) -> Option<u32> {
+    let value: Value = serde_json::from_str(text.trim()).ok()?;
+    let bpm = value
+        .pointer("/data/heart_rate")
+        .or_else(|| value.get("heart_rate"))
+        .or_else(|| value.get("heartRate"))
+        .or_else(|| value.get("bpm"))
+        .unwrap_or(&value);
+
+    bpm.as_u64()
+        .or_else(|| bpm.as_f64().map(|bpm| bpm.round() as u64))
+        .map(|bpm| bpm as u32)
+        .filter(|bpm| *bpm > 0)
+}
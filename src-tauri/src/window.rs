@@ -0,0 +1,164 @@
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use tauri::{AppHandle, LogicalSize, Manager, PhysicalSize, State, WebviewWindow};
+
+use crate::events;
+use crate::settings::SettingsState;
+
+const COMPACT_SIZE: LogicalSize<f64> = LogicalSize {
+    width: 800.0,
+    height: 140.0,
+};
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WindowSettings {
+    pub always_on_top: bool,
+    /// Borderless subtitle strip instead of the full window.
+    pub compact: bool,
+}
+
+/// Click-through is deliberately not saved, a window that ignores the mouse
+/// on every start could only be recovered with the hotkey.
+#[derive(Clone, Default, Serialize)]
+pub struct WindowMode {
+    pub always_on_top: bool,
+    pub click_through: bool,
+    pub compact: bool,
+}
+
+#[derive(Default)]
+pub struct WindowState {
+    mode: Mutex<WindowMode>,
+    // Size to go back to when leaving compact mode
+    normal_size: Mutex<Option<PhysicalSize<u32>>>,
+}
+
+fn main_window(app: &AppHandle) -> Result<WebviewWindow, String> {
+    app.get_webview_window("main")
+        .ok_or_else(|| "Main window is not open".to_string())
+}
+
+/// Restores the saved always-on-top and compact modes.
+pub fn restore(app: &AppHandle) {
+    let saved = app.state::<SettingsState>().get().window;
+    let result = apply_always_on_top(app, saved.always_on_top)
+        .and_then(|_| apply_compact(app, saved.compact));
+
+    if let Err(e) = result {
+        log::warn!("[WINDOW] Failed to restore window mode: {}", e);
+    }
+}
+
+fn apply_always_on_top(app: &AppHandle, enabled: bool) -> Result<(), String> {
+    main_window(app)?
+        .set_always_on_top(enabled)
+        .map_err(|e| format!("Failed to change always on top: {}", e))?;
+
+    app.state::<WindowState>()
+        .mode
+        .lock()
+        .unwrap()
+        .always_on_top = enabled;
+    Ok(())
+}
+
+fn apply_click_through(app: &AppHandle, enabled: bool) -> Result<(), String> {
+    main_window(app)?
+        .set_ignore_cursor_events(enabled)
+        .map_err(|e| format!("Failed to change click-through: {}", e))?;
+
+    app.state::<WindowState>()
+        .mode
+        .lock()
+        .unwrap()
+        .click_through = enabled;
+    Ok(())
+}
+
+fn apply_compact(app: &AppHandle, enabled: bool) -> Result<(), String> {
+    let window = main_window(app)?;
+    let state = app.state::<WindowState>();
+
+    if state.mode.lock().unwrap().compact == enabled {
+        return Ok(());
+    }
+
+    let result = if enabled {
+        *state.normal_size.lock().unwrap() = window.inner_size().ok();
+        window
+            .set_decorations(false)
+            .and_then(|_| window.set_size(COMPACT_SIZE))
+    } else {
+        let normal_size = state.normal_size.lock().unwrap().take();
+        window
+            .set_decorations(true)
+            .and_then(|_| match normal_size {
+                Some(size) => window.set_size(size),
+                None => Ok(()),
+            })
+    };
+    result.map_err(|e| format!("Failed to change compact mode: {}", e))?;
+
+    state.mode.lock().unwrap().compact = enabled;
+    Ok(())
+}
+
+fn changed(app: &AppHandle, save: bool) -> Result<WindowMode, String> {
+    let mode = app.state::<WindowState>().mode.lock().unwrap().clone();
+
+    if save {
+        let settings_state = app.state::<SettingsState>();
+        let mut settings = settings_state.get();
+        settings.window = WindowSettings {
+            always_on_top: mode.always_on_top,
+            compact: mode.compact,
+        };
+        settings_state.set(settings)?;
+    }
+
+    log::info!(
+        "[WINDOW] Always on top: {}, click-through: {}, compact: {}",
+        mode.always_on_top,
+        mode.click_through,
+        mode.compact
+    );
+
+    let _ = events::emit(app, "window-mode-changed", mode.clone());
+    Ok(mode)
+}
+
+#[tauri::command]
+pub fn get_window_mode(state: State<'_, WindowState>) -> WindowMode {
+    state.mode.lock().unwrap().clone()
+}
+
+#[tauri::command]
+pub fn set_always_on_top(app: AppHandle, enabled: bool) -> Result<WindowMode, String> {
+    apply_always_on_top(&app, enabled)?;
+    changed(&app, true)
+}
+
+#[tauri::command]
+pub fn set_click_through(app: AppHandle, enabled: bool) -> Result<WindowMode, String> {
+    apply_click_through(&app, enabled)?;
+    changed(&app, false)
+}
+
+/// Bound to a hotkey, a click-through window can't be clicked to turn it off.
+#[tauri::command]
+pub fn toggle_click_through(app: AppHandle) -> Result<WindowMode, String> {
+    let enabled = !app
+        .state::<WindowState>()
+        .mode
+        .lock()
+        .unwrap()
+        .click_through;
+    set_click_through(app, enabled)
+}
+
+#[tauri::command]
+pub fn set_compact_mode(app: AppHandle, enabled: bool) -> Result<WindowMode, String> {
+    apply_compact(&app, enabled)?;
+    changed(&app, true)
+}
@@ -0,0 +1,299 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+
+use crate::events;
+use crate::network;
+use crate::secrets;
+use crate::settings::SettingsState;
+use crate::shutdown;
+
+const API_URL: &str = "https://www.googleapis.com/youtube/v3";
+
+// Each poll costs quota units, YouTube's suggested interval is often shorter than the daily quota allows
+const MIN_POLL_INTERVAL: Duration = Duration::from_secs(10);
+const RETRY_DELAY: Duration = Duration::from_secs(30);
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum YoutubeRoute {
+    /// Translate and send to the VRChat chatbox.
+    Chatbox,
+    /// Translate and only show it in the subtitle window and overlay.
+    Subtitles,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct YoutubeSettings {
+    pub enabled: bool,
+    /// ID of the live stream's video, the API key lives in the credential store.
+    pub video_id: String,
+    /// Messages starting with this are relayed, empty relays every allowed message.
+    pub relay_prefix: String,
+    pub route: YoutubeRoute,
+    /// The channel owner and moderators may always relay.
+    pub allow_moderators: bool,
+    /// Channel IDs or display names that may relay besides moderators.
+    pub allowlist: Vec<String>,
+    pub max_per_minute: u32,
+}
+
+impl Default for YoutubeSettings {
+    fn default() -> Self {
+        YoutubeSettings {
+            enabled: false,
+            video_id: String::new(),
+            relay_prefix: "!say ".to_string(),
+            route: YoutubeRoute::Chatbox,
+            allow_moderators: true,
+            allowlist: Vec::new(),
+            max_per_minute: 6,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MessagePage {
+    #[serde(default)]
+    items: Vec<ChatItem>,
+    next_page_token: Option<String>,
+    #[serde(default)]
+    polling_interval_millis: u64,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ChatItem {
+    snippet: ChatSnippet,
+    author_details: AuthorDetails,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ChatSnippet {
+    #[serde(default)]
+    display_message: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AuthorDetails {
+    channel_id: String,
+    display_name: String,
+    #[serde(default)]
+    is_chat_owner: bool,
+    #[serde(default)]
+    is_chat_moderator: bool,
+}
+
+impl YoutubeSettings {
+    fn allows(&self, author: &AuthorDetails) -> bool {
+        (self.allow_moderators && (author.is_chat_owner || author.is_chat_moderator))
+            || self.allowlist.iter().any(|allowed| {
+                allowed == &author.channel_id || allowed.eq_ignore_ascii_case(&author.display_name)
+            })
+    }
+}
+
+async fn get(
+    client: &reqwest::Client,
+    path: &str,
+    query: &[(&str, &str)],
+) -> Result<Value, String> {
+    let response = client
+        .get(format!("{}/{}", API_URL, path))
+        .query(query)
+        .send()
+        .await
+        .map_err(|e| format!("YouTube request failed: {}", e))?;
+
+    let status = response.status();
+    let body: Value = response
+        .json()
+        .await
+        .map_err(|e| format!("Invalid YouTube response: {}", e))?;
+
+    if !status.is_success() {
+        return Err(format!(
+            "YouTube returned {}: {}",
+            status,
+            body["error"]["message"].as_str().unwrap_or("unknown error")
+        ));
+    }
+    Ok(body)
+}
+
+async fn live_chat_id(
+    client: &reqwest::Client,
+    key: &str,
+    video_id: &str,
+) -> Result<String, String> {
+    let videos = get(
+        client,
+        "videos",
+        &[
+            ("part", "liveStreamingDetails"),
+            ("id", video_id),
+            ("key", key),
+        ],
+    )
+    .await?;
+
+    videos["items"][0]["liveStreamingDetails"]["activeLiveChatId"]
+        .as_str()
+        .map(str::to_string)
+        .ok_or_else(|| format!("Video {} has no active live chat", video_id))
+}
+
+// Keeps the relay within max_per_minute, messages over the limit are dropped rather than queued
+struct RateLimit {
+    sent: VecDeque<Instant>,
+}
+
+impl RateLimit {
+    fn allow(&mut self, per_minute: u32) -> bool {
+        let minute = Duration::from_secs(60);
+        while self
+            .sent
+            .front()
+            .map_or(false, |sent| sent.elapsed() > minute)
+        {
+            self.sent.pop_front();
+        }
+
+        if self.sent.len() >= per_minute as usize {
+            return false;
+        }
+        self.sent.push_back(Instant::now());
+        true
+    }
+}
+
+fn relay(app: &AppHandle, settings: &YoutubeSettings, limit: &mut RateLimit, item: ChatItem) {
+    if !settings.allows(&item.author_details) {
+        return;
+    }
+
+    let text = match item
+        .snippet
+        .display_message
+        .strip_prefix(settings.relay_prefix.as_str())
+    {
+        Some(text) if !text.trim().is_empty() => text.trim().to_string(),
+        _ => return,
+    };
+
+    if !limit.allow(settings.max_per_minute) {
+        log::debug!(
+            "[YOUTUBE] Dropped a message from {}, over the rate limit",
+            item.author_details.display_name
+        );
+        return;
+    }
+
+    log::info!(
+        "[YOUTUBE] Relaying a message from {}",
+        item.author_details.display_name
+    );
+    let action = match settings.route {
+        YoutubeRoute::Chatbox => "translate",
+        YoutubeRoute::Subtitles => "translate_subtitles",
+    };
+    let _ = events::emit(
+        app,
+        "remote-control",
+        json!({ "action": action, "text": text }),
+    );
+}
+
+/// Polls the live chat of the configured stream and relays allowed messages for translation.
+pub fn start(app: &AppHandle) {
+    let app = app.clone();
+
+    shutdown::spawn(&app.clone(), "youtube", async move {
+        let client = network::http_client(&app);
+        let mut limit = RateLimit {
+            sent: VecDeque::new(),
+        };
+
+        loop {
+            let settings = app.state::<SettingsState>().get().youtube;
+            if !settings.enabled || settings.video_id.is_empty() {
+                tokio::time::sleep(RETRY_DELAY).await;
+                continue;
+            }
+
+            let key = match secrets::get(secrets::YOUTUBE_API_KEY) {
+                Ok(Some(key)) => key,
+                Ok(None) => {
+                    log::warn!("[YOUTUBE] YouTube API key is not set");
+                    tokio::time::sleep(RETRY_DELAY).await;
+                    continue;
+                }
+                Err(e) => {
+                    log::warn!("[YOUTUBE] {}", e);
+                    tokio::time::sleep(RETRY_DELAY).await;
+                    continue;
+                }
+            };
+
+            let chat_id = match network::retry(&app, || {
+                live_chat_id(&client, &key, &settings.video_id)
+            })
+            .await
+            {
+                Ok(chat_id) => chat_id,
+                Err(e) => {
+                    log::warn!("[YOUTUBE] {}", e);
+                    tokio::time::sleep(RETRY_DELAY).await;
+                    continue;
+                }
+            };
+
+            log::info!("[YOUTUBE] Following the live chat of {}", settings.video_id);
+            let mut page_token: Option<String> = None;
+
+            // Stop following once the settings change, the outer loop picks up the new ones
+            while app.state::<SettingsState>().get().youtube == settings {
+                let mut query = vec![
+                    ("part", "snippet,authorDetails"),
+                    ("liveChatId", chat_id.as_str()),
+                    ("key", key.as_str()),
+                ];
+                if let Some(token) = page_token.as_deref() {
+                    query.push(("pageToken", token));
+                }
+
+                let page: MessagePage =
+                    match network::retry(&app, || get(&client, "liveChat/messages", &query))
+                        .await
+                        .and_then(|body| serde_json::from_value(body).map_err(|e| e.to_string()))
+                    {
+                        Ok(page) => page,
+                        Err(e) => {
+                            log::warn!("[YOUTUBE] {}", e);
+                            break;
+                        }
+                    };
+
+                // The first page is the chat's backlog, only relay what comes after it
+                if page_token.is_some() {
+                    for item in page.items {
+                        relay(&app, &settings, &mut limit, item);
+                    }
+                }
+                page_token = page.next_page_token;
+
+                let interval =
+                    Duration::from_millis(page.polling_interval_millis).max(MIN_POLL_INTERVAL);
+                tokio::time::sleep(interval).await;
+            }
+
+            tokio::time::sleep(RETRY_DELAY).await;
+        }
+    });
+}
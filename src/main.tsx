@@ -1,6 +1,7 @@
 import React from "react"
 import ReactDOM from "react-dom/client";
 import App from "./page";
+import Subtitles from "./pages/Subtitles";
 
 import "@fontsource/inter";
 import "./globals.css";
@@ -8,6 +9,7 @@ import "./globals.css";
 // To get rid of TS compilation errors
 (() => { return React.StrictMode })();
 
+// The detachable subtitle window loads the same bundle with #subtitles
 ReactDOM.createRoot(document.getElementById("root") as HTMLElement).render(
-  <App />
+  window.location.hash == "#subtitles" ? <Subtitles /> : <App />
 );
@@ -24,12 +24,13 @@ import {
 } from '@mui/icons-material';
 
 import { invoke } from '@tauri-apps/api/core'
+import { listen } from './util/events'
 import { open } from '@tauri-apps/plugin-shell'
 
 import SettingsPage from './pages/Settings';
 import Scroll from "./components/Scroll"
 
-import { DEFAULT_CONFIG, load_config, update_config } from './util/config';
+import { Config, DEFAULT_CONFIG, load_config, update_config, validate_config } from './util/config';
 import { Lang } from './util/constants';
 import { getVersion } from '@tauri-apps/api/app';
 
@@ -70,23 +71,24 @@ function App() {
       setTimeout(() => localStorage.setItem("changelogsViewed", version), 1000)
     })
 
-    const cfg = load_config()
     const language = localStorage.getItem("lang") as Lang | null
     
     setQuickstartVisible(localStorage.getItem("quickstartMenu") == null || language == null)
     setLang(language == null ? "en" : language)
 
-    setConfig(cfg)
-
-    // Check if API key is not set and show prompt
-    if (!cfg.api_settings.qwen_asr_api_key || cfg.api_settings.qwen_asr_api_key.trim() === "") {
-      // Show API key prompt after quickstart is done
-      setTimeout(() => {
-        if (localStorage.getItem("quickstartMenu") != null && language != null) {
-          setApiKeyPromptVisible(true)
-        }
-      }, 500)
-    }
+    load_config().then((cfg) => {
+      setConfig(cfg)
+
+      // Check if API key is not set and show prompt
+      if (!cfg.api_settings.qwen_asr_api_key_set) {
+        // Show API key prompt after quickstart is done
+        setTimeout(() => {
+          if (localStorage.getItem("quickstartMenu") != null && language != null) {
+            setApiKeyPromptVisible(true)
+          }
+        }, 500)
+      }
+    })
 
     translateGT("Hello, how are you?", "en-US", "tr-TR").then((out) => { console.log("Can access to Google servers: " + out) }).catch(err => {
       console.log(err)
@@ -94,7 +96,11 @@ function App() {
       setGoogleServersErrorVisible(true)
     })
 
-    invoke("start_vrc_listener")
+    const profileUnlisten = listen<Config>("profile-switched", (event) => setConfig(validate_config(event.payload)))
+    const importUnlisten = listen<Config>("settings-imported", (event) => setConfig(validate_config(event.payload)))
+    const configUnlisten = listen<Config>("config-changed", (event) => setConfig(validate_config(event.payload)))
+
+    invoke("start_vrc_listener").catch((e) => console.error(`[OSC] ${e}`))
     
     setTimeout(() => setLoaded(true), 300);
 
@@ -108,6 +114,12 @@ function App() {
         localStorage.setItem("last_donation", `${Date.now()}`)
       }
     }
+
+    return () => {
+      profileUnlisten.then((unlisten) => unlisten())
+      importUnlisten.then((unlisten) => unlisten())
+      configUnlisten.then((unlisten) => unlisten())
+    }
   }, [])
 
   const handleApiKeyConfirm = () => {
@@ -124,7 +136,8 @@ function App() {
       ...config,
       api_settings: {
         ...config.api_settings,
-        qwen_asr_api_key: trimmedKey
+        qwen_asr_api_key: trimmedKey,
+        qwen_asr_api_key_set: true
       }
     })
     
@@ -1,6 +1,6 @@
 import * as React from "react"
 
-import { Select, MenuItem, Button } from "@mui/material"
+import { Select, MenuItem, Button, TextField } from "@mui/material"
 
 import {
     info,
@@ -20,18 +20,21 @@ import {
 } from '@mui/icons-material';
 
 import { invoke } from '@tauri-apps/api/core'
-import { listen } from '@tauri-apps/api/event';
+import { listen } from '../util/events'
 import { open } from '@tauri-apps/plugin-shell'
 
 import { calculateMinWaitTime, Lang, langSource, langTo } from "../util/constants"
 
 import { Config } from "../util/config";
+import { resolveInputDevice } from "../util/devices";
 import { Recognizer } from "../recognizers/recognizer";
 import { WebSpeech } from "../recognizers/WebSpeech";
 import { QwenASR } from "../recognizers/QwenASR";
+import { GrpcASR } from "../recognizers/GrpcASR";
 
 import { localization } from "../util/localization";
 import translateGT from "../translators/google_translate";
+import translatePlugin from "../translators/plugin";
 
 type KikitanProps = {
     config: Config;
@@ -39,18 +42,61 @@ type KikitanProps = {
     lang: Lang;
 }
 
+type Detection = {
+    text: string
+    capturedAt: number
+    recognizedAt: number
+    // False for text that should only show up in the subtitles and overlay
+    chatbox: boolean
+    // English from the ASR engine's own translate task, skips the translation provider
+    translation?: string
+}
+
 let sr: Recognizer | null = null;
-let detectionQueue: string[] = []
+let detectionQueue: Detection[] = []
 let lock = false
 
+// When the recognizer first heard the utterance that is currently being spoken
+let capturedAt: number | null = null
+
+// The last translation shown, so editing it can be remembered against what was said
+let lastTranslation: { original: string, sourceLanguage: string, targetLanguage: string } | null = null
+
+// Translation that came with the last final recognition result
+let engineTranslation: string | undefined = undefined
+
+// A slow translation provider would otherwise let the queue, and the chatbox lag, grow without limit
+const MAX_DETECTION_QUEUE = 3
+
+function enqueueDetection(text: string, spoken = true, chatbox = true) {
+    const recognizedAt = Date.now()
+    detectionQueue = [...detectionQueue, { text, capturedAt: (spoken && capturedAt) || recognizedAt, recognizedAt, chatbox, translation: spoken ? engineTranslation : undefined }]
+    if (spoken) {
+        capturedAt = null
+        engineTranslation = undefined
+    }
+
+    // Merge the oldest pending sentences instead of dropping them, so nothing said is lost
+    while (detectionQueue.length > MAX_DETECTION_QUEUE) {
+        const [first, second] = detectionQueue
+        detectionQueue = [{ text: `${first.text} ${second.text}`, capturedAt: first.capturedAt, recognizedAt: second.recognizedAt, chatbox: first.chatbox || second.chatbox, translation: first.translation && second.translation ? `${first.translation} ${second.translation}` : undefined }, ...detectionQueue.slice(2)]
+
+        warn(`[DETECTION] Translation is falling behind, merged two queued detections`)
+    }
+}
+
 export default function Kikitan({ config, setConfig, lang }: KikitanProps) {
     const [detecting, setDetecting] = React.useState(false)
     const [translating, setTranslating] = React.useState(false)
     const [srStatus, setSRStatus] = React.useState(true)
     const [vrcMuted, setVRCMuted] = React.useState(false)
+    const [headsetPaused, setHeadsetPaused] = React.useState(false)
+    const [lowPower, setLowPower] = React.useState(false)
 
     const [detection, setDetection] = React.useState("")
     const [translated, setTranslated] = React.useState("")
+    const [editing, setEditing] = React.useState(false)
+    const [draft, setDraft] = React.useState("")
 
     const [defaultMicrophone, setDefaultMicrophone] = React.useState(localization.waiting_for_mic_access[lang])
     const [lastDefaultMicrophone, setLastDefaultMicrophone] = React.useState("")
@@ -60,6 +106,81 @@ export default function Kikitan({ config, setConfig, lang }: KikitanProps) {
     const [sourceLanguage, setSourceLanguage] = React.useState(config.source_language)
     const [targetLanguage, setTargetLanguage] = React.useState(config.target_language)
 
+    const swapLanguages = () => {
+        const new_t = sourceLanguage.includes("en-") ? "en" : sourceLanguage.includes("es-") ? "es" : sourceLanguage
+        const new_s = targetLanguage == "en" ? "en-US" : targetLanguage == "es" ? "es-ES" : targetLanguage
+
+        setTargetLanguage(new_t)
+        setSourceLanguage(new_s)
+
+        setConfig({ ...config, source_language: new_s, target_language: new_t })
+    }
+
+    // Resends my fixed translation and remembers it for the next time the same thing is said
+    const sendCorrection = (text: string) => {
+        if (!lastTranslation || text.length == 0 || text == translated) return
+
+        const { original, sourceLanguage, targetLanguage } = lastTranslation
+        setTranslated(text)
+
+        invoke("remember_correction", { sourceLanguage, targetLanguage, original, translation: text })
+            .catch((e) => error(`[MEMORY] Failed to remember the correction: ${e}`))
+        invoke("send_translation", { original: original.replace(/%/g, "%25"), translation: text, sourceLanguage, targetLanguage })
+            .catch(() => { })
+    }
+
+    React.useEffect(() => {
+        const unlisten = listen<{ action: string, pressed: boolean }>("hotkey", (event) => {
+            const { action, pressed } = event.payload
+            info(`[HOTKEY] ${action} ${pressed ? "pressed" : "released"}`)
+
+            if (action == "push_to_talk") setSRStatus(pressed)
+            if (!pressed) return
+
+            if (action == "toggle_translation") setSRStatus(!srStatus)
+            else if (action == "switch_language_pair") swapLanguages()
+            else if (action == "toggle_click_through") invoke("toggle_click_through")
+            else if (action == "clear_chatbox") invoke("send_message", { address: config.vrchat_settings.osc_address, port: `${config.vrchat_settings.osc_port}`, msg: "" })
+        })
+
+        const trayUnlisten = listen<string>("tray-action", (event) => {
+            if (event.payload == "toggle_translation") setSRStatus(!srStatus)
+        })
+
+        const remoteUnlisten = listen<{ action: string, text: string | null, source?: string, target?: string }>("remote-control", (event) => {
+            const { action, text, source, target } = event.payload
+            info(`[CONTROL API] ${action}`)
+
+            if (action == "pause") setSRStatus(false)
+            else if (action == "resume") setSRStatus(true)
+            else if (action == "translate" && text) enqueueDetection(text, false)
+            else if (action == "translate_subtitles" && text) enqueueDetection(text, false, false)
+            else if (action == "set_languages" && langSource.some((l) => l.code == source) && langTo.some((l) => l.code == target)) {
+                setSourceLanguage(source!)
+                setTargetLanguage(target!)
+                setConfig({ ...config, source_language: source!, target_language: target! })
+            }
+        })
+
+        const vrchatUnlisten = listen<boolean>("vrchat-running", (event) => {
+            if (!config.vrchat_settings.follow_vrchat) return
+
+            info(`[VRCHAT] VRChat ${event.payload ? "started" : "exited"}, ${event.payload ? "starting" : "stopping"} translation`)
+            setSRStatus(event.payload)
+        }, true)
+
+        return () => {
+            unlisten.then((f) => f())
+            trayUnlisten.then((f) => f())
+            remoteUnlisten.then((f) => f())
+            vrchatUnlisten.then((f) => f())
+        }
+    }, [srStatus, sourceLanguage, targetLanguage, config])
+
+    React.useEffect(() => {
+        invoke("set_tray_status", { paused: !srStatus })
+    }, [srStatus])
+
     React.useEffect(() => {
         info(`[LANGUAGE] Changing language (${sourceLanguage} - ${targetLanguage}) - sr=${sr != null}`)
 
@@ -69,7 +190,7 @@ export default function Kikitan({ config, setConfig, lang }: KikitanProps) {
     }, [sourceLanguage, targetLanguage])
 
     React.useEffect(() => {
-        info(`[SR] SR status=${srStatus} - VRC Muted=${vrcMuted} - Disable Kikitan When Muted=${config.vrchat_settings.disable_kikitan_when_muted}`)
+        info(`[SR] SR status=${srStatus} - VRC Muted=${vrcMuted} - Mute Behavior=${config.vrchat_settings.mute_behavior} - Headset Paused=${headsetPaused} - Low Power=${lowPower}`)
 
         if (sr == null) {
             warn("[SR] SR is currently null, so ignoring the changes")
@@ -78,7 +199,9 @@ export default function Kikitan({ config, setConfig, lang }: KikitanProps) {
         }
 
         if (srStatus) {
-            if (vrcMuted && config.vrchat_settings.disable_kikitan_when_muted) {
+            // Qwen and gRPC audio goes through the backend, which drops it itself while muted
+            const webSpeechMuted = vrcMuted && config.vrchat_settings.mute_behavior == "pause" && !(sr instanceof QwenASR || sr instanceof GrpcASR)
+            if (webSpeechMuted || headsetPaused || lowPower) {
                 info("[SR] Pausing SR...")
                 sr.stop()
             }
@@ -91,27 +214,52 @@ export default function Kikitan({ config, setConfig, lang }: KikitanProps) {
             info("[SR] Stopping SR...")
             sr.stop()
         }
-    }, [srStatus, vrcMuted])
+    }, [srStatus, vrcMuted, headsetPaused, lowPower])
 
     React.useEffect(() => {
         (async () => {
             if (detectionQueue.length == 0 || lock) return;
 
-            const val = detectionQueue[0].replace(/%/g, "%25")
+            const current = detectionQueue[0]
             detectionQueue = detectionQueue.slice(1)
 
             lock = true
 
+            if (await invoke<boolean>("run_voice_command", { text: current.text }).catch(() => false)) {
+                info("[VOICE] Ran a voice command instead of translating")
+                lock = false
+                return
+            }
+
+            // Fixes misrecognized words before they get translated literally
+            const corrected = await invoke<string>("autocorrect", { text: current.text, language: sourceLanguage }).catch(() => current.text)
+            const val = corrected.replace(/%/g, "%25")
+
             info(`[TRANSLATION] Starting translation. Current detection queue length is ${detectionQueue.length}`)
 
-            invoke("send_typing", { address: config.vrchat_settings.osc_address, port: `${config.vrchat_settings.osc_port}` })
+            if (current.chatbox) invoke("send_typing", { address: config.vrchat_settings.osc_address, port: `${config.vrchat_settings.osc_port}` })
             let count = 3;
 
             while (count > 0) {
                 info(`[TRANSLATION] Attempting translation. Try ${4 - count}`)
                 try {
                     setTranslating(true)
-                    let text = await translateGT(val, sourceLanguage, targetLanguage)
+                    // The engine already translated to English, the target may have changed since
+                    const passthrough = current.translation && targetLanguage.startsWith("en") ? current.translation : undefined
+                    const remembered = passthrough ? null : await invoke<string | null>("lookup_translation_memory", { sourceLanguage, targetLanguage, text: corrected }).catch(() => null)
+                    const plugin = passthrough ? "asr" : remembered != null ? "memory" : config.plugins.translation_provider
+                    let text: string
+                    if (passthrough) {
+                        info("[TRANSLATION] Using the ASR engine's own translation")
+                        text = passthrough
+                    } else if (remembered != null) {
+                        info("[TRANSLATION] Using a remembered correction")
+                        text = remembered
+                    } else {
+                        const protectedText = await invoke<{ text: string, tokens: string[] }>("protect_symbols", { text: corrected })
+                        const translation = plugin ? await translatePlugin(plugin, protectedText.text, sourceLanguage, targetLanguage) : await translateGT(protectedText.text.replace(/%/g, "%25"), sourceLanguage, targetLanguage)
+                        text = await invoke<string>("restore_symbols", { text: translation, tokens: protectedText.tokens })
+                    }
                     info("[TRANSLATION] Translation succeeded!")
 
                     if (config.language_settings.english_gender_change && targetLanguage == "en") {
@@ -121,11 +269,44 @@ export default function Kikitan({ config, setConfig, lang }: KikitanProps) {
                         else text = text.replace(/\bhe\b/g, "she").replace(/\bHe\b/g, "She").replace(/\bhis\b/g, "her").replace(/\bHis\b/g, "Her").replace(/\bhim\b/g, "her").replace(/\bHim\b/g, "Her").replace(/\bhe's\b/g, "she's").replace(/\bHe's\b/g, "She's")
                     }
 
+                    const translatedAt = Date.now()
+
+                    lastTranslation = { original: corrected, sourceLanguage, targetLanguage }
                     setTranslated(text)
                     setTranslating(false)
 
+                    const recorded = invoke<{ id: number }>("record_history", {
+                        entry: {
+                            source_language: sourceLanguage,
+                            target_language: targetLanguage,
+                            asr_provider: sr instanceof QwenASR ? "qwen" : sr instanceof GrpcASR ? "grpc" : "webspeech",
+                            translation_provider: plugin || "google",
+                            original: corrected,
+                            translation: text
+                        }
+                    }).catch((e) => {
+                        error(`[HISTORY] Failed to record history: ${e}`)
+                        return null
+                    })
+                    if (!passthrough && remembered == null) invoke("record_usage", { provider: plugin || "google", characters: corrected.length, seconds: 0 })
+
+                    if (!current.chatbox) {
+                        count = 0
+                        break
+                    }
+
                     info("[TRANSLATION] Sending the message to chatbox...")
-                    invoke("send_message", { address: config.vrchat_settings.osc_address, port: `${config.vrchat_settings.osc_port}`, msg: config.vrchat_settings.translation_first ? `${text} (${val})` : `${val} (${text})` })
+                    invoke("send_translation", { original: val, translation: text, sourceLanguage, targetLanguage })
+                        .then(async () => invoke("record_latency", {
+                            utteranceId: (await recorded)?.id ?? null,
+                            sample: {
+                                captured_at: current.capturedAt,
+                                recognized_at: current.recognizedAt,
+                                translated_at: translatedAt,
+                                sent_at: Date.now()
+                            }
+                        }))
+                        .catch(() => { })
                     await new Promise(r => setTimeout(r, calculateMinWaitTime(text, config.vrchat_settings.chatbox_update_speed)));
 
                     count = 0
@@ -150,35 +331,67 @@ export default function Kikitan({ config, setConfig, lang }: KikitanProps) {
         listen<boolean>("vrchat-mute", (event) => {
             info(`[OSC] Received mute status ${event.payload}`)
             setVRCMuted(event.payload)
+        }, true)
+
+        listen<{ command: string, message: string }>("osc-error", (event) => {
+            error(`[OSC] ${event.payload.command} failed: ${event.payload.message}`)
+        })
+
+        listen<{ message: string, thread: string, backtrace: string }>("backend-fatal", (event) => {
+            error(`[BACKEND] ${event.payload.thread} panicked: ${event.payload.message}`)
+        })
+
+        listen<{ subsystem: string, reason: string }>("watchdog", (event) => {
+            warn(`[WATCHDOG] Restarted ${event.payload.subsystem}: ${event.payload.reason}`)
+        })
+
+        listen<{ removed: boolean, dashboard: boolean, paused: boolean }>("headset-state", (event) => {
+            info(`[HEADSET] Removed=${event.payload.removed} - Dashboard=${event.payload.dashboard} - Paused=${event.payload.paused}`)
+            setHeadsetPaused(event.payload.paused)
+        }, true)
+
+        listen<{ low_power: boolean, pause_capture: boolean }>("power-state", (event) => {
+            info(`[POWER] Low power=${event.payload.low_power}`)
+            setLowPower(event.payload.low_power && event.payload.pause_capture)
+        }, true)
+
+        listen<string>("clipboard-text", (event) => {
+            enqueueDetection(event.payload, false)
+
+            info(`[CLIPBOARD] Pushed the clipboard text into the queue. Current queue length is ${detectionQueue.length}`)
         })
 
         if (sr == null) {
             info(`[SR] Initializing SR...`)
+            // A preferred microphone showing up or going away changes this too, and reloads
             setInterval(() => {
-                navigator.mediaDevices.enumerateDevices()
-                    .then(function (devices) {
-                        let def = devices.filter((device) => device.kind == "audioinput")[0].label
-                        def = def.split("(")[1].split(")")[0]
-
-                        setDefaultMicrophone(def)
+                resolveInputDevice()
+                    .then(function (device) {
+                        setDefaultMicrophone(device.label.split("(")[1]?.split(")")[0] ?? device.label)
                     }).catch(function (err) {
                         error(`[MEDIA] Error while trying to pull the media devices: ${err.name + " " + err.message}`)
                     });
             }, 1000)
 
-            // Use QwenASR if API key is provided, otherwise fall back to WebSpeech
-            if (config.api_settings.qwen_asr_api_key && config.api_settings.qwen_asr_api_key.trim() !== "") {
-                sr = new QwenASR(sourceLanguage, config.api_settings.qwen_asr_api_key)
+            // An external engine takes precedence, then QwenASR if API key is provided, otherwise fall back to WebSpeech
+            if (config.grpc_asr.enabled) {
+                sr = new GrpcASR(sourceLanguage)
+                info("[SR] Using the external ASR engine for recognition")
+            } else if (config.api_settings.qwen_asr_api_key_set) {
+                sr = new QwenASR(sourceLanguage)
                 info("[SR] Using Qwen ASR for recognition")
             } else {
                 sr = new WebSpeech(sourceLanguage)
                 info("[SR] Using WebSpeech for recognition (no Qwen API key provided)")
             }
 
-            sr.onResult((result: string, isFinal: boolean) => {
+            sr.onResult((result: string, isFinal: boolean, translation?: string) => {
                 info(`[SR] Received recognition result: Final: ${isFinal} - Result Length: ${result.length}`)
+                if (capturedAt == null) capturedAt = Date.now()
                 if (config.mode == 1 || config.vrchat_settings.send_typing_status_while_talking) invoke("send_typing", { address: config.vrchat_settings.osc_address, port: `${config.vrchat_settings.osc_port}` })
 
+                if (isFinal) engineTranslation = translation
+
                 setDetection(result)
                 setDetecting(!isFinal)
             })
@@ -208,13 +421,15 @@ export default function Kikitan({ config, setConfig, lang }: KikitanProps) {
 
         if (!detecting && detection.length != 0) {
             if (config.mode == 0) {
-                detectionQueue = [...detectionQueue, (sourceLanguage == "ja" && config.language_settings.japanese_omit_questionmark) ? detection.replace(/？/g, "") : detection]
+                enqueueDetection((sourceLanguage == "ja" && config.language_settings.japanese_omit_questionmark) ? detection.replace(/？/g, "") : detection)
 
                 info(`[DETECTION] Pushed the detection into the queue. Current queue length is ${detectionQueue.length}`)
 
                 return
             }
 
+            capturedAt = null
+
             info(`[DETECTION] Sending the detection to chatbox...`)
             invoke("send_message", { address: config.vrchat_settings.osc_address, port: `${config.vrchat_settings.osc_port}`, msg: (sourceLanguage == "ja" && config.language_settings.japanese_omit_questionmark) ? detection.replace(/？/g, "") : detection })
         }
@@ -253,15 +468,7 @@ export default function Kikitan({ config, setConfig, lang }: KikitanProps) {
                         })}
                     </Select>
                     <div className="mt-7">
-                        <Button onClick={() => {
-                            const new_t = sourceLanguage.includes("en-") ? "en" : sourceLanguage.includes("es-") ? "es" : sourceLanguage
-                            const new_s = targetLanguage == "en" ? "en-US" : targetLanguage == "es" ? "es-ES" : targetLanguage
-
-                            setTargetLanguage(new_t)
-                            setSourceLanguage(new_s)
-
-                            setConfig({ ...config, source_language: new_s, target_language: new_t })
-                        }}>
+                        <Button onClick={swapLanguages}>
                             <SwapHorizIcon />
                         </Button>
                     </div>
@@ -269,7 +476,19 @@ export default function Kikitan({ config, setConfig, lang }: KikitanProps) {
             </div>
             <div>
                 <div className={`w-96 h-48 outline outline-1 transition-all rounded-md ${config.light_mode ? "text-black outline-slate-800" : "text-slate-200 outline-slate-400"} font-bold text-center ${srStatus ? "" : "bg-gray-400"}`}>
-                    <p className={`transition-all duration-300 align-middle ${translating ? "opacity-0" : "opacity-100"}`}>{translated}</p>
+                    {editing ? <TextField autoFocus multiline fullWidth value={draft} sx={{
+                        '& .MuiInputBase-input': { color: config.light_mode ? 'black' : 'white', textAlign: 'center' }
+                    }} onChange={(e) => setDraft(e.target.value)} onBlur={() => setEditing(false)} onKeyDown={(e) => {
+                        if (e.key == "Escape") setEditing(false)
+                        if (e.key != "Enter" || e.shiftKey) return
+                        e.preventDefault()
+                        setEditing(false)
+                        sendCorrection(draft.trim())
+                    }} /> : <p className={`transition-all duration-300 align-middle ${translating ? "opacity-0" : "opacity-100"}`} onClick={() => {
+                        if (!lastTranslation || translating) return
+                        setDraft(translated)
+                        setEditing(true)
+                    }}>{translated}</p>}
                 </div>
                 <div>
                     <Select sx={{
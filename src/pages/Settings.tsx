@@ -135,6 +135,100 @@ export default function Settings({ closeCallback, config, setConfig, lang }: Set
                             <MenuItem sx={{ color: config.light_mode ? 'black' : 'white' }} key={"male"} value={0}>♂</MenuItem>
                             <MenuItem sx={{ color: config.light_mode ? 'black' : 'white' }} key={"female"} value={1}>♀</MenuItem>
                         </Select>} />
+                    <FormControlLabel control={<Checkbox checked={config.autocorrect.enabled} onChange={(e) => {
+                        setConfig({
+                            ...config,
+                            autocorrect: {
+                                ...config.autocorrect,
+                                enabled: e.target.checked
+                            }
+                        })
+                    }} />} label={localization.autocorrect[lang]} />
+                    <TextField slotProps={{
+                        inputLabel: {
+                            style: { color: config.light_mode ? "black" : '#94A3B8' }
+                        },
+                        htmlInput: {
+                            style: { color: config.light_mode ? "black" : '#fff' }
+                        }
+                    }} className="mt-2 w-96" disabled={!config.autocorrect.enabled} value={config.autocorrect.glossary.join("\n")} label={localization.glossary[lang]} placeholder="Kikitan" variant="outlined" multiline onChange={(e) => {
+                        setConfig({
+                            ...config,
+                            autocorrect: {
+                                ...config.autocorrect,
+                                glossary: e.target.value.split("\n")
+                            }
+                        })
+                    }} />
+                    <p className={`mb-2 text-xs ${config.light_mode ? "text-black" : "text-slate-400"}`}>{localization.glossary_help[lang]}</p>
+                    <FormControlLabel control={<Checkbox checked={config.language_switch.enabled} onChange={(e) => {
+                        setConfig({
+                            ...config,
+                            language_switch: {
+                                ...config.language_switch,
+                                enabled: e.target.checked
+                            }
+                        })
+                    }} />} label={localization.auto_language_switch[lang]} />
+                    <TextField slotProps={{
+                        inputLabel: {
+                            style: { color: config.light_mode ? "black" : '#94A3B8' }
+                        },
+                        htmlInput: {
+                            style: { color: config.light_mode ? "black" : '#fff' }
+                        }
+                    }} className="mt-2 w-96" disabled={!config.language_switch.enabled} value={config.language_switch.languages.join("\n")} label={localization.switch_languages[lang]} placeholder={"en-US\nja-JP"} variant="outlined" multiline onChange={(e) => {
+                        setConfig({
+                            ...config,
+                            language_switch: {
+                                ...config.language_switch,
+                                languages: e.target.value.split("\n")
+                            }
+                        })
+                    }} />
+                    <p className={`mb-2 text-xs ${config.light_mode ? "text-black" : "text-slate-400"}`}>{localization.switch_languages_help[lang]}</p>
+                    <TextField slotProps={{
+                        inputLabel: {
+                            style: { color: config.light_mode ? "black" : '#94A3B8' }
+                        },
+                        htmlInput: {
+                            style: { color: config.light_mode ? "black" : '#fff' }
+                        }
+                    }} className="mt-4 w-96" value={config.input_device.preferred_patterns.join("\n")} label={localization.preferred_microphones[lang]} placeholder="Index HMD|Quest" variant="outlined" multiline onChange={(e) => {
+                        setConfig({
+                            ...config,
+                            input_device: {
+                                ...config.input_device,
+                                preferred_patterns: e.target.value.split("\n")
+                            }
+                        })
+                    }} />
+                    <p className={`mb-2 text-xs ${config.light_mode ? "text-black" : "text-slate-400"}`}>{localization.preferred_microphones_help[lang]}</p>
+                    <TextField slotProps={{
+                        inputLabel: {
+                            style: { color: config.light_mode ? "black" : '#94A3B8' }
+                        },
+                        htmlInput: {
+                            style: { color: config.light_mode ? "black" : '#fff' }
+                        }
+                    }} className="mt-2 w-48" value={config.input_device.capture_offset_ms} label={localization.capture_offset[lang]} variant="outlined" type="number" onChange={(e) => {
+                        setConfig({
+                            ...config,
+                            input_device: {
+                                ...config.input_device,
+                                capture_offset_ms: Math.max(0, parseInt(e.target.value) || 0)
+                            }
+                        })
+                    }} />
+                    <FormControlLabel className="mt-2" control={<Checkbox checked={config.telemetry.enabled} onChange={(e) => {
+                        setConfig({
+                            ...config,
+                            telemetry: {
+                                ...config.telemetry,
+                                enabled: e.target.checked
+                            }
+                        })
+                    }} />} label={localization.anonymous_telemetry[lang]} />
                 </FormGroup>
             </CustomTabPanel>
             <CustomTabPanel className="flex" value={page} index={1}>
@@ -148,15 +242,109 @@ export default function Settings({ closeCallback, config, setConfig, lang }: Set
                             }
                         })
                     }} />} label={localization.translation_first[lang]} />
-                    <FormControlLabel control={<Checkbox checked={config.vrchat_settings.disable_kikitan_when_muted} onChange={(e) => {
+                    <p className={`mt-2 ${config.light_mode ? "text-black" : "text-slate-400"}`}>{localization.when_muted[lang]}</p>
+                    <Select sx={{
+                        color: config.light_mode ? 'black' : 'white',
+                        '& .MuiOutlinedInput-notchedOutline': {
+                            borderColor: config.light_mode ? 'black' : '#94A3B8',
+                        },
+                        '&:hover .MuiOutlinedInput-notchedOutline': {
+                            borderColor: config.light_mode ? 'black' : '#94A3B8',
+                        }
+                    }} MenuProps={{
+                        sx: {
+                            "& .MuiPaper-root": {
+                                backgroundColor: config.light_mode ? '#94A3B8' : '#020617',
+                            }
+                        }
+                    }} className="w-96 mb-2" value={config.vrchat_settings.mute_behavior} onChange={(e) => {
+                        setConfig({
+                            ...config,
+                            vrchat_settings: {
+                                ...config.vrchat_settings,
+                                mute_behavior: e.target.value as Config["vrchat_settings"]["mute_behavior"]
+                            }
+                        })
+                    }} >
+                        <MenuItem sx={{ color: config.light_mode ? 'black' : 'white' }} value="pause">{localization.mute_pause[lang]}</MenuItem>
+                        <MenuItem sx={{ color: config.light_mode ? 'black' : 'white' }} value="transcribe">{localization.mute_transcribe[lang]}</MenuItem>
+                        <MenuItem sx={{ color: config.light_mode ? 'black' : 'white' }} value="ignore">{localization.mute_ignore[lang]}</MenuItem>
+                    </Select>
+                    <FormControlLabel control={<Checkbox checked={config.vr_controller.enabled} onChange={(e) => {
+                        setConfig({
+                            ...config,
+                            vr_controller: {
+                                ...config.vr_controller,
+                                enabled: e.target.checked
+                            }
+                        })
+                    }} />} label={localization.vr_push_to_translate[lang]} />
+                    <div className="flex flex-row mb-2">
+                        <Select sx={{
+                            color: config.light_mode ? 'black' : 'white',
+                            '& .MuiOutlinedInput-notchedOutline': {
+                                borderColor: config.light_mode ? 'black' : '#94A3B8',
+                            },
+                            '&:hover .MuiOutlinedInput-notchedOutline': {
+                                borderColor: config.light_mode ? 'black' : '#94A3B8',
+                            }
+                        }} MenuProps={{
+                            sx: {
+                                "& .MuiPaper-root": {
+                                    backgroundColor: config.light_mode ? '#94A3B8' : '#020617',
+                                }
+                            }
+                        }} className="w-44" disabled={!config.vr_controller.enabled} value={config.vr_controller.button} onChange={(e) => {
+                            setConfig({
+                                ...config,
+                                vr_controller: {
+                                    ...config.vr_controller,
+                                    button: e.target.value as Config["vr_controller"]["button"]
+                                }
+                            })
+                        }} >
+                            <MenuItem sx={{ color: config.light_mode ? 'black' : 'white' }} value="grip">Grip</MenuItem>
+                            <MenuItem sx={{ color: config.light_mode ? 'black' : 'white' }} value="trigger">Trigger</MenuItem>
+                            <MenuItem sx={{ color: config.light_mode ? 'black' : 'white' }} value="menu">Menu</MenuItem>
+                            <MenuItem sx={{ color: config.light_mode ? 'black' : 'white' }} value="a">A / X</MenuItem>
+                            <MenuItem sx={{ color: config.light_mode ? 'black' : 'white' }} value="touchpad">Touchpad / Thumbstick</MenuItem>
+                        </Select>
+                        <Select sx={{
+                            color: config.light_mode ? 'black' : 'white',
+                            '& .MuiOutlinedInput-notchedOutline': {
+                                borderColor: config.light_mode ? 'black' : '#94A3B8',
+                            },
+                            '&:hover .MuiOutlinedInput-notchedOutline': {
+                                borderColor: config.light_mode ? 'black' : '#94A3B8',
+                            }
+                        }} MenuProps={{
+                            sx: {
+                                "& .MuiPaper-root": {
+                                    backgroundColor: config.light_mode ? '#94A3B8' : '#020617',
+                                }
+                            }
+                        }} className="w-44 ml-8" disabled={!config.vr_controller.enabled} value={config.vr_controller.mode} onChange={(e) => {
+                            setConfig({
+                                ...config,
+                                vr_controller: {
+                                    ...config.vr_controller,
+                                    mode: e.target.value as Config["vr_controller"]["mode"]
+                                }
+                            })
+                        }} >
+                            <MenuItem sx={{ color: config.light_mode ? 'black' : 'white' }} value="hold">{localization.vr_hold[lang]}</MenuItem>
+                            <MenuItem sx={{ color: config.light_mode ? 'black' : 'white' }} value="toggle">{localization.vr_toggle[lang]}</MenuItem>
+                        </Select>
+                    </div>
+                    <FormControlLabel control={<Checkbox checked={config.vrchat_settings.follow_vrchat} onChange={(e) => {
                         setConfig({
                             ...config,
                             vrchat_settings: {
                                 ...config.vrchat_settings,
-                                disable_kikitan_when_muted: e.target.checked
+                                follow_vrchat: e.target.checked
                             }
                         })
-                    }} />} label={localization.disable_kikitan_when_muted[lang]} />
+                    }} />} label={localization.follow_vrchat[lang]} />
                     <FormControlLabel className="mb-2" control={<Checkbox checked={config.vrchat_settings.send_typing_status_while_talking} onChange={(e) => {
                         setConfig({
                             ...config,
@@ -166,6 +354,32 @@ export default function Settings({ closeCallback, config, setConfig, lang }: Set
                             }
                         })
                     }} />} label={localization.send_typing_status_while_talking[lang]} />
+                    <FormControlLabel className="mb-2" control={<Checkbox checked={config.vrchat_settings.typewriter} onChange={(e) => {
+                        setConfig({
+                            ...config,
+                            vrchat_settings: {
+                                ...config.vrchat_settings,
+                                typewriter: e.target.checked
+                            }
+                        })
+                    }} />} label={localization.typewriter[lang]} />
+                    <TextField slotProps={{
+                        inputLabel: {
+                            style: { color: config.light_mode ? "black" : '#94A3B8' }
+                        },
+                        htmlInput: {
+                            style: { color: config.light_mode ? "black" : '#fff' }
+                        }
+                    }} className="w-96" value={config.vrchat_settings.message_template} label={localization.message_template[lang]} placeholder={config.vrchat_settings.translation_first ? "{translated} ({original})" : "{original} ({translated})"} variant="outlined" multiline onChange={(e) => {
+                        setConfig({
+                            ...config,
+                            vrchat_settings: {
+                                ...config.vrchat_settings,
+                                message_template: e.target.value
+                            }
+                        })
+                    }} />
+                    <p className={`mb-2 text-xs ${config.light_mode ? "text-black" : "text-slate-400"}`}>{localization.message_template_help[lang]}</p>
                     <div className="flex transition-all">
                         <TextField slotProps={{
                             inputLabel: {
@@ -254,12 +468,14 @@ export default function Settings({ closeCallback, config, setConfig, lang }: Set
                         htmlInput: {
                             style: { color: config.light_mode ? "black" : '#fff' }
                         }
-                    }} className="mt-2 w-96" value={config.api_settings.qwen_asr_api_key} id="qwen-asr-api-key" label={localization.qwen_asr_api_key[lang]} variant="outlined" type="password" onChange={(e) => {
+                    }} className="mt-2 w-96" value={config.api_settings.qwen_asr_api_key} placeholder={config.api_settings.qwen_asr_api_key_set ? "••••••••••••••••" : ""} id="qwen-asr-api-key" label={localization.qwen_asr_api_key[lang]} variant="outlined" type="password" onChange={(e) => {
+                        // Erasing what was typed clears the stored key
                         setConfig({
                             ...config,
                             api_settings: {
                                 ...config.api_settings,
-                                qwen_asr_api_key: e.target.value
+                                qwen_asr_api_key: e.target.value,
+                                qwen_asr_api_key_set: e.target.value !== ""
                             }
                         })
                     }} />
@@ -0,0 +1,40 @@
+import React from "react";
+import { listen } from '../util/events'
+
+const MAX_LINES = 3
+
+type Subtitle = {
+    id: number,
+    original: string,
+    translation: string,
+    // Whose preferred language the translation is in
+    player: string | null,
+    // What another player said, not me
+    incoming: boolean
+}
+
+export default function Subtitles() {
+    const [lines, setLines] = React.useState<Subtitle[]>([])
+
+    React.useEffect(() => {
+        document.body.style.background = "transparent"
+
+        const unlisten = listen<Subtitle>("subtitle", (event) => {
+            setLines((lines) => [...lines, event.payload].slice(-MAX_LINES))
+        })
+
+        return () => {
+            unlisten.then((f) => f())
+        }
+    }, [])
+
+    return <div data-tauri-drag-region className="w-screen h-screen flex flex-col justify-end items-center gap-2 p-2 cursor-move">
+        {lines.map((line) => (
+            <div data-tauri-drag-region key={`${line.incoming ? "in" : "out"}-${line.id}-${line.original}`} className={`max-w-full px-4 py-1 rounded-md text-white text-center ${line.incoming ? "bg-sky-950/70" : "bg-black/60"}`}>
+                <p data-tauri-drag-region className="text-sm opacity-80">{line.original}</p>
+                <p data-tauri-drag-region className="text-2xl font-bold">{line.translation}</p>
+                {line.player && <p data-tauri-drag-region className="text-xs opacity-60">→ {line.player}</p>}
+            </div>
+        ))}
+    </div>
+}
@@ -0,0 +1,186 @@
+import { Recognizer } from "./recognizer";
+import {
+    info,
+    error,
+    debug
+} from '@tauri-apps/plugin-log';
+import { invoke } from '@tauri-apps/api/core';
+import { listen, TranscriptOrder } from '../util/events'
+import { errorMessage } from '../util/errors';
+import { deviceConstraint, resolveInputDevice } from '../util/devices';
+
+type Transcript = {
+    text: string,
+    final: boolean,
+    // Only set when the engine ran its translate task
+    translation: string
+}
+
+// Streams microphone audio to an external engine through the backend's gRPC client
+export class GrpcASR extends Recognizer {
+    private audioContext: AudioContext | null = null;
+    private audioProcessor: ScriptProcessorNode | null = null;
+    private audioSource: MediaStreamAudioSourceNode | null = null;
+    private resultCallback: ((result: string, final: boolean, translation?: string) => void) | null = null;
+    private connected: boolean = false;
+    private reconnectAttempts: number = 0;
+    private maxReconnectAttempts: number = 5;
+    private unlisten: (() => void)[] = [];
+    private order = new TranscriptOrder();
+
+    async start() {
+        if (this.running) {
+            info("[GRPC-ASR] Already running");
+            return;
+        }
+
+        this.running = true;
+        info("[GRPC-ASR] Starting recognition...");
+
+        try {
+            await this.connect();
+            await this.startAudioCapture();
+        } catch (e) {
+            error("[GRPC-ASR] Error starting recognition: " + errorMessage(e));
+            this.running = false;
+        }
+    }
+
+    stop() {
+        info("[GRPC-ASR] Stopping recognition...");
+        this.running = false;
+        this.stopAudioCapture();
+        this.disconnect();
+    }
+
+    set_lang(lang: string) {
+        debug("[GRPC-ASR] Language set to " + lang);
+        this.language = lang;
+
+        // The language is only sent when the stream opens
+        if (this.running) {
+            this.stop();
+            setTimeout(() => {
+                this.start();
+            }, 500);
+        }
+    }
+
+    status(): boolean {
+        return this.running;
+    }
+
+    onResult(callback: (result: string, final: boolean, translation?: string) => void) {
+        this.resultCallback = callback;
+    }
+
+    private async connect() {
+        if (this.unlisten.length == 0) {
+            this.unlisten.push(await listen<Transcript>('grpc-asr-transcript', (event) => {
+                if (!this.order.accept(event.stamp, event.payload.final)) return;
+                if (this.resultCallback && event.payload.text) {
+                    this.resultCallback(event.payload.text, event.payload.final, event.payload.translation || undefined);
+                }
+            }));
+
+            this.unlisten.push(await listen('grpc-asr-close', () => {
+                info("[GRPC-ASR] Stream closed by the engine");
+                this.connected = false;
+                this.handleReconnect();
+            }));
+
+            this.unlisten.push(await listen('grpc-asr-error', (event) => {
+                error("[GRPC-ASR] Stream error: " + event.payload);
+                this.connected = false;
+                this.handleReconnect();
+            }));
+        }
+
+        await invoke('grpc_asr_connect', { language: this.language });
+
+        info("[GRPC-ASR] Connected to the ASR engine");
+        this.connected = true;
+        this.reconnectAttempts = 0;
+    }
+
+    private handleReconnect() {
+        if (this.running && this.reconnectAttempts < this.maxReconnectAttempts) {
+            this.reconnectAttempts++;
+            info(`[GRPC-ASR] Attempting to reconnect (${this.reconnectAttempts}/${this.maxReconnectAttempts})...`);
+            setTimeout(() => {
+                this.connect().catch(e => error("[GRPC-ASR] Failed to reconnect: " + errorMessage(e)));
+            }, 2000 * this.reconnectAttempts);
+        }
+    }
+
+    private disconnect() {
+        if (this.connected) {
+            invoke('grpc_asr_close').catch(e => error("[GRPC-ASR] Error closing stream: " + errorMessage(e)));
+            this.connected = false;
+        }
+
+        this.unlisten.forEach(unlisten => unlisten());
+        this.unlisten = [];
+    }
+
+    private async startAudioCapture() {
+        const device = await resolveInputDevice();
+        info("[GRPC-ASR] Capturing from " + device.label);
+
+        const stream = await navigator.mediaDevices.getUserMedia({
+            audio: {
+                ...deviceConstraint(device),
+                channelCount: 1,
+                sampleRate: 16000,
+                echoCancellation: true,
+                noiseSuppression: true,
+                autoGainControl: true
+            }
+        });
+
+        this.audioContext = new AudioContext({ sampleRate: 16000 });
+        this.audioSource = this.audioContext.createMediaStreamSource(stream);
+        this.audioProcessor = this.audioContext.createScriptProcessor(4096, 1, 1);
+
+        this.audioProcessor.onaudioprocess = (e) => {
+            if (!this.running || !this.connected) return;
+
+            const input = e.inputBuffer.getChannelData(0);
+            const pcm16 = new Int16Array(input.length);
+            for (let i = 0; i < input.length; i++) {
+                const s = Math.max(-1, Math.min(1, input[i]));
+                pcm16[i] = s < 0 ? s * 0x8000 : s * 0x7FFF;
+            }
+
+            let binary = '';
+            const bytes = new Uint8Array(pcm16.buffer);
+            for (let i = 0; i < bytes.byteLength; i++) {
+                binary += String.fromCharCode(bytes[i]);
+            }
+
+            invoke('grpc_asr_send', { audio: btoa(binary) })
+                .catch(e => error("[GRPC-ASR] Error sending audio: " + errorMessage(e)));
+        };
+
+        this.audioSource.connect(this.audioProcessor);
+        this.audioProcessor.connect(this.audioContext.destination);
+
+        info("[GRPC-ASR] Audio capture started");
+    }
+
+    private stopAudioCapture() {
+        if (this.audioProcessor) {
+            this.audioProcessor.disconnect();
+            this.audioProcessor = null;
+        }
+        if (this.audioSource) {
+            this.audioSource.disconnect();
+            this.audioSource = null;
+        }
+        if (this.audioContext) {
+            this.audioContext.close();
+            this.audioContext = null;
+        }
+        info("[GRPC-ASR] Audio capture stopped");
+    }
+}
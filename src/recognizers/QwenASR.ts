@@ -6,10 +6,11 @@ import {
     warn
 } from '@tauri-apps/plugin-log';
 import { invoke } from '@tauri-apps/api/core';
-import { listen } from '@tauri-apps/api/event';
+import { listen, Stamp, TranscriptOrder } from '../util/events'
+import { errorMessage } from '../util/errors';
+import { deviceConstraint, resolveInputDevice } from '../util/devices';
 
 export class QwenASR extends Recognizer {
-    private apiKey: string;
     private mediaRecorder: MediaRecorder | null = null;
     private audioContext: AudioContext | null = null;
     private audioProcessor: ScriptProcessorNode | null = null;
@@ -19,6 +20,7 @@ export class QwenASR extends Recognizer {
     private reconnectAttempts: number = 0;
     private maxReconnectAttempts: number = 5;
     private enableServerVad: boolean = true;
+    private order = new TranscriptOrder();
     private currentTranscript: string = "";
     private sessionConfigured: boolean = false;
     private wsConnected: boolean = false;
@@ -26,9 +28,9 @@ export class QwenASR extends Recognizer {
     private closeUnlisten: (() => void) | null = null;
     private errorUnlisten: (() => void) | null = null;
 
-    constructor(lang: string, apiKey: string) {
+    // The backend reads the API key from the credential store when connecting
+    constructor(lang: string) {
         super(lang);
-        this.apiKey = apiKey;
     }
 
     async start() {
@@ -37,11 +39,6 @@ export class QwenASR extends Recognizer {
             return;
         }
 
-        if (!this.apiKey || this.apiKey.trim() === "") {
-            error("[QWEN-ASR] API key is required");
-            return;
-        }
-
         this.running = true;
         info("[QWEN-ASR] Starting recognition...");
 
@@ -49,7 +46,7 @@ export class QwenASR extends Recognizer {
             await this.connectWebSocket();
             await this.startAudioCapture();
         } catch (e) {
-            error("[QWEN-ASR] Error starting recognition: " + e);
+            error("[QWEN-ASR] Error starting recognition: " + errorMessage(e));
             this.running = false;
         }
     }
@@ -97,7 +94,7 @@ export class QwenASR extends Recognizer {
 
             // Set up event listeners for WebSocket messages
             this.messageUnlisten = await listen('qwen-ws-message', (event) => {
-                this.handleMessage(event.payload as string);
+                this.handleMessage(event.payload as string, event.stamp);
             });
 
             this.closeUnlisten = await listen('qwen-ws-close', () => {
@@ -113,8 +110,8 @@ export class QwenASR extends Recognizer {
             });
 
             // Connect via Tauri command
+            // The backend reads the API key from the OS credential store
             await invoke('qwen_ws_connect', {
-                apiKey: this.apiKey,
                 model: MODEL
             });
 
@@ -128,7 +125,7 @@ export class QwenASR extends Recognizer {
                 this.sendSessionUpdate();
             }, 100);
         } catch (err) {
-            error("[QWEN-ASR] Failed to connect WebSocket: " + err);
+            error("[QWEN-ASR] Failed to connect WebSocket: " + errorMessage(err));
             this.isConnecting = false;
             throw err;
         }
@@ -149,7 +146,7 @@ export class QwenASR extends Recognizer {
             try {
                 await invoke('qwen_ws_close');
             } catch (e) {
-                error("[QWEN-ASR] Error closing WebSocket: " + e);
+                error("[QWEN-ASR] Error closing WebSocket: " + errorMessage(e));
             }
             this.wsConnected = false;
         }
@@ -221,15 +218,22 @@ export class QwenASR extends Recognizer {
             await invoke('qwen_ws_send', { message: JSON.stringify(sessionUpdate) });
             this.sessionConfigured = true;
         } catch (e) {
-            error("[QWEN-ASR] Failed to send session update: " + e);
+            error("[QWEN-ASR] Failed to send session update: " + errorMessage(e));
         }
     }
 
-    private handleMessage(data: string) {
+    private handleMessage(data: string, stamp: Stamp) {
         try {
             const message = JSON.parse(data);
             debug("[QWEN-ASR] Received event: " + message.type);
 
+            // Deltas are appended, one arriving late would end up in the wrong utterance
+            const final = message.type === 'conversation.item.input_audio_transcription.completed';
+            if (message.type.startsWith('conversation.item.input_audio_transcription.') && !this.order.accept(stamp, final)) {
+                debug("[QWEN-ASR] Dropped a late " + message.type);
+                return;
+            }
+
             if (message.type === 'conversation.item.input_audio_transcription.completed') {
                 const transcript = message.transcript || '';
                 info(`[QWEN-ASR] Final transcript: ${transcript}`);
@@ -256,8 +260,12 @@ export class QwenASR extends Recognizer {
 
     private async startAudioCapture() {
         try {
+            const device = await resolveInputDevice();
+            info("[QWEN-ASR] Capturing from " + device.label);
+
             const stream = await navigator.mediaDevices.getUserMedia({ 
                 audio: {
+                    ...deviceConstraint(device),
                     channelCount: 1,
                     sampleRate: 16000,
                     echoCancellation: true,
@@ -290,7 +298,7 @@ export class QwenASR extends Recognizer {
 
                 invoke('qwen_ws_send', { message: JSON.stringify(audioEvent) })
                     .catch(e => {
-                        error("[QWEN-ASR] Error sending audio: " + e);
+                        error("[QWEN-ASR] Error sending audio: " + errorMessage(e));
                     });
             };
 
@@ -1,4 +1,7 @@
 import { Recognizer } from "./recognizer";
+import { invoke } from '@tauri-apps/api/core';
+import { UnlistenFn } from '@tauri-apps/api/event';
+import { listen } from '../util/events'
 
 import {
     info,
@@ -8,6 +11,9 @@ import {
 
 export class WebSpeech extends Recognizer {
     recognition: SpeechRecognition;
+    // "Mute capture" in the tray, recognition stays stopped while it's checked
+    private muted: boolean = false;
+    private mutedUnlisten: Promise<UnlistenFn> | null = null;
 
     constructor(lang: string) {
         super(lang);
@@ -21,6 +27,12 @@ export class WebSpeech extends Recognizer {
 
     start() {
         this.running = true;
+
+        this.mutedUnlisten = listen<boolean>('capture-muted', (event) => this.setMuted(event.payload))
+        invoke<boolean>('get_capture_muted').then((muted) => {
+            if (muted) this.setMuted(true)
+        })
+
         try {
             this.recognition.start();
 
@@ -30,7 +42,7 @@ export class WebSpeech extends Recognizer {
         }
 
         this.recognition.onend = () => {
-            if (this.running) {
+            if (this.running && !this.muted) {
                 setTimeout(() => {
                     try {
                         this.recognition.start();
@@ -40,7 +52,7 @@ export class WebSpeech extends Recognizer {
         }
 
         this.recognition.onnomatch = () => {
-            if (this.running) {
+            if (this.running && !this.muted) {
                 setTimeout(() => {
                     try {
                         this.recognition.start();
@@ -52,7 +64,7 @@ export class WebSpeech extends Recognizer {
         this.recognition.onerror = (e) => {
             if (e.message.trim().length != 0) error("[WEBSPEECH] Error: " + e.message)
 
-            if (this.running) {
+            if (this.running && !this.muted) {
                 setTimeout(() => {
                     try {
                         this.recognition.start();
@@ -62,10 +74,28 @@ export class WebSpeech extends Recognizer {
         }
     }
 
+    private setMuted(muted: boolean) {
+        if (muted == this.muted) return
+        this.muted = muted
+
+        if (muted) {
+            this.recognition.stop();
+            info("[WEBSPEECH] Capture muted")
+        } else if (this.running) {
+            try {
+                this.recognition.start();
+            } catch { /* empty */ }
+            info("[WEBSPEECH] Capture unmuted")
+        }
+    }
+
     stop() {
         this.running = false;
         this.recognition.stop();
 
+        this.mutedUnlisten?.then((unlisten) => unlisten())
+        this.mutedUnlisten = null
+
         info("[WEBSPEECH] Recognition stopped!")
     }
 
@@ -75,6 +105,8 @@ export class WebSpeech extends Recognizer {
         debug("[WEBSPEECH] Language set to " + lang)
         this.recognition.stop();
 
+        if (this.muted) return
+
         debug("[WEBSPEECH] Restarting in 500ms...")
         setTimeout(() => {
             this.recognition.start();
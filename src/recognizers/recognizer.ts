@@ -11,5 +11,6 @@ export abstract class Recognizer {
     abstract set_lang(lang: string): void;
     abstract status(): boolean;
 
-    abstract onResult(callback: (result: string, final: boolean) => void): void;
+    // Recognizers that can translate on their own pass the translation with final results
+    abstract onResult(callback: (result: string, final: boolean, translation?: string) => void): void;
 }
\ No newline at end of file
@@ -0,0 +1,5 @@
+import { invoke } from "@tauri-apps/api/core";
+
+export default async function (plugin: string, text: string, source: string, target: string) {
+    return await invoke<string>("plugin_translate", { plugin, text, source, target })
+}
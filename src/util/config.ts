@@ -1,5 +1,6 @@
 /* eslint-disable @typescript-eslint/no-explicit-any */
 import { langSource, langTo } from "./constants"
+import { invoke } from '@tauri-apps/api/core'
 
 import {
     info,
@@ -25,14 +26,48 @@ export type Config = {
     vrchat_settings: {
         translation_first: boolean,
         only_translation: boolean,
-        disable_kikitan_when_muted: boolean,
+        mute_behavior: "pause" | "transcribe" | "ignore",
         send_typing_status_while_talking: boolean,
         chatbox_update_speed: number,
+        message_template: string,
+        typewriter: boolean,
+        typewriter_words: number,
         osc_address: string,
-        osc_port: number
+        osc_port: number,
+        follow_vrchat: boolean
     },
     api_settings: {
-        qwen_asr_api_key: string
+        // Only ever holds a key being entered, the stored one stays in the backend
+        qwen_asr_api_key: string,
+        qwen_asr_api_key_set: boolean
+    },
+    plugins: {
+        translation_provider: string
+    },
+    autocorrect: {
+        enabled: boolean,
+        glossary: string[]
+    },
+    language_switch: {
+        enabled: boolean,
+        languages: string[]
+    },
+    grpc_asr: {
+        enabled: boolean,
+        endpoint: string,
+        translate_to_english: boolean
+    },
+    input_device: {
+        preferred_patterns: string[],
+        capture_offset_ms: number
+    },
+    telemetry: {
+        enabled: boolean
+    },
+    vr_controller: {
+        enabled: boolean,
+        button: "grip" | "trigger" | "menu" | "a" | "touchpad",
+        mode: "hold" | "toggle"
     }
 }
 
@@ -49,14 +84,47 @@ export const DEFAULT_CONFIG: Config = {
     vrchat_settings: {
         translation_first: true,
         only_translation: false,
-        disable_kikitan_when_muted: false,
+        mute_behavior: "ignore",
         send_typing_status_while_talking: true,
         chatbox_update_speed: speed_presets.slow,
+        message_template: "",
+        typewriter: false,
+        typewriter_words: 4,
         osc_address: "127.0.0.1",
-        osc_port: 9000
+        osc_port: 9000,
+        follow_vrchat: false
     },
     api_settings: {
-        qwen_asr_api_key: ""
+        qwen_asr_api_key: "",
+        qwen_asr_api_key_set: false
+    },
+    plugins: {
+        translation_provider: ""
+    },
+    autocorrect: {
+        enabled: false,
+        glossary: []
+    },
+    language_switch: {
+        enabled: false,
+        languages: []
+    },
+    grpc_asr: {
+        enabled: false,
+        endpoint: "http://127.0.0.1:50051",
+        translate_to_english: false
+    },
+    input_device: {
+        preferred_patterns: [],
+        capture_offset_ms: 0
+    },
+    telemetry: {
+        enabled: false
+    },
+    vr_controller: {
+        enabled: false,
+        button: "grip",
+        mode: "hold"
     }
 }
 
@@ -97,15 +165,20 @@ export function validate_config(config: Config): Config {
     return cfg
 }
 
-export function load_config(): Config {
+export async function load_config(): Promise<Config> {
     info("[CONFIG] Loading config...")
     if (typeof window === "undefined") return DEFAULT_CONFIG
 
-    const val = window.localStorage.getItem("config")
-    if (val == null) return DEFAULT_CONFIG
+    // Configs from older versions live in localStorage, hand them over to the backend once
+    const legacy = window.localStorage.getItem("config")
+    if (legacy != null) {
+        await invoke("set_settings", { settings: validate_config(JSON.parse(legacy)) })
+        window.localStorage.removeItem("config")
+
+        info("[CONFIG] Migrated config from localStorage")
+    }
 
-    const config = validate_config(JSON.parse(val))
-    update_config(config)
+    const config = validate_config(await invoke<Config>("get_settings"))
 
     info("[CONFIG] Loaded config!")
 
@@ -124,5 +197,5 @@ export function update_config(config: Config) {
     
     info(`[CONFIG] Updating config to ${JSON.stringify(sanitizedConfig, null, 2)}`)
 
-    localStorage.setItem("config", JSON.stringify(config))
+    invoke("set_settings", { settings: config })
 }
\ No newline at end of file
@@ -0,0 +1,26 @@
+import { invoke } from '@tauri-apps/api/core'
+
+export type InputDevice = {
+    // Undefined leaves the choice to the system default
+    deviceId?: string,
+    label: string
+}
+
+// Browser aliases for whatever the system default is, never worth matching against
+const ALIASES = ["default", "communications"]
+
+// The microphone matching the preferred patterns from the settings, otherwise the system default
+export async function resolveInputDevice(): Promise<InputDevice> {
+    const inputs = (await navigator.mediaDevices.enumerateDevices()).filter((device) => device.kind == "audioinput")
+    const candidates = inputs.filter((device) => !ALIASES.includes(device.deviceId))
+
+    const picked = await invoke<number | null>("pick_input_device", { labels: candidates.map((device) => device.label) }).catch(() => null)
+    if (picked != null) return { deviceId: candidates[picked].deviceId, label: candidates[picked].label }
+
+    return { label: inputs[0]?.label ?? "" }
+}
+
+// Constraint for getUserMedia, empty for the system default
+export function deviceConstraint(device: InputDevice): MediaTrackConstraints {
+    return device.deviceId ? { deviceId: { exact: device.deviceId } } : {}
+}
@@ -0,0 +1,38 @@
+import { invoke } from '@tauri-apps/api/core'
+import { Lang } from "./constants"
+
+// Commands that return localizable errors reject with this instead of a string
+export type BackendError = {
+    code: string,
+    params: Record<string, string>,
+    message: string
+}
+
+const BACKEND_LANGUAGES: Partial<Record<Lang, string>> = { en: "en", jp: "ja", cn: "zh", kr: "ko" }
+const templates: Partial<Record<Lang, Promise<Record<string, string>>>> = {}
+
+function isBackendError(e: unknown): e is BackendError {
+    return typeof e === "object" && e != null && "code" in e && "message" in e
+}
+
+// English text of any rejected invoke, for logs
+export function errorMessage(e: unknown): string {
+    return isBackendError(e) ? e.message : String(e)
+}
+
+// Text to show the user, in their UI language where the backend has a translation
+export async function localizeError(e: unknown, lang: Lang): Promise<string> {
+    const language = BACKEND_LANGUAGES[lang]
+    if (!isBackendError(e) || language == null) return errorMessage(e)
+
+    templates[lang] ??= invoke<Record<string, string>>("get_error_messages", { language })
+
+    try {
+        const template = (await templates[lang])![e.code]
+        if (template == null) return e.message
+
+        return Object.entries(e.params).reduce((text, [name, value]) => text.replace(`{${name}}`, value), template)
+    } catch {
+        return e.message
+    }
+}
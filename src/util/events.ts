@@ -0,0 +1,40 @@
+import { listen as listenRaw, Event, UnlistenFn } from '@tauri-apps/api/event'
+
+// Where an event stands in its stream, every event the backend emits carries one
+export type Stamp = {
+    seq: number,
+    utterance: number
+}
+
+type Stamped<T> = Stamp & { payload: T }
+
+export type StampedEvent<T> = Event<T> & { stamp: Stamp }
+
+// Unwraps backend events. With `latestOnly`, events older than one already handled are
+// dropped, so a late partial transcript can't overwrite newer text
+export async function listen<T>(event: string, handler: (event: StampedEvent<T>) => void, latestOnly = false): Promise<UnlistenFn> {
+    let last = 0
+
+    return listenRaw<Stamped<T>>(event, (raw) => {
+        const { seq, utterance, payload } = raw.payload
+        if (latestOnly && seq != 0 && seq <= last) return
+        last = Math.max(last, seq)
+
+        handler({ ...raw, payload, stamp: { seq, utterance } })
+    })
+}
+
+// Keeps recognizer results in order. A partial is stale once anything newer was handled,
+// a final only once its utterance has been finished, so late finals still get through
+export class TranscriptOrder {
+    private last = 0
+    private finished = 0
+
+    accept(stamp: Stamp, final: boolean): boolean {
+        if (final ? stamp.utterance < this.finished : stamp.seq <= this.last || stamp.utterance < this.finished) return false
+
+        this.last = Math.max(this.last, stamp.seq)
+        if (final) this.finished = Math.max(this.finished, stamp.utterance + 1)
+        return true
+    }
+}
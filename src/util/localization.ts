@@ -19,11 +19,31 @@ export const localization = {
     vrchat_settings: { en: "VRChat Settings", jp: "VRChat 設定", cn: "VRChat 设置", kr: "VRChat 설정", tr: "VRChat Ayarları" },
     omit_questionmark: { en: "[Japanese] Omit the trailing question mark", jp: "[日本語] 末尾の疑問符を省略", cn: "[日语] 省略末尾的问号", kr: "[일본어] 물음표를 생략", tr: "[Japonca] Son soru işaretini atla" },
     translation_first: { en: "Translation first", jp: "最初に翻訳文を表示", cn: "先显示翻译结果", kr: "먼저 번역 결과 표시", tr: "Önce çeviriyi göster" },
-    disable_kikitan_when_muted: { en: "Disable Kikitan when muted in game", jp: "ゲーム内でミュートされているときに Kikitan を無効にする", cn: "在游戏中被静音时禁用 Kikitan", kr: "게임 내에서 음소거 상태일 때 Kikitan 비활성화", tr: "Oyunda susturulduğunda Kikitan'ı devre dışı bırak" },
+    when_muted: { en: "When muted in game", jp: "ゲーム内でミュートされているとき", cn: "在游戏中被静音时", kr: "게임 내에서 음소거 상태일 때", tr: "Oyunda susturulduğunda" },
+    mute_pause: { en: "Pause Kikitan", jp: "Kikitan を一時停止する", cn: "暂停 Kikitan", kr: "Kikitan 일시 정지", tr: "Kikitan'ı duraklat" },
+    mute_transcribe: { en: "Keep transcribing, don't send to the chatbox", jp: "文字起こしは続け、チャットボックスには送信しない", cn: "继续转录，但不发送到聊天框", kr: "전사는 계속하고 채팅창에는 보내지 않음", tr: "Yazıya dökmeye devam et, sohbet kutusuna gönderme" },
+    mute_ignore: { en: "Keep going as usual", jp: "通常どおり続ける", cn: "照常继续", kr: "평소처럼 계속", tr: "Normal şekilde devam et" },
+    follow_vrchat: { en: "Start and stop with VRChat", jp: "VRChat と一緒に開始・停止する", cn: "随 VRChat 启动和停止", kr: "VRChat과 함께 시작 및 중지", tr: "VRChat ile birlikte başlat ve durdur" },
     chatbox_update_speed: { en: "Chatbox update speed", jp: "チャットボックス更新速度", cn: "聊天框更新速度", kr: "채팅창 업데이트 속도", tr: "Sohbet kutusu güncelleme hızı" },
     slow: { en: "Slow", jp: "遅い", cn: "慢", kr: "느림", tr: "Yavaş" },
     medium: { en: "Medium", jp: "中", cn: "中", kr: "중간", tr: "Orta" },
     fast: { en: "Fast", jp: "速い", cn: "快", kr: "빠름", tr: "Hızlı" },
+    typewriter: { en: "Reveal long translations a few words at a time", jp: "長い翻訳を数語ずつ表示する", cn: "逐步显示较长的翻译", kr: "긴 번역을 몇 단어씩 표시", tr: "Uzun çevirileri birkaç kelime halinde göster" },
+    message_template: { en: "Chatbox format", jp: "チャットボックスの書式", cn: "聊天框格式", kr: "채팅창 형식", tr: "Sohbet kutusu biçimi" },
+    message_template_help: { en: "{original}, {translated}, {source}, {target}, {original_romaji}, {translated_romaji}. Leave empty to use the option above.", jp: "{original}, {translated}, {source}, {target}, {original_romaji}, {translated_romaji}。空欄の場合は上の設定に従います。", cn: "{original}, {translated}, {source}, {target}, {original_romaji}, {translated_romaji}。留空则使用上面的选项。", kr: "{original}, {translated}, {source}, {target}, {original_romaji}, {translated_romaji}. 비워 두면 위 설정을 따릅니다.", tr: "{original}, {translated}, {source}, {target}, {original_romaji}, {translated_romaji}. Yukarıdaki seçeneği kullanmak için boş bırakın." },
+    autocorrect: { en: "Correct misrecognized words before translating", jp: "翻訳前に誤認識された単語を修正する", cn: "翻译前纠正识别错误的单词", kr: "번역 전에 잘못 인식된 단어 교정", tr: "Çeviriden önce yanlış tanınan kelimeleri düzelt" },
+    glossary: { en: "Glossary", jp: "用語集", cn: "术语表", kr: "용어집", tr: "Sözlük" },
+    glossary_help: { en: "One name or term per line, spelled the way it should be. Hunspell dictionaries (.aff and .dic) go in the dictionaries folder of the app data.", jp: "1行に1つの名前または用語を正しい綴りで入力します。Hunspell 辞書 (.aff と .dic) はアプリデータの dictionaries フォルダに置いてください。", cn: "每行一个名称或术语，按正确拼写填写。Hunspell 词典（.aff 和 .dic）放在应用数据的 dictionaries 文件夹中。", kr: "한 줄에 하나의 이름이나 용어를 올바른 철자로 입력하세요. Hunspell 사전(.aff 및 .dic)은 앱 데이터의 dictionaries 폴더에 넣으세요.", tr: "Her satıra doğru yazılışıyla bir isim veya terim. Hunspell sözlükleri (.aff ve .dic) uygulama verisindeki dictionaries klasörüne konur." },
+    preferred_microphones: { en: "Preferred microphones", jp: "優先するマイク", cn: "首选麦克风", kr: "선호하는 마이크", tr: "Tercih edilen mikrofonlar" },
+    preferred_microphones_help: { en: "One pattern per line, e.g. Index HMD|Quest. The first line that matches a connected microphone wins, otherwise the system default is used.", jp: "1行に1つのパターン（例: Index HMD|Quest）。接続中のマイクに一致する最初の行が使われ、一致しない場合はシステムの既定が使われます。", cn: "每行一个模式，例如 Index HMD|Quest。匹配到已连接麦克风的第一行生效，否则使用系统默认设备。", kr: "한 줄에 하나의 패턴 (예: Index HMD|Quest). 연결된 마이크와 일치하는 첫 번째 줄이 사용되며, 없으면 시스템 기본값을 사용합니다.", tr: "Her satıra bir desen, ör. Index HMD|Quest. Bağlı bir mikrofonla eşleşen ilk satır kullanılır, yoksa sistem varsayılanı kullanılır." },
+    capture_offset: { en: "Microphone delay (ms)", jp: "マイクの遅延 (ms)", cn: "麦克风延迟 (毫秒)", kr: "마이크 지연 (ms)", tr: "Mikrofon gecikmesi (ms)" },
+    auto_language_switch: { en: "Switch languages automatically when I change the language I speak", jp: "話す言語を変えたら自動で言語を切り替える", cn: "切换说话语言时自动切换语言", kr: "말하는 언어를 바꾸면 자동으로 언어 전환", tr: "Konuştuğum dili değiştirdiğimde dilleri otomatik değiştir" },
+    switch_languages: { en: "Languages I speak", jp: "話す言語", cn: "我说的语言", kr: "내가 말하는 언어", tr: "Konuştuğum diller" },
+    switch_languages_help: { en: "One recognition language code per line. Your voice is learned for each language while it is selected, so switch by hand a few times first.", jp: "1行に1つの認識言語コードを入力します。選択中の言語ごとに声を学習するため、最初は数回手動で切り替えてください。", cn: "每行一个识别语言代码。选中某个语言时会学习你在该语言下的声音，所以请先手动切换几次。", kr: "한 줄에 하나의 인식 언어 코드를 입력하세요. 언어가 선택된 동안 목소리를 학습하므로 처음에는 몇 번 직접 전환하세요.", tr: "Her satıra bir tanıma dili kodu. Sesiniz her dil seçiliyken öğrenilir, bu yüzden önce birkaç kez elle değiştirin." },
+    vr_push_to_translate: { en: "Push to translate with a long press on a VR controller button", jp: "VRコントローラーのボタン長押しで翻訳する", cn: "长按 VR 手柄按键进行翻译", kr: "VR 컨트롤러 버튼을 길게 눌러 번역", tr: "VR kumanda düğmesine uzun basarak çevir" },
+    vr_hold: { en: "While held", jp: "押している間", cn: "按住时", kr: "누르고 있는 동안", tr: "Basılı tutarken" },
+    vr_toggle: { en: "Toggle", jp: "切り替え", cn: "切换", kr: "전환", tr: "Aç/Kapat" },
+    anonymous_telemetry: { en: "Share anonymous usage statistics", jp: "匿名の利用統計を送信する", cn: "发送匿名使用统计", kr: "익명 사용 통계 보내기", tr: "Anonim kullanım istatistiklerini paylaş" },
     osc_address: { en: "OSC Address", jp: "OSC アドレス", cn: "OSC 地址", kr: "OSC 주소", tr: "OSC Adresi" },
     osc_port: { en: "OSC Port", jp: "OSC ポート", cn: "OSC 端口", kr: "OSC 포트", tr: "OSC Portu" },
     send_typing_status_while_talking: {en:"Send typing status while talking", jp:"話している間に入力状態を送信", cn:"说话时发送输入状态", kr:"말하는 동안 입력 상태 전송", tr:"Konuşurken yazma durumu gönder"},